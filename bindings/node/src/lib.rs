@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+
+use cashmere::{lint_structured_with_config, Config, LintResult};
+use napi::{Error, Result};
+use napi_derive::napi;
+
+fn to_js(result: &LintResult) -> Result<serde_json::Value> {
+    serde_json::to_value(result).map_err(|err| Error::from_reason(err.to_string()))
+}
+
+/// The `cashmere.toml` nearest to `path`, and whether it ignores `path`
+fn config_for(path: &str) -> Result<(Config, bool)> {
+    let file = Path::new(path);
+    let dir = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let config = Config::discover(dir).map_err(Error::from_reason)?;
+    let ignored = config.is_ignored(file);
+    Ok((config, ignored))
+}
+
+fn ignored(path: String) -> Result<serde_json::Value> {
+    to_js(&LintResult {
+        file: path,
+        diagnostics: Vec::new(),
+        parse_errors: Vec::new(),
+    })
+}
+
+/// Lint source text as if it were the file `filename`, applying the
+/// `cashmere.toml` nearest to it. Ignored files come back with no diagnostics.
+#[napi]
+pub fn lint_text(source: String, filename: String) -> Result<serde_json::Value> {
+    let (config, is_ignored) = config_for(&filename)?;
+    if is_ignored {
+        return ignored(filename);
+    }
+    to_js(&lint_structured_with_config(&source, &filename, &config))
+}
+
+/// Read and lint a file, applying the nearest `cashmere.toml`. Ignored files
+/// come back with no diagnostics.
+#[napi]
+pub fn lint_file(path: String) -> Result<serde_json::Value> {
+    let (config, is_ignored) = config_for(&path)?;
+    if is_ignored {
+        return ignored(path);
+    }
+    let source = fs::read_to_string(&path)
+        .map_err(|err| Error::from_reason(format!("failed to read {}: {}", path, err)))?;
+    to_js(&lint_structured_with_config(&source, &path, &config))
+}
@@ -0,0 +1,60 @@
+// The wrappers `#[pyfunction]` generates convert the returned `PyErr` into itself
+#![allow(clippy::useless_conversion)]
+
+use std::path::Path;
+
+use cashmere_core::{lint_structured_with_config, Config};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::Value;
+
+/// Convert JSON into the equivalent Python dicts, lists and scalars
+fn to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(value) => value.into_py(py),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => value.into_py(py),
+            None => number.as_f64().unwrap_or_default().into_py(py),
+        },
+        Value::String(value) => value.into_py(py),
+        Value::Array(items) => {
+            let list = PyList::empty_bound(py);
+            for item in items {
+                list.append(to_py(py, item)?)?;
+            }
+            list.into_py(py)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new_bound(py);
+            for (key, item) in map {
+                dict.set_item(key, to_py(py, item)?)?;
+            }
+            dict.into_py(py)
+        }
+    })
+}
+
+/// Lint `source` as if it were the file at `path`, applying the nearest
+/// `cashmere.toml`, and return its diagnostics as a list of dicts
+#[pyfunction]
+#[pyo3(signature = (source, path = "workflow.ts"))]
+fn lint_text(py: Python<'_>, source: &str, path: &str) -> PyResult<PyObject> {
+    let dir = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let config = Config::discover(dir).map_err(PyValueError::new_err)?;
+    let result = lint_structured_with_config(source, path, &config);
+    let diagnostics = serde_json::to_value(&result.diagnostics)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    to_py(py, &diagnostics)
+}
+
+#[pymodule]
+fn cashmere(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(lint_text, m)?)?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,39 @@
+"""Smoke tests for the built module: `maturin develop`, then `python -m unittest discover tests`"""
+
+import os
+import tempfile
+import unittest
+
+import cashmere
+
+UNAWAITED = """
+export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        step.do('send-email', async () => {});
+    }
+}
+"""
+
+
+class LintTextTest(unittest.TestCase):
+    def test_reports_diagnostics_as_dicts(self):
+        diagnostics = cashmere.lint_text(UNAWAITED, "workflow.js")
+        self.assertEqual(len(diagnostics), 1)
+        self.assertEqual(diagnostics[0]["rule"], "await-step")
+        self.assertEqual(diagnostics[0]["severity"], "error")
+        self.assertEqual(diagnostics[0]["line"], 4)
+        self.assertEqual(diagnostics[0]["fix"]["edits"][0]["replacement"], "await ")
+
+    def test_applies_nearest_config(self):
+        with tempfile.TemporaryDirectory() as dir:
+            with open(os.path.join(dir, "cashmere.toml"), "w") as config:
+                config.write('[rules]\nawait-step = "off"\n')
+            path = os.path.join(dir, "workflow.js")
+            self.assertEqual(cashmere.lint_text(UNAWAITED, path), [])
+
+    def test_version(self):
+        self.assertRegex(cashmere.__version__, r"^\d+\.\d+\.\d+")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,48 @@
+/* Generated with cbindgen from src/capi.rs; do not edit by hand. */
+
+#ifndef CASHMERE_H
+#define CASHMERE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Lint `source_len` bytes of UTF-8 source at `source` as if they were the file
+ * `filename`, returning a `LintResult` as a NUL-terminated JSON string.
+ * Returns NULL if a pointer is NULL or the input isn't valid UTF-8. Free the
+ * result with `cashmere_free_string`.
+ *
+ * # Safety
+ *
+ * `source` must point to `source_len` readable bytes and `filename` to a
+ * NUL-terminated string.
+ */
+char *cashmere_lint_utf8(const char *source, size_t source_len, const char *filename);
+
+/**
+ * Free a string returned by `cashmere_lint_utf8`. Passing NULL does nothing.
+ *
+ * # Safety
+ *
+ * `string` must be NULL or a pointer returned by `cashmere_lint_utf8` that
+ * hasn't been freed yet.
+ */
+void cashmere_free_string(char *string);
+
+/**
+ * The version of cashmere as a static NUL-terminated string
+ */
+const char *cashmere_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CASHMERE_H */
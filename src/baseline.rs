@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::linter::LintDiagnostic;
+
+/// A diagnostic recorded in the baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BaselineEntry {
+    /// File path relative to the baseline file
+    file: String,
+    rule: String,
+    /// Identifies the diagnostic independently of its line number, so that
+    /// unrelated edits elsewhere in the file don't invalidate the baseline
+    fingerprint: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BaselineFile {
+    diagnostics: Vec<BaselineEntry>,
+}
+
+/// Computes position-independent fingerprints for diagnostics, relative to the
+/// directory containing the baseline file
+struct Fingerprinter {
+    root: PathBuf,
+    sources: HashMap<String, Option<String>>,
+}
+
+impl Fingerprinter {
+    fn new(baseline_path: &Path) -> Self {
+        let root = std::path::absolute(baseline_path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        Self {
+            root,
+            sources: HashMap::new(),
+        }
+    }
+
+    fn relative_path(&self, file: &str) -> String {
+        let absolute = std::path::absolute(file).unwrap_or_else(|_| PathBuf::from(file));
+        absolute
+            .strip_prefix(&self.root)
+            .unwrap_or(&absolute)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// The fingerprint is derived from the file, rule, message and the trimmed text
+    /// of the line the diagnostic points at
+    fn entry(&mut self, diagnostic: &LintDiagnostic) -> BaselineEntry {
+        let file = self.relative_path(&diagnostic.file);
+        let source = self
+            .sources
+            .entry(diagnostic.file.clone())
+            .or_insert_with(|| fs::read_to_string(&diagnostic.file).ok());
+        let line_text = source
+            .as_deref()
+            .and_then(|source| source.lines().nth(diagnostic.line.saturating_sub(1)))
+            .unwrap_or("")
+            .trim();
+
+        let key = format!(
+            "{}\0{}\0{}\0{}",
+            file, diagnostic.rule, diagnostic.message, line_text
+        );
+        BaselineEntry {
+            file,
+            rule: diagnostic.rule.clone(),
+            fingerprint: format!("{:016x}", xxh3_64(key.as_bytes())),
+        }
+    }
+}
+
+/// Write every diagnostic to a baseline file at `path`
+pub fn generate(path: &Path, diagnostics: &[LintDiagnostic]) -> Result<(), String> {
+    let mut fingerprinter = Fingerprinter::new(path);
+    let mut entries: Vec<BaselineEntry> = diagnostics
+        .iter()
+        .map(|diagnostic| fingerprinter.entry(diagnostic))
+        .collect();
+    entries.sort_by(|a, b| {
+        (&a.file, &a.rule, &a.fingerprint).cmp(&(&b.file, &b.rule, &b.fingerprint))
+    });
+
+    let json = serde_json::to_string_pretty(&BaselineFile {
+        diagnostics: entries,
+    })
+    .map_err(|err| format!("Failed to serialize baseline: {}", err))?;
+    fs::write(path, json + "\n")
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+}
+
+/// Diagnostics recorded in a baseline file, used to suppress pre-existing issues
+pub struct Baseline {
+    /// Remaining number of diagnostics each fingerprint may still suppress
+    remaining_suppressions: HashMap<String, usize>,
+    fingerprinter: Fingerprinter,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read baseline {}: {}", path.display(), err))?;
+        let baseline: BaselineFile = serde_json::from_str(&json)
+            .map_err(|err| format!("Invalid baseline {}: {}", path.display(), err))?;
+
+        // Each baseline entry suppresses at most one diagnostic, so a second copy
+        // of a baselined problem in the same file is still reported
+        let mut remaining_suppressions: HashMap<String, usize> = HashMap::new();
+        for entry in baseline.diagnostics {
+            *remaining_suppressions.entry(entry.fingerprint).or_default() += 1;
+        }
+
+        Ok(Self {
+            remaining_suppressions,
+            fingerprinter: Fingerprinter::new(path),
+        })
+    }
+
+    /// Remove diagnostics recorded in the baseline.
+    /// Returns the remaining diagnostics and the number that were suppressed.
+    pub fn filter(&mut self, diagnostics: Vec<LintDiagnostic>) -> (Vec<LintDiagnostic>, usize) {
+        let mut suppressed = 0;
+        let kept = diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let fingerprint = self.fingerprinter.entry(diagnostic).fingerprint;
+                match self.remaining_suppressions.get_mut(&fingerprint) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        suppressed += 1;
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect();
+
+        (kept, suppressed)
+    }
+}
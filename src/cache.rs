@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::config::Config;
+use crate::linter::LintDiagnostic;
+use crate::steps::ExportedWorkflowClass;
+
+pub const CACHE_FILE_NAME: &str = ".cashmere-cache";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Hash of the file contents the diagnostics were produced from
+    hash: u64,
+    diagnostics: Vec<LintDiagnostic>,
+    /// `WorkflowEntrypoint` classes the file exports, for `unique-workflow-classes`
+    workflow_classes: Vec<ExportedWorkflowClass>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// cashmere version that wrote the cache
+    version: String,
+    /// Fingerprint of the configuration the cached diagnostics were produced with
+    config: u64,
+    files: HashMap<String, CacheEntry>,
+}
+
+/// Diagnostics and exported workflow classes from previous runs, keyed by file path and
+/// content hash
+pub struct LintCache {
+    path: PathBuf,
+    contents: CacheFile,
+}
+
+impl LintCache {
+    /// Load the cache at `path`. A missing or unreadable cache, or one written by a
+    /// different cashmere version or with a different config, starts out empty.
+    pub fn load(path: &Path, config: &Config) -> Self {
+        let version = env!("CARGO_PKG_VERSION").to_string();
+        let config_fingerprint = config.fingerprint();
+
+        let contents = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|cache| cache.version == version && cache.config == config_fingerprint)
+            .unwrap_or_else(|| CacheFile {
+                version,
+                config: config_fingerprint,
+                files: HashMap::new(),
+            });
+
+        Self {
+            path: path.to_path_buf(),
+            contents,
+        }
+    }
+
+    /// Cached diagnostics and exported workflow classes for `file`, if its contents
+    /// haven't changed since they were cached
+    pub fn get(
+        &self,
+        file: &str,
+        hash: u64,
+    ) -> Option<(Vec<LintDiagnostic>, Vec<ExportedWorkflowClass>)> {
+        self.contents
+            .files
+            .get(file)
+            .filter(|entry| entry.hash == hash)
+            .map(|entry| (entry.diagnostics.clone(), entry.workflow_classes.clone()))
+    }
+
+    pub fn insert(
+        &mut self,
+        file: &str,
+        hash: u64,
+        diagnostics: Vec<LintDiagnostic>,
+        workflow_classes: Vec<ExportedWorkflowClass>,
+    ) {
+        self.contents.files.insert(
+            file.to_string(),
+            CacheEntry {
+                hash,
+                diagnostics,
+                workflow_classes,
+            },
+        );
+    }
+
+    /// Write the cache back to disk, dropping entries for files that no longer exist
+    pub fn save(&mut self) -> Result<(), String> {
+        self.contents
+            .files
+            .retain(|file, _| Path::new(file).is_file());
+        let json = serde_json::to_string(&self.contents)
+            .map_err(|err| format!("Failed to serialize lint cache: {}", err))?;
+        fs::write(&self.path, json)
+            .map_err(|err| format!("Failed to write {}: {}", self.path.display(), err))
+    }
+}
+
+pub fn hash_content(source: &str) -> u64 {
+    xxh3_64(source.as_bytes())
+}
@@ -0,0 +1,58 @@
+//! A C API for embedding the linter in tools written in other native languages.
+//! `include/cashmere.h` declares these functions; regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/cashmere.h`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::slice;
+
+use crate::linter::lint_source_structured;
+
+/// Lint `source_len` bytes of UTF-8 source at `source` as if they were the file
+/// `filename`, returning a `LintResult` as a NUL-terminated JSON string.
+/// Returns NULL if a pointer is NULL or the input isn't valid UTF-8. Free the
+/// result with `cashmere_free_string`.
+///
+/// # Safety
+///
+/// `source` must point to `source_len` readable bytes and `filename` to a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cashmere_lint_utf8(
+    source: *const c_char,
+    source_len: usize,
+    filename: *const c_char,
+) -> *mut c_char {
+    if source.is_null() || filename.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(source) = std::str::from_utf8(slice::from_raw_parts(source.cast(), source_len)) else {
+        return std::ptr::null_mut();
+    };
+    let Ok(filename) = CStr::from_ptr(filename).to_str() else {
+        return std::ptr::null_mut();
+    };
+    let result = lint_source_structured(source, filename, &[]);
+    match serde_json::to_string(&result).map(CString::new) {
+        Ok(Ok(json)) => json.into_raw(),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Free a string returned by `cashmere_lint_utf8`. Passing NULL does nothing.
+///
+/// # Safety
+///
+/// `string` must be NULL or a pointer returned by `cashmere_lint_utf8` that
+/// hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cashmere_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// The version of cashmere as a static NUL-terminated string
+#[no_mangle]
+pub extern "C" fn cashmere_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
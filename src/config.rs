@@ -0,0 +1,643 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "plugins")]
+use std::sync::Arc;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::Deserialize;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::linter::{LintDiagnostic, Severity};
+#[cfg(feature = "plugins")]
+use crate::plugin::Plugin;
+use crate::rules::{duration_millis, RuleMeta, ALL_RULES};
+
+pub const CONFIG_FILE_NAME: &str = "cashmere.toml";
+
+/// How a rule is reported, as configured in `cashmere.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    #[serde(alias = "warning")]
+    Warn,
+    Error,
+}
+
+impl RuleLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleLevel::Off => "off",
+            RuleLevel::Warn => "warn",
+            RuleLevel::Error => "error",
+        }
+    }
+}
+
+impl From<Severity> for RuleLevel {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Warning => RuleLevel::Warn,
+            Severity::Error => RuleLevel::Error,
+        }
+    }
+}
+
+/// Where a banned call is reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BanScope {
+    /// Anywhere in a linted file
+    #[default]
+    File,
+    /// Only inside the callbacks passed to step calls
+    Step,
+}
+
+/// A call banned by a `[[banned_calls]]` entry, reported by the `banned-call` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BannedCall {
+    /// The callee, such as `console.log` or `legacyHttpClient`
+    pub call: String,
+    /// Reported instead of the default message
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default = "default_ban_severity")]
+    pub severity: RuleLevel,
+    #[serde(default)]
+    pub within: BanScope,
+}
+
+fn default_ban_severity() -> RuleLevel {
+    RuleLevel::Error
+}
+
+/// How the `await-step` rule treats a step promise passed to another function, as in
+/// `handle(p)`, where it can't see whether the function awaits it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapePolicy {
+    /// Reported unless the promise is also awaited where it was created
+    #[default]
+    Violation,
+    /// Counted as awaited, trusting the function to await it
+    Handled,
+}
+
+impl EscapePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscapePolicy::Violation => "violation",
+            EscapePolicy::Handled => "handled",
+        }
+    }
+}
+
+/// Options for the `await-step` rule
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AwaitStepOptions {
+    #[serde(default)]
+    pub escapes: EscapePolicy,
+}
+
+/// Options for the `no-dynamic-step-name` rule
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicStepNameOptions {
+    /// Values allowed in step names besides loop variables and `event`, as dotted
+    /// names like `batch.id` that also allow anything under them
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+/// Options for the `max-steps` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaxStepsOptions {
+    /// The most steps a workflow instance may run
+    #[serde(default = "default_max_steps")]
+    pub limit: u64,
+}
+
+/// The number of steps Cloudflare allows per workflow instance
+fn default_max_steps() -> u64 {
+    1024
+}
+
+impl Default for MaxStepsOptions {
+    fn default() -> Self {
+        Self {
+            limit: default_max_steps(),
+        }
+    }
+}
+
+/// Options for the `step-name-convention` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepNameConventionOptions {
+    /// A regular expression every literal step name must match
+    #[serde(default = "default_step_name_pattern")]
+    pub pattern: String,
+}
+
+/// Lowercase words and digits joined by single hyphens, like `fetch-user-2`
+pub const KEBAB_CASE_PATTERN: &str = "^[a-z0-9]+(-[a-z0-9]+)*$";
+
+fn default_step_name_pattern() -> String {
+    KEBAB_CASE_PATTERN.to_string()
+}
+
+impl Default for StepNameConventionOptions {
+    fn default() -> Self {
+        Self {
+            pattern: default_step_name_pattern(),
+        }
+    }
+}
+
+/// Options for the `valid-wait-for-event` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaitForEventOptions {
+    /// The shortest `timeout` allowed, as a duration like `"1 minute"`
+    #[serde(default = "default_min_timeout")]
+    pub min_timeout: String,
+}
+
+fn default_min_timeout() -> String {
+    "1 second".to_string()
+}
+
+impl Default for WaitForEventOptions {
+    fn default() -> Self {
+        Self {
+            min_timeout: default_min_timeout(),
+        }
+    }
+}
+
+/// Options for the rules that take them, from `[rule_options.<rule-id>]` tables
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleOptions {
+    #[serde(default, rename = "no-dynamic-step-name")]
+    pub no_dynamic_step_name: DynamicStepNameOptions,
+    #[serde(default, rename = "max-steps")]
+    pub max_steps: MaxStepsOptions,
+    #[serde(default, rename = "step-name-convention")]
+    pub step_name_convention: StepNameConventionOptions,
+    #[serde(default, rename = "valid-wait-for-event")]
+    pub valid_wait_for_event: WaitForEventOptions,
+    #[serde(default, rename = "await-step")]
+    pub await_step: AwaitStepOptions,
+}
+
+/// The on-disk shape of `cashmere.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    ignore: Vec<String>,
+    #[serde(default)]
+    rules: BTreeMap<String, RuleLevel>,
+    #[serde(default)]
+    step_methods: Vec<String>,
+    #[serde(default)]
+    banned_calls: Vec<BannedCall>,
+    #[serde(default)]
+    plugins: Vec<PathBuf>,
+    #[serde(default)]
+    rule_options: RuleOptions,
+}
+
+/// Where a rule's effective level came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleSource {
+    Default,
+    ConfigFile,
+    CommandLine,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// The config file this was loaded from, or `None` if no config file was found
+    pub path: Option<PathBuf>,
+    /// Glob patterns for paths that should not be linted, relative to the config file
+    pub ignore: Vec<String>,
+    /// Per-rule levels from the config file, overriding the defaults
+    pub rules: BTreeMap<String, RuleLevel>,
+    /// Per-rule levels from command-line flags or editor settings, overriding the config file
+    pub overrides: BTreeMap<String, RuleLevel>,
+    /// Extra method names on a step object that are linted like `step.do`
+    pub step_methods: Vec<String>,
+    /// Calls reported by the `banned-call` rule
+    pub banned_calls: Vec<BannedCall>,
+    /// WebAssembly plugins providing extra rules, resolved against the config file
+    pub plugins: Vec<PathBuf>,
+    /// Options for individual rules
+    pub rule_options: RuleOptions,
+    /// The plugins, loaded
+    #[cfg(feature = "plugins")]
+    pub(crate) loaded_plugins: Vec<Arc<Plugin>>,
+    /// Directory that ignore patterns are resolved against
+    root: PathBuf,
+    ignore_set: GlobSet,
+}
+
+impl Config {
+    /// Find the nearest `cashmere.toml` in `start` or any of its ancestors.
+    /// Falls back to the default configuration if there is none.
+    pub fn discover(start: &Path) -> Result<Self, String> {
+        let start = absolute(start);
+        let dir = if start.is_file() {
+            start.parent().unwrap_or(&start)
+        } else {
+            &start
+        };
+
+        for ancestor in dir.ancestors() {
+            let candidate = ancestor.join(CONFIG_FILE_NAME);
+            if candidate.is_file() {
+                return Self::load(&candidate);
+            }
+        }
+
+        Ok(Self {
+            root: dir.to_path_buf(),
+            ..Self::default()
+        })
+    }
+
+    /// Load a specific config file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let file: ConfigFile = toml::from_str(&contents)
+            .map_err(|err| format!("Invalid config in {}: {}", path.display(), err))?;
+
+        if let Some(ban) = file
+            .banned_calls
+            .iter()
+            .find(|ban| !is_call_path(&ban.call))
+        {
+            return Err(format!(
+                "Invalid banned call {:?} in {}: expected a name like \"console.log\"",
+                ban.call,
+                path.display()
+            ));
+        }
+
+        if let Some(allowed) = file
+            .rule_options
+            .no_dynamic_step_name
+            .allow
+            .iter()
+            .find(|allowed| !is_call_path(allowed))
+        {
+            return Err(format!(
+                "Invalid allowed step name value {:?} in {}: expected a name like \"batch.id\"",
+                allowed,
+                path.display()
+            ));
+        }
+
+        if file.rule_options.max_steps.limit == 0 {
+            return Err(format!(
+                "Invalid max-steps limit 0 in {}: expected a positive number of steps",
+                path.display()
+            ));
+        }
+
+        let pattern = &file.rule_options.step_name_convention.pattern;
+        if let Err(err) = Regex::new(pattern) {
+            return Err(format!(
+                "Invalid step-name-convention pattern {:?} in {}: {}",
+                pattern,
+                path.display(),
+                err
+            ));
+        }
+
+        let min_timeout = &file.rule_options.valid_wait_for_event.min_timeout;
+        if duration_millis(min_timeout).is_none() {
+            return Err(format!(
+                "Invalid valid-wait-for-event min_timeout {:?} in {}: expected a duration like \"1 minute\"",
+                min_timeout,
+                path.display()
+            ));
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &file.ignore {
+            let glob = Glob::new(pattern).map_err(|err| {
+                format!(
+                    "Invalid ignore pattern {:?} in {}: {}",
+                    pattern,
+                    path.display(),
+                    err
+                )
+            })?;
+            builder.add(glob);
+        }
+        let ignore_set = builder
+            .build()
+            .map_err(|err| format!("Invalid ignore patterns in {}: {}", path.display(), err))?;
+
+        let path = absolute(path);
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let plugins: Vec<PathBuf> = file
+            .plugins
+            .iter()
+            .map(|plugin| root.join(plugin))
+            .collect();
+        #[cfg(feature = "plugins")]
+        let loaded_plugins = plugins
+            .iter()
+            .map(|plugin| Plugin::load(plugin))
+            .collect::<Result<Vec<_>, _>>()?;
+        #[cfg(not(feature = "plugins"))]
+        if !plugins.is_empty() {
+            return Err(format!(
+                "{} lists plugins, but this cashmere was built without the `plugins` feature",
+                path.display()
+            ));
+        }
+
+        let config = Self {
+            path: Some(path),
+            ignore: file.ignore,
+            rules: file.rules,
+            overrides: BTreeMap::new(),
+            step_methods: file.step_methods,
+            banned_calls: file.banned_calls,
+            plugins,
+            rule_options: file.rule_options,
+            #[cfg(feature = "plugins")]
+            loaded_plugins,
+            root,
+            ignore_set,
+        };
+        let config_path = config.path.as_deref().unwrap_or(Path::new(""));
+        let mut seen = std::collections::HashSet::new();
+        if let Some(duplicate) = config.known_rules().find(|rule| !seen.insert(rule.id)) {
+            return Err(format!(
+                "Rule {:?} is defined more than once by {} and its plugins",
+                duplicate.id,
+                config_path.display()
+            ));
+        }
+        if let Some(unknown) = config
+            .rules
+            .keys()
+            .find(|id| config.find_rule(id).is_none())
+        {
+            return Err(format!(
+                "Unknown rule {:?} in {} (run `cashmere rules` to list available rules)",
+                unknown,
+                config_path.display()
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Every built-in rule, followed by the rules from loaded plugins
+    pub fn known_rules(&self) -> impl Iterator<Item = &'static RuleMeta> + '_ {
+        #[cfg(feature = "plugins")]
+        let plugin_rules = self
+            .loaded_plugins
+            .iter()
+            .flat_map(|plugin| plugin.rules().iter().copied());
+        #[cfg(not(feature = "plugins"))]
+        let plugin_rules = std::iter::empty();
+        ALL_RULES.iter().chain(plugin_rules)
+    }
+
+    /// A built-in rule or one from a loaded plugin
+    pub fn find_rule(&self, id: &str) -> Option<&'static RuleMeta> {
+        self.known_rules().find(|rule| rule.id == id)
+    }
+
+    /// Apply command-line overrides: `only` restricts reporting to the given rules
+    /// (if non-empty) and `deny` forces rules to be reported as errors
+    pub fn with_overrides(mut self, only: &[String], deny: &[String]) -> Self {
+        if !only.is_empty() {
+            let rules: Vec<&RuleMeta> = self.known_rules().collect();
+            for rule in rules {
+                if !only.iter().any(|id| id == rule.id) {
+                    self.overrides.insert(rule.id.to_string(), RuleLevel::Off);
+                }
+            }
+        }
+        for id in deny {
+            self.overrides.insert(id.clone(), RuleLevel::Error);
+        }
+        self
+    }
+
+    /// Apply settings sent by an editor over the LSP: rule levels take precedence over
+    /// the config file and step methods are added to the configured ones
+    pub fn with_editor_settings(
+        mut self,
+        rules: &BTreeMap<String, RuleLevel>,
+        step_methods: &[String],
+    ) -> Self {
+        let known: Vec<(String, RuleLevel)> = rules
+            .iter()
+            .filter(|(id, _)| self.find_rule(id).is_some())
+            .map(|(id, &level)| (id.clone(), level))
+            .collect();
+        self.overrides.extend(known);
+        for method in step_methods {
+            if !self.step_methods.contains(method) {
+                self.step_methods.push(method.clone());
+            }
+        }
+        self
+    }
+
+    /// The effective level of a rule and where it was set
+    pub fn rule_level(&self, rule: &RuleMeta) -> (RuleLevel, RuleSource) {
+        if let Some(&level) = self.overrides.get(rule.id) {
+            (level, RuleSource::CommandLine)
+        } else if let Some(&level) = self.rules.get(rule.id) {
+            (level, RuleSource::ConfigFile)
+        } else {
+            (rule.default_level(), RuleSource::Default)
+        }
+    }
+
+    /// Whether `path` matches one of the configured ignore patterns
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+        let path = absolute(path);
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => self.ignore_set.is_match(relative),
+            Err(_) => false,
+        }
+    }
+
+    /// A hash of every setting that affects lint results, used to invalidate the lint cache
+    pub fn fingerprint(&self) -> u64 {
+        #[cfg(feature = "plugins")]
+        let plugins: Vec<u64> = self.loaded_plugins.iter().map(|p| p.hash()).collect();
+        #[cfg(not(feature = "plugins"))]
+        let plugins: Vec<u64> = Vec::new();
+        xxh3_64(
+            format!(
+                "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+                self.ignore,
+                self.rules,
+                self.overrides,
+                self.step_methods,
+                self.banned_calls,
+                self.rule_options,
+                plugins
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// Drop diagnostics for disabled rules and apply configured severities.
+    /// Rules left at their default level keep the severity they reported.
+    pub fn apply(&self, diagnostics: Vec<LintDiagnostic>) -> Vec<LintDiagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                let Some(rule) = self.find_rule(&diagnostic.rule) else {
+                    return Some(diagnostic);
+                };
+                match self.rule_level(rule) {
+                    (RuleLevel::Off, _) => return None,
+                    (_, RuleSource::Default) => {}
+                    (RuleLevel::Warn, _) => diagnostic.severity = Severity::Warning,
+                    (RuleLevel::Error, _) => diagnostic.severity = Severity::Error,
+                }
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
+
+/// Print the effective configuration for `path` as TOML, annotated with where each value came from
+pub fn print_config(config: &Config, path: &Path, threads: Option<usize>) {
+    println!("# Effective configuration for {}", path.display());
+    match &config.path {
+        Some(config_path) => println!("# Config file: {}", config_path.display()),
+        None => println!("# Config file: none found, using defaults"),
+    }
+    if config.is_ignored(path) {
+        println!(
+            "# Note: {} matches an ignore pattern and won't be linted",
+            path.display()
+        );
+    }
+    match threads.filter(|&n| n > 0) {
+        Some(threads) => println!("# Threads: {} (--threads / CASHMERE_THREADS)", threads),
+        None => println!("# Threads: one per CPU core"),
+    }
+    println!();
+
+    println!("ignore = [");
+    for pattern in &config.ignore {
+        println!("    {:?},", pattern);
+    }
+    println!("]");
+    println!();
+
+    println!("step_methods = [");
+    for method in &config.step_methods {
+        println!("    {:?},", method);
+    }
+    println!("]");
+    println!();
+
+    if !config.plugins.is_empty() {
+        println!("plugins = [");
+        for plugin in &config.plugins {
+            println!("    {:?},", plugin.display().to_string());
+        }
+        println!("]");
+        println!();
+    }
+
+    println!("[rules]");
+    for rule in config.known_rules() {
+        let (level, source) = config.rule_level(rule);
+        let source = match source {
+            RuleSource::Default => "default",
+            RuleSource::ConfigFile => CONFIG_FILE_NAME,
+            RuleSource::CommandLine => "command line",
+        };
+        println!("{} = \"{}\"  # {}", rule.id, level.as_str(), source);
+    }
+
+    let step_name_options = &config.rule_options.no_dynamic_step_name;
+    if *step_name_options != DynamicStepNameOptions::default() {
+        println!();
+        println!("[rule_options.no-dynamic-step-name]");
+        println!("allow = {:?}", step_name_options.allow);
+    }
+
+    let max_steps_options = &config.rule_options.max_steps;
+    if *max_steps_options != MaxStepsOptions::default() {
+        println!();
+        println!("[rule_options.max-steps]");
+        println!("limit = {}", max_steps_options.limit);
+    }
+
+    let naming_options = &config.rule_options.step_name_convention;
+    if *naming_options != StepNameConventionOptions::default() {
+        println!();
+        println!("[rule_options.step-name-convention]");
+        println!("pattern = {:?}", naming_options.pattern);
+    }
+
+    let wait_options = &config.rule_options.valid_wait_for_event;
+    if *wait_options != WaitForEventOptions::default() {
+        println!();
+        println!("[rule_options.valid-wait-for-event]");
+        println!("min_timeout = {:?}", wait_options.min_timeout);
+    }
+
+    let await_options = &config.rule_options.await_step;
+    if *await_options != AwaitStepOptions::default() {
+        println!();
+        println!("[rule_options.await-step]");
+        println!("escapes = \"{}\"", await_options.escapes.as_str());
+    }
+
+    for ban in &config.banned_calls {
+        println!();
+        println!("[[banned_calls]]");
+        println!("call = {:?}", ban.call);
+        if let Some(message) = &ban.message {
+            println!("message = {:?}", message);
+        }
+        println!("severity = \"{}\"", ban.severity.as_str());
+        if ban.within == BanScope::Step {
+            println!("within = \"step\"");
+        }
+    }
+}
+
+/// Whether `call` is a dotted name such as `console.log`
+fn is_call_path(call: &str) -> bool {
+    call.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    })
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
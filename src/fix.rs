@@ -0,0 +1,56 @@
+use similar::TextDiff;
+
+use crate::linter::{LintDiagnostic, TextEdit};
+
+/// Apply the fixes attached to `diagnostics` to `source`.
+///
+/// Returns the fixed source and the number of fixes applied. A fix is skipped
+/// entirely if any of its edits overlap an edit from a fix that was already applied.
+pub fn apply_fixes(source: &str, diagnostics: &[LintDiagnostic]) -> (String, usize) {
+    let mut fixes: Vec<&[TextEdit]> = diagnostics
+        .iter()
+        .filter_map(|d| d.fix.as_ref())
+        .map(|fix| fix.edits.as_slice())
+        .filter(|edits| !edits.is_empty())
+        .collect();
+    fixes.sort_by_key(|edits| edits.iter().map(|e| e.start).min());
+
+    let mut accepted: Vec<&TextEdit> = Vec::new();
+    let mut applied = 0;
+    for edits in fixes {
+        let conflicts = edits
+            .iter()
+            .any(|edit| accepted.iter().any(|other| overlaps(edit, other)));
+        if conflicts {
+            continue;
+        }
+        accepted.extend(edits);
+        applied += 1;
+    }
+
+    // Apply from the end of the file backwards so earlier offsets stay valid
+    accepted.sort_by_key(|edit| (edit.start, edit.end));
+    let mut output = source.to_string();
+    for edit in accepted.iter().rev() {
+        output.replace_range(edit.start as usize..edit.end as usize, &edit.replacement);
+    }
+
+    (output, applied)
+}
+
+fn overlaps(a: &TextEdit, b: &TextEdit) -> bool {
+    // Two insertions at the same offset would have an ambiguous order
+    if a.start == b.start {
+        return true;
+    }
+    a.start < b.end && b.start < a.end
+}
+
+/// Render the difference between `original` and `fixed` as a unified diff
+pub fn unified_diff(path: &str, original: &str, fixed: &str) -> String {
+    TextDiff::from_lines(original, fixed)
+        .unified_diff()
+        .context_radius(3)
+        .header(path, path)
+        .to_string()
+}
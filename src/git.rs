@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files under `root` that differ from `since`, including uncommitted and untracked files.
+/// Deleted files are left out. Returned paths are absolute.
+pub fn changed_files(root: &Path, since: &str) -> Result<HashSet<PathBuf>, String> {
+    let dir = if root.is_file() {
+        root.parent().unwrap_or(Path::new("."))
+    } else {
+        root
+    };
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+
+    // `--relative` restricts the diff to `dir` and prints paths relative to it
+    let diffed = run_git(
+        &dir,
+        &[
+            "diff",
+            "--name-only",
+            "--relative",
+            "--diff-filter=d",
+            since,
+            "--",
+        ],
+    )?;
+    let untracked = run_git(&dir, &["ls-files", "--others", "--exclude-standard"])?;
+
+    Ok(diffed
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| dir.join(line))
+        .collect())
+}
+
+/// The hooks directory of the repository containing `dir`
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf, String> {
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let hooks = run_git(&dir, &["rev-parse", "--git-path", "hooks"])?;
+    // `--git-path` prints a path relative to `dir` unless it's outside of it
+    Ok(dir.join(hooks.trim()))
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        // Print non-ASCII paths verbatim instead of quoting them
+        .args(["-c", "core.quotepath=off"])
+        .args(args)
+        .output()
+        .map_err(|err| format!("Failed to run git: {}", err))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use crate::linter::StepCall;
+use crate::steps::Workflow;
+
+/// Text format for a workflow step graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Mermaid,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mermaid" => Some(GraphFormat::Mermaid),
+            "dot" => Some(GraphFormat::Dot),
+            _ => None,
+        }
+    }
+}
+
+/// Render each workflow's steps, sleeps and waits as a chain in document order
+pub fn render_graph(workflows: &[Workflow], format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Mermaid => render_mermaid(workflows),
+        GraphFormat::Dot => render_dot(workflows),
+    }
+}
+
+fn render_mermaid(workflows: &[Workflow]) -> String {
+    let mut out = String::from("flowchart TD\n");
+    for (i, workflow) in workflows.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    subgraph w{}[\"{}\"]",
+            i,
+            escape_mermaid(&workflow.name)
+        );
+        let steps = ordered_steps(workflow);
+        for (j, step) in steps.iter().enumerate() {
+            let label = escape_mermaid(&label(step));
+            let node = match step.method.as_str() {
+                "sleep" | "sleepUntil" => format!("([\"{}\"])", label),
+                "waitForEvent" => format!("{{{{\"{}\"}}}}", label),
+                _ => format!("[\"{}\"]", label),
+            };
+            let _ = writeln!(out, "        w{}s{}{}", i, j, node);
+        }
+        for j in 1..steps.len() {
+            let _ = writeln!(out, "        w{}s{} --> w{}s{}", i, j - 1, i, j);
+        }
+        out.push_str("    end\n");
+    }
+    out
+}
+
+fn render_dot(workflows: &[Workflow]) -> String {
+    let mut out = String::from("digraph workflows {\n");
+    for (i, workflow) in workflows.iter().enumerate() {
+        let _ = writeln!(out, "    subgraph cluster_{} {{", i);
+        let _ = writeln!(out, "        label=\"{}\";", escape_dot(&workflow.name));
+        let steps = ordered_steps(workflow);
+        for (j, step) in steps.iter().enumerate() {
+            let shape = match step.method.as_str() {
+                "sleep" | "sleepUntil" => "ellipse",
+                "waitForEvent" => "hexagon",
+                _ => "box",
+            };
+            let _ = writeln!(
+                out,
+                "        w{}s{} [label=\"{}\", shape={}];",
+                i,
+                j,
+                escape_dot(&label(step)),
+                shape
+            );
+        }
+        for j in 1..steps.len() {
+            let _ = writeln!(out, "        w{}s{} -> w{}s{};", i, j - 1, i, j);
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn ordered_steps(workflow: &Workflow) -> Vec<&StepCall> {
+    let mut steps: Vec<&StepCall> = workflow.steps.iter().collect();
+    steps.sort_by_key(|step| step.span.start);
+    steps
+}
+
+fn label(step: &StepCall) -> String {
+    match &step.name {
+        Some(name) => format!("{}: {}", step.method, name),
+        None => step.method.clone(),
+    }
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+/// Marker used to recognise hooks written by cashmere
+const HOOK_MARKER: &str = "# Installed by `cashmere hook install`";
+
+/// Lints the staged version of every added, copied, modified or renamed JS/TS file
+/// by piping it into `cashmere --stdin`. Set `CASHMERE` to use a specific binary.
+const PRE_COMMIT_HOOK: &str = r#"#!/bin/sh
+# Installed by `cashmere hook install`
+cashmere="${CASHMERE:-cashmere}"
+status=0
+
+IFS='
+'
+for file in $(git diff --cached --name-only --diff-filter=ACMR -- \
+    '*.js' '*.jsx' '*.ts' '*.tsx' '*.mjs' '*.cjs' '*.mts' '*.cts'); do
+    git show ":$file" | "$cashmere" --stdin --stdin-filename "$file" || status=1
+done
+
+exit $status
+"#;
+
+/// Install the pre-commit hook into the repository containing `dir`
+pub fn install(dir: &Path, force: bool) -> Result<PathBuf, String> {
+    let hooks_dir = git::hooks_dir(dir)?;
+    let hook_path = hooks_dir.join("pre-commit");
+
+    if let Ok(existing) = fs::read_to_string(&hook_path) {
+        if !existing.contains(HOOK_MARKER) && !force {
+            return Err(format!(
+                "{} already exists (use --force to overwrite it)",
+                hook_path.display()
+            ));
+        }
+    }
+
+    fs::create_dir_all(&hooks_dir)
+        .map_err(|err| format!("Failed to create {}: {}", hooks_dir.display(), err))?;
+    fs::write(&hook_path, PRE_COMMIT_HOOK)
+        .map_err(|err| format!("Failed to write {}: {}", hook_path.display(), err))?;
+    make_executable(&hook_path)?;
+
+    Ok(hook_path)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|err| format!("Failed to make {} executable: {}", path.display(), err))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+
+use crate::linter::LintDiagnostic;
+use crate::report::{summary_line, Summary};
+
+/// Number of source lines shown above and below each finding
+const CONTEXT_LINES: usize = 2;
+
+/// Most lines of a multi-line finding (e.g. a `step.do` with a long callback) that are shown
+const MAX_HIT_LINES: usize = 10;
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "of",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>cashmere report</title>
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 2rem; color: #1f2328; }
+.filters { margin-bottom: 1rem; border: 1px solid #d0d7de; border-radius: 6px; }
+.filters label { margin-right: 1rem; }
+details.file { border: 1px solid #d0d7de; border-radius: 6px; margin-bottom: 1rem; padding: 0.5rem 1rem; }
+details.file > summary { cursor: pointer; font-weight: 600; }
+.count { color: #59636e; font-weight: normal; }
+.diagnostic { margin: 1rem 0; }
+.diagnostic .severity { text-transform: uppercase; font-size: 0.8em; font-weight: 600; }
+.error .severity { color: #cf222e; }
+.warning .severity { color: #9a6700; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.5rem 0; overflow-x: auto; }
+.line { display: block; padding: 0 1rem; }
+.line.hit { background: #ffebe9; }
+.ln { display: inline-block; width: 3em; color: #59636e; user-select: none; }
+.kw { color: #cf222e; }
+.str { color: #0a3069; }
+.num { color: #0550ae; }
+.com { color: #59636e; font-style: italic; }
+</style>
+</head>
+<body>
+<h1>cashmere report</h1>
+"#;
+
+const SCRIPT: &str = r#"<script>
+document.querySelectorAll('.filters input').forEach(function (box) {
+  box.addEventListener('change', function () {
+    var hidden = new Set();
+    document.querySelectorAll('.filters input:not(:checked)').forEach(function (b) {
+      hidden.add(b.dataset.rule);
+    });
+    document.querySelectorAll('.diagnostic').forEach(function (d) {
+      d.hidden = hidden.has(d.dataset.rule);
+    });
+    document.querySelectorAll('details.file').forEach(function (f) {
+      f.hidden = !f.querySelector('.diagnostic:not([hidden])');
+    });
+  });
+});
+</script>
+</body>
+</html>
+"#;
+
+/// Render a standalone HTML report with collapsible per-file sections, highlighted
+/// source snippets around each finding and checkboxes to filter by rule
+pub fn render_html(diagnostics: &[LintDiagnostic], summary: &Summary) -> String {
+    let mut html = String::from(HEAD);
+    let _ = writeln!(
+        html,
+        "<p class=\"summary\">{}</p>",
+        escape(&summary_line(summary))
+    );
+    if diagnostics.is_empty() {
+        html.push_str(SCRIPT);
+        return html;
+    }
+
+    let mut rule_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        *rule_counts.entry(&diagnostic.rule).or_default() += 1;
+    }
+    html.push_str("<fieldset class=\"filters\">\n<legend>Rules</legend>\n");
+    for (rule, count) in &rule_counts {
+        let _ = writeln!(
+            html,
+            "<label><input type=\"checkbox\" data-rule=\"{0}\" checked> <code>{0}</code> <span class=\"count\">({1})</span></label>",
+            escape(rule),
+            count
+        );
+    }
+    html.push_str("</fieldset>\n");
+
+    let mut by_file: BTreeMap<&str, Vec<&LintDiagnostic>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        by_file
+            .entry(&diagnostic.file)
+            .or_default()
+            .push(diagnostic);
+    }
+    let mut sources: HashMap<&str, Option<String>> = HashMap::new();
+    for (file, diagnostics) in by_file {
+        let _ = writeln!(
+            html,
+            "<details class=\"file\" open>\n<summary><code>{}</code> <span class=\"count\">({})</span></summary>",
+            escape(file),
+            diagnostics.len()
+        );
+        let source = sources
+            .entry(file)
+            .or_insert_with(|| fs::read_to_string(file).ok());
+        for diagnostic in diagnostics {
+            let severity = diagnostic.severity.as_str();
+            let _ = writeln!(
+                html,
+                "<div class=\"diagnostic {}\" data-rule=\"{}\">",
+                severity,
+                escape(&diagnostic.rule)
+            );
+            let _ = writeln!(
+                html,
+                "<p><span class=\"severity\">{}</span> <code>{}</code> line {}, column {}: {}</p>",
+                severity,
+                escape(&diagnostic.rule),
+                diagnostic.line,
+                diagnostic.column,
+                escape(&diagnostic.message)
+            );
+            if let Some(source) = source.as_deref() {
+                html.push_str(&snippet(source, diagnostic.line, diagnostic.end_line));
+            }
+            html.push_str("</div>\n");
+        }
+        html.push_str("</details>\n");
+    }
+
+    html.push_str(SCRIPT);
+    html
+}
+
+/// The lines from `start_line` to `end_line` (1-based) plus some context, with line
+/// numbers and syntax highlighting
+fn snippet(source: &str, start_line: usize, end_line: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    if start_line == 0 || start_line > lines.len() {
+        return String::new();
+    }
+    let end_line = end_line.clamp(start_line, start_line + MAX_HIT_LINES - 1);
+    let first = start_line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (end_line + CONTEXT_LINES).min(lines.len());
+
+    let mut html = String::from("<pre><code>");
+    for number in first..=last {
+        let class = if (start_line..=end_line).contains(&number) {
+            "line hit"
+        } else {
+            "line"
+        };
+        let _ = write!(
+            html,
+            "<span class=\"{}\"><span class=\"ln\">{}</span>{}</span>",
+            class,
+            number,
+            highlight(lines[number - 1])
+        );
+    }
+    html.push_str("</code></pre>\n");
+    html
+}
+
+/// Highlight keywords, strings, numbers and comments in a single line of JS/TS.
+/// Each line is highlighted on its own, so constructs spanning lines are only
+/// highlighted up to the end of the line they start on.
+fn highlight(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut html = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+
+        let class = if c == '/' && next == Some('/') {
+            i = chars.len();
+            "com"
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i - 1] == '*' && chars[i] == '/' && i > start + 2) {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            "com"
+        } else if matches!(c, '"' | '\'' | '`') {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            "str"
+        } else if c.is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '_'))
+            {
+                i += 1;
+            }
+            "num"
+        } else if c.is_alphabetic() || matches!(c, '_' | '$') {
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$')) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            // Property names like the `do` in `step.do` aren't keywords
+            let is_property = start > 0 && chars[start - 1] == '.';
+            if !is_property && KEYWORDS.contains(&word.as_str()) {
+                "kw"
+            } else {
+                html.push_str(&escape(&word));
+                continue;
+            }
+        } else {
+            i += 1;
+            html.push_str(&escape(&c.to_string()));
+            continue;
+        };
+
+        let text: String = chars[start..i].iter().collect();
+        let _ = write!(html, "<span class=\"{}\">{}</span>", class, escape(&text));
+    }
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cashmere::config::CONFIG_FILE_NAME;
+use cashmere::rules::ALL_RULES;
+use cashmere::wrangler::WRANGLER_CONFIG_FILES;
+
+/// Write a `cashmere.toml` with the recommended settings into `dir`
+pub fn run_init(dir: &Path, force: bool) -> Result<PathBuf, String> {
+    let config_path = dir.join(CONFIG_FILE_NAME);
+    if config_path.exists() && !force {
+        return Err(format!(
+            "{} already exists (use --force to overwrite it)",
+            config_path.display()
+        ));
+    }
+
+    let uses_wrangler = detect_wrangler(dir);
+    fs::write(&config_path, config_template(uses_wrangler))
+        .map_err(|err| format!("Failed to write {}: {}", config_path.display(), err))?;
+
+    if uses_wrangler {
+        println!("Detected a wrangler project, ignoring wrangler build output");
+    }
+    Ok(config_path)
+}
+
+/// A project uses wrangler if it has a wrangler config file or depends on the `wrangler` package
+fn detect_wrangler(dir: &Path) -> bool {
+    if WRANGLER_CONFIG_FILES
+        .iter()
+        .any(|name| dir.join(name).is_file())
+    {
+        return true;
+    }
+
+    let Ok(package_json) = fs::read_to_string(dir.join("package.json")) else {
+        return false;
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&package_json) else {
+        return false;
+    };
+    ["dependencies", "devDependencies"].iter().any(|key| {
+        package
+            .get(key)
+            .and_then(|deps| deps.get("wrangler"))
+            .is_some()
+    })
+}
+
+fn config_template(uses_wrangler: bool) -> String {
+    let mut ignore = vec![r#"    "**/*.d.ts","#];
+    if uses_wrangler {
+        ignore.push(r#"    ".wrangler/**","#);
+    }
+
+    let rules: Vec<String> = ALL_RULES
+        .iter()
+        .map(|rule| {
+            format!(
+                "# {}\n{} = \"{}\"",
+                rule.description,
+                rule.id,
+                rule.default_level().as_str()
+            )
+        })
+        .collect();
+
+    format!(
+        r#"# cashmere configuration
+
+# Glob patterns (relative to this file) for paths that should not be linted.
+# node_modules, .git, dist, build, target, .next and coverage are always skipped.
+ignore = [
+{}
+]
+
+# Rule levels: "error", "warn" or "off"
+[rules]
+{}
+"#,
+        ignore.join("\n"),
+        rules.join("\n")
+    )
+}
@@ -0,0 +1,144 @@
+//! Lint Cloudflare Workflows code from Rust.
+//!
+//! [`lint_source`] lints a single source text; [`collect_files`] and
+//! [`lint_file`] walk and lint a project the same way the `cashmere` CLI does.
+//! Rules implement [`rules::Rule`] and are run from a [`rules::RuleRegistry`],
+//! [`visit`] runs the same traversal for analyses that aren't lint rules, and
+//! [`report`] and [`html`] render diagnostics in the CLI's output formats.
+
+#[cfg(feature = "fs")]
+pub mod baseline;
+#[cfg(feature = "fs")]
+pub mod cache;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod config;
+pub mod fix;
+pub mod graph;
+pub mod html;
+pub mod linter;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod report;
+pub mod rules;
+pub mod stats;
+pub mod steps;
+pub mod testing;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "fs")]
+pub mod wrangler;
+
+#[cfg(feature = "fs")]
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+#[cfg(feature = "fs")]
+use walkdir::WalkDir;
+
+pub use config::Config;
+use linter::run_rules;
+pub use linter::{lint_source, lint_source_structured, LintDiagnostic, LintResult, Severity};
+use rules::RuleRegistry;
+use stats::Stats;
+use steps::ExportedWorkflowClass;
+
+/// Whether `path` has a JavaScript or TypeScript extension
+pub fn is_js_or_ts_file(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => matches!(
+            ext,
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts"
+        ),
+        None => false,
+    }
+}
+
+/// Whether a directory is never linted, such as `node_modules` or build output
+pub fn should_skip_dir(name: &str) -> bool {
+    matches!(
+        name,
+        "node_modules" | ".git" | "dist" | "build" | "target" | ".next" | "coverage"
+    )
+}
+
+/// Lint source text and apply the config, recording parse and lint times in `stats`
+pub fn lint_with_config(
+    source_text: &str,
+    file_path: &str,
+    config: &Config,
+    stats: &Stats,
+) -> Vec<LintDiagnostic> {
+    lint_with_config_and_classes(source_text, file_path, config, stats).0
+}
+
+/// Like [`lint_with_config`], but also returns the `WorkflowEntrypoint` classes the
+/// file exports, for checks across files such as `unique-workflow-classes`
+pub fn lint_with_config_and_classes(
+    source_text: &str,
+    file_path: &str,
+    config: &Config,
+    stats: &Stats,
+) -> (Vec<LintDiagnostic>, Vec<ExportedWorkflowClass>) {
+    let rules = RuleRegistry::builtin().enabled_by(config);
+    let (diagnostics, _, workflow_classes, timings) =
+        run_rules(source_text, file_path, &config.step_methods, &rules);
+    stats.record_lint(timings);
+    (config.apply(diagnostics), workflow_classes)
+}
+
+/// Like [`lint_with_config`], but returns a [`LintResult`] that also carries parse errors
+pub fn lint_structured_with_config(
+    source_text: &str,
+    file_path: &str,
+    config: &Config,
+) -> LintResult {
+    let rules = RuleRegistry::builtin().enabled_by(config);
+    let (diagnostics, parse_errors, _, _) =
+        run_rules(source_text, file_path, &config.step_methods, &rules);
+    LintResult {
+        file: file_path.to_string(),
+        diagnostics: config.apply(diagnostics),
+        parse_errors,
+    }
+}
+
+/// Read and lint a file, returning `None` if it can't be read
+#[cfg(feature = "fs")]
+pub fn lint_file(path: &Path, config: &Config, stats: &Stats) -> Option<Vec<LintDiagnostic>> {
+    let source_text = fs::read_to_string(path).ok()?;
+    Some(lint_with_config(
+        &source_text,
+        path.to_str().unwrap_or(""),
+        config,
+        stats,
+    ))
+}
+
+/// Collect all JS/TS files under `root`, or `root` itself if it is a file,
+/// leaving out anything matched by the config's ignore patterns
+#[cfg(feature = "fs")]
+pub fn collect_files(root: &Path, config: &Config) -> Vec<PathBuf> {
+    if root.is_file() {
+        if is_js_or_ts_file(root) && !config.is_ignored(root) {
+            return vec![root.to_path_buf()];
+        }
+        return Vec::new();
+    }
+
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            if e.file_type().is_dir() && should_skip_dir(e.file_name().to_str().unwrap_or("")) {
+                return false;
+            }
+            !config.is_ignored(e.path())
+        })
+        .filter_map(|e| e.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file() && is_js_or_ts_file(path))
+        .collect()
+}
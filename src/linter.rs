@@ -1,36 +1,147 @@
-use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 use oxc_allocator::Allocator;
 use oxc_ast::ast::*;
+use oxc_diagnostics::OxcDiagnostic;
 use oxc_parser::{Parser as OxcParser, ParserReturn};
 use oxc_span::{GetSpan, SourceType, Span};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::rules::{
+    callee_path, Branch, FunctionScope, Rule, RuleContext, RuleMeta, RuleRegistry, StepUsage,
+};
+use crate::steps::{
+    extends_workflow_entrypoint, module_exports, param_name, takes_workflow_step,
+    workflow_step_params, ExportedWorkflowClass,
+};
+use crate::visit::{VisitContext, Visitor};
+
+/// A single text replacement, expressed in byte offsets into the linted source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: u32,
+    pub end: u32,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn insert(offset: u32, text: &str) -> Self {
+        Self {
+            start: offset,
+            end: offset,
+            replacement: text.to_string(),
+        }
+    }
+}
+
+/// An automatic fix for a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// What the fix does, such as "Add await", used as the title of the editor quick fix
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LintDiagnostic {
     pub file: String,
     pub line: usize,
     pub column: usize,
+    /// Line and column just past the end of the flagged code
+    pub end_line: usize,
+    pub end_column: usize,
+    /// Byte offsets of the flagged code in the linted source
+    pub start: u32,
+    pub end: u32,
     pub message: String,
     pub rule: String,
+    pub severity: Severity,
+    pub fix: Option<Fix>,
+    /// Other locations that help explain the diagnostic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedLocation>,
+}
+
+/// A secondary location attached to a diagnostic, e.g. where an unawaited promise was declared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedLocation {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub start: u32,
+    pub end: u32,
+    pub message: String,
+}
+
+impl RelatedLocation {
+    pub fn new(source: &str, span: Span, message: &str) -> Self {
+        let (line, column) = offset_to_line_col(source, span.start as usize);
+        let (end_line, end_column) = offset_to_line_col(source, span.end as usize);
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+            start: span.start,
+            end: span.end,
+            message: message.to_string(),
+        }
+    }
 }
 
 impl LintDiagnostic {
-    pub fn new(file: &str, source: &str, span: Span, message: &str, rule: &str) -> Self {
+    pub fn new(file: &str, source: &str, span: Span, message: &str, rule: &RuleMeta) -> Self {
         let (line, column) = offset_to_line_col(source, span.start as usize);
+        let (end_line, end_column) = offset_to_line_col(source, span.end as usize);
         Self {
             file: file.to_string(),
             line,
             column,
+            end_line,
+            end_column,
+            start: span.start,
+            end: span.end,
             message: message.to_string(),
-            rule: rule.to_string(),
+            rule: rule.id.to_string(),
+            severity: rule.default_severity,
+            fix: None,
+            related: Vec::new(),
         }
     }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn with_related(mut self, related: RelatedLocation) -> Self {
+        self.related.push(related);
+        self
+    }
 }
 
-fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
+/// Convert a byte offset into a 1-based line and column, counting columns in characters
+pub(crate) fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
     let mut line = 1;
     let mut col = 1;
-    for (i, ch) in source.chars().enumerate() {
+    for (i, ch) in source.char_indices() {
         if i >= offset {
             break;
         }
@@ -44,134 +155,238 @@ fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
     (line, col)
 }
 
-/// Tracks step promise calls within a function scope
-#[derive(Debug, Default)]
-struct StepPromiseTracker {
-    /// Maps variable names to the span of the step call they were assigned from
-    /// e.g., `const p = step.do(...)` maps "p" -> span of step.do call
-    var_to_step_span: HashMap<String, Span>,
-    /// Maps step call spans to their method name (for error reporting)
-    step_span_to_name: HashMap<Span, String>,
-    /// Set of step call spans that have been awaited (directly or via Promise.all/race/etc.)
-    awaited_step_spans: HashSet<Span>,
-    /// Step calls that were not assigned to a variable and not immediately awaited
-    unassigned_unawaited_steps: Vec<(Span, String)>,
+/// A step call found while linting, e.g. for the step inventory shown in editors
+#[derive(Debug, Clone)]
+pub struct StepCall {
+    /// Name of the step object the method was called on, e.g. `step`
+    pub receiver: String,
+    /// Step method that was called, e.g. `do` or `sleep`
+    pub method: String,
+    /// Name of the step, if the first argument is a string literal
+    pub name: Option<String>,
+    pub span: Span,
+    /// Span of the method name, e.g. the `do` in `step.do(...)`
+    pub method_span: Span,
+    /// Span of the string literal holding the step name, including its quotes
+    pub name_span: Option<Span>,
+    /// Spans of the call's arguments
+    pub argument_spans: Vec<Span>,
+    /// Properties set in a `step.do` config object as dotted paths like `retries.limit`.
+    /// Empty if there is no config and `None` if it isn't an object literal.
+    pub config_keys: Option<Vec<String>>,
+}
+
+/// Walks a program, collecting its step calls and running each rule's hooks
+pub struct Linter<'a> {
+    ctx: RuleContext<'a>,
+    rules: Vec<Box<dyn Rule>>,
+    /// Every step call seen, whether or not it was awaited
+    step_calls: Vec<StepCall>,
+    /// The body of a `map` or `flatMap` callback whose results an awaited `Promise.all`
+    /// and friends await
+    awaited_callback: Option<Span>,
+    /// Whether the values returned by the function being linted are awaited
+    returns_awaited: bool,
+    /// Step calls put in an array or object held in a variable, with the variable's name
+    /// and where it is named, e.g. the `pending` of `pending.push(step.do(...))`
+    stored_steps: Vec<(Span, String, Span)>,
+    /// Extra method names treated like `step.do`, from the `step_methods` setting
+    step_methods: &'a [String],
+    /// A visitor called alongside the rules, and the context passed to it
+    visitor: Option<(&'a mut dyn Visitor, &'a VisitContext<'a>)>,
 }
 
-impl StepPromiseTracker {
-    fn new() -> Self {
-        Self::default()
+impl<'a> Linter<'a> {
+    /// A linter that only collects step calls; add rules with [`Linter::with_rules`]
+    pub fn new(source: &'a str, file_path: &'a str) -> Self {
+        Self {
+            ctx: RuleContext::new(source, file_path),
+            rules: Vec::new(),
+            step_calls: Vec::new(),
+            awaited_callback: None,
+            returns_awaited: false,
+            stored_steps: Vec::new(),
+            step_methods: &[],
+            visitor: None,
+        }
     }
 
-    /// Record a step call that was assigned to a variable
-    fn record_assigned_step(&mut self, var_name: &str, span: Span, method_name: String) {
-        self.var_to_step_span.insert(var_name.to_string(), span);
-        self.step_span_to_name.insert(span, method_name);
+    /// Run fresh instances of the rules in `registry`
+    pub fn with_rules(mut self, registry: &RuleRegistry) -> Self {
+        self.rules = registry.instantiate();
+        self
     }
 
-    /// Record a step call that was NOT assigned to a variable and NOT immediately awaited
-    fn record_unassigned_unawaited_step(&mut self, span: Span, method_name: String) {
-        self.unassigned_unawaited_steps.push((span, method_name));
+    /// Also treat calls to these methods on a step object as step calls
+    pub fn with_step_methods(mut self, step_methods: &'a [String]) -> Self {
+        self.step_methods = step_methods;
+        self
     }
 
-    /// Mark a step call as awaited by its span
-    fn mark_awaited_by_span(&mut self, span: Span) {
-        self.awaited_step_spans.insert(span);
+    /// Also call `visitor`'s hooks, passing it `ctx`
+    pub(crate) fn with_visitor(
+        mut self,
+        visitor: &'a mut dyn Visitor,
+        ctx: &'a VisitContext<'a>,
+    ) -> Self {
+        self.visitor = Some((visitor, ctx));
+        self
     }
 
-    /// Mark a step call as awaited by variable name
-    fn mark_awaited_by_var(&mut self, var_name: &str) {
-        if let Some(&span) = self.var_to_step_span.get(var_name) {
-            self.awaited_step_spans.insert(span);
+    fn enter_function(&mut self, is_async: bool, body: Option<Span>) {
+        let scope = FunctionScope { is_async, body };
+        for rule in &mut self.rules {
+            rule.enter_function(&mut self.ctx, scope);
         }
-    }
-
-    /// Get all step calls that were not awaited
-    fn get_unawaited_steps(&self) -> Vec<(Span, String)> {
-        let mut result = Vec::new();
-
-        // Check assigned step calls
-        for (var_name, &span) in &self.var_to_step_span {
-            if !self.awaited_step_spans.contains(&span) {
-                if let Some(method_name) = self.step_span_to_name.get(&span) {
-                    result.push((span, method_name.clone()));
-                } else {
-                    result.push((span, format!("step (var: {})", var_name)));
-                }
-            }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.enter_function(ctx, scope);
         }
-
-        // Add unassigned unawaited steps
-        result.extend(self.unassigned_unawaited_steps.clone());
-
-        result
     }
-}
-
-pub struct Linter<'a> {
-    source: &'a str,
-    file_path: &'a str,
-    diagnostics: Vec<LintDiagnostic>,
-    /// Stack of trackers for nested function scopes
-    tracker_stack: Vec<StepPromiseTracker>,
-}
 
-impl<'a> Linter<'a> {
-    pub fn new(source: &'a str, file_path: &'a str) -> Self {
-        Self {
-            source,
-            file_path,
-            diagnostics: Vec::new(),
-            tracker_stack: Vec::new(),
+    fn leave_function(&mut self) {
+        for rule in &mut self.rules {
+            rule.leave_function(&mut self.ctx);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.leave_function(ctx);
         }
     }
 
-    fn current_tracker(&mut self) -> Option<&mut StepPromiseTracker> {
-        self.tracker_stack.last_mut()
-    }
-
-    fn push_tracker(&mut self) {
-        self.tracker_stack.push(StepPromiseTracker::new());
+    fn check_awaited_vars(&mut self, names: &[&str]) {
+        for rule in &mut self.rules {
+            rule.check_awaited_vars(&mut self.ctx, names);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_awaited_vars(ctx, names);
+        }
     }
 
-    fn pop_tracker_and_report(&mut self) {
-        if let Some(tracker) = self.tracker_stack.pop() {
-            for (span, method_name) in tracker.get_unawaited_steps() {
-                self.diagnostics.push(LintDiagnostic::new(
-                    self.file_path,
-                    self.source,
-                    span,
-                    &format!(
-                        "`{}` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.",
-                        method_name
-                    ),
-                    "await-step",
-                ));
+    /// Ask each rule to fix its diagnostics that don't carry a fix yet
+    fn apply_fixers(&mut self) {
+        let mut diagnostics = std::mem::take(self.ctx.diagnostics_mut());
+        for diagnostic in &mut diagnostics {
+            if diagnostic.fix.is_some() {
+                continue;
+            }
+            if let Some(rule) = self
+                .rules
+                .iter()
+                .find(|rule| rule.meta().fixable && rule.meta().id == diagnostic.rule)
+            {
+                diagnostic.fix = rule.fix(&self.ctx, diagnostic);
             }
         }
+        *self.ctx.diagnostics_mut() = diagnostics;
     }
 
     pub fn lint_program(&mut self, program: &Program) {
-        // Push a tracker for the top-level scope
-        self.push_tracker();
+        self.ctx.local_classes = program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::ClassDeclaration(class) => class.id.as_ref(),
+                Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                    Some(Declaration::ClassDeclaration(class)) => class.id.as_ref(),
+                    _ => None,
+                },
+                Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                    ExportDefaultDeclarationKind::ClassDeclaration(class) => class.id.as_ref(),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(|id| id.name.to_string())
+            .collect();
+        self.ctx.exports = program
+            .body
+            .iter()
+            .flat_map(|stmt| match stmt {
+                Statement::ExportNamedDeclaration(export) => {
+                    let mut names = Vec::new();
+                    if let Some(declaration) = &export.declaration {
+                        declaration_names(declaration, &mut names);
+                    }
+                    // `export { Local as Exported }`, but not re-exports from other modules
+                    if export.source.is_none() {
+                        names.extend(
+                            export
+                                .specifiers
+                                .iter()
+                                .map(|specifier| specifier.local.name().to_string()),
+                        );
+                    }
+                    names
+                }
+                Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                    ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                        class.id.iter().map(|id| id.name.to_string()).collect()
+                    }
+                    ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
+                        func.id.iter().map(|id| id.name.to_string()).collect()
+                    }
+                    ExportDefaultDeclarationKind::Identifier(id) => vec![id.name.to_string()],
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            })
+            .collect();
+        self.ctx.imports = program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::ImportDeclaration(import) => Some(import),
+                _ => None,
+            })
+            .flat_map(|import| {
+                let module = import.source.value.to_string();
+                import.specifiers.iter().flatten().map(move |specifier| {
+                    let imported = match specifier {
+                        ImportDeclarationSpecifier::ImportSpecifier(specifier) => {
+                            specifier.imported.name().to_string()
+                        }
+                        ImportDeclarationSpecifier::ImportDefaultSpecifier(_) => {
+                            "default".to_string()
+                        }
+                        ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => "*".to_string(),
+                    };
+                    (specifier.local().name.to_string(), module.clone(), imported)
+                })
+            })
+            .collect();
+        // The top level is a scope of its own
+        self.enter_function(false, None);
         for stmt in &program.body {
             self.lint_statement(stmt);
         }
-        self.pop_tracker_and_report();
+        self.leave_function();
+        self.apply_fixers();
     }
 
     fn lint_statement(&mut self, stmt: &Statement) {
         match stmt {
             Statement::ExpressionStatement(expr_stmt) => {
+                match &expr_stmt.expression {
+                    Expression::CallExpression(call) => {
+                        self.ctx.statement_call = Some(call.span());
+                    }
+                    Expression::AwaitExpression(await_expr) => {
+                        if let Expression::CallExpression(call) = &await_expr.argument {
+                            self.ctx.awaited_statement_call = Some(call.span());
+                        }
+                    }
+                    _ => {}
+                }
                 self.lint_expression(&expr_stmt.expression, false);
             }
             Statement::VariableDeclaration(decl) => {
                 self.lint_variable_declaration(decl);
             }
             Statement::FunctionDeclaration(func) => {
-                self.lint_function_body(func.body.as_deref());
+                self.declare(func.id.iter().map(|id| id.name.to_string()).collect());
+                self.lint_function(func);
             }
             Statement::ClassDeclaration(class) => {
+                self.declare(class.id.iter().map(|id| id.name.to_string()).collect());
                 self.lint_class(class);
             }
             Statement::BlockStatement(block) => {
@@ -181,43 +396,91 @@ impl<'a> Linter<'a> {
             }
             Statement::IfStatement(if_stmt) => {
                 self.lint_expression(&if_stmt.test, false);
-                self.lint_statement(&if_stmt.consequent);
+                self.in_branch(if_stmt.span, 0, 2, |linter| {
+                    linter.lint_statement(&if_stmt.consequent)
+                });
                 if let Some(alt) = &if_stmt.alternate {
-                    self.lint_statement(alt);
+                    self.in_branch(if_stmt.span, 1, 2, |linter| linter.lint_statement(alt));
                 }
             }
             Statement::WhileStatement(while_stmt) => {
+                self.check_loop(stmt);
                 self.lint_expression(&while_stmt.test, false);
-                self.lint_statement(&while_stmt.body);
+                self.in_branch(while_stmt.span, 0, 2, |linter| {
+                    linter.lint_statement(&while_stmt.body)
+                });
+            }
+            Statement::DoWhileStatement(do_while) => {
+                self.check_loop(stmt);
+                self.lint_statement(&do_while.body);
+                self.lint_expression(&do_while.test, false);
             }
             Statement::ForStatement(for_stmt) => {
-                if let Some(init) = &for_stmt.init {
-                    if let ForStatementInit::VariableDeclaration(decl) = init {
-                        self.lint_variable_declaration(decl);
-                    }
+                self.check_loop(stmt);
+                let mut variables = Vec::new();
+                if let Some(ForStatementInit::VariableDeclaration(decl)) = &for_stmt.init {
+                    self.lint_variable_declaration(decl);
+                    declared_names(decl, &mut variables);
                 }
-                self.lint_statement(&for_stmt.body);
+                self.with_loop(variables, for_iterations(for_stmt), |linter| {
+                    linter.in_branch(for_stmt.span, 0, 2, |linter| {
+                        linter.lint_statement(&for_stmt.body)
+                    })
+                });
             }
             Statement::ForInStatement(for_in) => {
-                self.lint_statement(&for_in.body);
+                self.check_loop(stmt);
+                let mut variables = Vec::new();
+                if let ForStatementLeft::VariableDeclaration(decl) = &for_in.left {
+                    declared_names(decl, &mut variables);
+                }
+                self.declare(variables.clone());
+                self.with_loop(variables, None, |linter| {
+                    linter.in_branch(for_in.span, 0, 2, |linter| {
+                        linter.lint_statement(&for_in.body)
+                    })
+                });
             }
             Statement::ForOfStatement(for_of) => {
+                self.check_loop(stmt);
                 self.lint_expression(&for_of.right, false);
-                self.lint_statement(&for_of.body);
+                let mut variables = Vec::new();
+                if let ForStatementLeft::VariableDeclaration(decl) = &for_of.left {
+                    declared_names(decl, &mut variables);
+                }
+                self.declare(variables.clone());
+                let iterations = array_literal_len(&for_of.right);
+                self.with_loop(variables, iterations, |linter| {
+                    linter.in_branch(for_of.span, 0, 2, |linter| {
+                        linter.lint_statement(&for_of.body)
+                    })
+                });
+            }
+            Statement::ThrowStatement(throw) => {
+                self.check_throw(throw);
+                self.lint_expression(&throw.argument, false);
             }
             Statement::ReturnStatement(ret) => {
                 if let Some(arg) = &ret.argument {
-                    self.lint_expression(arg, false);
+                    self.lint_expression(arg, self.returns_awaited);
                 }
             }
             Statement::TryStatement(try_stmt) => {
+                self.check_try(try_stmt);
                 for s in &try_stmt.block.body {
                     self.lint_statement(s);
                 }
                 if let Some(handler) = &try_stmt.handler {
-                    for s in &handler.body.body {
-                        self.lint_statement(s);
+                    if let Some(param) = &handler.param {
+                        let mut names = Vec::new();
+                        pattern_names(&param.pattern, &mut names);
+                        self.declare(names);
                     }
+                    self.in_branch(try_stmt.span, 1, 2, |linter| {
+                        for s in &handler.body.body {
+                            linter.lint_statement(s);
+                        }
+                    });
                 }
                 if let Some(finalizer) = &try_stmt.finalizer {
                     for s in &finalizer.body {
@@ -227,15 +490,32 @@ impl<'a> Linter<'a> {
             }
             Statement::SwitchStatement(switch) => {
                 self.lint_expression(&switch.discriminant, false);
+                // Cases without statements fall through into the next one, so they share
+                // its arm
+                let mut arms = switch
+                    .cases
+                    .iter()
+                    .filter(|case| !case.consequent.is_empty())
+                    .count() as u32;
+                if switch.cases.iter().all(|case| case.test.is_some()) {
+                    arms += 1;
+                }
+                let mut arm = 0;
                 for case in &switch.cases {
-                    for s in &case.consequent {
-                        self.lint_statement(s);
+                    if case.consequent.is_empty() {
+                        continue;
                     }
+                    self.in_branch(switch.span, arm, arms, |linter| {
+                        for s in &case.consequent {
+                            linter.lint_statement(s);
+                        }
+                    });
+                    arm += 1;
                 }
             }
             Statement::ExportDefaultDeclaration(export) => match &export.declaration {
                 ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
-                    self.lint_function_body(func.body.as_deref());
+                    self.lint_function(func);
                 }
                 ExportDefaultDeclarationKind::ClassDeclaration(class) => {
                     self.lint_class(class);
@@ -251,6 +531,9 @@ impl<'a> Linter<'a> {
                     self.lint_declaration(decl);
                 }
             }
+            Statement::ImportDeclaration(import) => {
+                self.check_import(import);
+            }
             _ => {}
         }
     }
@@ -258,7 +541,7 @@ impl<'a> Linter<'a> {
     fn lint_declaration(&mut self, decl: &Declaration) {
         match decl {
             Declaration::FunctionDeclaration(func) => {
-                self.lint_function_body(func.body.as_deref());
+                self.lint_function(func);
             }
             Declaration::ClassDeclaration(class) => {
                 self.lint_class(class);
@@ -271,21 +554,52 @@ impl<'a> Linter<'a> {
     }
 
     fn lint_variable_declaration(&mut self, decl: &VariableDeclaration) {
+        let mut names = Vec::new();
+        declared_names(decl, &mut names);
+        self.declare(names);
         for declarator in &decl.declarations {
+            self.check_variable(declarator);
+            if let (BindingPattern::BindingIdentifier(id), Some(init)) =
+                (&declarator.id, &declarator.init)
+            {
+                self.record_step_alias(&id.name, init);
+                // `const pending = [step.do(...)]` and `{ a: step.do(...) }`
+                match init.get_inner_expression() {
+                    Expression::ArrayExpression(arr) => {
+                        for elem in &arr.elements {
+                            if let Some(expr) = elem.as_expression() {
+                                self.store_step_call(expr, &id.name, id.span);
+                            }
+                        }
+                    }
+                    Expression::ObjectExpression(obj) => {
+                        for prop in &obj.properties {
+                            if let ObjectPropertyKind::ObjectProperty(prop) = prop {
+                                self.store_step_call(&prop.value, &id.name, id.span);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
             if let Some(init) = &declarator.init {
                 // Check if initializer is a step call
                 if let Expression::CallExpression(call) = init {
                     if self.is_step_method_call(call) {
-                        // Get the variable name being assigned to
-                        if let BindingPattern::BindingIdentifier(id) = &declarator.id {
-                            let var_name = id.name.as_str();
-                            let method_name = self.get_step_method_name(call);
-                            if let Some(tracker) = self.current_tracker() {
-                                tracker.record_assigned_step(var_name, call.span(), method_name);
-                            }
-                        }
+                        let usage = match &declarator.id {
+                            BindingPattern::BindingIdentifier(id) => StepUsage::Assigned {
+                                name: Some(id.name.to_string()),
+                                binding: id.span,
+                            },
+                            pattern => StepUsage::Assigned {
+                                name: None,
+                                binding: pattern.span(),
+                            },
+                        };
+                        self.check_call(call);
+                        self.record_step_call(call, usage);
                         // Still lint the call's arguments
-                        self.lint_call_arguments(call);
+                        self.lint_step_call_arguments(call);
                         continue;
                     }
                 }
@@ -296,10 +610,27 @@ impl<'a> Linter<'a> {
     }
 
     fn lint_class(&mut self, class: &Class) {
+        self.check_class(class);
+        let is_workflow = extends_workflow_entrypoint(class);
         for element in &class.body.body {
             match element {
                 ClassElement::MethodDefinition(method) => {
-                    self.lint_function_body(method.value.body.as_deref());
+                    let is_run = is_workflow && method.key.static_name().as_deref() == Some("run");
+                    // `run(event, step)` gets its step as the second parameter, whatever its name
+                    let step_params = is_run.then(|| {
+                        method
+                            .value
+                            .params
+                            .items
+                            .get(1)
+                            .and_then(param_name)
+                            .into_iter()
+                            .collect()
+                    });
+                    self.in_workflow_body(step_params, |linter| {
+                        linter
+                            .lint_function_body(method.value.body.as_deref(), method.value.r#async)
+                    });
                 }
                 ClassElement::PropertyDefinition(prop) => {
                     if let Some(value) = &prop.value {
@@ -316,24 +647,104 @@ impl<'a> Linter<'a> {
         }
     }
 
-    fn lint_function_body(&mut self, body: Option<&FunctionBody>) {
+    /// Run `lint` with `variables` counted as loop variables, inside a loop that runs
+    /// `iterations` times if that is known
+    fn with_loop(
+        &mut self,
+        variables: Vec<String>,
+        iterations: Option<u64>,
+        lint: impl FnOnce(&mut Self),
+    ) {
+        let len = self.ctx.loop_variables.len();
+        let loops = self.ctx.loop_iterations.len();
+        self.ctx.loop_variables.extend(variables);
+        self.ctx.loop_iterations.extend(iterations);
+        lint(self);
+        self.ctx.loop_variables.truncate(len);
+        self.ctx.loop_iterations.truncate(loops);
+    }
+
+    /// Run `lint` inside arm `arm` of the `arms` arms of the conditional at `construct`
+    fn in_branch(&mut self, construct: Span, arm: u32, arms: u32, lint: impl FnOnce(&mut Self)) {
+        self.ctx.branches.push(Branch {
+            construct,
+            arm,
+            arms,
+        });
+        lint(self);
+        self.ctx.branches.pop();
+    }
+
+    /// Run `lint`, counting the code it walks as inside a workflow if it has `step_params`,
+    /// and those parameters as step objects whatever their names
+    fn in_workflow_body(&mut self, step_params: Option<Vec<String>>, lint: impl FnOnce(&mut Self)) {
+        let is_workflow = step_params.is_some();
+        let aliases = self.ctx.step_aliases.len();
+        self.ctx
+            .step_aliases
+            .extend(step_params.into_iter().flatten());
+        self.ctx.workflow_depth += usize::from(is_workflow);
+        lint(self);
+        self.ctx.workflow_depth -= usize::from(is_workflow);
+        self.ctx.step_aliases.truncate(aliases);
+    }
+
+    /// Count `names` as declared inside the enclosing step callback, if there is one
+    fn declare(&mut self, names: Vec<String>) {
+        if self.ctx.in_step_callback() {
+            self.ctx.step_callback_locals.extend(names);
+        }
+    }
+
+    fn lint_function(&mut self, func: &Function) {
+        self.declare(param_names(&func.params));
+        let step_params =
+            takes_workflow_step(&func.params).then(|| workflow_step_params(&func.params));
+        self.in_workflow_body(step_params, |linter| {
+            linter.lint_function_body(func.body.as_deref(), func.r#async)
+        });
+    }
+
+    fn lint_function_body(&mut self, body: Option<&FunctionBody>, is_async: bool) {
         if let Some(body) = body {
-            self.push_tracker();
+            let returns_awaited = std::mem::replace(
+                &mut self.returns_awaited,
+                self.awaited_callback == Some(body.span),
+            );
+            self.enter_function(is_async, Some(body.span));
             for stmt in &body.statements {
                 self.lint_statement(stmt);
             }
-            self.pop_tracker_and_report();
+            self.leave_function();
+            self.returns_awaited = returns_awaited;
         }
     }
 
     /// Helper to lint only the arguments of a call expression
     fn lint_call_arguments(&mut self, call: &CallExpression) {
         for arg in &call.arguments {
-            if let Argument::SpreadElement(spread) = arg {
-                self.lint_expression(&spread.argument, false);
-            } else if let Some(expr) = arg.as_expression() {
-                self.lint_expression(expr, false);
-            }
+            self.lint_argument(arg);
+        }
+    }
+
+    /// Lint the arguments of a step call, tracking when the linter is inside its callback
+    fn lint_step_call_arguments(&mut self, call: &CallExpression) {
+        for (index, arg) in call.arguments.iter().enumerate() {
+            // The callback is the last argument, after the step name and any config
+            let is_callback = index > 0 && index == call.arguments.len() - 1;
+            let locals = self.ctx.step_callback_locals.len();
+            self.ctx.step_callback_depth += usize::from(is_callback);
+            self.lint_argument(arg);
+            self.ctx.step_callback_depth -= usize::from(is_callback);
+            self.ctx.step_callback_locals.truncate(locals);
+        }
+    }
+
+    fn lint_argument(&mut self, arg: &Argument) {
+        if let Argument::SpreadElement(spread) = arg {
+            self.lint_expression(&spread.argument, false);
+        } else if let Some(expr) = arg.as_expression() {
+            self.lint_expression(expr, false);
         }
     }
 
@@ -350,14 +761,86 @@ impl<'a> Linter<'a> {
         false
     }
 
+    /// Remember `value` as stored in the variable `container`, named at `binding`, if it
+    /// is a step call
+    fn store_step_call(&mut self, value: &Expression, container: &str, binding: Span) {
+        if let Expression::CallExpression(call) = value.get_inner_expression() {
+            if self.is_step_method_call(call) {
+                self.stored_steps
+                    .push((call.span, container.to_string(), binding));
+            }
+        }
+    }
+
+    /// The variable holding stored step promises that `expr` reads one of, as in
+    /// `byId.a` or `results[0]`
+    fn stored_container<'b>(&self, expr: &'b Expression) -> Option<&'b str> {
+        let object = match expr.get_inner_expression() {
+            Expression::StaticMemberExpression(member) => &member.object,
+            Expression::ComputedMemberExpression(member) => &member.object,
+            _ => return None,
+        };
+        let Expression::Identifier(id) = object else {
+            return None;
+        };
+        self.stored_steps
+            .iter()
+            .any(|(_, container, _)| id.name == *container)
+            .then_some(id.name.as_str())
+    }
+
+    /// Variables holding stored step promises that a `Promise.all` and friends call
+    /// combines: `Promise.all(pending)`, `Promise.all([...pending])`,
+    /// `Promise.all([byId.a, byId.b])` or `Promise.all(Object.values(byId))`
+    fn combined_containers<'b>(&self, call: &'b CallExpression) -> Vec<&'b str> {
+        let mut names = Vec::new();
+        match call
+            .arguments
+            .first()
+            .and_then(Argument::as_expression)
+            .map(Expression::get_inner_expression)
+        {
+            Some(Expression::Identifier(id)) => names.push(id.name.as_str()),
+            Some(Expression::ArrayExpression(arr)) => {
+                for elem in &arr.elements {
+                    match elem {
+                        ArrayExpressionElement::SpreadElement(spread) => {
+                            if let Expression::Identifier(id) = &spread.argument {
+                                names.push(id.name.as_str());
+                            }
+                        }
+                        _ => names.extend(
+                            elem.as_expression()
+                                .and_then(|expr| self.stored_container(expr)),
+                        ),
+                    }
+                }
+            }
+            Some(Expression::CallExpression(values))
+                if callee_path(&values.callee).as_deref() == Some("Object.values") =>
+            {
+                if let Some(Expression::Identifier(id)) =
+                    values.arguments.first().and_then(Argument::as_expression)
+                {
+                    names.push(id.name.as_str());
+                }
+            }
+            _ => {}
+        }
+        names.retain(|name| {
+            self.stored_steps
+                .iter()
+                .any(|(_, container, _)| container == name)
+        });
+        names
+    }
+
     /// Extract identifier names from an array expression (for Promise.all([a, b, c]))
-    fn extract_identifiers_from_array(&self, arr: &ArrayExpression) -> Vec<String> {
+    fn extract_identifiers_from_array<'b>(&self, arr: &'b ArrayExpression) -> Vec<&'b str> {
         let mut identifiers = Vec::new();
         for elem in &arr.elements {
-            if let Some(expr) = elem.as_expression() {
-                if let Expression::Identifier(id) = expr {
-                    identifiers.push(id.name.to_string());
-                }
+            if let Some(Expression::Identifier(id)) = elem.as_expression() {
+                identifiers.push(id.name.as_str());
             }
         }
         identifiers
@@ -369,9 +852,11 @@ impl<'a> Linter<'a> {
 
         // Case 1: await identifier (e.g., await p)
         if let Expression::Identifier(id) = arg {
-            if let Some(tracker) = self.current_tracker() {
-                tracker.mark_awaited_by_var(id.name.as_str());
-            }
+            self.check_awaited_vars(&[id.name.as_str()]);
+        }
+        // `await byId.a` awaits a promise stored in `byId`
+        if let Some(container) = self.stored_container(arg) {
+            self.check_awaited_vars(&[container]);
         }
 
         // Case 2: await Promise.all([...]) / Promise.race([...]) / etc.
@@ -381,11 +866,7 @@ impl<'a> Linter<'a> {
                 if let Some(first_arg) = call.arguments.first() {
                     if let Some(Expression::ArrayExpression(arr)) = first_arg.as_expression() {
                         let identifiers = self.extract_identifiers_from_array(arr);
-                        if let Some(tracker) = self.current_tracker() {
-                            for var_name in identifiers {
-                                tracker.mark_awaited_by_var(&var_name);
-                            }
-                        }
+                        self.check_awaited_vars(&identifiers);
                     }
                 }
             }
@@ -401,50 +882,94 @@ impl<'a> Linter<'a> {
                 self.lint_expression(&await_expr.argument, true);
             }
             Expression::CallExpression(call) => {
+                self.check_call(call);
                 // Check if this is a step.do or step.sleep call
                 if self.is_step_method_call(call) {
-                    let method_name = self.get_step_method_name(call);
-                    if is_awaited {
-                        // Immediately awaited - mark as awaited by span
-                        if let Some(tracker) = self.current_tracker() {
-                            tracker.mark_awaited_by_span(call.span());
+                    let stored = self
+                        .stored_steps
+                        .iter()
+                        .find(|(span, _, _)| *span == call.span);
+                    let usage = if is_awaited {
+                        StepUsage::Awaited
+                    } else if let Some((_, container, binding)) = stored {
+                        StepUsage::Stored {
+                            container: container.clone(),
+                            binding: *binding,
                         }
+                    } else if self.ctx.is_statement_call(call.span()) {
+                        StepUsage::Statement
                     } else {
-                        // Not immediately awaited and not in a variable assignment
-                        // Record as unassigned unawaited step
-                        if let Some(tracker) = self.current_tracker() {
-                            tracker.record_unassigned_unawaited_step(call.span(), method_name);
-                        }
-                    }
+                        StepUsage::Unawaited
+                    };
+                    self.record_step_call(call, usage);
                     // Still lint the call's arguments
-                    self.lint_call_arguments(call);
+                    self.lint_step_call_arguments(call);
                     return;
                 }
 
+                // `pending.push(step.do(...))` stores the step promise in `pending`
+                if let Expression::StaticMemberExpression(member) = &call.callee {
+                    if let Expression::Identifier(id) = &member.object {
+                        if matches!(member.property.name.as_str(), "push" | "unshift") {
+                            for arg in &call.arguments {
+                                if let Some(expr) = arg.as_expression() {
+                                    self.store_step_call(expr, &id.name, id.span);
+                                }
+                            }
+                        }
+                    }
+                }
                 // Special case: if this is an awaited Promise.all/race/etc, treat array contents as awaited
                 if is_awaited && self.is_promise_combinator_call(call) {
+                    // So is an array or object of step promises passed to it
+                    let containers = self.combined_containers(call);
+                    if !containers.is_empty() {
+                        self.check_awaited_vars(&containers);
+                    }
                     self.lint_expression(&call.callee, false);
                     // Lint array argument with is_awaited=true so step calls inside are treated as awaited
                     if let Some(first_arg) = call.arguments.first() {
                         if let Some(expr) = first_arg.as_expression() {
+                            // `Promise.all(items.map(...))` awaits what the callback returns
+                            self.awaited_callback = mapped_callback(expr);
                             self.lint_expression(expr, true);
                         }
                     }
                 } else {
                     // Lint the callee and arguments normally
                     self.lint_expression(&call.callee, false);
-                    self.lint_call_arguments(call);
+                    let variables = iteration_callback_params(call);
+                    let iterations = iteration_callback_count(call);
+                    self.with_loop(variables, iterations, |linter| {
+                        linter.lint_call_arguments(call)
+                    });
                 }
             }
             Expression::ArrowFunctionExpression(arrow) => {
-                self.push_tracker();
-                for stmt in &arrow.body.statements {
-                    self.lint_statement(stmt);
-                }
-                self.pop_tracker_and_report();
+                self.declare(param_names(&arrow.params));
+                let returns_awaited = std::mem::replace(
+                    &mut self.returns_awaited,
+                    self.awaited_callback == Some(arrow.body.span),
+                );
+                let step_params =
+                    takes_workflow_step(&arrow.params).then(|| workflow_step_params(&arrow.params));
+                self.in_workflow_body(step_params, |linter| {
+                    linter.enter_function(arrow.r#async, Some(arrow.body.span));
+                    match arrow.get_expression() {
+                        // The body of `(item) => step.do(...)` is returned
+                        Some(expr) if linter.returns_awaited => linter.lint_expression(expr, true),
+                        _ => {
+                            for stmt in &arrow.body.statements {
+                                linter.lint_statement(stmt);
+                            }
+                        }
+                    }
+                    linter.leave_function();
+                });
+                self.returns_awaited = returns_awaited;
             }
             Expression::FunctionExpression(func) => {
-                self.lint_function_body(func.body.as_deref());
+                self.lint_function(func);
             }
             Expression::ClassExpression(class) => {
                 self.lint_class(class);
@@ -478,8 +1003,12 @@ impl<'a> Linter<'a> {
             }
             Expression::ConditionalExpression(cond) => {
                 self.lint_expression(&cond.test, false);
-                self.lint_expression(&cond.consequent, is_awaited);
-                self.lint_expression(&cond.alternate, is_awaited);
+                self.in_branch(cond.span, 0, 2, |linter| {
+                    linter.lint_expression(&cond.consequent, is_awaited)
+                });
+                self.in_branch(cond.span, 1, 2, |linter| {
+                    linter.lint_expression(&cond.alternate, is_awaited)
+                });
             }
             Expression::BinaryExpression(bin) => {
                 self.lint_expression(&bin.left, false);
@@ -487,11 +1016,34 @@ impl<'a> Linter<'a> {
             }
             Expression::LogicalExpression(log) => {
                 self.lint_expression(&log.left, false);
-                self.lint_expression(&log.right, false);
+                self.in_branch(log.span, 0, 2, |linter| {
+                    linter.lint_expression(&log.right, false)
+                });
             }
             Expression::AssignmentExpression(assign) => {
+                self.check_assignment(assign);
+                match &assign.left {
+                    AssignmentTarget::AssignmentTargetIdentifier(id) => {
+                        self.record_step_alias(&id.name, &assign.right);
+                    }
+                    // `results[i] = step.do(...)` and `byId.a = step.do(...)`
+                    AssignmentTarget::StaticMemberExpression(member) => {
+                        if let Expression::Identifier(id) = &member.object {
+                            self.store_step_call(&assign.right, &id.name, id.span);
+                        }
+                    }
+                    AssignmentTarget::ComputedMemberExpression(member) => {
+                        if let Expression::Identifier(id) = &member.object {
+                            self.store_step_call(&assign.right, &id.name, id.span);
+                        }
+                    }
+                    _ => {}
+                }
                 self.lint_expression(&assign.right, false);
             }
+            Expression::UpdateExpression(update) => {
+                self.check_update(update);
+            }
             Expression::SequenceExpression(seq) => {
                 for (i, expr) in seq.expressions.iter().enumerate() {
                     // Only the last expression in a sequence can be awaited
@@ -506,6 +1058,7 @@ impl<'a> Linter<'a> {
                 self.lint_expression(&unary.argument, false);
             }
             Expression::NewExpression(new_expr) => {
+                self.check_new(new_expr);
                 self.lint_expression(&new_expr.callee, false);
                 for arg in &new_expr.arguments {
                     if let Some(expr) = arg.as_expression() {
@@ -514,6 +1067,7 @@ impl<'a> Linter<'a> {
                 }
             }
             Expression::StaticMemberExpression(member) => {
+                self.check_member(member);
                 self.lint_expression(&member.object, false);
             }
             Expression::ComputedMemberExpression(member) => {
@@ -544,40 +1098,488 @@ impl<'a> Linter<'a> {
     fn is_step_method_call(&self, call: &CallExpression) -> bool {
         if let Expression::StaticMemberExpression(member) = &call.callee {
             let method_name = member.property.name.as_str();
-            if matches!(method_name, "do" | "sleep" | "waitForEvent" | "sleepUntil") {
-                // Check if the object is named "step" (or ends with step-like pattern)
+            if matches!(method_name, "do" | "sleep" | "waitForEvent" | "sleepUntil")
+                || self.step_methods.iter().any(|m| m == method_name)
+            {
                 if let Expression::Identifier(id) = &member.object {
-                    let name = id.name.as_str().to_lowercase();
-                    return name == "step" || name.ends_with("step");
+                    return self.ctx.is_step_object(&id.name);
                 }
             }
         }
         false
     }
 
-    /// Get the method name for error reporting (e.g., "step.do" or "step.sleep")
-    fn get_step_method_name(&self, call: &CallExpression) -> String {
-        if let Expression::StaticMemberExpression(member) = &call.callee {
-            let method_name = member.property.name.as_str();
-            if let Expression::Identifier(id) = &member.object {
-                return format!("{}.{}", id.name, method_name);
-            }
-            return format!("step.{}", method_name);
+    /// Remember `name` as a step object when `value` is one, as in `const s = step`
+    fn record_step_alias(&mut self, name: &str, value: &Expression) {
+        let Expression::Identifier(id) = value.get_inner_expression() else {
+            return;
+        };
+        if self.ctx.is_step_object(&id.name) && !self.ctx.is_step_object(name) {
+            self.ctx.step_aliases.push(name.to_string());
+        }
+    }
+
+    fn check_call(&mut self, call: &CallExpression) {
+        for rule in &mut self.rules {
+            rule.check_call(&mut self.ctx, call);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_call(ctx, call);
+        }
+    }
+
+    fn check_new(&mut self, new_expr: &NewExpression) {
+        for rule in &mut self.rules {
+            rule.check_new(&mut self.ctx, new_expr);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_new(ctx, new_expr);
         }
-        "step.do".to_string()
+    }
+
+    fn check_throw(&mut self, throw: &ThrowStatement) {
+        for rule in &mut self.rules {
+            rule.check_throw(&mut self.ctx, throw);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_throw(ctx, throw);
+        }
+    }
+
+    fn check_loop(&mut self, loop_stmt: &Statement) {
+        for rule in &mut self.rules {
+            rule.check_loop(&mut self.ctx, loop_stmt);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_loop(ctx, loop_stmt);
+        }
+    }
+
+    fn check_try(&mut self, try_stmt: &TryStatement) {
+        for rule in &mut self.rules {
+            rule.check_try(&mut self.ctx, try_stmt);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_try(ctx, try_stmt);
+        }
+    }
+
+    fn check_assignment(&mut self, assignment: &AssignmentExpression) {
+        for rule in &mut self.rules {
+            rule.check_assignment(&mut self.ctx, assignment);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_assignment(ctx, assignment);
+        }
+    }
+
+    fn check_update(&mut self, update: &UpdateExpression) {
+        for rule in &mut self.rules {
+            rule.check_update(&mut self.ctx, update);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_update(ctx, update);
+        }
+    }
+
+    fn check_class(&mut self, class: &Class) {
+        for rule in &mut self.rules {
+            rule.check_class(&mut self.ctx, class);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_class(ctx, class);
+        }
+    }
+
+    fn check_member(&mut self, member: &StaticMemberExpression) {
+        for rule in &mut self.rules {
+            rule.check_member(&mut self.ctx, member);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_member(ctx, member);
+        }
+    }
+
+    fn check_import(&mut self, import: &ImportDeclaration) {
+        for rule in &mut self.rules {
+            rule.check_import(&mut self.ctx, import);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_import(ctx, import);
+        }
+    }
+
+    fn check_variable(&mut self, declarator: &VariableDeclarator) {
+        for rule in &mut self.rules {
+            rule.check_variable(&mut self.ctx, declarator);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_variable(ctx, declarator);
+        }
+    }
+
+    /// Record a step call and pass it to the rules
+    fn record_step_call(&mut self, call: &CallExpression, usage: StepUsage) {
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            return;
+        };
+        let Expression::Identifier(receiver) = &member.object else {
+            return;
+        };
+        let (name, name_span) = match call.arguments.first() {
+            Some(Argument::StringLiteral(lit)) => (Some(lit.value.to_string()), Some(lit.span)),
+            _ => (None, None),
+        };
+        let step_call = StepCall {
+            receiver: receiver.name.to_string(),
+            method: member.property.name.to_string(),
+            name,
+            span: call.span(),
+            method_span: member.property.span,
+            name_span,
+            argument_spans: call.arguments.iter().map(|arg| arg.span()).collect(),
+            config_keys: match (member.property.name.as_str(), call.arguments.len()) {
+                ("do", 2) => Some(Vec::new()),
+                ("do", 3) => match &call.arguments[1] {
+                    Argument::ObjectExpression(config) => Some(object_keys(config, "")),
+                    _ => None,
+                },
+                _ => None,
+            },
+        };
+        for rule in &mut self.rules {
+            rule.check_step_call(&mut self.ctx, &step_call, call, &usage);
+        }
+        if let Some((visitor, ctx)) = &mut self.visitor {
+            visitor.visit_step_call(ctx, &step_call, &usage);
+        }
+        self.step_calls.push(step_call);
     }
 
     pub fn into_diagnostics(self) -> Vec<LintDiagnostic> {
-        self.diagnostics
+        self.ctx.into_diagnostics()
+    }
+
+    pub fn into_step_calls(self) -> Vec<StepCall> {
+        self.step_calls
+    }
+}
+
+/// The names bound by a variable declaration, including destructured ones
+fn declared_names(decl: &VariableDeclaration, names: &mut Vec<String>) {
+    for declarator in &decl.declarations {
+        pattern_names(&declarator.id, names);
+    }
+}
+
+/// The names bound by a function's parameters
+fn param_names(params: &FormalParameters) -> Vec<String> {
+    let mut names = Vec::new();
+    for param in &params.items {
+        pattern_names(&param.pattern, &mut names);
+    }
+    if let Some(rest) = &params.rest {
+        pattern_names(&rest.rest.argument, &mut names);
+    }
+    names
+}
+
+/// The names `declaration` binds, such as the class name of `class W {}`
+fn declaration_names(declaration: &Declaration, names: &mut Vec<String>) {
+    match declaration {
+        Declaration::VariableDeclaration(decl) => declared_names(decl, names),
+        Declaration::FunctionDeclaration(func) => {
+            names.extend(func.id.iter().map(|id| id.name.to_string()))
+        }
+        Declaration::ClassDeclaration(class) => {
+            names.extend(class.id.iter().map(|id| id.name.to_string()))
+        }
+        _ => {}
+    }
+}
+
+fn pattern_names(pattern: &BindingPattern, names: &mut Vec<String>) {
+    match pattern {
+        BindingPattern::BindingIdentifier(id) => names.push(id.name.to_string()),
+        BindingPattern::ObjectPattern(object) => {
+            for property in &object.properties {
+                pattern_names(&property.value, names);
+            }
+            if let Some(rest) = &object.rest {
+                pattern_names(&rest.argument, names);
+            }
+        }
+        BindingPattern::ArrayPattern(array) => {
+            for element in array.elements.iter().flatten() {
+                pattern_names(element, names);
+            }
+            if let Some(rest) = &array.rest {
+                pattern_names(&rest.argument, names);
+            }
+        }
+        BindingPattern::AssignmentPattern(assignment) => pattern_names(&assignment.left, names),
+    }
+}
+
+/// The parameters of a callback passed to `map`, `flatMap` or `forEach`, which are
+/// bound once per element like loop variables
+fn iteration_callback_params(call: &CallExpression) -> Vec<String> {
+    let mut names = Vec::new();
+    let Expression::StaticMemberExpression(member) = &call.callee else {
+        return names;
+    };
+    if !matches!(member.property.name.as_str(), "map" | "flatMap" | "forEach") {
+        return names;
+    }
+    let params = match call.arguments.first() {
+        Some(Argument::ArrowFunctionExpression(arrow)) => &arrow.params,
+        Some(Argument::FunctionExpression(func)) => &func.params,
+        _ => return names,
+    };
+    for param in &params.items {
+        pattern_names(&param.pattern, &mut names);
+    }
+    names
+}
+
+/// The span of the body of the function passed to `map` or `flatMap` in `expr`, if it
+/// is such a call
+fn mapped_callback(expr: &Expression) -> Option<Span> {
+    let Expression::CallExpression(call) = expr else {
+        return None;
+    };
+    let Expression::StaticMemberExpression(member) = &call.callee else {
+        return None;
+    };
+    if !matches!(member.property.name.as_str(), "map" | "flatMap") {
+        return None;
+    }
+    match call.arguments.first()? {
+        Argument::ArrowFunctionExpression(arrow) => Some(arrow.body.span),
+        Argument::FunctionExpression(func) => func.body.as_ref().map(|body| body.span),
+        _ => None,
     }
 }
 
-pub fn lint_source(source: &str, file_path: &str) -> Vec<LintDiagnostic> {
+/// How many times `map`, `flatMap` or `forEach` calls its callback, if it is called
+/// on an array literal
+fn iteration_callback_count(call: &CallExpression) -> Option<u64> {
+    match &call.callee {
+        Expression::StaticMemberExpression(member)
+            if matches!(member.property.name.as_str(), "map" | "flatMap" | "forEach") =>
+        {
+            array_literal_len(&member.object)
+        }
+        _ => None,
+    }
+}
+
+/// The number of elements of an array literal without spreads
+fn array_literal_len(expr: &Expression) -> Option<u64> {
+    match expr {
+        Expression::ArrayExpression(array)
+            if !array
+                .elements
+                .iter()
+                .any(|element| matches!(element, ArrayExpressionElement::SpreadElement(_))) =>
+        {
+            Some(array.elements.len() as u64)
+        }
+        _ => None,
+    }
+}
+
+/// How many times a counting loop like `for (let i = 0; i < 10; i++)` runs, if its
+/// start and end are number literals
+fn for_iterations(for_stmt: &ForStatement) -> Option<u64> {
+    let Some(ForStatementInit::VariableDeclaration(decl)) = &for_stmt.init else {
+        return None;
+    };
+    let [declarator] = decl.declarations.as_slice() else {
+        return None;
+    };
+    let BindingPattern::BindingIdentifier(counter) = &declarator.id else {
+        return None;
+    };
+    let Some(Expression::NumericLiteral(start)) = &declarator.init else {
+        return None;
+    };
+    let Some(Expression::BinaryExpression(test)) = &for_stmt.test else {
+        return None;
+    };
+    let (Expression::Identifier(left), Expression::NumericLiteral(end)) = (&test.left, &test.right)
+    else {
+        return None;
+    };
+    let increments = match &for_stmt.update {
+        Some(Expression::UpdateExpression(update)) => {
+            update.operator == UpdateOperator::Increment
+                && matches!(&update.argument, SimpleAssignmentTarget::AssignmentTargetIdentifier(id) if id.name == counter.name)
+        }
+        _ => false,
+    };
+    if left.name != counter.name || !increments {
+        return None;
+    }
+    let span = end.value.ceil() - start.value.ceil();
+    let iterations = match test.operator {
+        BinaryOperator::LessThan => span,
+        BinaryOperator::LessEqualThan => span + 1.0,
+        _ => return None,
+    };
+    (iterations >= 0.0).then_some(iterations as u64)
+}
+
+/// The dotted paths of the properties set in an object literal, including nested objects
+fn object_keys(object: &ObjectExpression, prefix: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    for property in &object.properties {
+        let ObjectPropertyKind::ObjectProperty(property) = property else {
+            continue;
+        };
+        let Some(name) = property.key.static_name() else {
+            continue;
+        };
+        let key = format!("{}{}", prefix, name);
+        match &property.value {
+            Expression::ObjectExpression(nested) => {
+                keys.extend(object_keys(nested, &format!("{}.", key)))
+            }
+            _ => keys.push(key),
+        }
+    }
+    keys
+}
+
+/// Time spent parsing a file and running the rule passes over it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LintTimings {
+    pub parse: Duration,
+    pub lint: Duration,
+}
+
+/// A syntax error the parser recovered from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub start: u32,
+    pub end: u32,
+    pub message: String,
+}
+
+/// Everything found when linting one file, in the shape the JSON reporters write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintResult {
+    pub file: String,
+    pub diagnostics: Vec<LintDiagnostic>,
+    /// Syntax errors in the file; diagnostics may be missing for code the parser skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parse_errors: Vec<ParseError>,
+}
+
+/// The built-in rules that run without being turned on in `cashmere.toml`
+fn default_rules() -> RuleRegistry {
+    let mut rules = RuleRegistry::builtin();
+    rules.retain(|meta| meta.enabled_by_default);
+    rules
+}
+
+/// Lint source text with the built-in rules that are on by default, treating
+/// `step_methods` as step methods in addition to the built-in ones
+pub fn lint_source(source: &str, file_path: &str, step_methods: &[String]) -> Vec<LintDiagnostic> {
+    lint_source_timed(source, file_path, step_methods, &default_rules()).0
+}
+
+/// Like [`lint_source`], but returns a [`LintResult`] that also carries parse errors
+pub fn lint_source_structured(
+    source: &str,
+    file_path: &str,
+    step_methods: &[String],
+) -> LintResult {
+    let (diagnostics, parse_errors, _, _) =
+        run_rules(source, file_path, step_methods, &default_rules());
+    LintResult {
+        file: file_path.to_string(),
+        diagnostics,
+        parse_errors,
+    }
+}
+
+/// Like [`lint_source`], but runs the rules in `rules` and also reports how long
+/// parsing and linting took
+pub fn lint_source_timed(
+    source: &str,
+    file_path: &str,
+    step_methods: &[String],
+    rules: &RuleRegistry,
+) -> (Vec<LintDiagnostic>, LintTimings) {
+    let (diagnostics, _, _, timings) = run_rules(source, file_path, step_methods, rules);
+    (diagnostics, timings)
+}
+
+/// Parse and lint `source`, returning the diagnostics, parse errors, the
+/// `WorkflowEntrypoint` classes the module exports and timings
+pub(crate) fn run_rules(
+    source: &str,
+    file_path: &str,
+    step_methods: &[String],
+    rules: &RuleRegistry,
+) -> (
+    Vec<LintDiagnostic>,
+    Vec<ParseError>,
+    Vec<ExportedWorkflowClass>,
+    LintTimings,
+) {
     let source_type = SourceType::from_path(file_path).unwrap_or_default();
     let allocator = Allocator::default();
-    let ParserReturn { program, .. } = OxcParser::new(&allocator, source, source_type).parse();
+    let parse_start = Instant::now();
+    let ParserReturn {
+        program, errors, ..
+    } = OxcParser::new(&allocator, source, source_type).parse();
+    let parse = parse_start.elapsed();
+
+    let parse_errors = parse_errors(source, &errors);
 
-    let mut linter = Linter::new(source, file_path);
+    let lint_start = Instant::now();
+    let mut linter = Linter::new(source, file_path)
+        .with_rules(rules)
+        .with_step_methods(step_methods);
     linter.lint_program(&program);
-    linter.into_diagnostics()
+    let mut diagnostics = linter.into_diagnostics();
+    // Rules that report when leaving a function or the file would otherwise come last
+    diagnostics.sort_by(|a, b| (a.start, a.end, &a.rule).cmp(&(b.start, b.end, &b.rule)));
+    let (_, workflow_classes) = module_exports(&program, source);
+    let lint = lint_start.elapsed();
+
+    (
+        diagnostics,
+        parse_errors,
+        workflow_classes,
+        LintTimings { parse, lint },
+    )
+}
+
+/// Convert the errors the parser recovered from into [`ParseError`]s
+pub(crate) fn parse_errors(source: &str, errors: &[OxcDiagnostic]) -> Vec<ParseError> {
+    errors
+        .iter()
+        .map(|error| {
+            let (start, end) = error
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.first())
+                .map_or((0, 0), |label| {
+                    (label.offset(), label.offset() + label.len())
+                });
+            let (line, column) = offset_to_line_col(source, start);
+            ParseError {
+                line,
+                column,
+                start: start as u32,
+                end: end as u32,
+                message: error.message.to_string(),
+            }
+        })
+        .collect()
 }
@@ -1,14 +1,153 @@
 use dashmap::DashMap;
-use std::sync::Arc;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use crate::linter::{lint_source, LintDiagnostic};
+use rayon::prelude::*;
+
+use cashmere::cache::hash_content;
+use cashmere::config::{Config, RuleLevel, CONFIG_FILE_NAME};
+use cashmere::graph::{render_graph, GraphFormat};
+use cashmere::linter::{LintDiagnostic, Severity};
+use cashmere::rules::find_rule;
+use cashmere::stats::Stats;
+use cashmere::steps::{find_workflows, WorkflowKind};
+use cashmere::{collect_files, lint_with_config};
+
+/// Command run by the step inventory code lens to list a workflow's steps
+const LIST_STEPS_COMMAND: &str = "cashmere.listSteps";
+
+/// Command returning a Mermaid or DOT graph of a file's workflows
+const EXPORT_GRAPH_COMMAND: &str = "cashmere.exportGraph";
+
+/// Semantic token type for the method name of a verified `WorkflowStep` call
+const STEP_TOKEN_TYPE: SemanticTokenType = SemanticTokenType::new("workflowStep");
+
+/// Step methods offered after `step.`, with their signatures
+const STEP_METHODS: &[(&str, &str)] = &[
+    ("do", "do(name, [config], callback)"),
+    ("sleep", "sleep(name, duration)"),
+    ("sleepUntil", "sleepUntil(name, timestamp)"),
+    ("waitForEvent", "waitForEvent(name, { type, timeout })"),
+];
+
+/// Durations offered inside the duration argument of `step.sleep`
+const DURATIONS: &[&str] = &[
+    "1 second",
+    "30 seconds",
+    "1 minute",
+    "5 minutes",
+    "1 hour",
+    "1 day",
+    "1 week",
+];
+
+/// What `step.do` does when its config doesn't say, keyed by config path
+const DEFAULT_STEP_CONFIG: &[(&str, &str)] = &[
+    ("retries.limit", "retries: 5"),
+    ("retries.delay", "delay: 10 seconds"),
+    ("retries.backoff", "backoff: exponential"),
+    ("timeout", "timeout: 10 minutes"),
+];
+
+/// How long to wait after an edit before linting, so rapid edits are linted once
+const DEFAULT_DEBOUNCE_MS: u64 = 150;
+
+/// How the `character` of an LSP position counts columns, negotiated with the client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// The first encoding the client offers that the server supports, in the client's
+    /// order of preference. Clients that don't say only support UTF-16.
+    fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        offered
+            .unwrap_or_default()
+            .iter()
+            .find_map(|kind| match kind.as_str() {
+                "utf-8" => Some(Self::Utf8),
+                "utf-16" => Some(Self::Utf16),
+                "utf-32" => Some(Self::Utf32),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    /// The number of code units `ch` takes up in this encoding
+    fn len(self, ch: char) -> u32 {
+        match self {
+            Self::Utf8 => ch.len_utf8() as u32,
+            Self::Utf16 => ch.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+/// Workspace scan diagnostics kept for `workspace/diagnostic`, with their result ID,
+/// by document URI
+type WorkspaceReports = DashMap<String, (String, Vec<Diagnostic>)>;
+
+/// Settings sent by the editor with `workspace/didChangeConfiguration`, either at the
+/// top level or under a `cashmere` key. They are merged with the on-disk config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditorSettings {
+    #[serde(default)]
+    rules: BTreeMap<String, RuleLevel>,
+    #[serde(default)]
+    step_methods: Vec<String>,
+    /// Milliseconds to wait after an edit before linting; 0 lints on every change
+    debounce_ms: Option<u64>,
+}
 
 pub struct Backend {
     client: Client,
     document_map: Arc<DashMap<String, String>>,
+    /// The most recent diagnostics for each open document, with the hash of the text
+    /// they were linted from
+    diagnostic_map: Arc<DashMap<String, (u64, Vec<LintDiagnostic>)>>,
+    /// Whether the client pulls diagnostics with `textDocument/diagnostic`, in which
+    /// case they aren't also pushed with `textDocument/publishDiagnostics`
+    pull_diagnostics: AtomicBool,
+    /// Diagnostics from the last workspace scan for files that aren't open, with their
+    /// result ID. Pull clients get these with `workspace/diagnostic` instead.
+    workspace_reports: Arc<WorkspaceReports>,
+    /// Workspace folders to lint in the background once the client is initialized
+    /// and again whenever a config file changes
+    workspace_roots: Mutex<Vec<PathBuf>>,
+    /// Whether the client can be asked to watch config files for changes
+    watch_config_files: AtomicBool,
+    /// Whether the client shows progress for server-initiated work
+    work_done_progress: AtomicBool,
+    /// Cancellation flags of running workspace scans, by progress token
+    scans: Arc<DashMap<String, Arc<AtomicBool>>>,
+    scan_count: AtomicU64,
+    settings: Arc<Mutex<EditorSettings>>,
+    /// Bumped whenever the settings or a config file change, so pulled diagnostics
+    /// aren't reported unchanged
+    config_version: AtomicU64,
+    position_encoding: OnceLock<PositionEncoding>,
+    /// Bumped on every edit to a document, so a debounced lint can tell it was superseded
+    edit_generations: Arc<DashMap<String, u64>>,
 }
 
 impl Backend {
@@ -16,52 +155,614 @@ impl Backend {
         Self {
             client,
             document_map: Arc::new(DashMap::new()),
+            diagnostic_map: Arc::new(DashMap::new()),
+            pull_diagnostics: AtomicBool::new(false),
+            workspace_reports: Arc::new(DashMap::new()),
+            workspace_roots: Mutex::new(Vec::new()),
+            watch_config_files: AtomicBool::new(false),
+            work_done_progress: AtomicBool::new(false),
+            scans: Arc::new(DashMap::new()),
+            scan_count: AtomicU64::new(0),
+            settings: Arc::new(Mutex::new(EditorSettings::default())),
+            config_version: AtomicU64::new(0),
+            position_encoding: OnceLock::new(),
+            edit_generations: Arc::new(DashMap::new()),
         }
     }
 
-    async fn lint_document(&self, uri: Url, text: String) {
-        let file_path = uri.to_string();
-        let diagnostics = lint_source(&text, &file_path);
+    fn debounce(&self) -> Duration {
+        let settings = self.settings.lock().unwrap();
+        Duration::from_millis(settings.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS))
+    }
 
-        let lsp_diagnostics: Vec<Diagnostic> = diagnostics
-            .into_iter()
-            .map(|d| self.convert_diagnostic(d))
+    fn encoding(&self) -> PositionEncoding {
+        self.position_encoding.get().copied().unwrap_or_default()
+    }
+
+    fn lint_text(&self, uri: &Url, text: &str) -> Vec<LintDiagnostic> {
+        lint_text(&self.settings, uri, text)
+    }
+
+    /// The diagnostics for `text`, the current contents of `uri`. A debounced edit may not
+    /// have been linted yet, in which case the text is linted now.
+    fn current_diagnostics(&self, uri: &Url, text: &str) -> Vec<LintDiagnostic> {
+        let key = uri.to_string();
+        let hash = hash_content(text);
+        if let Some(entry) = self.diagnostic_map.get(&key) {
+            if entry.0 == hash {
+                return entry.1.clone();
+            }
+        }
+        let diagnostics = self.lint_text(uri, text);
+        self.diagnostic_map.insert(key, (hash, diagnostics.clone()));
+        diagnostics
+    }
+
+    /// Re-lint every open document after the settings or a config file changed
+    async fn relint_open_documents(&self) {
+        self.config_version.fetch_add(1, Ordering::Relaxed);
+
+        let documents: Vec<(String, String)> = self
+            .document_map
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
             .collect();
+        for (key, text) in documents {
+            if let Ok(uri) = Url::parse(&key) {
+                self.lint_document(uri, text).await;
+            }
+        }
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            let client = self.client.clone();
+            tokio::spawn(async move { client.workspace_diagnostic_refresh().await });
+        }
+    }
 
-        self.client
-            .publish_diagnostics(uri, lsp_diagnostics, None)
-            .await;
+    /// Lint `roots`, or every workspace folder if `None`, in the background
+    fn spawn_workspace_lint(&self, roots: Option<Vec<PathBuf>>) {
+        let workspace_roots = self.workspace_roots.lock().unwrap().clone();
+        let roots = roots.unwrap_or_else(|| workspace_roots.clone());
+        if roots.is_empty() {
+            return;
+        }
+
+        let progress = self.work_done_progress.load(Ordering::Relaxed).then(|| {
+            let token = format!(
+                "cashmere/workspace-scan/{}",
+                self.scan_count.fetch_add(1, Ordering::Relaxed)
+            );
+            let cancelled = Arc::new(AtomicBool::new(false));
+            self.scans.insert(token.clone(), cancelled.clone());
+            ScanProgress {
+                token: NumberOrString::String(token),
+                cancelled,
+            }
+        });
+        let token = progress.as_ref().map(|scan| scan.token.clone());
+        let scans = self.scans.clone();
+        let scan = lint_workspace(
+            self.client.clone(),
+            roots,
+            workspace_roots,
+            self.document_map.clone(),
+            self.pull_reports(),
+            self.encoding(),
+            progress,
+        );
+        tokio::spawn(async move {
+            scan.await;
+            if let Some(NumberOrString::String(token)) = token {
+                scans.remove(&token);
+            }
+        });
     }
 
-    fn convert_diagnostic(&self, diag: LintDiagnostic) -> Diagnostic {
-        // LSP uses 0-based line and column numbers
-        let line = (diag.line - 1) as u32;
-        let column = (diag.column - 1) as u32;
+    /// Handle `window/workDoneProgress/cancel` by stopping the workspace scan it names
+    async fn cancel_progress(&self, params: WorkDoneProgressCancelParams) {
+        if let NumberOrString::String(token) = params.token {
+            if let Some(cancelled) = self.scans.get(&token) {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+    }
 
-        Diagnostic {
-            range: Range {
-                start: Position {
-                    line,
-                    character: column,
-                },
-                end: Position {
-                    line,
-                    character: column + 1,
+    async fn lint_document(&self, uri: Url, text: String) {
+        lint_document(
+            &self.client,
+            &self.diagnostic_map,
+            &self.settings,
+            self.pull_diagnostics.load(Ordering::Relaxed),
+            self.encoding(),
+            uri,
+            text,
+        )
+        .await;
+    }
+
+    /// Where workspace scans keep their diagnostics for a pull client, which would
+    /// otherwise see them twice if they were published as well
+    fn pull_reports(&self) -> Option<Arc<WorkspaceReports>> {
+        self.pull_diagnostics
+            .load(Ordering::Relaxed)
+            .then(|| self.workspace_reports.clone())
+    }
+
+    /// Quick fixes for the diagnostics in `uri` that overlap `range`
+    fn quick_fixes(&self, uri: &Url, range: Range) -> Vec<CodeActionOrCommand> {
+        let key = uri.to_string();
+        let Some(text) = self.document_map.get(&key) else {
+            return Vec::new();
+        };
+
+        self.current_diagnostics(uri, &text)
+            .iter()
+            .filter_map(|diag| {
+                let fix = diag.fix.as_ref()?;
+                let diagnostic = convert_diagnostic(uri, &text, self.encoding(), diag.clone());
+                if !ranges_overlap(diagnostic.range, range) {
+                    return None;
+                }
+
+                let edits = fix
+                    .edits
+                    .iter()
+                    .map(|edit| TextEdit {
+                        range: Range {
+                            start: offset_to_position(&text, edit.start as usize, self.encoding()),
+                            end: offset_to_position(&text, edit.end as usize, self.encoding()),
+                        },
+                        new_text: edit.replacement.clone(),
+                    })
+                    .collect();
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.title.clone(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic]),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), edits)])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+
+    /// Diagnostics for a `textDocument/diagnostic` request. The result ID is a hash of
+    /// the document text, so an unchanged document gets an `unchanged` report.
+    fn pull_report(
+        &self,
+        uri: &Url,
+        previous_result_id: Option<String>,
+    ) -> DocumentDiagnosticReport {
+        let key = uri.to_string();
+        let Some(text) = self.document_map.get(&key) else {
+            return DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport::default());
+        };
+        let result_id = format!(
+            "{:016x}-{}",
+            hash_content(&text),
+            self.config_version.load(Ordering::Relaxed)
+        );
+
+        if previous_result_id.as_deref() == Some(result_id.as_str()) {
+            return DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
                 },
+            });
+        }
+
+        let diagnostics = self.current_diagnostics(uri, &text);
+        DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items: diagnostics
+                    .into_iter()
+                    .map(|diag| convert_diagnostic(uri, &text, self.encoding(), diag))
+                    .collect(),
             },
-            severity: Some(DiagnosticSeverity::ERROR),
-            code: Some(NumberOrString::String(diag.rule)),
-            source: Some("cashmere".to_string()),
-            message: diag.message,
-            related_information: None,
-            tags: None,
-            code_description: None,
-            data: None,
+        })
+    }
+
+    /// Diagnostics for a `workspace/diagnostic` request, from the last workspace scan.
+    /// Files whose result ID the client already has get an `unchanged` report.
+    fn workspace_report(
+        &self,
+        previous_result_ids: Vec<PreviousResultId>,
+    ) -> WorkspaceDiagnosticReport {
+        let previous: HashMap<String, String> = previous_result_ids
+            .into_iter()
+            .map(|previous| (previous.uri.to_string(), previous.value))
+            .collect();
+        let items = self
+            .workspace_reports
+            .iter()
+            .filter_map(|entry| {
+                let uri = Url::parse(entry.key()).ok()?;
+                let (result_id, diagnostics) = entry.value();
+                if previous.get(entry.key()) == Some(result_id) {
+                    return Some(WorkspaceDocumentDiagnosticReport::Unchanged(
+                        WorkspaceUnchangedDocumentDiagnosticReport {
+                            uri,
+                            version: None,
+                            unchanged_document_diagnostic_report:
+                                UnchangedDocumentDiagnosticReport {
+                                    result_id: result_id.clone(),
+                                },
+                        },
+                    ));
+                }
+                Some(WorkspaceDocumentDiagnosticReport::Full(
+                    WorkspaceFullDocumentDiagnosticReport {
+                        uri,
+                        version: None,
+                        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                            result_id: Some(result_id.clone()),
+                            items: diagnostics.clone(),
+                        },
+                    },
+                ))
+            })
+            .collect();
+        WorkspaceDiagnosticReport { items }
+    }
+
+    /// Documentation for the rule behind the diagnostic at `position`, if there is one
+    fn rule_hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let key = uri.to_string();
+        let text = self.document_map.get(&key)?;
+        let diagnostic = self
+            .current_diagnostics(uri, &text)
+            .iter()
+            .map(|d| convert_diagnostic(uri, &text, self.encoding(), d.clone()))
+            .find(|d| d.range.start <= position && position <= d.range.end)?;
+        let Some(NumberOrString::String(rule_id)) = &diagnostic.code else {
+            return None;
+        };
+        let rule = find_rule(rule_id)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!(
+                    "**cashmere: `{}`**\n\n{}\n\n{}\n\n[Documentation]({})",
+                    rule.id, rule.description, rule.explanation, rule.docs_url
+                ),
+            }),
+            range: Some(diagnostic.range),
+        })
+    }
+
+    /// A code lens above each workflow summarizing its steps, sleeps and waits
+    fn step_lenses(&self, uri: &Url) -> Vec<CodeLens> {
+        let Some(text) = self.document_map.get(&uri.to_string()) else {
+            return Vec::new();
+        };
+
+        find_workflows(&text, uri.as_str())
+            .into_iter()
+            .map(|workflow| {
+                let position =
+                    offset_to_position(&text, workflow.span.start as usize, self.encoding());
+                let steps: Vec<String> = workflow
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let line =
+                            offset_to_position(&text, step.span.start as usize, self.encoding())
+                                .line
+                                + 1;
+                        match &step.name {
+                            Some(name) => format!("{} \"{}\" (line {})", step.method, name, line),
+                            None => format!("{} (line {})", step.method, line),
+                        }
+                    })
+                    .collect();
+                CodeLens {
+                    range: Range {
+                        start: position,
+                        end: position,
+                    },
+                    command: Some(Command {
+                        title: workflow.inventory(),
+                        command: LIST_STEPS_COMMAND.to_string(),
+                        arguments: Some(vec![serde_json::json!({
+                            "workflow": workflow.name,
+                            "steps": steps,
+                        })]),
+                    }),
+                    data: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Outline symbols for each workflow, with its named steps nested underneath
+    #[allow(deprecated)] // `DocumentSymbol::deprecated` has to be set even though it's deprecated
+    fn workflow_symbols(&self, uri: &Url) -> Vec<DocumentSymbol> {
+        let Some(text) = self.document_map.get(&uri.to_string()) else {
+            return Vec::new();
+        };
+        let range = |span: oxc_span::Span| Range {
+            start: offset_to_position(&text, span.start as usize, self.encoding()),
+            end: offset_to_position(&text, span.end as usize, self.encoding()),
+        };
+
+        find_workflows(&text, uri.as_str())
+            .into_iter()
+            .map(|workflow| {
+                let children = workflow
+                    .steps
+                    .iter()
+                    .filter_map(|step| {
+                        Some(DocumentSymbol {
+                            name: step.name.clone()?,
+                            detail: Some(format!("step.{}", step.method)),
+                            kind: SymbolKind::FUNCTION,
+                            tags: None,
+                            deprecated: None,
+                            range: range(step.span),
+                            selection_range: range(step.span),
+                            children: None,
+                        })
+                    })
+                    .collect();
+                DocumentSymbol {
+                    detail: Some(workflow.inventory()),
+                    name: workflow.name,
+                    kind: match workflow.kind {
+                        WorkflowKind::Class => SymbolKind::CLASS,
+                        WorkflowKind::Function => SymbolKind::FUNCTION,
+                    },
+                    tags: None,
+                    deprecated: None,
+                    range: range(workflow.span),
+                    selection_range: range(workflow.span),
+                    children: Some(children),
+                }
+            })
+            .collect()
+    }
+
+    /// Semantic tokens for the method names of step calls inside workflows, so they can be
+    /// highlighted differently from look-alike calls on other objects
+    fn step_tokens(&self, uri: &Url) -> Vec<SemanticToken> {
+        let Some(text) = self.document_map.get(&uri.to_string()) else {
+            return Vec::new();
+        };
+        let encoding = self.encoding();
+
+        let mut spans: Vec<oxc_span::Span> = find_workflows(&text, uri.as_str())
+            .into_iter()
+            .flat_map(|workflow| workflow.steps)
+            .map(|step| step.method_span)
+            .collect();
+        spans.sort_by_key(|span| span.start);
+
+        // Tokens are encoded relative to the previous one
+        let mut previous = Position::default();
+        spans
+            .into_iter()
+            .map(|span| {
+                let start = offset_to_position(&text, span.start as usize, encoding);
+                let end = offset_to_position(&text, span.end as usize, encoding);
+                let token = SemanticToken {
+                    delta_line: start.line - previous.line,
+                    delta_start: if start.line == previous.line {
+                        start.character - previous.character
+                    } else {
+                        start.character
+                    },
+                    length: end.character - start.character,
+                    token_type: 0,
+                    token_modifiers_bitset: 0,
+                };
+                previous = start;
+                token
+            })
+            .collect()
+    }
+
+    /// The step name literal at `position` and the ranges of every literal naming the same
+    /// step in its workflow. Ranges cover the name without its quotes.
+    fn step_name_ranges(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<(String, Range, Vec<Range>)> {
+        let text = self.document_map.get(&uri.to_string())?;
+        let encoding = self.encoding();
+        let range = |span: oxc_span::Span| Range {
+            start: offset_to_position(&text, span.start as usize + 1, encoding),
+            end: offset_to_position(&text, span.end as usize - 1, encoding),
+        };
+
+        find_workflows(&text, uri.as_str())
+            .into_iter()
+            .find_map(|workflow| {
+                let (name, span) = workflow.steps.iter().find_map(|step| {
+                    let span = step.name_span?;
+                    let range = range(span);
+                    if range.start <= position && position <= range.end {
+                        Some((step.name.clone()?, span))
+                    } else {
+                        None
+                    }
+                })?;
+                let ranges = workflow
+                    .steps
+                    .iter()
+                    .filter(|step| step.name.as_ref() == Some(&name))
+                    .filter_map(|step| step.name_span.map(range))
+                    .collect();
+                Some((name, range(span), ranges))
+            })
+    }
+
+    /// Step methods after `step.` when the receiver is a workflow's `WorkflowStep`, and
+    /// duration strings inside `step.sleep`
+    fn completions(&self, uri: &Url, position: Position) -> Vec<CompletionItem> {
+        let Some(text) = self.document_map.get(&uri.to_string()) else {
+            return Vec::new();
+        };
+        let encoding = self.encoding();
+        let offset = position_to_offset(&text, position, encoding);
+        let is_ident = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$');
+
+        let before = &text[..offset];
+        let member = before.trim_end_matches(is_ident);
+        if let Some(object) = member.strip_suffix('.') {
+            let receiver = &object[object.trim_end_matches(is_ident).len()..];
+            if receiver.is_empty() {
+                return Vec::new();
+            }
+            // A bare `step.` doesn't parse, so complete it with a placeholder member first
+            let source = if member.len() == offset {
+                format!("{}x{}", before, &text[offset..])
+            } else {
+                text.to_string()
+            };
+            let is_step = find_workflows(&source, uri.as_str())
+                .iter()
+                .any(|workflow| {
+                    workflow.span.start as usize <= offset
+                        && offset <= workflow.span.end as usize
+                        && workflow.step_params.iter().any(|param| param == receiver)
+                });
+            if !is_step {
+                return Vec::new();
+            }
+            return STEP_METHODS
+                .iter()
+                .map(|(method, signature)| CompletionItem {
+                    label: method.to_string(),
+                    kind: Some(CompletionItemKind::METHOD),
+                    detail: Some(format!("WorkflowStep.{}", signature)),
+                    ..Default::default()
+                })
+                .collect();
         }
+
+        let duration = find_workflows(&text, uri.as_str())
+            .into_iter()
+            .flat_map(|workflow| workflow.steps)
+            .filter(|step| step.method == "sleep")
+            .find_map(|step| {
+                let span = *step.argument_spans.get(1)?;
+                let is_string = matches!(text.as_bytes()[span.start as usize], b'\'' | b'"' | b'`');
+                let inside = (span.start as usize) < offset && offset < span.end as usize;
+                (is_string && inside).then_some(span)
+            });
+        let Some(span) = duration else {
+            return Vec::new();
+        };
+        let range = Range {
+            start: offset_to_position(&text, span.start as usize + 1, encoding),
+            end: offset_to_position(&text, span.end as usize - 1, encoding),
+        };
+        DURATIONS
+            .iter()
+            .enumerate()
+            .map(|(i, duration)| CompletionItem {
+                label: duration.to_string(),
+                kind: Some(CompletionItemKind::VALUE),
+                sort_text: Some(format!("{:02}", i)),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: duration.to_string(),
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Inlay hints in `range` showing the default retry settings of `step.do` calls that
+    /// don't configure them, after the step name or the config object
+    fn retry_hints(&self, uri: &Url, range: Range) -> Vec<InlayHint> {
+        let Some(text) = self.document_map.get(&uri.to_string()) else {
+            return Vec::new();
+        };
+        let encoding = self.encoding();
+
+        find_workflows(&text, uri.as_str())
+            .into_iter()
+            .flat_map(|workflow| workflow.steps)
+            .filter_map(|step| {
+                let keys = step.config_keys.as_ref()?;
+                let defaults: Vec<&str> = DEFAULT_STEP_CONFIG
+                    .iter()
+                    .filter(|(path, _)| {
+                        !keys.iter().any(|key| {
+                            key == path
+                                || path
+                                    .strip_prefix(key.as_str())
+                                    .is_some_and(|rest| rest.starts_with('.'))
+                        })
+                    })
+                    .map(|(_, label)| *label)
+                    .collect();
+                if defaults.is_empty() {
+                    return None;
+                }
+                // After the config object if there is one, otherwise after the step name
+                let anchor = step.argument_spans[step.argument_spans.len() - 2];
+                let position = offset_to_position(&text, anchor.end as usize, encoding);
+                if position < range.start || range.end < position {
+                    return None;
+                }
+                Some(InlayHint {
+                    position,
+                    label: InlayHintLabel::String(defaults.join(", ")),
+                    kind: Some(InlayHintKind::PARAMETER),
+                    text_edits: None,
+                    tooltip: Some(InlayHintTooltip::String(
+                        "Defaults used because the step's config doesn't set them".to_string(),
+                    )),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                })
+            })
+            .collect()
+    }
+
+    /// The graph for `cashmere.exportGraph`, whose argument is `{ "uri": ..., "format":
+    /// "mermaid" | "dot" }`. Open documents are read from their unsaved contents.
+    fn export_graph(
+        &self,
+        args: Option<&serde_json::Value>,
+    ) -> std::result::Result<String, String> {
+        let args = args.ok_or("Missing file argument")?;
+        let uri = args["uri"]
+            .as_str()
+            .and_then(|uri| Url::parse(uri).ok())
+            .ok_or("Missing or invalid uri")?;
+        let format = match args["format"].as_str() {
+            None => GraphFormat::Mermaid,
+            Some(name) => GraphFormat::from_name(name).ok_or_else(|| {
+                format!("Unknown graph format {:?}, expected mermaid or dot", name)
+            })?,
+        };
+
+        let text = match self.document_map.get(&uri.to_string()) {
+            Some(text) => text.clone(),
+            None => {
+                let path = uri
+                    .to_file_path()
+                    .map_err(|_| "Not a file uri".to_string())?;
+                std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?
+            }
+        };
+        Ok(render_graph(&find_workflows(&text, uri.as_str()), format))
     }
 
     fn is_supported_file(&self, uri: &Url) -> bool {
-        if let Some(path) = uri.path().split('/').last() {
+        if let Some(path) = uri.path().split('/').next_back() {
             let extensions = ["js", "jsx", "ts", "tsx", "mjs", "cjs", "mts", "cts"];
             return extensions.iter().any(|ext| path.ends_with(ext));
         }
@@ -71,12 +772,119 @@ impl Backend {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let pull_diagnostics = params
+            .capabilities
+            .text_document
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.pull_diagnostics
+            .store(pull_diagnostics, Ordering::Relaxed);
+
+        let roots: Vec<PathBuf> = match &params.workspace_folders {
+            Some(folders) => folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect(),
+            #[allow(deprecated)] // older clients only send `rootUri`
+            None => params
+                .root_uri
+                .iter()
+                .filter_map(|uri| uri.to_file_path().ok())
+                .collect(),
+        };
+        *self.workspace_roots.lock().unwrap() = roots;
+
+        let watch_config_files = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files)
+            .and_then(|watched_files| watched_files.dynamic_registration)
+            .unwrap_or(false);
+        self.watch_config_files
+            .store(watch_config_files, Ordering::Relaxed);
+        let work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        self.work_done_progress
+            .store(work_done_progress, Ordering::Relaxed);
+
+        let encoding = PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|general| general.position_encodings.as_deref()),
+        );
+        let _ = self.position_encoding.set(encoding);
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
+                position_encoding: Some(encoding.kind()),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        ..Default::default()
+                    },
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![
+                        ".".to_string(),
+                        "'".to_string(),
+                        "\"".to_string(),
+                    ]),
+                    ..Default::default()
+                }),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: SemanticTokensLegend {
+                                token_types: vec![STEP_TOKEN_TYPE],
+                                token_modifiers: Vec::new(),
+                            },
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        LIST_STEPS_COMMAND.to_string(),
+                        EXPORT_GRAPH_COMMAND.to_string(),
+                    ],
+                    ..Default::default()
+                }),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("cashmere".to_string()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: true,
+                        ..Default::default()
+                    },
+                )),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -90,12 +898,124 @@ impl LanguageServer for Backend {
         self.client
             .log_message(MessageType::INFO, "Cashmere LSP server initialized")
             .await;
+
+        if self.watch_config_files.load(Ordering::Relaxed) {
+            let client = self.client.clone();
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(format!("**/{}", CONFIG_FILE_NAME)),
+                    kind: None,
+                }],
+            };
+            tokio::spawn(async move {
+                let registration = Registration {
+                    id: "cashmere-config-watcher".to_string(),
+                    method: "workspace/didChangeWatchedFiles".to_string(),
+                    register_options: serde_json::to_value(options).ok(),
+                };
+                client.register_capability(vec![registration]).await
+            });
+        }
+        self.spawn_workspace_lint(None);
     }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let settings = match params.settings {
+            serde_json::Value::Null => return,
+            serde_json::Value::Object(mut map) if map.contains_key("cashmere") => {
+                map.remove("cashmere").unwrap_or_default()
+            }
+            settings => settings,
+        };
+        let settings: EditorSettings = match serde_json::from_value(settings) {
+            Ok(settings) => settings,
+            Err(err) => {
+                self.client
+                    .log_message(
+                        MessageType::ERROR,
+                        format!("Invalid cashmere settings: {}", err),
+                    )
+                    .await;
+                return;
+            }
+        };
+        for id in settings.rules.keys().filter(|id| find_rule(id).is_none()) {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Unknown rule {:?} in settings", id),
+                )
+                .await;
+        }
+        *self.settings.lock().unwrap() = settings;
+        self.relint_open_documents().await;
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let to_paths = |folders: Vec<WorkspaceFolder>| -> Vec<PathBuf> {
+            folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect()
+        };
+        let added = to_paths(params.event.added);
+        let removed = to_paths(params.event.removed);
+
+        let remaining = {
+            let mut roots = self.workspace_roots.lock().unwrap();
+            roots.retain(|root| !removed.contains(root));
+            for root in &added {
+                if !roots.contains(root) {
+                    roots.push(root.clone());
+                }
+            }
+            roots.clone()
+        };
+        // Files in a removed folder nested inside another one fall back to the outer folder
+        let mut affected: Vec<PathBuf> = remaining
+            .iter()
+            .filter(|root| removed.iter().any(|old| old.starts_with(root)))
+            .cloned()
+            .collect();
+        affected.extend(added);
+        for root in removed {
+            tokio::spawn(clear_workspace(
+                self.client.clone(),
+                root,
+                remaining.clone(),
+                self.document_map.clone(),
+                self.pull_reports(),
+            ));
+        }
+        self.spawn_workspace_lint(Some(affected));
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let config_changed = params.changes.iter().any(|change| {
+            change
+                .uri
+                .path()
+                .rsplit('/')
+                .next()
+                .is_some_and(|name| name == CONFIG_FILE_NAME)
+        });
+        if !config_changed {
+            return;
+        }
+        self.client
+            .log_message(
+                MessageType::INFO,
+                format!("{} changed, re-linting", CONFIG_FILE_NAME),
+            )
+            .await;
+        self.relint_open_documents().await;
+        self.spawn_workspace_lint(None);
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
         if !self.is_supported_file(&uri) {
@@ -103,6 +1023,8 @@ impl LanguageServer for Backend {
         }
 
         let text = params.text_document.text;
+        // The open document is reported from its own contents from now on
+        self.workspace_reports.remove(uri.as_str());
         self.document_map.insert(uri.to_string(), text.clone());
         self.lint_document(uri, text).await;
     }
@@ -113,11 +1035,48 @@ impl LanguageServer for Backend {
             return;
         }
 
-        if let Some(change) = params.content_changes.into_iter().next() {
-            let text = change.text;
-            self.document_map.insert(uri.to_string(), text.clone());
+        let Some(change) = params.content_changes.into_iter().next() else {
+            return;
+        };
+        let key = uri.to_string();
+        let text = change.text;
+        self.document_map.insert(key.clone(), text.clone());
+        let generation = {
+            let mut generation = self.edit_generations.entry(key.clone()).or_default();
+            *generation += 1;
+            *generation
+        };
+
+        let debounce = self.debounce();
+        if debounce.is_zero() {
             self.lint_document(uri, text).await;
+            return;
         }
+
+        // Wait for the edits to settle in the background, so later changes and requests
+        // aren't held up, and leave the lint to a later change if there is one
+        let client = self.client.clone();
+        let diagnostic_map = self.diagnostic_map.clone();
+        let settings = self.settings.clone();
+        let edit_generations = self.edit_generations.clone();
+        let pull = self.pull_diagnostics.load(Ordering::Relaxed);
+        let encoding = self.encoding();
+        tokio::spawn(async move {
+            tokio::time::sleep(debounce).await;
+            if edit_generations.get(&key).map(|g| *g) != Some(generation) {
+                return;
+            }
+            lint_document(
+                &client,
+                &diagnostic_map,
+                &settings,
+                pull,
+                encoding,
+                uri,
+                text,
+            )
+            .await;
+        });
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
@@ -132,15 +1091,548 @@ impl LanguageServer for Backend {
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        self.document_map
-            .remove(&params.text_document.uri.to_string());
+        let uri = params.text_document.uri;
+        let key = uri.to_string();
+        self.document_map.remove(&key);
+        self.diagnostic_map.remove(&key);
+        self.edit_generations.remove(&key);
+
+        // A pull client gets a closed workspace file back in the workspace report,
+        // linted from what is on disk
+        if !self.pull_diagnostics.load(Ordering::Relaxed) || !self.is_supported_file(&uri) {
+            return;
+        }
+        let workspace_roots = self.workspace_roots.lock().unwrap().clone();
+        let Some(text) = uri
+            .to_file_path()
+            .ok()
+            .filter(|path| innermost_root(path, &workspace_roots).is_some())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        else {
+            return;
+        };
+        let diagnostics = self
+            .lint_text(&uri, &text)
+            .into_iter()
+            .map(|diag| convert_diagnostic(&uri, &text, self.encoding(), diag))
+            .collect();
+        self.workspace_reports
+            .insert(key, report_entry(diagnostics));
+        let client = self.client.clone();
+        tokio::spawn(async move { client.workspace_diagnostic_refresh().await });
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        Ok(self
+            .pull_report(&params.text_document.uri, params.previous_result_id)
+            .into())
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        Ok(self.workspace_report(params.previous_result_ids).into())
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        Ok(self.rule_hover(&position.text_document.uri, position.position))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        Ok(Some(DocumentSymbolResponse::Nested(
+            self.workflow_symbols(&params.text_document.uri),
+        )))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: self.step_tokens(&params.text_document.uri),
+        })))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let items = self.completions(&position.text_document.uri, position.position);
+        Ok((!items.is_empty()).then_some(CompletionResponse::Array(items)))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        Ok(Some(
+            self.retry_hints(&params.text_document.uri, params.range),
+        ))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        Ok(self
+            .step_name_ranges(&params.text_document.uri, params.position)
+            .map(
+                |(name, range, _)| PrepareRenameResponse::RangeWithPlaceholder {
+                    range,
+                    placeholder: name,
+                },
+            ))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let position = params.text_document_position;
+        let uri = position.text_document.uri;
+        let Some((name, _, ranges)) = self.step_name_ranges(&uri, position.position) else {
+            return Ok(None);
+        };
+        if params.new_name.contains(['\'', '"', '`', '\\', '\n']) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Step names can't contain quotes, backslashes or newlines",
+            ));
+        }
+
+        self.client
+            .show_message(
+                MessageType::WARNING,
+                format!(
+                    "Renaming step \"{}\" changes its replay identity: instances that already ran it will run it again under the new name",
+                    name
+                ),
+            )
+            .await;
+        let edits = ranges
+            .into_iter()
+            .map(|range| TextEdit {
+                range,
+                new_text: params.new_name.clone(),
+            })
+            .collect();
+        Ok(Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri, edits)])),
+            ..Default::default()
+        }))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        Ok(Some(self.step_lenses(&params.text_document.uri)))
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>> {
+        match params.command.as_str() {
+            LIST_STEPS_COMMAND => {}
+            EXPORT_GRAPH_COMMAND => {
+                return self
+                    .export_graph(params.arguments.first())
+                    .map(|graph| Some(serde_json::Value::String(graph)))
+                    .map_err(tower_lsp::jsonrpc::Error::invalid_params);
+            }
+            _ => {
+                return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                    "Unknown command {}",
+                    params.command
+                )));
+            }
+        }
+
+        let Some(args) = params.arguments.first() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Missing workflow argument",
+            ));
+        };
+        let steps: Vec<&str> = args["steps"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|step| step.as_str())
+            .collect();
+        let message = if steps.is_empty() {
+            format!(
+                "{} has no steps",
+                args["workflow"].as_str().unwrap_or("Workflow")
+            )
+        } else {
+            format!(
+                "{}: {}",
+                args["workflow"].as_str().unwrap_or("Workflow"),
+                steps.join(", ")
+            )
+        };
+        self.client.show_message(MessageType::INFO, message).await;
+        Ok(None)
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let actions = self.quick_fixes(&params.text_document.uri, params.range);
+        Ok((!actions.is_empty()).then_some(actions))
+    }
+}
+
+/// Convert a diagnostic for `text` into an LSP diagnostic, with positions in `encoding`
+fn convert_diagnostic(
+    uri: &Url,
+    text: &str,
+    encoding: PositionEncoding,
+    diag: LintDiagnostic,
+) -> Diagnostic {
+    let range = |start: u32, end: u32| Range {
+        start: offset_to_position(text, start as usize, encoding),
+        end: offset_to_position(text, end as usize, encoding),
+    };
+    let related_information = diag
+        .related
+        .iter()
+        .map(|related| DiagnosticRelatedInformation {
+            location: Location {
+                uri: uri.clone(),
+                range: range(related.start, related.end),
+            },
+            message: related.message.clone(),
+        })
+        .collect::<Vec<_>>();
+    let code_description = find_rule(&diag.rule)
+        .and_then(|rule| Url::parse(rule.docs_url).ok())
+        .map(|href| CodeDescription { href });
+    Diagnostic {
+        range: range(diag.start, diag.end),
+        severity: Some(match diag.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        code: Some(NumberOrString::String(diag.rule)),
+        source: Some("cashmere".to_string()),
+        message: diag.message,
+        related_information: (!related_information.is_empty()).then_some(related_information),
+        tags: None,
+        code_description,
+        data: None,
+    }
+}
+
+/// The progress token of a workspace scan and the flag set when the client cancels it
+struct ScanProgress {
+    token: NumberOrString,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScanProgress {
+    async fn send(&self, client: &Client, progress: WorkDoneProgress) {
+        client
+            .send_notification::<notification::Progress>(ProgressParams {
+                token: self.token.clone(),
+                value: ProgressParamsValue::WorkDone(progress),
+            })
+            .await;
     }
 }
 
+/// Lint a document's text with the config for its path merged with the editor settings
+fn lint_text(settings: &Mutex<EditorSettings>, uri: &Url, text: &str) -> Vec<LintDiagnostic> {
+    let path = uri.to_file_path().ok();
+    let config = path
+        .as_deref()
+        .and_then(|path| Config::discover(path).ok())
+        .unwrap_or_default();
+    if path.as_deref().is_some_and(|path| config.is_ignored(path)) {
+        return Vec::new();
+    }
+    let config = {
+        let settings = settings.lock().unwrap();
+        config.with_editor_settings(&settings.rules, &settings.step_methods)
+    };
+    lint_with_config(text, uri.as_str(), &config, &Stats::default())
+}
+
+/// Lint an open document and remember its diagnostics, publishing them unless the
+/// client pulls them
+async fn lint_document(
+    client: &Client,
+    diagnostic_map: &DashMap<String, (u64, Vec<LintDiagnostic>)>,
+    settings: &Mutex<EditorSettings>,
+    pull_diagnostics: bool,
+    encoding: PositionEncoding,
+    uri: Url,
+    text: String,
+) {
+    let diagnostics = lint_text(settings, &uri, &text);
+    diagnostic_map.insert(uri.to_string(), (hash_content(&text), diagnostics.clone()));
+    if pull_diagnostics {
+        return;
+    }
+
+    let lsp_diagnostics: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .map(|diag| convert_diagnostic(&uri, &text, encoding, diag))
+        .collect();
+
+    client.publish_diagnostics(uri, lsp_diagnostics, None).await;
+}
+
+/// Lint every JS/TS file in `roots`, respecting each folder's config and ignores, and
+/// publish the diagnostics. With `reports`, they are kept there for `workspace/diagnostic`
+/// instead and the client is asked to pull them. Files inside another of the
+/// `workspace_roots` nested in a folder are left to that folder and its config. Open
+/// documents are skipped because they are linted from their unsaved contents as they
+/// change. With `progress`, the scan is reported to the client, which can cancel it.
+async fn lint_workspace(
+    client: Client,
+    roots: Vec<PathBuf>,
+    workspace_roots: Vec<PathBuf>,
+    document_map: Arc<DashMap<String, String>>,
+    reports: Option<Arc<WorkspaceReports>>,
+    encoding: PositionEncoding,
+    mut progress: Option<ScanProgress>,
+) {
+    if let Some(scan) = &progress {
+        let created = client
+            .send_request::<request::WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: scan.token.clone(),
+            })
+            .await;
+        if created.is_err() {
+            progress = None;
+        }
+    }
+    if let Some(scan) = &progress {
+        scan.send(
+            &client,
+            WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: "Linting workspace".to_string(),
+                cancellable: Some(true),
+                message: None,
+                percentage: Some(0),
+            }),
+        )
+        .await;
+    }
+
+    let cancelled = progress
+        .as_ref()
+        .map(|scan| scan.cancelled.clone())
+        .unwrap_or_default();
+    let total = Arc::new(AtomicUsize::new(0));
+    let linted = Arc::new(AtomicUsize::new(0));
+    let mut task = {
+        let (total, linted) = (total.clone(), linted.clone());
+        tokio::task::spawn_blocking(move || {
+            let mut errors = Vec::new();
+            let mut jobs = Vec::new();
+            for root in roots {
+                let config = match Config::discover(&root) {
+                    Ok(config) => config,
+                    Err(err) => {
+                        errors.push(err);
+                        continue;
+                    }
+                };
+                let files = collect_files(&root, &config)
+                    .into_iter()
+                    .filter(|path| innermost_root(path, &workspace_roots) == Some(root.as_path()))
+                    .collect::<Vec<_>>();
+                total.fetch_add(files.len(), Ordering::Relaxed);
+                jobs.push((config, files));
+            }
+
+            let mut results = Vec::new();
+            for (config, files) in jobs {
+                results.par_extend(files.into_par_iter().filter_map(|path| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let text = std::fs::read_to_string(&path).ok()?;
+                    let diagnostics = lint_with_config(
+                        &text,
+                        path.to_str().unwrap_or(""),
+                        &config,
+                        &Stats::default(),
+                    );
+                    linted.fetch_add(1, Ordering::Relaxed);
+                    Some((path, text, diagnostics))
+                }));
+            }
+            (results, errors)
+        })
+    };
+
+    // Report how far the scan has got until it finishes
+    let results = loop {
+        tokio::select! {
+            results = &mut task => break results,
+            _ = tokio::time::sleep(Duration::from_millis(200)) => {
+                if let Some(scan) = &progress {
+                    let (linted, total) = (linted.load(Ordering::Relaxed), total.load(Ordering::Relaxed));
+                    scan.send(
+                        &client,
+                        WorkDoneProgress::Report(WorkDoneProgressReport {
+                            cancellable: Some(true),
+                            message: Some(format!("{}/{} files", linted, total)),
+                            percentage: (total > 0).then(|| (linted * 100 / total) as u32),
+                        }),
+                    )
+                    .await;
+                }
+            }
+        }
+    };
+    let Ok((results, errors)) = results else {
+        return;
+    };
+
+    for err in errors {
+        client.log_message(MessageType::ERROR, err).await;
+    }
+    for (path, text, diagnostics) in results {
+        let Ok(uri) = Url::from_file_path(&path) else {
+            continue;
+        };
+        if document_map.contains_key(&uri.to_string()) {
+            continue;
+        }
+        let lsp_diagnostics: Vec<Diagnostic> = diagnostics
+            .into_iter()
+            .map(|diag| convert_diagnostic(&uri, &text, encoding, diag))
+            .collect();
+        match &reports {
+            Some(reports) => {
+                reports.insert(uri.to_string(), report_entry(lsp_diagnostics));
+            }
+            None => client.publish_diagnostics(uri, lsp_diagnostics, None).await,
+        }
+    }
+    if reports.is_some() {
+        let client = client.clone();
+        tokio::spawn(async move { client.workspace_diagnostic_refresh().await });
+    }
+
+    if let Some(scan) = &progress {
+        let (linted, total) = (
+            linted.load(Ordering::Relaxed),
+            total.load(Ordering::Relaxed),
+        );
+        let message = if scan.cancelled.load(Ordering::Relaxed) {
+            format!("Cancelled after linting {} of {} files", linted, total)
+        } else {
+            format!("Linted {} files", linted)
+        };
+        scan.send(
+            &client,
+            WorkDoneProgress::End(WorkDoneProgressEnd {
+                message: Some(message),
+            }),
+        )
+        .await;
+    }
+}
+
+/// A [`WorkspaceReports`] entry for `diagnostics`, with a result ID derived from them
+fn report_entry(diagnostics: Vec<Diagnostic>) -> (String, Vec<Diagnostic>) {
+    let json = serde_json::to_string(&diagnostics).unwrap_or_default();
+    (format!("{:016x}", hash_content(&json)), diagnostics)
+}
+
+/// Clear the diagnostics published for files in a workspace folder that was removed,
+/// except for open documents and files still covered by another folder. With `reports`,
+/// the files are dropped from there and the client is asked to pull diagnostics again.
+async fn clear_workspace(
+    client: Client,
+    root: PathBuf,
+    workspace_roots: Vec<PathBuf>,
+    document_map: Arc<DashMap<String, String>>,
+    reports: Option<Arc<WorkspaceReports>>,
+) {
+    let files = tokio::task::spawn_blocking(move || {
+        let config = Config::discover(&root).unwrap_or_default();
+        collect_files(&root, &config)
+            .into_iter()
+            .filter(|path| innermost_root(path, &workspace_roots).is_none())
+            .collect::<Vec<_>>()
+    })
+    .await;
+    let Ok(files) = files else {
+        return;
+    };
+
+    for path in files {
+        let Ok(uri) = Url::from_file_path(&path) else {
+            continue;
+        };
+        if let Some(reports) = &reports {
+            reports.remove(uri.as_str());
+        } else if !document_map.contains_key(&uri.to_string()) {
+            client.publish_diagnostics(uri, Vec::new(), None).await;
+        }
+    }
+    if reports.is_some() {
+        client.workspace_diagnostic_refresh().await.ok();
+    }
+}
+
+/// The most deeply nested of `roots` that contains `path`
+fn innermost_root<'a>(path: &Path, roots: &'a [PathBuf]) -> Option<&'a Path> {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(PathBuf::as_path)
+}
+
+/// Convert a byte offset into an LSP position, counting characters in `encoding`'s code units
+fn offset_to_position(text: &str, offset: usize, encoding: PositionEncoding) -> Position {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, ch) in text.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += encoding.len(ch);
+        }
+    }
+    Position { line, character }
+}
+
+/// Convert an LSP position into a byte offset, clamping to the end of the line
+fn position_to_offset(text: &str, position: Position, encoding: PositionEncoding) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, ch) in text.char_indices() {
+        if line == position.line && (character >= position.character || ch == '\n') {
+            return i;
+        }
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else if line == position.line {
+            character += encoding.len(ch);
+        }
+    }
+    text.len()
+}
+
+fn ranges_overlap(a: Range, b: Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
 pub async fn run_lsp_server() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| Backend::new(client));
+    let (service, socket) = LspService::build(Backend::new)
+        .custom_method("window/workDoneProgress/cancel", Backend::cancel_progress)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -1,13 +1,37 @@
-mod linter;
+mod git;
+mod hook;
+mod init;
 mod lsp;
+mod mcp;
+mod serve;
+mod watch;
 
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
 use std::{env, fs};
 
-use clap::Parser;
-use walkdir::WalkDir;
+use clap::builder::PossibleValuesParser;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use rayon::prelude::*;
 
-use linter::{lint_source, LintDiagnostic};
+use cashmere::baseline::{self, Baseline};
+use cashmere::cache::{hash_content, LintCache, CACHE_FILE_NAME};
+use cashmere::config::RuleLevel;
+use cashmere::config::{self, Config};
+use cashmere::linter::LintDiagnostic;
+use cashmere::report::{
+    print_summary, print_text_report, render_eslint_json, render_github_annotations,
+    render_markdown, render_tap, write_ndjson, ExitPolicy, FailOn, Format, Summary,
+};
+use cashmere::stats::Stats;
+use cashmere::steps::ExportedWorkflowClass;
+use cashmere::wrangler::{
+    check_bindings, check_duplicate_classes, collect_entrypoint_files, Wrangler,
+};
+use cashmere::{collect_files, fix, html, lint_with_config, lint_with_config_and_classes, rules};
 
 #[derive(Parser, Debug)]
 #[command(name = "cashmere")]
@@ -16,6 +40,9 @@ use linter::{lint_source, LintDiagnostic};
     about = "A fast linter for Cloudflare Workflows TypeScript/JavaScript code, built with Rust."
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Directory or file to lint (defaults to current directory)
     #[arg(default_value = ".")]
     path: String,
@@ -23,34 +50,470 @@ struct Args {
     /// Run as LSP server
     #[arg(long)]
     lsp: bool,
+
+    /// Keep running and re-lint files as they change
+    #[arg(long)]
+    watch: bool,
+
+    /// Automatically fix problems where possible and rewrite the files
+    #[arg(long, conflicts_with = "watch")]
+    fix: bool,
+
+    /// Print a unified diff of the fixes `--fix` would apply without writing any files
+    #[arg(long, conflicts_with_all = ["watch", "fix"])]
+    fix_dry_run: bool,
+
+    /// Only re-lint files whose contents changed since the last run (stored in .cashmere-cache)
+    #[arg(long, conflicts_with_all = ["watch", "fix", "fix_dry_run"])]
+    cache: bool,
+
+    /// Fail if more than this many warnings are reported
+    #[arg(long, value_name = "N")]
+    max_warnings: Option<usize>,
+
+    /// Lowest severity that causes a non-zero exit code
+    #[arg(long, value_enum, default_value_t = FailOn::Error)]
+    fail_on: FailOn,
+
+    /// Always exit with code 0, even if issues are found
+    #[arg(long)]
+    exit_zero: bool,
+
+    /// Output format for diagnostics
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Write the `markdown`, `html`, `tap` or `eslint-json` report to this file instead of stdout,
+    /// or the `github-summary` job summary instead of `$GITHUB_STEP_SUMMARY`
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Most annotations to emit with `--format github-summary`; further issues are only logged
+    #[arg(long, value_name = "N", default_value_t = 50)]
+    max_annotations: usize,
+
+    /// Only print the final summary, not individual diagnostics
+    #[arg(long, short, conflicts_with_all = ["watch", "fix_dry_run"])]
+    quiet: bool,
+
+    /// Print files walked and parsed, parse and lint times, and diagnostics per rule
+    #[arg(long, conflicts_with = "watch")]
+    stats: bool,
+
+    /// Record all current diagnostics in a baseline file instead of reporting them
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    baseline_generate: Option<PathBuf>,
+
+    /// Suppress diagnostics recorded in a baseline file, reporting only new ones
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["watch", "baseline_generate"])]
+    baseline: Option<PathBuf>,
+
+    /// Only lint files with uncommitted changes (including untracked files)
+    #[arg(long, conflicts_with = "watch")]
+    changed: bool,
+
+    /// Only lint modules reachable from the `main` entry of the nearest wrangler.toml,
+    /// wrangler.json or wrangler.jsonc
+    #[arg(long, conflicts_with = "watch")]
+    entrypoints: bool,
+
+    /// Only lint files that changed since the given git ref (e.g. `origin/main`)
+    #[arg(long, value_name = "REF", conflicts_with_all = ["watch", "changed"])]
+    since: Option<String>,
+
+    /// Lint source text read from stdin instead of files
+    #[arg(
+        long,
+        conflicts_with_all = ["watch", "fix", "fix_dry_run", "cache", "changed", "since", "entrypoints"]
+    )]
+    stdin: bool,
+
+    /// File name to report (and resolve config for) when linting stdin
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "stdin",
+        default_value_if("stdin", "true", "stdin.ts")
+    )]
+    stdin_filename: Option<String>,
+
+    /// Only report diagnostics from this rule (can be repeated)
+    #[arg(long, global = true, value_name = "RULE", value_parser = rule_ids())]
+    rule: Vec<String>,
+
+    /// Report this rule as an error regardless of the config (can be repeated)
+    #[arg(long, global = true, value_name = "RULE", value_parser = rule_ids())]
+    deny: Vec<String>,
+
+    /// Number of worker threads used for linting (defaults to the number of CPU cores)
+    #[arg(long, global = true, env = "CASHMERE_THREADS")]
+    threads: Option<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create a cashmere.toml with the recommended settings
+    Init {
+        /// Directory to create the config in (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Overwrite an existing cashmere.toml
+        #[arg(long)]
+        force: bool,
+    },
+    /// List all available rules
+    Rules,
+    /// Print the effective configuration for a file or directory
+    PrintConfig {
+        /// File or directory to resolve the configuration for (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Run a Model Context Protocol server over stdio for AI coding agents
+    Mcp,
+    /// Serve an HTTP API for linting snippets (`POST /lint`, `GET /rules`)
+    Serve {
+        /// Port to listen on (0 picks a free port)
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// Manage git hooks
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HookAction {
+    /// Install a pre-commit hook that lints staged JS/TS files
+    Install {
+        /// Overwrite an existing pre-commit hook
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Rule IDs accepted by `--rule` and `--deny`, also used for shell completion
+fn rule_ids() -> PossibleValuesParser {
+    PossibleValuesParser::new(rules::ALL_RULES.iter().map(|rule| rule.id))
 }
 
-fn is_js_or_ts_file(path: &Path) -> bool {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some(ext) => matches!(
-            ext,
-            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts"
-        ),
-        None => false,
+/// Lint a file, reusing cached diagnostics if its contents haven't changed.
+/// Records the content hash on the result if the file had to be linted.
+fn lint_file_cached(
+    path: &Path,
+    config: &Config,
+    cache: &LintCache,
+    stats: &Stats,
+) -> Option<FileResult> {
+    let source_text = fs::read_to_string(path).ok()?;
+    let file_path = path.to_str().unwrap_or("");
+    let hash = hash_content(&source_text);
+    if let Some((diagnostics, workflow_classes)) = cache.get(file_path, hash) {
+        return Some(FileResult {
+            diagnostics,
+            workflow_classes,
+            ..FileResult::default()
+        });
     }
+
+    let (diagnostics, workflow_classes) =
+        lint_with_config_and_classes(&source_text, file_path, config, stats);
+    Some(FileResult {
+        diagnostics,
+        cache_hash: Some(hash),
+        workflow_classes,
+        ..FileResult::default()
+    })
 }
 
-fn should_skip_dir(name: &str) -> bool {
-    matches!(
-        name,
-        "node_modules" | ".git" | "dist" | "build" | "target" | ".next" | "coverage"
-    )
+/// Lint a file, apply any available fixes and write the result back to disk.
+/// The result holds the diagnostics that remain after fixing and the number of fixes applied.
+fn fix_file(path: &Path, config: &Config, stats: &Stats) -> Option<FileResult> {
+    let source_text = fs::read_to_string(path).ok()?;
+    let file_path = path.to_str().unwrap_or("");
+    let (diagnostics, workflow_classes) =
+        lint_with_config_and_classes(&source_text, file_path, config, stats);
+    let unfixed = |diagnostics, workflow_classes| FileResult {
+        diagnostics,
+        workflow_classes,
+        ..FileResult::default()
+    };
+
+    let (fixed_text, applied) = fix::apply_fixes(&source_text, &diagnostics);
+    if applied == 0 {
+        return Some(unfixed(diagnostics, workflow_classes));
+    }
+
+    if let Err(err) = fs::write(path, &fixed_text) {
+        eprintln!("Failed to write fixes to {}: {}", path.display(), err);
+        return Some(unfixed(diagnostics, workflow_classes));
+    }
+    // Fixes such as adding `export` change which classes the file exports
+    let (diagnostics, workflow_classes) =
+        lint_with_config_and_classes(&fixed_text, file_path, config, stats);
+    Some(FileResult {
+        diagnostics,
+        fixes_applied: applied,
+        workflow_classes,
+        ..FileResult::default()
+    })
 }
 
-fn lint_file(path: &Path) -> Option<Vec<LintDiagnostic>> {
+/// Lint a file and render the fixes that would be applied as a unified diff.
+/// The result holds the diagnostics, the diff (if there is anything to fix) and the number of fixes.
+fn preview_fixes(path: &Path, config: &Config, stats: &Stats) -> Option<FileResult> {
     let source_text = fs::read_to_string(path).ok()?;
-    Some(lint_source(&source_text, path.to_str().unwrap_or("")))
+    let file_path = path.to_str().unwrap_or("");
+    let (diagnostics, workflow_classes) =
+        lint_with_config_and_classes(&source_text, file_path, config, stats);
+
+    let (fixed_text, applied) = fix::apply_fixes(&source_text, &diagnostics);
+    let diff = (applied > 0).then(|| fix::unified_diff(file_path, &source_text, &fixed_text));
+    Some(FileResult {
+        diagnostics,
+        fixes_applied: applied,
+        diff,
+        workflow_classes,
+        ..FileResult::default()
+    })
+}
+
+/// Lint source text read from stdin, reported under `path`.
+/// Returns `None` if the path is ignored by the config.
+fn lint_stdin(path: &Path, config: &Config, stats: &Stats) -> Option<(PathBuf, FileResult)> {
+    if config.is_ignored(path) {
+        return None;
+    }
+
+    let mut source_text = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut source_text) {
+        eprintln!("Error: failed to read stdin: {}", err);
+        std::process::exit(2);
+    }
+    let diagnostics = lint_with_config(&source_text, path.to_str().unwrap_or(""), config, stats);
+    Some((
+        path.to_path_buf(),
+        FileResult {
+            diagnostics,
+            ..FileResult::default()
+        },
+    ))
+}
+
+/// Run the `wrangler-binding` rule against the nearest wrangler config, keeping the
+/// diagnostics for files under `root` that the config doesn't ignore
+fn lint_wrangler_bindings(root: &Path, config: &Config) -> Vec<LintDiagnostic> {
+    if config.rule_level(&rules::WRANGLER_BINDING).0 == RuleLevel::Off {
+        return Vec::new();
+    }
+    let wrangler = match Wrangler::discover(root) {
+        Ok(Some(wrangler)) => wrangler,
+        Ok(None) => return Vec::new(),
+        Err(err) => {
+            eprintln!("Warning: {}", err);
+            return Vec::new();
+        }
+    };
+    let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    config
+        .apply(check_bindings(&wrangler))
+        .into_iter()
+        .filter(|diagnostic| {
+            let path = Path::new(&diagnostic.file);
+            path.starts_with(&root) && !config.is_ignored(path)
+        })
+        .collect()
+}
+
+/// Run the `unique-workflow-classes` rule across the classes the linted files export
+fn lint_workflow_classes(
+    results: &[(PathBuf, FileResult)],
+    config: &Config,
+) -> Vec<LintDiagnostic> {
+    if config.rule_level(&rules::UNIQUE_WORKFLOW_CLASSES).0 == RuleLevel::Off {
+        return Vec::new();
+    }
+    let files: Vec<(&Path, &[ExportedWorkflowClass])> = results
+        .iter()
+        .map(|(path, result)| (path.as_path(), result.workflow_classes.as_slice()))
+        .collect();
+    config.apply(check_duplicate_classes(&files))
+}
+
+/// The outcome of linting a single file
+#[derive(Default)]
+struct FileResult {
+    diagnostics: Vec<LintDiagnostic>,
+    fixes_applied: usize,
+    /// Unified diff of the available fixes (`--fix-dry-run` only)
+    diff: Option<String>,
+    /// Content hash to record in the lint cache, if the file was freshly linted
+    cache_hash: Option<u64>,
+    /// Diagnostics left after applying the baseline; these are the ones reported
+    reported: Vec<LintDiagnostic>,
+    /// Number of diagnostics suppressed by the baseline
+    suppressed: usize,
+    /// `WorkflowEntrypoint` classes the file exports, for `unique-workflow-classes`
+    workflow_classes: Vec<ExportedWorkflowClass>,
+}
+
+fn process_file(
+    path: &Path,
+    args: &Args,
+    config: &Config,
+    cache: Option<&LintCache>,
+    stats: &Stats,
+) -> Option<FileResult> {
+    if let Some(cache) = cache {
+        lint_file_cached(path, config, cache, stats)
+    } else if args.fix {
+        fix_file(path, config, stats)
+    } else if args.fix_dry_run {
+        preview_fixes(path, config, stats)
+    } else {
+        let source_text = fs::read_to_string(path).ok()?;
+        let (diagnostics, workflow_classes) =
+            lint_with_config_and_classes(&source_text, path.to_str().unwrap_or(""), config, stats);
+        Some(FileResult {
+            diagnostics,
+            workflow_classes,
+            ..FileResult::default()
+        })
+    }
+}
+
+/// Apply the baseline to a linted file and, with `--format ndjson`, stream the
+/// remaining diagnostics right away instead of waiting for the run to finish
+fn finish_file(
+    mut result: FileResult,
+    format: Format,
+    baseline: Option<&Mutex<Baseline>>,
+) -> FileResult {
+    let diagnostics = result.diagnostics.clone();
+    (result.reported, result.suppressed) = match baseline {
+        Some(baseline) => baseline.lock().unwrap().filter(diagnostics),
+        None => (diagnostics, 0),
+    };
+
+    if format == Format::Ndjson {
+        if let Err(err) = write_ndjson(&result.reported) {
+            eprintln!("Error: failed to write diagnostics: {}", err);
+            std::process::exit(2);
+        }
+    }
+    result
+}
+
+/// The first option that prints text to stdout and so can't be combined with a
+/// format that isn't human-readable text
+fn format_conflict(args: &Args) -> Option<&'static str> {
+    [
+        (args.watch, "--watch"),
+        (args.fix_dry_run, "--fix-dry-run"),
+        (args.quiet, "--quiet"),
+        (args.stats, "--stats"),
+        (args.baseline_generate.is_some(), "--baseline-generate"),
+    ]
+    .into_iter()
+    .find_map(|(set, flag)| set.then_some(flag))
+}
+
+/// Configure the global thread pool used for linting. `None` or `0` uses all available cores.
+fn configure_threads(threads: Option<usize>) {
+    let Some(threads) = threads.filter(|&n| n > 0) else {
+        return;
+    };
+    if let Err(err) = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+    {
+        eprintln!("Warning: failed to configure {} threads: {}", threads, err);
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    let start = Instant::now();
     let args = Args::parse();
 
+    match &args.command {
+        Some(Command::Init { path, force }) => {
+            match init::run_init(Path::new(path), *force) {
+                Ok(config_path) => println!("✓ Created {}", config_path.display()),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
+                }
+            }
+            return;
+        }
+        Some(Command::Rules) => {
+            rules::print_rules();
+            return;
+        }
+        Some(Command::PrintConfig { path }) => {
+            match Config::discover(Path::new(path)) {
+                Ok(config) => config::print_config(
+                    &config.with_overrides(&args.rule, &args.deny),
+                    Path::new(path),
+                    args.threads,
+                ),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
+                }
+            }
+            return;
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(*shell, &mut Args::command(), "cashmere", &mut io::stdout());
+            return;
+        }
+        Some(Command::Mcp) => {
+            mcp::run_mcp_server(&args.rule, &args.deny);
+            return;
+        }
+        Some(Command::Serve { port, host }) => {
+            let config = match Config::discover(Path::new(".")) {
+                Ok(config) => config.with_overrides(&args.rule, &args.deny),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
+                }
+            };
+            if let Err(err) = serve::run_http_server(host, *port, config).await {
+                eprintln!("Error: {}", err);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Hook {
+            action: HookAction::Install { force },
+        }) => {
+            match hook::install(Path::new("."), *force) {
+                Ok(hook_path) => println!("✓ Installed pre-commit hook at {}", hook_path.display()),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
+                }
+            }
+            return;
+        }
+        None => {}
+    }
+
     if args.lsp {
         // Run as LSP server
         lsp::run_lsp_server().await;
@@ -58,67 +521,263 @@ async fn main() {
     }
 
     // Run as CLI
-    let root = if args.path == "." {
+    if !args.format.is_text() {
+        if let Some(flag) = format_conflict(&args) {
+            eprintln!(
+                "Error: --format {} cannot be used with {}",
+                args.format.name(),
+                flag
+            );
+            std::process::exit(2);
+        }
+    }
+    if args.output.is_some() && !args.format.is_report() && args.format != Format::GithubSummary {
+        eprintln!(
+            "Error: --output requires --format markdown, html, tap, eslint-json or github-summary"
+        );
+        std::process::exit(2);
+    }
+    configure_threads(args.threads);
+
+    let root = if let Some(stdin_filename) = &args.stdin_filename {
+        // Config discovery and ignore patterns apply as if the file were on disk
+        PathBuf::from(stdin_filename)
+    } else if args.path == "." {
         env::current_dir().expect("Failed to get current directory")
     } else {
         Path::new(&args.path).to_path_buf()
     };
 
-    let mut all_diagnostics: Vec<LintDiagnostic> = Vec::new();
-    let mut files_checked = 0;
+    let config = match Config::discover(&root) {
+        Ok(config) => config.with_overrides(&args.rule, &args.deny),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
+    };
 
-    if root.is_file() {
-        if is_js_or_ts_file(&root) {
-            if let Some(diagnostics) = lint_file(&root) {
-                all_diagnostics.extend(diagnostics);
-                files_checked += 1;
-            }
+    if args.watch {
+        watch::run_watch(&root, &config, args.format);
+        return;
+    }
+
+    let mut cache = args
+        .cache
+        .then(|| LintCache::load(Path::new(CACHE_FILE_NAME), &config));
+
+    let baseline = match args.baseline.as_deref().map(Baseline::load).transpose() {
+        Ok(baseline) => baseline.map(Mutex::new),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
         }
-    } else {
-        for entry in WalkDir::new(&root)
+    };
+
+    let mut stats = Stats::default();
+    let results: Vec<(PathBuf, FileResult)> = if args.stdin {
+        stats.record_walk(1, Default::default());
+        lint_stdin(&root, &config, &stats)
+            .map(|(path, result)| (path, finish_file(result, args.format, baseline.as_ref())))
             .into_iter()
-            .filter_entry(|e| {
-                if e.file_type().is_dir() {
-                    !should_skip_dir(e.file_name().to_str().unwrap_or(""))
-                } else {
-                    true
+            .collect()
+    } else {
+        let walk_start = Instant::now();
+        let mut files = if args.entrypoints {
+            match collect_entrypoint_files(&root, &config) {
+                Ok(files) => files,
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
                 }
-            })
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-
-            if path.is_file() && is_js_or_ts_file(path) {
-                if let Some(diagnostics) = lint_file(path) {
-                    all_diagnostics.extend(diagnostics);
-                    files_checked += 1;
+            }
+        } else {
+            collect_files(&root, &config)
+        };
+        stats.record_walk(files.len(), walk_start.elapsed());
+        let since = args.since.as_deref().or(args.changed.then_some("HEAD"));
+        if let Some(since) = since {
+            match git::changed_files(&root, since) {
+                Ok(changed) => files.retain(|path| {
+                    changed.contains(&std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+                }),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(2);
                 }
             }
         }
+        files
+            .into_par_iter()
+            .filter_map(|path| {
+                let result = process_file(&path, &args, &config, cache.as_ref(), &stats)?;
+                Some((path, finish_file(result, args.format, baseline.as_ref())))
+            })
+            .collect()
+    };
+
+    let mut all_diagnostics: Vec<LintDiagnostic> = Vec::new();
+    let files_checked = results.len();
+    let mut fixes_applied = 0;
+    let mut files_fixed = 0;
+    let mut suppressed = 0;
+
+    // Bindings are checked against the whole Worker and class names across every linted
+    // file, so only when every file is linted
+    if !args.stdin && !args.changed && args.since.is_none() {
+        let mut diagnostics = lint_wrangler_bindings(&root, &config);
+        diagnostics.extend(lint_workflow_classes(&results, &config));
+        let result = FileResult {
+            diagnostics,
+            ..FileResult::default()
+        };
+        let result = finish_file(result, args.format, baseline.as_ref());
+        suppressed += result.suppressed;
+        all_diagnostics.extend(result.reported);
+    }
+    let mut checked_files = Vec::with_capacity(files_checked);
+
+    for (path, result) in results {
+        checked_files.push(path.to_str().unwrap_or("").to_string());
+        if let Some(diff) = &result.diff {
+            print!("{}", diff);
+        }
+        if result.fixes_applied > 0 {
+            fixes_applied += result.fixes_applied;
+            files_fixed += 1;
+        }
+        if let (Some(cache), Some(hash)) = (cache.as_mut(), result.cache_hash) {
+            cache.insert(
+                path.to_str().unwrap_or(""),
+                hash,
+                result.diagnostics,
+                result.workflow_classes,
+            );
+        }
+        suppressed += result.suppressed;
+        all_diagnostics.extend(result.reported);
     }
 
-    // Print diagnostics
-    for diagnostic in &all_diagnostics {
+    if let Some(cache) = cache.as_mut() {
+        if let Err(err) = cache.save() {
+            eprintln!("Warning: {}", err);
+        }
+    }
+
+    if let Some(baseline_path) = &args.baseline_generate {
+        if let Err(err) = baseline::generate(baseline_path, &all_diagnostics) {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
         println!(
-            "{}:{}:{} - {} [{}]",
-            diagnostic.file,
-            diagnostic.line,
-            diagnostic.column,
-            diagnostic.message,
-            diagnostic.rule
+            "✓ Wrote {} issue(s) to baseline {}",
+            all_diagnostics.len(),
+            baseline_path.display()
         );
+        return;
     }
 
-    // Print summary
-    println!();
-    if all_diagnostics.is_empty() {
-        println!("✓ No issues found ({} files checked)", files_checked);
-    } else {
+    let text_output = args.format.is_text() && !args.quiet;
+    if let Some(baseline_path) = &args.baseline {
+        if suppressed > 0 && text_output {
+            println!(
+                "{} pre-existing issue(s) suppressed by baseline {}",
+                suppressed,
+                baseline_path.display()
+            );
+        }
+    }
+
+    if args.fix_dry_run {
+        if fixes_applied > 0 {
+            println!();
+        }
         println!(
-            "✗ Found {} issue(s) in {} file(s) checked",
-            all_diagnostics.len(),
-            files_checked
+            "{} fix(es) available in {} file(s) (dry run, no files were changed)",
+            fixes_applied, files_fixed
+        );
+    } else if fixes_applied > 0 && text_output {
+        println!(
+            "✓ Fixed {} issue(s) in {} file(s)",
+            fixes_applied, files_fixed
+        );
+    }
+
+    let summary = if args.format == Format::Ndjson {
+        // Diagnostics were already streamed; stdout carries nothing but NDJSON
+        Summary::new(&all_diagnostics, files_checked)
+    } else if args.format.is_report() {
+        let summary = Summary::new(&all_diagnostics, files_checked);
+        let report = match args.format {
+            Format::Html => html::render_html(&all_diagnostics, &summary),
+            Format::Tap => render_tap(&checked_files, &all_diagnostics, args.fail_on),
+            Format::EslintJson => render_eslint_json(&checked_files, &all_diagnostics),
+            _ => render_markdown(&all_diagnostics, &summary),
+        };
+        match &args.output {
+            Some(output) => {
+                if let Err(err) = fs::write(output, report) {
+                    eprintln!("Error: failed to write {}: {}", output.display(), err);
+                    std::process::exit(2);
+                }
+                println!(
+                    "✓ Wrote {} report to {}",
+                    args.format.name(),
+                    output.display()
+                );
+            }
+            None => print!("{}", report),
+        }
+        summary
+    } else if args.format == Format::GithubSummary {
+        let summary = Summary::new(&all_diagnostics, files_checked);
+        print!(
+            "{}",
+            render_github_annotations(&all_diagnostics, args.max_annotations)
         );
-        std::process::exit(1);
+        print_summary(&summary);
+        // Outside GitHub Actions there is nowhere to write the job summary unless --output is given
+        let summary_path = args
+            .output
+            .clone()
+            .or_else(|| env::var_os("GITHUB_STEP_SUMMARY").map(PathBuf::from));
+        if let Some(summary_path) = summary_path {
+            let written = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&summary_path)
+                .and_then(|mut file| {
+                    file.write_all(render_markdown(&all_diagnostics, &summary).as_bytes())
+                });
+            if let Err(err) = written {
+                eprintln!("Error: failed to write {}: {}", summary_path.display(), err);
+                std::process::exit(2);
+            }
+        }
+        summary
+    } else if args.quiet {
+        let summary = Summary::new(&all_diagnostics, files_checked);
+        print_summary(&summary);
+        summary
+    } else {
+        print_text_report(args.format, &all_diagnostics, files_checked)
+    };
+    if args.stats {
+        stats.print(&all_diagnostics, start.elapsed());
+    }
+    let policy = ExitPolicy {
+        fail_on: args.fail_on,
+        max_warnings: args.max_warnings,
+        exit_zero: args.exit_zero,
+    };
+    if let Some(message) = policy.max_warnings_message(&summary) {
+        if args.format == Format::Ndjson {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+    let exit_code = policy.exit_code(&summary);
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
 }
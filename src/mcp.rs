@@ -0,0 +1,210 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use cashmere::config::Config;
+use cashmere::rules::{find_rule, ALL_RULES};
+use cashmere::{lint_structured_with_config, LintResult};
+
+/// Protocol version used when the client asks for one we don't know
+const PROTOCOL_VERSION: &str = "2025-06-18";
+const SUPPORTED_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve the Model Context Protocol over stdio until stdin closes, exposing
+/// `lint_file`, `lint_text` and `explain_rule` tools
+pub fn run_mcp_server(only: &[String], deny: &[String]) {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message, only, deny),
+            Err(err) => Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())),
+        };
+        if let Some(response) = response {
+            let _ = writeln!(stdout, "{}", response);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// Handle one JSON-RPC message, returning the response for requests
+fn handle_message(message: &Value, only: &[String], deny: &[String]) -> Option<Value> {
+    // Notifications such as `notifications/initialized` have no id and get no response
+    let id = message.get("id")?.clone();
+    let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(&params, only, deny),
+        _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|version| SUPPORTED_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSION);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "cashmere", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": concat!(
+            "Lint Cloudflare Workflows code before suggesting it. Every step.do, step.sleep, ",
+            "step.sleepUntil and step.waitForEvent call must be awaited."
+        ),
+    })
+}
+
+fn tools() -> Value {
+    let rule_ids: Vec<&str> = ALL_RULES.iter().map(|rule| rule.id).collect();
+    json!([
+        {
+            "name": "lint_file",
+            "description": concat!(
+                "Lint a JavaScript or TypeScript file on disk for Cloudflare Workflows mistakes, ",
+                "using the nearest cashmere.toml. Returns the diagnostics as JSON."
+            ),
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path of the file to lint" }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "lint_text",
+            "description": concat!(
+                "Lint Cloudflare Workflows source code that hasn't been written to disk yet. ",
+                "Returns the diagnostics as JSON."
+            ),
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string", "description": "Source code to lint" },
+                    "filename": {
+                        "type": "string",
+                        "description": "File name used to pick the language and cashmere.toml (defaults to workflow.ts)"
+                    }
+                },
+                "required": ["source"]
+            }
+        },
+        {
+            "name": "explain_rule",
+            "description": "Explain what a cashmere rule checks and why it matters.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "rule": { "type": "string", "enum": rule_ids }
+                },
+                "required": ["rule"]
+            }
+        }
+    ])
+}
+
+fn call_tool(params: &Value, only: &[String], deny: &[String]) -> Result<Value, (i64, String)> {
+    let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+    let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+    let argument = |key: &str| arguments.get(key).and_then(Value::as_str);
+    let missing = |key: &str| (INVALID_PARAMS, format!("missing `{}` argument", key));
+
+    let outcome = match name {
+        "lint_file" => {
+            let path = argument("path").ok_or_else(|| missing("path"))?;
+            fs::read_to_string(path)
+                .map_err(|err| format!("failed to read {}: {}", path, err))
+                .and_then(|source| lint(&source, path, only, deny))
+                .map(lint_output)
+        }
+        "lint_text" => {
+            let source = argument("source").ok_or_else(|| missing("source"))?;
+            let filename = argument("filename").unwrap_or("workflow.ts");
+            lint(source, filename, only, deny).map(lint_output)
+        }
+        "explain_rule" => {
+            let id = argument("rule").ok_or_else(|| missing("rule"))?;
+            explain_rule(id).map(|text| (text, None))
+        }
+        _ => return Err((INVALID_PARAMS, format!("unknown tool `{}`", name))),
+    };
+
+    // Tool failures are reported to the model as results, not protocol errors
+    Ok(match outcome {
+        Ok((text, structured)) => {
+            let mut result = json!({ "content": [{ "type": "text", "text": text }] });
+            if let Some(structured) = structured {
+                result["structuredContent"] = structured;
+            }
+            result
+        }
+        Err(err) => json!({ "content": [{ "type": "text", "text": err }], "isError": true }),
+    })
+}
+
+/// Lint `source` as `filename`, applying the config found from the file's directory
+fn lint(
+    source: &str,
+    filename: &str,
+    only: &[String],
+    deny: &[String],
+) -> Result<LintResult, String> {
+    let path = Path::new(filename);
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let config = Config::discover(dir)?.with_overrides(only, deny);
+    Ok(lint_structured_with_config(source, filename, &config))
+}
+
+fn lint_output(result: LintResult) -> (String, Option<Value>) {
+    let structured = serde_json::to_value(&result).unwrap_or(Value::Null);
+    let text = if result.diagnostics.is_empty() && result.parse_errors.is_empty() {
+        format!("No problems found in {}", result.file)
+    } else {
+        serde_json::to_string_pretty(&structured).unwrap_or_default()
+    };
+    (text, Some(structured))
+}
+
+fn explain_rule(id: &str) -> Result<String, String> {
+    let rule = find_rule(id).ok_or_else(|| {
+        let known: Vec<&str> = ALL_RULES.iter().map(|rule| rule.id).collect();
+        format!("unknown rule `{}`; known rules: {}", id, known.join(", "))
+    })?;
+    Ok(format!(
+        "{} ({}{})\n\n{}\n\n{}\n\nDocs: {}",
+        rule.id,
+        rule.default_severity.as_str(),
+        if rule.fixable { ", fixable" } else { "" },
+        rule.description,
+        rule.explanation,
+        rule.docs_url
+    ))
+}
@@ -0,0 +1,392 @@
+//! Third-party rules compiled to WebAssembly and listed under `plugins` in
+//! `cashmere.toml`, so teams can ship their own rules without forking cashmere.
+//!
+//! A plugin is a module (binary `.wasm` or `.wat` text) with no imports that
+//! exports, for plugin API version 1:
+//!
+//! - `memory`
+//! - `cashmere_alloc(len: i32) -> i32`, returning a buffer of `len` bytes for the host to write into
+//! - `cashmere_rules() -> i64`, describing the plugin's rules as JSON:
+//!   `{"api_version": 1, "rules": [{"id", "description", "explanation", "docs_url", "default_severity"}]}`
+//! - `cashmere_check(ptr: i32, len: i32) -> i64`, given `{"rule", "file", "source", "events"}`
+//!   JSON and returning `[{"start", "end", "message", "severity"}]`, where `start` and `end` are
+//!   byte offsets into the source and `severity` is optional
+//!
+//! Results are returned as a pointer into `memory` in the high 32 bits and a length in the
+//! low 32 bits. `events` are the linter's traversal hooks in source order, tagged by `type`:
+//! `enter_function`, `leave_function`, `step_call`, `awaited_vars` and `call`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use oxc_ast::ast::CallExpression;
+use oxc_span::{GetSpan, Span};
+use serde::{Deserialize, Serialize};
+use wasmtime::{Engine, Instance, Module, Store};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{
+    callee_path, FunctionScope, Rule, RuleContext, RuleMeta, RuleRegistry, StepUsage,
+};
+
+/// The plugin API version this build of cashmere speaks
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Instructions a plugin may run per call before it is stopped
+const FUEL_PER_CALL: u64 = 1_000_000_000;
+
+#[derive(Deserialize)]
+struct Manifest {
+    api_version: u32,
+    rules: Vec<ManifestRule>,
+}
+
+#[derive(Deserialize)]
+struct ManifestRule {
+    id: String,
+    description: String,
+    #[serde(default)]
+    explanation: String,
+    #[serde(default)]
+    docs_url: String,
+    #[serde(default = "default_plugin_severity")]
+    default_severity: Severity,
+}
+
+fn default_plugin_severity() -> Severity {
+    Severity::Error
+}
+
+/// A diagnostic returned by `cashmere_check`
+#[derive(Deserialize)]
+struct PluginDiagnostic {
+    start: u32,
+    end: u32,
+    message: String,
+    #[serde(default)]
+    severity: Option<Severity>,
+}
+
+#[derive(Serialize)]
+struct CheckInput<'a> {
+    rule: &'a str,
+    file: &'a str,
+    source: &'a str,
+    events: &'a [Event],
+}
+
+/// A traversal hook, serialized for `cashmere_check`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event {
+    EnterFunction {
+        is_async: bool,
+        /// The function body; `None` for the top level of the file
+        body: Option<(u32, u32)>,
+    },
+    LeaveFunction,
+    StepCall {
+        receiver: String,
+        method: String,
+        name: Option<String>,
+        start: u32,
+        end: u32,
+        /// `awaited`, `assigned`, `stored`, `statement` or `unawaited`
+        usage: &'static str,
+        /// The variable an `assigned` promise, or the array or object a `stored` one,
+        /// was stored in
+        variable: Option<String>,
+        config_keys: Option<Vec<String>>,
+    },
+    AwaitedVars {
+        names: Vec<String>,
+    },
+    Call {
+        /// The dotted name of the callee, e.g. `console.log`, if it has one
+        callee: Option<String>,
+        start: u32,
+        end: u32,
+    },
+}
+
+/// Plugins loaded so far, by path and a hash of their contents
+type LoadedPlugins = HashMap<(PathBuf, u64), Arc<Plugin>>;
+
+/// A loaded plugin module and the rules it provides
+pub struct Plugin {
+    path: PathBuf,
+    module: Module,
+    rules: Vec<&'static RuleMeta>,
+    hash: u64,
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<&str> = self.rules.iter().map(|rule| rule.id).collect();
+        f.debug_struct("Plugin")
+            .field("path", &self.path)
+            .field("rules", &ids)
+            .finish()
+    }
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).expect("the plugin engine config is valid")
+    })
+}
+
+impl Plugin {
+    /// Load a plugin and read its rules. Plugins are cached by path and contents, so
+    /// reloading the config doesn't recompile them.
+    pub fn load(path: &Path) -> Result<Arc<Plugin>, String> {
+        static LOADED: OnceLock<Mutex<LoadedPlugins>> = OnceLock::new();
+
+        let fail = |err: String| format!("Failed to load plugin {}: {}", path.display(), err);
+        let bytes = fs::read(path).map_err(|err| fail(err.to_string()))?;
+        let hash = xxh3_64(&bytes);
+        let key = (path.to_path_buf(), hash);
+        let loaded = LOADED.get_or_init(Default::default);
+        if let Some(plugin) = loaded.lock().unwrap().get(&key) {
+            return Ok(Arc::clone(plugin));
+        }
+
+        let module = Module::new(engine(), &bytes).map_err(|err| fail(err.to_string()))?;
+        let mut plugin = Plugin {
+            path: path.to_path_buf(),
+            module,
+            rules: Vec::new(),
+            hash,
+        };
+        let manifest: Manifest =
+            serde_json::from_slice(&plugin.call("cashmere_rules", None).map_err(fail)?)
+                .map_err(|err| fail(format!("invalid cashmere_rules output: {}", err)))?;
+        if manifest.api_version != PLUGIN_API_VERSION {
+            return Err(fail(format!(
+                "it uses plugin API version {}, but this cashmere supports version {}",
+                manifest.api_version, PLUGIN_API_VERSION
+            )));
+        }
+        // Rule metadata lives for the whole process; the cache above loads each plugin once
+        plugin.rules = manifest
+            .rules
+            .into_iter()
+            .map(|rule| {
+                &*Box::leak(Box::new(RuleMeta {
+                    id: Box::leak(rule.id.into_boxed_str()),
+                    default_severity: rule.default_severity,
+                    enabled_by_default: true,
+                    fixable: false,
+                    description: Box::leak(rule.description.into_boxed_str()),
+                    explanation: Box::leak(rule.explanation.into_boxed_str()),
+                    docs_url: Box::leak(rule.docs_url.into_boxed_str()),
+                }))
+            })
+            .collect();
+
+        let plugin = Arc::new(plugin);
+        loaded.lock().unwrap().insert(key, Arc::clone(&plugin));
+        Ok(plugin)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn rules(&self) -> &[&'static RuleMeta] {
+        &self.rules
+    }
+
+    /// A hash of the module's contents, so cached results are dropped when it changes
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Call an export in a fresh instance, passing `input` if given, and return its output
+    fn call(&self, export: &str, input: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        let mut store = Store::new(engine(), ());
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|err| err.to_string())?;
+        let instance =
+            Instance::new(&mut store, &self.module, &[]).map_err(|err| err.to_string())?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("it doesn't export `memory`")?;
+
+        let result = match input {
+            Some(input) => {
+                let alloc = instance
+                    .get_typed_func::<i32, i32>(&mut store, "cashmere_alloc")
+                    .map_err(|err| err.to_string())?;
+                let ptr = alloc
+                    .call(&mut store, input.len() as i32)
+                    .map_err(|err| err.to_string())?;
+                memory
+                    .write(&mut store, ptr as u32 as usize, input)
+                    .map_err(|err| err.to_string())?;
+                instance
+                    .get_typed_func::<(i32, i32), i64>(&mut store, export)
+                    .map_err(|err| err.to_string())?
+                    .call(&mut store, (ptr, input.len() as i32))
+            }
+            None => instance
+                .get_typed_func::<(), i64>(&mut store, export)
+                .map_err(|err| err.to_string())?
+                .call(&mut store, ()),
+        }
+        .map_err(|err| format!("`{}` failed: {}", export, err))?;
+
+        let ptr = (result as u64 >> 32) as usize;
+        let len = (result as u64 & 0xffff_ffff) as usize;
+        memory
+            .data(&store)
+            .get(ptr..ptr + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| format!("`{}` returned a buffer outside its memory", export))
+    }
+
+    fn check(
+        &self,
+        rule: &str,
+        file: &str,
+        source: &str,
+        events: &[Event],
+    ) -> Result<Vec<PluginDiagnostic>, String> {
+        let input = serde_json::to_vec(&CheckInput {
+            rule,
+            file,
+            source,
+            events,
+        })
+        .map_err(|err| err.to_string())?;
+        let output = self.call("cashmere_check", Some(&input))?;
+        serde_json::from_slice(&output)
+            .map_err(|err| format!("invalid cashmere_check output: {}", err))
+    }
+}
+
+/// Add a rule to `registry` for every rule the plugins provide
+pub fn register_plugins(registry: &mut RuleRegistry, plugins: &[Arc<Plugin>]) {
+    for plugin in plugins {
+        for &meta in plugin.rules() {
+            let plugin = Arc::clone(plugin);
+            registry.register_with(meta, move || {
+                Box::new(PluginRule {
+                    plugin: Arc::clone(&plugin),
+                    meta,
+                    events: Vec::new(),
+                    depth: 0,
+                })
+            });
+        }
+    }
+}
+
+/// Records the traversal of a file and hands it to the plugin once the file is done
+struct PluginRule {
+    plugin: Arc<Plugin>,
+    meta: &'static RuleMeta,
+    events: Vec<Event>,
+    /// Function scopes entered and not yet left, including the top level
+    depth: usize,
+}
+
+impl PluginRule {
+    fn check_file(&mut self, ctx: &mut RuleContext) {
+        let events = std::mem::take(&mut self.events);
+        let source = ctx.source();
+        match self
+            .plugin
+            .check(self.meta.id, ctx.file_path(), source, &events)
+        {
+            Ok(diagnostics) => {
+                let len = source.len() as u32;
+                for found in diagnostics {
+                    let end = found.end.min(len);
+                    let span = Span::new(found.start.min(end), end);
+                    let mut diagnostic = ctx.diagnostic(span, &found.message, self.meta);
+                    if let Some(severity) = found.severity {
+                        diagnostic.severity = severity;
+                    }
+                    ctx.report(diagnostic);
+                }
+            }
+            Err(err) => {
+                let message = format!("Plugin {} failed: {}", self.plugin.path.display(), err);
+                let diagnostic = ctx.diagnostic(Span::new(0, 0), &message, self.meta);
+                ctx.report(diagnostic);
+            }
+        }
+    }
+}
+
+impl Rule for PluginRule {
+    fn meta(&self) -> &'static RuleMeta {
+        self.meta
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, scope: FunctionScope) {
+        self.depth += 1;
+        self.events.push(Event::EnterFunction {
+            is_async: scope.is_async,
+            body: scope.body.map(|body| (body.start, body.end)),
+        });
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        self.events.push(Event::LeaveFunction);
+        self.depth = self.depth.saturating_sub(1);
+        // Leaving the top level means the whole file has been walked
+        if self.depth == 0 {
+            self.check_file(ctx);
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        _ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        let (usage, variable) = match usage {
+            StepUsage::Awaited => ("awaited", None),
+            StepUsage::Assigned { name, .. } => ("assigned", name.clone()),
+            StepUsage::Stored { container, .. } => ("stored", Some(container.clone())),
+            StepUsage::Statement => ("statement", None),
+            StepUsage::Unawaited => ("unawaited", None),
+        };
+        self.events.push(Event::StepCall {
+            receiver: call.receiver.clone(),
+            method: call.method.clone(),
+            name: call.name.clone(),
+            start: call.span.start,
+            end: call.span.end,
+            usage,
+            variable,
+            config_keys: call.config_keys.clone(),
+        });
+    }
+
+    fn check_awaited_vars(&mut self, _ctx: &mut RuleContext, names: &[&str]) {
+        self.events.push(Event::AwaitedVars {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        });
+    }
+
+    fn check_call(&mut self, _ctx: &mut RuleContext, call: &CallExpression) {
+        self.events.push(Event::Call {
+            callee: callee_path(&call.callee),
+            start: call.span().start,
+            end: call.span().end,
+        });
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::linter::{LintDiagnostic, Severity};
+
+/// Counts from a finished lint run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub files_checked: usize,
+}
+
+impl Summary {
+    pub fn new(diagnostics: &[LintDiagnostic], files_checked: usize) -> Self {
+        let warnings = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .count();
+        Self {
+            errors: diagnostics.len() - warnings,
+            warnings,
+            files_checked,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.errors + self.warnings
+    }
+}
+
+/// How diagnostics are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Format {
+    /// Human-readable diagnostics followed by a summary line
+    Text,
+    /// Human-readable diagnostics grouped under a header per file, with per-rule totals
+    Stylish,
+    /// One JSON object per line, streamed as each file is linted
+    Ndjson,
+    /// A Markdown report grouped by file, suitable for a pull request comment
+    Markdown,
+    /// A standalone HTML report with source snippets and rule filters
+    Html,
+    /// Test Anything Protocol output with one test point per checked file
+    Tap,
+    /// ESLint's JSON results schema, for tools that already parse ESLint output
+    EslintJson,
+    /// GitHub Actions annotations grouped by file, plus a Markdown job summary
+    /// written to `$GITHUB_STEP_SUMMARY`
+    GithubSummary,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Stylish => "stylish",
+            Format::Ndjson => "ndjson",
+            Format::Markdown => "markdown",
+            Format::Html => "html",
+            Format::Tap => "tap",
+            Format::EslintJson => "eslint-json",
+            Format::GithubSummary => "github-summary",
+        }
+    }
+
+    /// Whether the format is meant for people reading a terminal
+    pub fn is_text(&self) -> bool {
+        matches!(self, Format::Text | Format::Stylish)
+    }
+
+    /// Whether the format renders a whole report at the end of the run,
+    /// which can be written to a file with `--output`
+    pub fn is_report(&self) -> bool {
+        matches!(
+            self,
+            Format::Markdown | Format::Html | Format::Tap | Format::EslintJson
+        )
+    }
+}
+
+/// Write diagnostics to stdout as newline-delimited JSON and flush immediately,
+/// so consumers can process them while the rest of the run is still going
+pub fn write_ndjson(diagnostics: &[LintDiagnostic]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for diagnostic in diagnostics {
+        serde_json::to_writer(&mut stdout, diagnostic)?;
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()
+}
+
+/// Print diagnostics followed by a summary line
+pub fn print_report(diagnostics: &[LintDiagnostic], files_checked: usize) -> Summary {
+    for diagnostic in diagnostics {
+        let prefix = match diagnostic.severity {
+            Severity::Warning => "warning: ",
+            Severity::Error => "",
+        };
+        println!(
+            "{}:{}:{} - {}{} [{}]",
+            diagnostic.file,
+            diagnostic.line,
+            diagnostic.column,
+            prefix,
+            diagnostic.message,
+            diagnostic.rule
+        );
+    }
+
+    let summary = Summary::new(diagnostics, files_checked);
+    println!();
+    print_summary(&summary);
+    summary
+}
+
+/// Print diagnostics in one of the human-readable formats followed by a summary line
+pub fn print_text_report(
+    format: Format,
+    diagnostics: &[LintDiagnostic],
+    files_checked: usize,
+) -> Summary {
+    match format {
+        Format::Stylish => print_stylish(diagnostics, files_checked),
+        _ => print_report(diagnostics, files_checked),
+    }
+}
+
+/// Print diagnostics ESLint-style: each file path once as a header followed by aligned
+/// `line:column severity message rule` rows, then the number of diagnostics per rule
+pub fn print_stylish(diagnostics: &[LintDiagnostic], files_checked: usize) -> Summary {
+    let mut by_file: BTreeMap<&str, Vec<&LintDiagnostic>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        by_file
+            .entry(&diagnostic.file)
+            .or_default()
+            .push(diagnostic);
+    }
+
+    for (file, diagnostics) in &by_file {
+        let positions: Vec<String> = diagnostics
+            .iter()
+            .map(|d| format!("{}:{}", d.line, d.column))
+            .collect();
+        let position_width = positions.iter().map(String::len).max().unwrap_or(0);
+        let message_width = diagnostics
+            .iter()
+            .map(|d| d.message.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        println!("{}", file);
+        for (diagnostic, position) in diagnostics.iter().zip(&positions) {
+            println!(
+                "  {:<position_width$}  {:<7}  {:<message_width$}  {}",
+                position,
+                diagnostic.severity.as_str(),
+                diagnostic.message,
+                diagnostic.rule
+            );
+        }
+        println!();
+    }
+
+    if !diagnostics.is_empty() {
+        let mut rule_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for diagnostic in diagnostics {
+            *rule_counts.entry(&diagnostic.rule).or_default() += 1;
+        }
+        let rule_width = rule_counts.keys().map(|rule| rule.len()).max().unwrap_or(0);
+        println!("Rule totals:");
+        for (rule, count) in &rule_counts {
+            println!("  {:<rule_width$}  {}", rule, count);
+        }
+    }
+
+    let summary = Summary::new(diagnostics, files_checked);
+    println!();
+    print_summary(&summary);
+    summary
+}
+
+/// Print only the pass/fail summary line
+pub fn print_summary(summary: &Summary) {
+    println!("{}", summary_line(summary));
+}
+
+pub fn summary_line(summary: &Summary) -> String {
+    if summary.total() == 0 {
+        format!(
+            "✓ No issues found ({} files checked)",
+            summary.files_checked
+        )
+    } else if summary.errors == 0 {
+        format!(
+            "⚠ Found {} warning(s) in {} file(s) checked",
+            summary.warnings, summary.files_checked
+        )
+    } else if summary.warnings == 0 {
+        format!(
+            "✗ Found {} issue(s) in {} file(s) checked",
+            summary.total(),
+            summary.files_checked
+        )
+    } else {
+        format!(
+            "✗ Found {} issue(s) ({} warning(s)) in {} file(s) checked",
+            summary.total(),
+            summary.warnings,
+            summary.files_checked
+        )
+    }
+}
+
+/// Render a Markdown report with a per-rule summary table and diagnostics grouped by file,
+/// e.g. for a CI bot to post as a pull request comment
+pub fn render_markdown(diagnostics: &[LintDiagnostic], summary: &Summary) -> String {
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "## cashmere report");
+    let _ = writeln!(markdown);
+    let _ = writeln!(markdown, "{}", summary_line(summary));
+    if diagnostics.is_empty() {
+        return markdown;
+    }
+
+    let mut rule_counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let (errors, warnings) = rule_counts.entry(&diagnostic.rule).or_default();
+        match diagnostic.severity {
+            Severity::Error => *errors += 1,
+            Severity::Warning => *warnings += 1,
+        }
+    }
+    let _ = writeln!(markdown);
+    let _ = writeln!(markdown, "| Rule | Errors | Warnings |");
+    let _ = writeln!(markdown, "| --- | ---: | ---: |");
+    for (rule, (errors, warnings)) in &rule_counts {
+        let _ = writeln!(markdown, "| `{}` | {} | {} |", rule, errors, warnings);
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&LintDiagnostic>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        by_file
+            .entry(&diagnostic.file)
+            .or_default()
+            .push(diagnostic);
+    }
+    for (file, diagnostics) in by_file {
+        let _ = writeln!(markdown);
+        let _ = writeln!(markdown, "### `{}`", file);
+        let _ = writeln!(markdown);
+        for diagnostic in diagnostics {
+            let _ = writeln!(
+                markdown,
+                "- **{}** `{}` at line {}, column {}: {}",
+                diagnostic.severity.as_str(),
+                diagnostic.rule,
+                diagnostic.line,
+                diagnostic.column,
+                diagnostic.message
+            );
+        }
+    }
+    markdown
+}
+
+/// Render GitHub Actions workflow commands: one collapsible group per file, with an
+/// annotation for each of the first `max_annotations` diagnostics and a plain log line
+/// for the rest, which GitHub would otherwise drop
+pub fn render_github_annotations(diagnostics: &[LintDiagnostic], max_annotations: usize) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut by_file: BTreeMap<String, Vec<&LintDiagnostic>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        // Annotations need paths relative to the repository checkout
+        let path = std::path::Path::new(&diagnostic.file);
+        let file = path
+            .strip_prefix(&cwd)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        by_file.entry(file).or_default().push(diagnostic);
+    }
+
+    let mut output = String::new();
+    let mut annotated = 0;
+    for (file, diagnostics) in &by_file {
+        let _ = writeln!(
+            output,
+            "::group::{} ({} issue(s))",
+            escape_github_data(file),
+            diagnostics.len()
+        );
+        for diagnostic in diagnostics {
+            if annotated < max_annotations {
+                annotated += 1;
+                let _ = writeln!(
+                    output,
+                    "::{} file={},line={},col={},endLine={},endColumn={},title={}::{}",
+                    match diagnostic.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    escape_github_property(file),
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.end_line,
+                    diagnostic.end_column,
+                    escape_github_property(&format!("cashmere ({})", diagnostic.rule)),
+                    escape_github_data(&diagnostic.message)
+                );
+            } else {
+                let _ = writeln!(
+                    output,
+                    "{}:{}:{} - {}: {} [{}]",
+                    file,
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.severity.as_str(),
+                    diagnostic.message,
+                    diagnostic.rule
+                );
+            }
+        }
+        let _ = writeln!(output, "::endgroup::");
+    }
+    if diagnostics.len() > annotated {
+        let _ = writeln!(
+            output,
+            "::notice title=cashmere::{} more issue(s) were not annotated (limit {}); see the log or job summary",
+            diagnostics.len() - annotated,
+            max_annotations
+        );
+    }
+    output
+}
+
+/// Escape the message of a GitHub workflow command
+fn escape_github_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a GitHub workflow command
+fn escape_github_property(value: &str) -> String {
+    escape_github_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Render TAP version 13 output with one test point per checked file. A file fails if it
+/// has a diagnostic at or above the `--fail-on` severity, and its diagnostics are
+/// attached as a YAML block.
+pub fn render_tap(files: &[String], diagnostics: &[LintDiagnostic], fail_on: FailOn) -> String {
+    let mut by_file: HashMap<&str, Vec<&LintDiagnostic>> = HashMap::new();
+    for diagnostic in diagnostics {
+        by_file
+            .entry(&diagnostic.file)
+            .or_default()
+            .push(diagnostic);
+    }
+
+    let mut tap = String::new();
+    let _ = writeln!(tap, "TAP version 13");
+    let _ = writeln!(tap, "1..{}", files.len());
+    for (index, file) in files.iter().enumerate() {
+        let diagnostics = by_file.get(file.as_str()).map(Vec::as_slice).unwrap_or(&[]);
+        let failed = diagnostics.iter().any(|d| fail_on.includes(d.severity));
+        let status = if failed { "not ok" } else { "ok" };
+        let _ = writeln!(tap, "{} {} - {}", status, index + 1, file);
+        if diagnostics.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(tap, "  ---");
+        let _ = writeln!(tap, "  diagnostics:");
+        for diagnostic in diagnostics {
+            // JSON strings are valid YAML scalars, so serde_json takes care of escaping
+            let _ = writeln!(
+                tap,
+                "    - message: {}",
+                serde_json::Value::from(diagnostic.message.as_str())
+            );
+            let _ = writeln!(tap, "      rule: {}", diagnostic.rule);
+            let _ = writeln!(tap, "      severity: {}", diagnostic.severity.as_str());
+            let _ = writeln!(tap, "      line: {}", diagnostic.line);
+            let _ = writeln!(tap, "      column: {}", diagnostic.column);
+            let _ = writeln!(tap, "      endLine: {}", diagnostic.end_line);
+            let _ = writeln!(tap, "      endColumn: {}", diagnostic.end_column);
+        }
+        let _ = writeln!(tap, "  ...");
+    }
+    tap
+}
+
+/// Render results in the schema of ESLint's `json` formatter: one entry per checked
+/// file with its messages and error/warning counts
+pub fn render_eslint_json(files: &[String], diagnostics: &[LintDiagnostic]) -> String {
+    let mut by_file: HashMap<&str, Vec<&LintDiagnostic>> = HashMap::new();
+    for diagnostic in diagnostics {
+        by_file
+            .entry(&diagnostic.file)
+            .or_default()
+            .push(diagnostic);
+    }
+
+    let results: Vec<serde_json::Value> = files
+        .iter()
+        .map(|file| {
+            let mut diagnostics = by_file.get(file.as_str()).cloned().unwrap_or_default();
+            diagnostics.sort_by_key(|d| (d.line, d.column));
+            // ESLint fix ranges are offsets into the JS string, counted in UTF-16 code units
+            let source = diagnostics
+                .iter()
+                .any(|d| d.fix.is_some())
+                .then(|| std::fs::read_to_string(file).ok())
+                .flatten();
+            let count = |severity: Severity, fixable: bool| {
+                diagnostics
+                    .iter()
+                    .filter(|d| d.severity == severity && (!fixable || d.fix.is_some()))
+                    .count()
+            };
+            let messages: Vec<serde_json::Value> = diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    let mut message = serde_json::json!({
+                        "ruleId": diagnostic.rule,
+                        "severity": match diagnostic.severity {
+                            Severity::Warning => 1,
+                            Severity::Error => 2,
+                        },
+                        "message": diagnostic.message,
+                        "line": diagnostic.line,
+                        "column": diagnostic.column,
+                        "endLine": diagnostic.end_line,
+                        "endColumn": diagnostic.end_column,
+                    });
+                    if let (Some(source), Some(fix)) = (&source, &diagnostic.fix) {
+                        if let [edit] = fix.edits.as_slice() {
+                            let utf16 = |offset: u32| {
+                                source
+                                    .get(..offset as usize)
+                                    .map(|prefix| prefix.encode_utf16().count())
+                            };
+                            if let (Some(start), Some(end)) = (utf16(edit.start), utf16(edit.end)) {
+                                message["fix"] = serde_json::json!({
+                                    "range": [start, end],
+                                    "text": edit.replacement,
+                                });
+                            }
+                        }
+                    }
+                    message
+                })
+                .collect();
+            let path = std::path::absolute(file).unwrap_or_else(|_| file.into());
+            serde_json::json!({
+                "filePath": path.to_string_lossy(),
+                "messages": messages,
+                "suppressedMessages": [],
+                "errorCount": count(Severity::Error, false),
+                "fatalErrorCount": 0,
+                "warningCount": count(Severity::Warning, false),
+                "fixableErrorCount": count(Severity::Error, true),
+                "fixableWarningCount": count(Severity::Warning, true),
+                "usedDeprecatedRules": [],
+            })
+        })
+        .collect();
+    format!("{}\n", serde_json::Value::from(results))
+}
+
+/// The lowest severity that makes a run fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum FailOn {
+    Warning,
+    Error,
+}
+
+/// Decides the process exit code from the results of a run
+#[derive(Debug, Clone, Copy)]
+pub struct ExitPolicy {
+    pub fail_on: FailOn,
+    pub max_warnings: Option<usize>,
+    /// Always exit with 0, e.g. while rolling cashmere out in report-only mode
+    pub exit_zero: bool,
+}
+
+impl FailOn {
+    /// Whether a diagnostic of this severity counts as a failure
+    pub fn includes(&self, severity: Severity) -> bool {
+        match self {
+            FailOn::Error => severity == Severity::Error,
+            FailOn::Warning => true,
+        }
+    }
+}
+
+impl ExitPolicy {
+    /// Message explaining why the run failed `--max-warnings`, if it did
+    pub fn max_warnings_message(&self, summary: &Summary) -> Option<String> {
+        let max_warnings = self.max_warnings?;
+        (summary.warnings > max_warnings).then(|| {
+            format!(
+                "✗ Too many warnings ({}, maximum allowed is {})",
+                summary.warnings, max_warnings
+            )
+        })
+    }
+
+    /// Exit code for a finished run: 0 on success and 1 if the run failed
+    pub fn exit_code(&self, summary: &Summary) -> i32 {
+        let failed = match self.fail_on {
+            FailOn::Error => summary.errors > 0,
+            FailOn::Warning => summary.total() > 0,
+        } || self.max_warnings_message(summary).is_some();
+
+        if failed && !self.exit_zero {
+            1
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,619 @@
+mod async_run;
+mod await_step;
+mod await_workflow_api;
+mod banned_call;
+mod conditional_await;
+mod export_workflow_class;
+mod max_steps;
+mod no_busy_wait;
+mod no_console_in_step;
+mod no_destructured_step_methods;
+mod no_discarded_step_promises;
+mod no_dynamic_step_name;
+mod no_io_outside_step;
+mod no_large_step_result;
+mod no_node_builtins;
+mod no_nondeterminism;
+mod no_outer_mutation_in_step;
+mod no_process_env;
+mod no_self_run;
+mod no_step_in_finally;
+mod no_step_in_promise_executor;
+mod no_step_in_timer;
+mod no_swallowed_step_error;
+mod no_unused_step_result;
+mod prefer_retries_config;
+mod prefer_step_await;
+mod serializable_step_result;
+mod step_name_convention;
+mod typed_event_payload;
+mod unique_step_names;
+mod unique_workflow_classes;
+mod valid_retry_config;
+mod valid_run_params;
+mod valid_sleep_duration;
+mod valid_sleep_until;
+mod valid_step_callback;
+mod valid_step_error;
+mod valid_wait_for_event;
+mod wrangler_binding;
+
+use std::sync::Arc;
+
+use oxc_ast::ast::{
+    AssignmentExpression, CallExpression, Class, Expression, ImportDeclaration, NewExpression,
+    Statement, StaticMemberExpression, ThrowStatement, TryStatement, UpdateExpression,
+    VariableDeclarator,
+};
+use oxc_span::Span;
+
+use crate::config::{Config, RuleLevel};
+use crate::linter::{Fix, LintDiagnostic, Severity, StepCall};
+
+pub use async_run::{AsyncRun, ASYNC_RUN};
+pub use await_step::{AwaitStep, AWAIT_STEP};
+pub use await_workflow_api::{AwaitWorkflowApi, AWAIT_WORKFLOW_API};
+pub use banned_call::{BannedCalls, BANNED_CALL};
+pub use conditional_await::{ConditionalAwait, CONDITIONAL_AWAIT};
+pub use export_workflow_class::{ExportWorkflowClass, EXPORT_WORKFLOW_CLASS};
+pub use max_steps::{MaxSteps, MAX_STEPS};
+pub use no_busy_wait::{NoBusyWait, NO_BUSY_WAIT};
+pub use no_console_in_step::{NoConsoleInStep, NO_CONSOLE_IN_STEP};
+pub use no_destructured_step_methods::{NoDestructuredStepMethods, NO_DESTRUCTURED_STEP_METHODS};
+pub use no_discarded_step_promises::{NoDiscardedStepPromises, NO_DISCARDED_STEP_PROMISES};
+pub use no_dynamic_step_name::{NoDynamicStepName, NO_DYNAMIC_STEP_NAME};
+pub use no_io_outside_step::{NoIoOutsideStep, NO_IO_OUTSIDE_STEP};
+pub use no_large_step_result::{NoLargeStepResult, NO_LARGE_STEP_RESULT};
+pub use no_node_builtins::{NoNodeBuiltins, NO_NODE_BUILTINS};
+pub use no_nondeterminism::{NoNondeterminism, NO_NONDETERMINISM};
+pub use no_outer_mutation_in_step::{NoOuterMutationInStep, NO_OUTER_MUTATION_IN_STEP};
+pub use no_process_env::{NoProcessEnv, NO_PROCESS_ENV};
+pub use no_self_run::{NoSelfRun, NO_SELF_RUN};
+pub use no_step_in_finally::{NoStepInFinally, NO_STEP_IN_FINALLY};
+pub use no_step_in_promise_executor::{NoStepInPromiseExecutor, NO_STEP_IN_PROMISE_EXECUTOR};
+pub use no_step_in_timer::{NoStepInTimer, NO_STEP_IN_TIMER};
+pub use no_swallowed_step_error::{NoSwallowedStepError, NO_SWALLOWED_STEP_ERROR};
+pub use no_unused_step_result::{NoUnusedStepResult, NO_UNUSED_STEP_RESULT};
+pub use prefer_retries_config::{PreferRetriesConfig, PREFER_RETRIES_CONFIG};
+pub use prefer_step_await::{PreferStepAwait, PREFER_STEP_AWAIT};
+pub use serializable_step_result::{SerializableStepResult, SERIALIZABLE_STEP_RESULT};
+pub use step_name_convention::{StepNameConvention, STEP_NAME_CONVENTION};
+pub use typed_event_payload::{TypedEventPayload, TYPED_EVENT_PAYLOAD};
+pub use unique_step_names::{UniqueStepNames, UNIQUE_STEP_NAMES};
+pub use unique_workflow_classes::UNIQUE_WORKFLOW_CLASSES;
+pub use valid_retry_config::{ValidRetryConfig, VALID_RETRY_CONFIG};
+pub use valid_run_params::{ValidRunParams, VALID_RUN_PARAMS};
+pub(crate) use valid_sleep_duration::duration_millis;
+pub use valid_sleep_duration::{ValidSleepDuration, VALID_SLEEP_DURATION};
+pub use valid_sleep_until::{ValidSleepUntil, VALID_SLEEP_UNTIL};
+pub use valid_step_callback::{ValidStepCallback, VALID_STEP_CALLBACK};
+pub use valid_step_error::{ValidStepError, VALID_STEP_ERROR};
+pub use valid_wait_for_event::{ValidWaitForEvent, VALID_WAIT_FOR_EVENT};
+pub use wrangler_binding::WRANGLER_BINDING;
+
+/// Static information about a lint rule
+#[derive(Debug, Clone, Copy)]
+pub struct RuleMeta {
+    /// Identifier used in diagnostics and in `cashmere.toml`
+    pub id: &'static str,
+    pub default_severity: Severity,
+    /// Whether the rule runs without being turned on in `cashmere.toml`
+    pub enabled_by_default: bool,
+    /// Whether the rule can attach automatic fixes to its diagnostics
+    pub fixable: bool,
+    /// One-line description of what the rule checks
+    pub description: &'static str,
+    /// Why the rule matters, shown when hovering over a diagnostic in an editor
+    pub explanation: &'static str,
+    pub docs_url: &'static str,
+}
+
+impl RuleMeta {
+    /// The level the rule runs at when `cashmere.toml` doesn't set one
+    pub fn default_level(&self) -> RuleLevel {
+        if self.enabled_by_default {
+            RuleLevel::from(self.default_severity)
+        } else {
+            RuleLevel::Off
+        }
+    }
+}
+
+/// Every built-in rule
+pub const ALL_RULES: &[RuleMeta] = &[
+    AWAIT_STEP,
+    BANNED_CALL,
+    WRANGLER_BINDING,
+    NO_IO_OUTSIDE_STEP,
+    NO_NONDETERMINISM,
+    UNIQUE_STEP_NAMES,
+    NO_DYNAMIC_STEP_NAME,
+    VALID_SLEEP_DURATION,
+    VALID_SLEEP_UNTIL,
+    VALID_WAIT_FOR_EVENT,
+    VALID_RETRY_CONFIG,
+    SERIALIZABLE_STEP_RESULT,
+    NO_OUTER_MUTATION_IN_STEP,
+    PREFER_STEP_AWAIT,
+    NO_STEP_IN_TIMER,
+    VALID_STEP_ERROR,
+    MAX_STEPS,
+    VALID_STEP_CALLBACK,
+    NO_STEP_IN_PROMISE_EXECUTOR,
+    NO_DISCARDED_STEP_PROMISES,
+    AWAIT_WORKFLOW_API,
+    ASYNC_RUN,
+    VALID_RUN_PARAMS,
+    EXPORT_WORKFLOW_CLASS,
+    NO_SWALLOWED_STEP_ERROR,
+    NO_STEP_IN_FINALLY,
+    PREFER_RETRIES_CONFIG,
+    NO_SELF_RUN,
+    NO_BUSY_WAIT,
+    STEP_NAME_CONVENTION,
+    NO_DESTRUCTURED_STEP_METHODS,
+    NO_CONSOLE_IN_STEP,
+    NO_PROCESS_ENV,
+    NO_NODE_BUILTINS,
+    TYPED_EVENT_PAYLOAD,
+    NO_UNUSED_STEP_RESULT,
+    NO_LARGE_STEP_RESULT,
+    CONDITIONAL_AWAIT,
+    UNIQUE_WORKFLOW_CLASSES,
+];
+
+pub fn find_rule(id: &str) -> Option<&'static RuleMeta> {
+    ALL_RULES.iter().find(|rule| rule.id == id)
+}
+
+/// The dotted name of a callee such as `console.log` or `this.client.fetch`, if it is one
+pub(crate) fn callee_path(callee: &Expression) -> Option<String> {
+    match callee {
+        Expression::Identifier(id) => Some(id.name.to_string()),
+        Expression::ThisExpression(_) => Some("this".to_string()),
+        Expression::StaticMemberExpression(member) => Some(format!(
+            "{}.{}",
+            callee_path(&member.object)?,
+            member.property.name
+        )),
+        Expression::ParenthesizedExpression(paren) => callee_path(&paren.expression),
+        _ => None,
+    }
+}
+
+/// A function scope entered while linting; the top level of a file has no body
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionScope {
+    pub is_async: bool,
+    pub body: Option<Span>,
+}
+
+/// One arm of a conditional construct the linter is inside, such as the `else` of an `if`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Branch {
+    /// The `if`, `switch`, loop, `try` or expression that branches
+    pub construct: Span,
+    /// Which arm this is, counting from 0
+    pub arm: u32,
+    /// How many arms the construct has, counting a missing `else` or `default`, or a
+    /// loop body that doesn't run, as one
+    pub arms: u32,
+}
+
+/// How the promise returned by a step call is used where the call appears
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepUsage {
+    /// Awaited directly, or as an element of an awaited `Promise.all([...])` and friends
+    Awaited,
+    /// Assigned to a variable, e.g. `const p = step.do(...)`. `name` is `None` when the
+    /// promise is destructured.
+    Assigned { name: Option<String>, binding: Span },
+    /// Put in an array or object held in a variable, e.g. `pending.push(step.do(...))`.
+    /// Awaiting the variable with `Promise.all(pending)` and friends awaits the promise.
+    /// `binding` is where the variable is named.
+    Stored { container: String, binding: Span },
+    /// A bare expression statement, e.g. `step.do(...);`
+    Statement,
+    /// Any other use that doesn't await it
+    Unawaited,
+}
+
+/// The file being linted and the diagnostics reported for it so far
+pub struct RuleContext<'a> {
+    source: &'a str,
+    file_path: &'a str,
+    diagnostics: Vec<LintDiagnostic>,
+    /// Number of workflow bodies the linter is inside
+    pub(crate) workflow_depth: usize,
+    /// Number of step callbacks the linter is inside
+    pub(crate) step_callback_depth: usize,
+    /// Variables bound by the loops and array callbacks the linter is inside
+    pub(crate) loop_variables: Vec<String>,
+    /// Iteration counts of the enclosing loops that have a constant one
+    pub(crate) loop_iterations: Vec<u64>,
+    /// Classes declared at the top level of the file
+    pub(crate) local_classes: Vec<String>,
+    /// Local names the file exports, by name or as the default export
+    pub(crate) exports: Vec<String>,
+    /// Names the file imports, with the module and the name they are imported from
+    pub(crate) imports: Vec<(String, String, String)>,
+    /// Names declared inside the step callbacks the linter is inside, including their parameters
+    pub(crate) step_callback_locals: Vec<String>,
+    /// The call of the expression statement being linted, if it is one
+    pub(crate) statement_call: Option<Span>,
+    /// The call awaited by the expression statement being linted, if it is one
+    pub(crate) awaited_statement_call: Option<Span>,
+    /// Names a step object was assigned to, as in `const s = step`, and the step
+    /// parameters of the workflows the linter is inside
+    pub(crate) step_aliases: Vec<String>,
+    /// The conditional arms the linter is inside, outermost first
+    pub(crate) branches: Vec<Branch>,
+}
+
+impl<'a> RuleContext<'a> {
+    pub(crate) fn new(source: &'a str, file_path: &'a str) -> Self {
+        Self {
+            source,
+            file_path,
+            diagnostics: Vec::new(),
+            workflow_depth: 0,
+            step_callback_depth: 0,
+            loop_variables: Vec::new(),
+            loop_iterations: Vec::new(),
+            local_classes: Vec::new(),
+            exports: Vec::new(),
+            imports: Vec::new(),
+            step_callback_locals: Vec::new(),
+            statement_call: None,
+            awaited_statement_call: None,
+            step_aliases: Vec::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    /// Whether the code being linted is inside the `run` method of a `WorkflowEntrypoint`
+    /// class or a function taking a `WorkflowStep`, including functions nested in them
+    pub fn in_workflow(&self) -> bool {
+        self.workflow_depth > 0
+    }
+
+    /// Whether the code being linted is inside the callback passed to a step call,
+    /// such as the last argument of `step.do(name, config, callback)`
+    pub fn in_step_callback(&self) -> bool {
+        self.step_callback_depth > 0
+    }
+
+    /// Whether `name` is bound by an enclosing `for` loop or by the parameters of a
+    /// callback passed to `map`, `flatMap` or `forEach`, such as `i` in `items.map((item, i) => ...)`
+    pub fn is_loop_variable(&self, name: &str) -> bool {
+        self.loop_variables.iter().any(|variable| variable == name)
+    }
+
+    /// How many times the code being linted runs for each run of its function: the
+    /// product of the iteration counts of the enclosing loops with a constant count, such
+    /// as `for (let i = 0; i < 10; i++)` or a `for...of` over an array literal. Loops with
+    /// other counts are counted once.
+    pub fn loop_iterations(&self) -> u64 {
+        self.loop_iterations
+            .iter()
+            .fold(1, |product, &count| product.saturating_mul(count))
+    }
+
+    /// Whether `name` is declared inside the enclosing step callback, as one of its
+    /// parameters or variables or in a function nested in it
+    pub fn is_step_callback_local(&self, name: &str) -> bool {
+        self.step_callback_locals.iter().any(|local| local == name)
+    }
+
+    /// The conditional arms the code being linted is inside, outermost first: `if` and
+    /// `else` branches, `switch` cases, loop bodies other than `do...while`, `catch`
+    /// blocks, the arms of `a ? b : c` and the right side of `&&`, `||` and `??`
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    /// Whether the call at `span` makes up a whole expression statement, like
+    /// `items.forEach(...);`, so its result is discarded
+    pub fn is_statement_call(&self, span: Span) -> bool {
+        self.statement_call == Some(span)
+    }
+
+    /// Whether the call at `span` is awaited by a whole expression statement, like
+    /// `await step.do(...);`, so its result is discarded
+    pub fn is_awaited_statement_call(&self, span: Span) -> bool {
+        self.awaited_statement_call == Some(span)
+    }
+
+    /// Whether `name` holds a step object: it is named `step` or ends in `step` in any
+    /// case, like `workflowStep`, it is the enclosing workflow's `WorkflowStep` parameter
+    /// or the second parameter of its `run` method, or a step object was assigned to it
+    pub fn is_step_object(&self, name: &str) -> bool {
+        let lowercase = name.to_lowercase();
+        lowercase == "step"
+            || lowercase.ends_with("step")
+            || self.step_aliases.iter().any(|alias| alias == name)
+    }
+
+    /// Whether `name` is a class declared at the top level of the file being linted
+    pub fn is_local_class(&self, name: &str) -> bool {
+        self.local_classes.iter().any(|class| class == name)
+    }
+
+    /// Whether the top-level `name` is exported from the file being linted, by name, under
+    /// another name or as the default export
+    pub fn is_exported(&self, name: &str) -> bool {
+        self.exports.iter().any(|export| export == name)
+    }
+
+    /// The module `name` is imported from and the name it has there, such as
+    /// `("cloudflare:workflows", "NonRetryableError")`; default and namespace imports
+    /// have the names `default` and `*`
+    pub fn import_of(&self, name: &str) -> Option<(&str, &str)> {
+        self.imports
+            .iter()
+            .find(|(local, _, _)| local == name)
+            .map(|(_, module, imported)| (module.as_str(), imported.as_str()))
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn file_path(&self) -> &'a str {
+        self.file_path
+    }
+
+    /// Build a diagnostic for `span` in this file with the rule's default severity
+    pub fn diagnostic(&self, span: Span, message: &str, rule: &RuleMeta) -> LintDiagnostic {
+        LintDiagnostic::new(self.file_path, self.source, span, message, rule)
+    }
+
+    pub fn report(&mut self, diagnostic: LintDiagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub(crate) fn diagnostics_mut(&mut self) -> &mut Vec<LintDiagnostic> {
+        &mut self.diagnostics
+    }
+
+    pub(crate) fn into_diagnostics(self) -> Vec<LintDiagnostic> {
+        self.diagnostics
+    }
+}
+
+/// A lint rule. The linter walks each file once and calls these hooks on every
+/// enabled rule; a fresh instance is created per file, so rules can keep state.
+pub trait Rule {
+    fn meta(&self) -> &'static RuleMeta;
+
+    /// A function body (or the top level of the file) is entered
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {}
+
+    /// The innermost function body entered is left
+    fn leave_function(&mut self, _ctx: &mut RuleContext) {}
+
+    /// A call on a step object, such as `step.do(...)`, with its call expression for
+    /// rules that look at the arguments themselves
+    fn check_step_call(
+        &mut self,
+        _ctx: &mut RuleContext,
+        _call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+    }
+
+    /// Variables awaited by `await x` or `await Promise.all([x, y])`
+    fn check_awaited_vars(&mut self, _ctx: &mut RuleContext, _names: &[&str]) {}
+
+    /// Any call expression, including step calls
+    fn check_call(&mut self, _ctx: &mut RuleContext, _call: &CallExpression) {}
+
+    /// A `new` expression, such as `new Date()`
+    fn check_new(&mut self, _ctx: &mut RuleContext, _new_expr: &NewExpression) {}
+
+    /// A `throw` statement
+    fn check_throw(&mut self, _ctx: &mut RuleContext, _throw: &ThrowStatement) {}
+
+    /// A `try` statement, before its blocks are walked
+    fn check_try(&mut self, _ctx: &mut RuleContext, _try_stmt: &TryStatement) {}
+
+    /// A `for`, `for...in`, `for...of`, `while` or `do...while` loop, before its body is walked
+    fn check_loop(&mut self, _ctx: &mut RuleContext, _loop_stmt: &Statement) {}
+
+    /// An assignment, such as `total += x`
+    fn check_assignment(&mut self, _ctx: &mut RuleContext, _assignment: &AssignmentExpression) {}
+
+    /// An increment or decrement, such as `count++`
+    fn check_update(&mut self, _ctx: &mut RuleContext, _update: &UpdateExpression) {}
+
+    /// A variable declarator, such as the `x = 1` in `const x = 1`
+    fn check_variable(&mut self, _ctx: &mut RuleContext, _declarator: &VariableDeclarator) {}
+
+    /// A class declaration or expression, before its members are walked
+    fn check_class(&mut self, _ctx: &mut RuleContext, _class: &Class) {}
+
+    /// A static member access, such as `process.env`, before its object is walked
+    fn check_member(&mut self, _ctx: &mut RuleContext, _member: &StaticMemberExpression) {}
+
+    /// An `import` declaration, such as `import fs from "node:fs"`
+    fn check_import(&mut self, _ctx: &mut RuleContext, _import: &ImportDeclaration) {}
+
+    /// An automatic fix for one of this rule's diagnostics that doesn't have one yet,
+    /// asked for once the whole file has been linted
+    fn fix(&self, _ctx: &RuleContext, _diagnostic: &LintDiagnostic) -> Option<Fix> {
+        None
+    }
+}
+
+/// Creates a fresh instance of a rule for each linted file
+pub type RuleFactory = Arc<dyn Fn() -> Box<dyn Rule> + Send + Sync>;
+
+fn new_rule<R: Rule + Default + 'static>() -> Box<dyn Rule> {
+    Box::new(R::default())
+}
+
+/// The set of rules to run, built-in or registered by an embedding tool
+#[derive(Clone)]
+pub struct RuleRegistry {
+    rules: Vec<(&'static RuleMeta, RuleFactory)>,
+}
+
+impl RuleRegistry {
+    /// A registry with no rules
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// A registry with every built-in rule
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register::<AwaitStep>();
+        registry.register::<BannedCalls>();
+        registry.register::<NoIoOutsideStep>();
+        registry.register::<NoNondeterminism>();
+        registry.register::<UniqueStepNames>();
+        registry.register::<NoDynamicStepName>();
+        registry.register::<ValidSleepDuration>();
+        registry.register::<ValidSleepUntil>();
+        registry.register::<ValidWaitForEvent>();
+        registry.register::<ValidRetryConfig>();
+        registry.register::<SerializableStepResult>();
+        registry.register::<NoOuterMutationInStep>();
+        registry.register::<PreferStepAwait>();
+        registry.register::<NoStepInTimer>();
+        registry.register::<ValidStepError>();
+        registry.register::<MaxSteps>();
+        registry.register::<ValidStepCallback>();
+        registry.register::<NoStepInPromiseExecutor>();
+        registry.register::<NoDiscardedStepPromises>();
+        registry.register::<AwaitWorkflowApi>();
+        registry.register::<AsyncRun>();
+        registry.register::<ValidRunParams>();
+        registry.register::<ExportWorkflowClass>();
+        registry.register::<NoSwallowedStepError>();
+        registry.register::<NoStepInFinally>();
+        registry.register::<PreferRetriesConfig>();
+        registry.register::<NoSelfRun>();
+        registry.register::<NoBusyWait>();
+        registry.register::<StepNameConvention>();
+        registry.register::<NoDestructuredStepMethods>();
+        registry.register::<NoConsoleInStep>();
+        registry.register::<NoProcessEnv>();
+        registry.register::<NoNodeBuiltins>();
+        registry.register::<TypedEventPayload>();
+        registry.register::<NoUnusedStepResult>();
+        registry.register::<NoLargeStepResult>();
+        registry.register::<ConditionalAwait>();
+        registry
+    }
+
+    /// Add a rule, replacing any registered rule with the same id
+    pub fn register<R: Rule + Default + 'static>(&mut self) -> &mut Self {
+        self.register_with(R::default().meta(), new_rule::<R>)
+    }
+
+    /// Add a rule created by `factory`, such as one configured at runtime, replacing
+    /// any registered rule with the same id
+    pub fn register_with(
+        &mut self,
+        meta: &'static RuleMeta,
+        factory: impl Fn() -> Box<dyn Rule> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.rules.retain(|(existing, _)| existing.id != meta.id);
+        self.rules.push((meta, Arc::new(factory)));
+        self
+    }
+
+    /// Keep only the rules for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&RuleMeta) -> bool) -> &mut Self {
+        self.rules.retain(|(meta, _)| keep(meta));
+        self
+    }
+
+    /// Configure the rules from `config` and drop the ones it turns off, so they
+    /// aren't run at all
+    pub fn enabled_by(mut self, config: &Config) -> Self {
+        #[cfg(feature = "plugins")]
+        crate::plugin::register_plugins(&mut self, &config.loaded_plugins);
+        if self.metas().any(|meta| meta.id == BANNED_CALL.id) {
+            let bans = config.banned_calls.clone();
+            self.register_with(&BANNED_CALL, move || {
+                Box::new(BannedCalls::new(bans.clone()))
+            });
+        }
+        if self.metas().any(|meta| meta.id == NO_DYNAMIC_STEP_NAME.id) {
+            let options = config.rule_options.no_dynamic_step_name.clone();
+            self.register_with(&NO_DYNAMIC_STEP_NAME, move || {
+                Box::new(NoDynamicStepName::new(options.clone()))
+            });
+        }
+        if self.metas().any(|meta| meta.id == MAX_STEPS.id) {
+            let options = config.rule_options.max_steps.clone();
+            self.register_with(&MAX_STEPS, move || Box::new(MaxSteps::new(options.clone())));
+        }
+        if self.metas().any(|meta| meta.id == STEP_NAME_CONVENTION.id) {
+            let options = config.rule_options.step_name_convention.clone();
+            self.register_with(&STEP_NAME_CONVENTION, move || {
+                Box::new(StepNameConvention::new(options.clone()))
+            });
+        }
+        if self.metas().any(|meta| meta.id == VALID_WAIT_FOR_EVENT.id) {
+            let options = config.rule_options.valid_wait_for_event.clone();
+            self.register_with(&VALID_WAIT_FOR_EVENT, move || {
+                Box::new(ValidWaitForEvent::new(options.clone()))
+            });
+        }
+        if self.metas().any(|meta| meta.id == AWAIT_STEP.id) {
+            let options = config.rule_options.await_step.clone();
+            self.register_with(&AWAIT_STEP, move || {
+                Box::new(AwaitStep::new(options.clone()))
+            });
+        }
+        self.retain(|meta| config.rule_level(meta).0 != RuleLevel::Off);
+        self
+    }
+
+    pub fn metas(&self) -> impl Iterator<Item = &'static RuleMeta> + '_ {
+        self.rules.iter().map(|(meta, _)| *meta)
+    }
+
+    /// Fresh instances of every rule, for linting one file
+    pub fn instantiate(&self) -> Vec<Box<dyn Rule>> {
+        self.rules.iter().map(|(_, new)| new()).collect()
+    }
+}
+
+impl Default for RuleRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Print a table of all rules for `cashmere rules`
+pub fn print_rules() {
+    let id_width = ALL_RULES
+        .iter()
+        .map(|rule| rule.id.len())
+        .max()
+        .unwrap_or(0)
+        .max("RULE".len());
+
+    println!(
+        "{:<id_width$}  {:<8}  {:<7}  DESCRIPTION",
+        "RULE", "SEVERITY", "FIXABLE"
+    );
+    for rule in ALL_RULES {
+        println!(
+            "{:<id_width$}  {:<8}  {:<7}  {}",
+            rule.id,
+            if rule.enabled_by_default {
+                rule.default_severity.as_str()
+            } else {
+                "off"
+            },
+            if rule.fixable { "yes" } else { "no" },
+            rule.description
+        );
+    }
+}
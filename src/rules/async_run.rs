@@ -0,0 +1,80 @@
+use oxc_ast::ast::{Class, ClassElement, Expression, MethodDefinitionKind, Statement};
+use oxc_span::GetSpan;
+
+use crate::linter::{Fix, Severity, TextEdit};
+use crate::rules::{Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const ASYNC_RUN: RuleMeta = RuleMeta {
+    id: "async-run",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "The `run` method of a workflow must be `async`",
+    explanation: "Workflows calls `run(event, step)` and waits on the Promise it returns to \
+know when the workflow has finished. A `run` that isn't `async` can't `await` its steps, so \
+they are left dangling, and returns `undefined` instead of a Promise unless it returns one \
+explicitly. Declare it as `async run(event, step)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Reports `run` methods of `WorkflowEntrypoint` classes that aren't `async` and don't
+/// return a Promise
+#[derive(Debug, Default)]
+pub struct AsyncRun;
+
+/// Whether `statements` return something that may be a Promise, such as `return this.process()`
+fn returns_promise(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::ReturnStatement(ret) => matches!(
+            &ret.argument,
+            Some(Expression::CallExpression(_) | Expression::NewExpression(_))
+        ),
+        _ => false,
+    })
+}
+
+impl Rule for AsyncRun {
+    fn meta(&self) -> &'static RuleMeta {
+        &ASYNC_RUN
+    }
+
+    fn check_class(&mut self, ctx: &mut RuleContext, class: &Class) {
+        if !extends_workflow_entrypoint(class) {
+            return;
+        }
+        for element in &class.body.body {
+            let ClassElement::MethodDefinition(method) = element else {
+                continue;
+            };
+            if method.kind != MethodDefinitionKind::Method
+                || method.key.static_name().as_deref() != Some("run")
+                || method.value.r#async
+            {
+                continue;
+            }
+            let body = method.value.body.as_deref();
+            if body.is_none_or(|body| returns_promise(&body.statements)) {
+                continue;
+            }
+            let class_name = class
+                .id
+                .as_ref()
+                .map_or("this workflow".to_string(), |id| format!("`{}`", id.name));
+            let message = format!(
+                "`run` of {} isn't `async`, so it can't await its steps and Workflows doesn't get a Promise to wait on; declare it `async run(...)`",
+                class_name
+            );
+            let key = method.key.span();
+            let mut diagnostic = ctx.diagnostic(key, &message, &ASYNC_RUN);
+            // `*run()` would need `async *run()`, which is a different kind of method
+            if !method.value.generator {
+                diagnostic = diagnostic.with_fix(Fix {
+                    title: "Make `run` async".to_string(),
+                    edits: vec![TextEdit::insert(key.start, "async ")],
+                });
+            }
+            ctx.report(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+
+use oxc_ast::ast::{CallExpression, Expression};
+use oxc_span::{GetSpan, Span};
+
+use crate::config::{AwaitStepOptions, EscapePolicy};
+use crate::linter::{Fix, LintDiagnostic, RelatedLocation, Severity, StepCall, TextEdit};
+use crate::rules::{callee_path, FunctionScope, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const AWAIT_STEP: RuleMeta = RuleMeta {
+    id: "await-step",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "Step calls (step.do, step.sleep, ...) must be awaited",
+    explanation: "Workflows replays the `run` method from the top after every step, restoring \
+completed steps from their stored results. A step call that isn't awaited leaves a dangling \
+Promise: the workflow can move on or finish before the step has been recorded, so replays \
+see a different sequence of steps, and any error the step throws is swallowed instead of \
+triggering retries.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// A step call that was never awaited
+struct UnawaitedStep {
+    span: Span,
+    method_name: String,
+    /// The variable the promise was assigned to and the span of its binding
+    binding: Option<(String, Span)>,
+    /// Whether the variable holds an array or object the promise was stored in
+    stored: bool,
+    /// Where the variable was first passed to another function, and the function's name
+    escape: Option<(Span, String)>,
+}
+
+/// Tracks step promise calls within a function scope
+#[derive(Debug, Default)]
+struct StepPromiseTracker {
+    /// Maps variable names to the span of the step call they were assigned from
+    /// e.g., `const p = step.do(...)` maps "p" -> span of step.do call
+    var_to_step_span: HashMap<String, Span>,
+    /// Maps variable names to the span of their binding, e.g. the `p` in `const p = ...`
+    var_binding_spans: HashMap<String, Span>,
+    /// Maps step call spans to their method name (for error reporting)
+    step_span_to_name: HashMap<Span, String>,
+    /// Set of step call spans that have been awaited (directly or via Promise.all/race/etc.)
+    awaited_step_spans: HashSet<Span>,
+    /// Step calls that were not assigned to a variable and not immediately awaited
+    unassigned_unawaited_steps: Vec<(Span, String)>,
+    /// Step calls stored in an array or object held in a variable, with the variable's
+    /// name and where it is named, e.g. `pending.push(step.do(...))`
+    stored_steps: Vec<(Span, String, Span)>,
+    /// Maps variable names to the first argument that passed them to another function,
+    /// and that function's name, e.g. `handle(p)` maps "p" -> span of `p`, "handle"
+    var_escapes: HashMap<String, (Span, String)>,
+    /// Whether the function owning this scope is async (so `await` is allowed)
+    is_async: bool,
+    /// The closing brace of the function body, where its variables go out of scope
+    scope_end: Option<Span>,
+}
+
+impl StepPromiseTracker {
+    fn new(is_async: bool, scope_end: Option<Span>) -> Self {
+        Self {
+            is_async,
+            scope_end,
+            ..Self::default()
+        }
+    }
+
+    /// Record a step call that was assigned to the variable bound at `binding`
+    fn record_assigned_step(
+        &mut self,
+        var_name: &str,
+        binding: Span,
+        span: Span,
+        method_name: String,
+    ) {
+        self.var_to_step_span.insert(var_name.to_string(), span);
+        self.var_binding_spans.insert(var_name.to_string(), binding);
+        self.step_span_to_name.insert(span, method_name);
+    }
+
+    /// Record a step call stored in the array or object `container`, named at `binding`
+    fn record_stored_step(
+        &mut self,
+        container: &str,
+        binding: Span,
+        span: Span,
+        method_name: String,
+    ) {
+        self.stored_steps
+            .push((span, container.to_string(), binding));
+        self.step_span_to_name.insert(span, method_name);
+    }
+
+    /// Record a step call that was NOT assigned to a variable and NOT immediately awaited
+    fn record_unassigned_unawaited_step(&mut self, span: Span, method_name: String) {
+        self.unassigned_unawaited_steps.push((span, method_name));
+    }
+
+    /// Mark a step call as awaited by its span
+    fn mark_awaited_by_span(&mut self, span: Span) {
+        self.awaited_step_spans.insert(span);
+    }
+
+    /// Record that the variable `var_name` was passed to `callee` at `span`, if it holds
+    /// a step promise
+    fn record_escape(&mut self, var_name: &str, span: Span, callee: String) {
+        let holds_steps = self.var_to_step_span.contains_key(var_name)
+            || self
+                .stored_steps
+                .iter()
+                .any(|(_, container, _)| container == var_name);
+        if holds_steps {
+            self.var_escapes
+                .entry(var_name.to_string())
+                .or_insert((span, callee));
+        }
+    }
+
+    /// Mark a step call as awaited by variable name
+    fn mark_awaited_by_var(&mut self, var_name: &str) {
+        if let Some(&span) = self.var_to_step_span.get(var_name) {
+            self.awaited_step_spans.insert(span);
+        }
+        for (span, container, _) in &self.stored_steps {
+            if container == var_name {
+                self.awaited_step_spans.insert(*span);
+            }
+        }
+    }
+
+    /// Get all step calls that were not awaited
+    fn get_unawaited_steps(&self) -> Vec<UnawaitedStep> {
+        let mut result = Vec::new();
+
+        // Check assigned step calls
+        for (var_name, &span) in &self.var_to_step_span {
+            if !self.awaited_step_spans.contains(&span) {
+                let method_name = match self.step_span_to_name.get(&span) {
+                    Some(method_name) => method_name.clone(),
+                    None => format!("step (var: {})", var_name),
+                };
+                result.push(UnawaitedStep {
+                    span,
+                    method_name,
+                    binding: self
+                        .var_binding_spans
+                        .get(var_name)
+                        .map(|&binding| (var_name.clone(), binding)),
+                    stored: false,
+                    escape: self.var_escapes.get(var_name).cloned(),
+                });
+            }
+        }
+
+        // Check stored step calls
+        for (span, container, binding) in &self.stored_steps {
+            if !self.awaited_step_spans.contains(span) {
+                result.push(UnawaitedStep {
+                    span: *span,
+                    method_name: self.step_span_to_name[span].clone(),
+                    binding: Some((container.clone(), *binding)),
+                    stored: true,
+                    escape: self.var_escapes.get(container).cloned(),
+                });
+            }
+        }
+
+        // Add unassigned unawaited steps
+        result.extend(
+            self.unassigned_unawaited_steps
+                .iter()
+                .map(|(span, method_name)| UnawaitedStep {
+                    span: *span,
+                    method_name: method_name.clone(),
+                    binding: None,
+                    stored: false,
+                    escape: None,
+                }),
+        );
+
+        result
+    }
+}
+
+/// Reports step calls whose promise is never awaited in the function that made them
+#[derive(Debug, Default)]
+pub struct AwaitStep {
+    /// Stack of trackers for nested function scopes
+    tracker_stack: Vec<StepPromiseTracker>,
+    /// Step calls that can be fixed by inserting `await` in front of them
+    fixable_step_spans: HashSet<Span>,
+    /// How step promises passed to other functions are treated
+    escapes: EscapePolicy,
+}
+
+impl AwaitStep {
+    pub fn new(options: AwaitStepOptions) -> Self {
+        Self {
+            escapes: options.escapes,
+            ..Self::default()
+        }
+    }
+}
+
+impl Rule for AwaitStep {
+    fn meta(&self) -> &'static RuleMeta {
+        &AWAIT_STEP
+    }
+
+    fn enter_function(&mut self, ctx: &mut RuleContext, scope: FunctionScope) {
+        // The closing brace of the body; expression-bodied arrows have none
+        let scope_end = scope
+            .body
+            .filter(|body| body.end > 0 && ctx.source().as_bytes()[body.end as usize - 1] == b'}')
+            .map(|body| Span::new(body.end - 1, body.end));
+        self.tracker_stack
+            .push(StepPromiseTracker::new(scope.is_async, scope_end));
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        let Some(tracker) = self.tracker_stack.pop() else {
+            return;
+        };
+        for UnawaitedStep {
+            span,
+            method_name,
+            binding,
+            stored,
+            escape,
+        } in tracker.get_unawaited_steps()
+        {
+            let message = match (&binding, &escape) {
+                (Some((var_name, _)), Some((_, callee))) => format!(
+                    "`{}` must be awaited, but `{}` is passed to `{}`, where cashmere can't see whether it is awaited. Await it here, or set `escapes = \"handled\"` under `[rule_options.await-step]` if your helpers await the promises they are given.",
+                    method_name, var_name, callee
+                ),
+                (Some((var_name, _)), None) if stored => format!(
+                    "`{}` must be awaited, but it is stored in `{}`, which is never awaited or passed to an awaited `Promise.all` and friends.",
+                    method_name, var_name
+                ),
+                _ => format!(
+                    "`{}` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.",
+                    method_name
+                ),
+            };
+            let mut diagnostic = ctx.diagnostic(span, &message, &AWAIT_STEP);
+            if let Some((var_name, binding)) = binding {
+                diagnostic = diagnostic.with_related(RelatedLocation::new(
+                    ctx.source(),
+                    binding,
+                    &if stored {
+                        format!("the step promise is stored in `{}` here", var_name)
+                    } else {
+                        format!("`{}` is assigned the step promise here", var_name)
+                    },
+                ));
+                if let Some((argument, callee)) = &escape {
+                    diagnostic = diagnostic.with_related(RelatedLocation::new(
+                        ctx.source(),
+                        *argument,
+                        &format!("`{}` is passed to `{}` here", var_name, callee),
+                    ));
+                }
+                if let Some(scope_end) = tracker.scope_end {
+                    diagnostic = diagnostic.with_related(RelatedLocation::new(
+                        ctx.source(),
+                        scope_end,
+                        &format!(
+                            "`{}` goes out of scope here without being awaited",
+                            var_name
+                        ),
+                    ));
+                }
+            }
+            ctx.report(diagnostic);
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        _ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        let Some(tracker) = self.tracker_stack.last_mut() else {
+            return;
+        };
+        let method_name = format!("{}.{}", call.receiver, call.method);
+        match usage {
+            StepUsage::Awaited => tracker.mark_awaited_by_span(call.span),
+            StepUsage::Assigned {
+                name: Some(var_name),
+                binding,
+            } => tracker.record_assigned_step(var_name, *binding, call.span, method_name),
+            // Destructuring a step promise isn't tracked
+            StepUsage::Assigned { name: None, .. } => {}
+            StepUsage::Stored { container, binding } => {
+                tracker.record_stored_step(container, *binding, call.span, method_name)
+            }
+            StepUsage::Statement => {
+                // A bare `step.do(...);` statement inside an async function can be fixed
+                // by inserting `await` in front of the call
+                if tracker.is_async {
+                    self.fixable_step_spans.insert(call.span);
+                }
+                tracker.record_unassigned_unawaited_step(call.span, method_name);
+            }
+            StepUsage::Unawaited => {
+                tracker.record_unassigned_unawaited_step(call.span, method_name)
+            }
+        }
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Some(tracker) = self.tracker_stack.last_mut() else {
+            return;
+        };
+        // `Promise.all(p)` and friends are followed where they are awaited
+        let callee = callee_path(&call.callee);
+        if callee
+            .as_deref()
+            .is_some_and(|callee| callee.starts_with("Promise."))
+        {
+            return;
+        }
+        let callee = callee.unwrap_or_else(|| {
+            let span = call.callee.span();
+            ctx.source()[span.start as usize..span.end as usize].to_string()
+        });
+        for argument in &call.arguments {
+            let Some(Expression::Identifier(id)) = argument.as_expression() else {
+                continue;
+            };
+            match self.escapes {
+                EscapePolicy::Handled => tracker.mark_awaited_by_var(&id.name),
+                EscapePolicy::Violation => {
+                    tracker.record_escape(&id.name, argument.span(), callee.clone())
+                }
+            }
+        }
+    }
+
+    fn check_awaited_vars(&mut self, _ctx: &mut RuleContext, names: &[&str]) {
+        if let Some(tracker) = self.tracker_stack.last_mut() {
+            for name in names {
+                tracker.mark_awaited_by_var(name);
+            }
+        }
+    }
+
+    fn fix(&self, _ctx: &RuleContext, diagnostic: &LintDiagnostic) -> Option<Fix> {
+        let span = Span::new(diagnostic.start, diagnostic.end);
+        self.fixable_step_spans.contains(&span).then(|| Fix {
+            title: "Add await".to_string(),
+            edits: vec![TextEdit::insert(span.start, "await ")],
+        })
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use oxc_ast::ast::{BindingPattern, CallExpression, Expression, VariableDeclarator};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Fix, Severity, TextEdit};
+use crate::rules::{callee_path, FunctionScope, Rule, RuleContext, RuleMeta};
+
+pub const AWAIT_WORKFLOW_API: RuleMeta = RuleMeta {
+    id: "await-workflow-api",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "Calls on Workflow bindings and instances must be awaited",
+    explanation: "Creating a workflow instance with `env.MY_WORKFLOW.create()`, and controlling \
+one with `instance.pause()`, `instance.terminate()` and friends, returns a Promise. Without \
+`await`, the Worker can respond, and be torn down, before the call reaches Workflows, and any \
+error it throws is swallowed. A variable assigned `env.MY_WORKFLOW.create()` without `await` \
+holds that Promise rather than the instance.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Methods of a Workflow binding, such as `env.MY_WORKFLOW`, that return a Promise
+const BINDING_METHODS: &[&str] = &["create", "createBatch", "get"];
+
+/// Methods of a workflow instance that return a Promise
+const INSTANCE_METHODS: &[&str] = &[
+    "pause",
+    "resume",
+    "terminate",
+    "restart",
+    "status",
+    "sendEvent",
+];
+
+/// Reports unawaited calls on Workflow bindings and instances
+#[derive(Debug, Default)]
+pub struct AwaitWorkflowApi {
+    /// Variables holding a workflow instance, from `await env.MY_WORKFLOW.create()` or `.get()`
+    instances: HashSet<String>,
+    /// Whether each function entered is async
+    is_async: Vec<bool>,
+}
+
+/// The method called on a Workflow binding by `callee`, such as `create` in
+/// `env.MY_WORKFLOW.create` or `this.env.MY_WORKFLOW.create`
+fn binding_method(callee: &Expression) -> Option<String> {
+    let path = callee_path(callee)?;
+    let mut segments = path.rsplit('.');
+    let method = segments.next()?;
+    segments.next()?;
+    (segments.next()? == "env" && BINDING_METHODS.contains(&method)).then(|| method.to_string())
+}
+
+/// Whether `expr` evaluates to a workflow instance: `await env.MY_WORKFLOW.get(id)` or
+/// `.create()`, or a variable assigned one
+fn is_instance(expr: &Expression, instances: &HashSet<String>) -> bool {
+    match expr {
+        Expression::Identifier(id) => instances.contains(id.name.as_str()),
+        Expression::ParenthesizedExpression(paren) => is_instance(&paren.expression, instances),
+        Expression::AwaitExpression(await_expr) => matches!(
+            &await_expr.argument,
+            Expression::CallExpression(call)
+                if matches!(binding_method(&call.callee).as_deref(), Some("create" | "get"))
+        ),
+        _ => false,
+    }
+}
+
+impl AwaitWorkflowApi {
+    fn report(&self, ctx: &mut RuleContext, span: Span, message: &str) {
+        let mut diagnostic = ctx.diagnostic(span, message, &AWAIT_WORKFLOW_API);
+        // `await` is only allowed in async functions
+        if self.is_async.last().copied().unwrap_or(false) {
+            diagnostic = diagnostic.with_fix(Fix {
+                title: "Add await".to_string(),
+                edits: vec![TextEdit::insert(span.start, "await ")],
+            });
+        }
+        ctx.report(diagnostic);
+    }
+}
+
+impl Rule for AwaitWorkflowApi {
+    fn meta(&self) -> &'static RuleMeta {
+        &AWAIT_WORKFLOW_API
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, scope: FunctionScope) {
+        self.is_async.push(scope.is_async);
+    }
+
+    fn leave_function(&mut self, _ctx: &mut RuleContext) {
+        self.is_async.pop();
+    }
+
+    fn check_variable(&mut self, ctx: &mut RuleContext, declarator: &VariableDeclarator) {
+        let (BindingPattern::BindingIdentifier(id), Some(init)) =
+            (&declarator.id, &declarator.init)
+        else {
+            return;
+        };
+        if is_instance(init, &self.instances) {
+            self.instances.insert(id.name.to_string());
+            return;
+        }
+        let Expression::CallExpression(call) = init else {
+            return;
+        };
+        if !matches!(
+            binding_method(&call.callee).as_deref(),
+            Some("create" | "createBatch")
+        ) {
+            return;
+        }
+        let callee = call.callee.span();
+        let message = format!(
+            "`{}` is assigned the Promise returned by `{}()` rather than the workflow instance; add `await`",
+            id.name,
+            &ctx.source()[callee.start as usize..callee.end as usize]
+        );
+        self.report(ctx, call.span, &message);
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        if !ctx.is_statement_call(call.span) {
+            return;
+        }
+        let is_api_call = matches!(
+            binding_method(&call.callee).as_deref(),
+            Some("create" | "createBatch")
+        ) || matches!(
+            &call.callee,
+            Expression::StaticMemberExpression(member)
+                if INSTANCE_METHODS.contains(&member.property.name.as_str())
+                    && is_instance(&member.object, &self.instances)
+        );
+        if !is_api_call {
+            return;
+        }
+        let callee = call.callee.span();
+        let message = format!(
+            "`{}()` must be awaited; otherwise the Worker can finish before Workflows receives the call, and its errors are swallowed",
+            &ctx.source()[callee.start as usize..callee.end as usize]
+        );
+        self.report(ctx, call.span, &message);
+    }
+}
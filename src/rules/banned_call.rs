@@ -0,0 +1,63 @@
+use oxc_ast::ast::CallExpression;
+use oxc_span::GetSpan;
+
+use crate::config::{BanScope, BannedCall, RuleLevel};
+use crate::linter::Severity;
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta};
+
+pub const BANNED_CALL: RuleMeta = RuleMeta {
+    id: "banned-call",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Calls banned by `[[banned_calls]]` in cashmere.toml",
+    explanation: "The project's cashmere.toml bans this call, either everywhere in workflow \
+files or inside step callbacks. Calls inside a step callback run again whenever the step is \
+retried, so side effects such as logging or requests through a non-idempotent client are \
+often better kept out of them.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports calls matching the `[[banned_calls]]` entries of the config
+#[derive(Debug, Default)]
+pub struct BannedCalls {
+    bans: Vec<BannedCall>,
+}
+
+impl BannedCalls {
+    pub fn new(bans: Vec<BannedCall>) -> Self {
+        Self { bans }
+    }
+}
+
+impl Rule for BannedCalls {
+    fn meta(&self) -> &'static RuleMeta {
+        &BANNED_CALL
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Some(callee) = callee_path(&call.callee) else {
+            return;
+        };
+        for ban in &self.bans {
+            if ban.call != callee || ban.severity == RuleLevel::Off {
+                continue;
+            }
+            if ban.within == BanScope::Step && !ctx.in_step_callback() {
+                continue;
+            }
+            let message = match &ban.message {
+                Some(message) => message.clone(),
+                None if ban.within == BanScope::Step => {
+                    format!("`{}` must not be called inside a step", callee)
+                }
+                None => format!("`{}` must not be called", callee),
+            };
+            let mut diagnostic = ctx.diagnostic(call.span(), &message, &BANNED_CALL);
+            if ban.severity == RuleLevel::Warn {
+                diagnostic.severity = Severity::Warning;
+            }
+            ctx.report(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use oxc_ast::ast::CallExpression;
+use oxc_span::Span;
+
+use crate::linter::{RelatedLocation, Severity, StepCall};
+use crate::rules::{Branch, FunctionScope, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const CONDITIONAL_AWAIT: RuleMeta = RuleMeta {
+    id: "conditional-await",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step promises must be awaited on every path",
+    explanation: "A step promise held in a variable and awaited only inside an `if`, a loop or \
+one side of `a ? b : c` is left dangling whenever that branch doesn't run: the workflow moves on \
+or finishes before the step has been recorded, and errors the step throws are swallowed. Await \
+the promise where every path reaches it, such as after the branch, or in every branch.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// A step promise assigned to a variable
+#[derive(Debug)]
+struct StepPromise {
+    name: String,
+    binding: Span,
+    call: Span,
+    method: String,
+    /// The branches the promise was assigned in
+    branches: Vec<Branch>,
+    /// The branches of each `await` of the variable, below those it was assigned in
+    awaits: Vec<Vec<Branch>>,
+}
+
+/// Whether `awaits` reach every path through `prefix`: one of them is in no branch
+/// below it, or some construct below it has an arm for every path and each arm is
+/// covered in turn
+fn covers(awaits: &[Vec<Branch>], prefix: &[Branch]) -> bool {
+    let below: Vec<&Vec<Branch>> = awaits
+        .iter()
+        .filter(|branches| branches.starts_with(prefix))
+        .collect();
+    if below.iter().any(|branches| branches.len() == prefix.len()) {
+        return true;
+    }
+    let mut constructs: Vec<Branch> = below
+        .iter()
+        .map(|branches| branches[prefix.len()])
+        .collect();
+    constructs.dedup_by_key(|branch| branch.construct);
+    constructs.iter().any(|branch| {
+        (0..branch.arms).all(|arm| {
+            let mut arm_prefix = prefix.to_vec();
+            arm_prefix.push(Branch { arm, ..*branch });
+            covers(awaits, &arm_prefix)
+        })
+    })
+}
+
+/// Reports step promises held in variables that are awaited on some paths but not others
+#[derive(Debug, Default)]
+pub struct ConditionalAwait {
+    /// Step promises assigned in each function being walked
+    scopes: Vec<Vec<StepPromise>>,
+}
+
+impl Rule for ConditionalAwait {
+    fn meta(&self) -> &'static RuleMeta {
+        &CONDITIONAL_AWAIT
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        let Some(promises) = self.scopes.pop() else {
+            return;
+        };
+        for promise in promises {
+            // Promises that are never awaited are reported by `await-step`
+            if promise.awaits.is_empty() || covers(&promise.awaits, &[]) {
+                continue;
+            }
+            let message = format!(
+                "`{}` holds the `{}` promise but is only awaited on some paths, so it dangles on the others; await it where every path reaches, or in every branch",
+                promise.name, promise.method
+            );
+            let diagnostic = ctx
+                .diagnostic(promise.call, &message, &CONDITIONAL_AWAIT)
+                .with_related(RelatedLocation::new(
+                    ctx.source(),
+                    promise.binding,
+                    &format!("`{}` is assigned the step promise here", promise.name),
+                ));
+            ctx.report(diagnostic);
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        let (
+            Some(promises),
+            StepUsage::Assigned {
+                name: Some(name),
+                binding,
+            },
+        ) = (self.scopes.last_mut(), usage)
+        else {
+            return;
+        };
+        // A later assignment to the same name replaces the promise
+        promises.retain(|promise| promise.name != *name);
+        promises.push(StepPromise {
+            name: name.clone(),
+            binding: *binding,
+            call: call.span,
+            method: format!("{}.{}", call.receiver, call.method),
+            branches: ctx.branches().to_vec(),
+            awaits: Vec::new(),
+        });
+    }
+
+    fn check_awaited_vars(&mut self, ctx: &mut RuleContext, names: &[&str]) {
+        let Some(promises) = self.scopes.last_mut() else {
+            return;
+        };
+        for promise in promises {
+            if !names.contains(&promise.name.as_str()) {
+                continue;
+            }
+            // An await outside the branches the promise was assigned in, as with `var`,
+            // isn't tracked
+            let branches = match ctx.branches().strip_prefix(promise.branches.as_slice()) {
+                Some(below) => below.to_vec(),
+                None => Vec::new(),
+            };
+            promise.awaits.push(branches);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use oxc_ast::ast::{Class, ClassType};
+
+use crate::linter::{Fix, Severity, TextEdit};
+use crate::rules::{Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const EXPORT_WORKFLOW_CLASS: RuleMeta = RuleMeta {
+    id: "export-workflow-class",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "Classes extending `WorkflowEntrypoint` must be exported",
+    explanation: "A `[[workflows]]` binding in the wrangler config names the class that runs the \
+workflow, and the runtime looks that class up among the Worker's exports. A \
+`WorkflowEntrypoint` class that its module doesn't export can't be bound, so the workflow \
+never runs. Export it with `export class MyWorkflow extends WorkflowEntrypoint`, and re-export \
+it from the Worker's `main` module if it lives elsewhere.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Reports top-level `WorkflowEntrypoint` classes that the file doesn't export
+#[derive(Debug, Default)]
+pub struct ExportWorkflowClass;
+
+impl Rule for ExportWorkflowClass {
+    fn meta(&self) -> &'static RuleMeta {
+        &EXPORT_WORKFLOW_CLASS
+    }
+
+    fn check_class(&mut self, ctx: &mut RuleContext, class: &Class) {
+        if class.r#type != ClassType::ClassDeclaration || !extends_workflow_entrypoint(class) {
+            return;
+        }
+        let Some(id) = &class.id else {
+            return;
+        };
+        // Classes declared inside functions can't be exported, so only top-level ones are checked
+        if !ctx.is_local_class(&id.name) || ctx.is_exported(&id.name) {
+            return;
+        }
+        let message = format!(
+            "Workflow class `{}` isn't exported, so no `[[workflows]]` binding can use it and the workflow never runs; export it",
+            id.name
+        );
+        let diagnostic = ctx
+            .diagnostic(id.span, &message, &EXPORT_WORKFLOW_CLASS)
+            .with_fix(Fix {
+                title: format!("Export `{}`", id.name),
+                edits: vec![TextEdit::insert(class.span.start, "export ")],
+            });
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,120 @@
+use oxc_ast::ast::CallExpression;
+use oxc_span::Span;
+
+use crate::config::MaxStepsOptions;
+use crate::linter::{Severity, StepCall};
+use crate::rules::{FunctionScope, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const MAX_STEPS: RuleMeta = RuleMeta {
+    id: "max-steps",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflows must stay under the maximum number of steps per instance",
+    explanation: "Cloudflare limits how many steps a single workflow instance can run, 1024 by \
+default. This rule counts the step calls in each workflow, multiplying those in loops with a \
+constant number of iterations, and warns once the count reaches 90% of the limit. Batch work \
+into fewer steps, or split it across several workflow instances. The limit can be changed \
+under `[rule_options.max-steps]`.",
+    docs_url: "https://developers.cloudflare.com/workflows/reference/limits/",
+};
+
+/// A workflow body being counted
+#[derive(Debug)]
+struct Count {
+    steps: u64,
+    /// The step call that brought the count to the warning threshold
+    threshold_call: Option<Span>,
+}
+
+/// Reports workflows whose step calls approach or exceed the step limit
+#[derive(Debug)]
+pub struct MaxSteps {
+    limit: u64,
+    /// Whether each function entered is in a workflow
+    in_workflow: Vec<bool>,
+    count: Option<Count>,
+}
+
+impl MaxSteps {
+    pub fn new(options: MaxStepsOptions) -> Self {
+        Self {
+            limit: options.limit,
+            in_workflow: Vec::new(),
+            count: None,
+        }
+    }
+
+    /// The step count at which the rule starts warning
+    fn threshold(&self) -> u64 {
+        self.limit.saturating_mul(9).div_ceil(10).max(1)
+    }
+}
+
+impl Default for MaxSteps {
+    fn default() -> Self {
+        Self::new(MaxStepsOptions::default())
+    }
+}
+
+impl Rule for MaxSteps {
+    fn meta(&self) -> &'static RuleMeta {
+        &MAX_STEPS
+    }
+
+    fn enter_function(&mut self, ctx: &mut RuleContext, _scope: FunctionScope) {
+        let outer = self.in_workflow.last().copied().unwrap_or(false);
+        if ctx.in_workflow() && !outer {
+            self.count = Some(Count {
+                steps: 0,
+                threshold_call: None,
+            });
+        }
+        self.in_workflow.push(ctx.in_workflow());
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        let inner = self.in_workflow.pop().unwrap_or(false);
+        let outer = self.in_workflow.last().copied().unwrap_or(false);
+        if !inner || outer {
+            return;
+        }
+        let Some(Count {
+            steps,
+            threshold_call: Some(span),
+        }) = self.count.take()
+        else {
+            return;
+        };
+        let message = if steps > self.limit {
+            format!(
+                "Workflow makes about {} step calls, more than the limit of {} steps per instance; batch work into fewer steps",
+                steps, self.limit
+            )
+        } else {
+            format!(
+                "Workflow makes about {} step calls, close to the limit of {} steps per instance",
+                steps, self.limit
+            )
+        };
+        let diagnostic = ctx.diagnostic(span, &message, &MAX_STEPS);
+        ctx.report(diagnostic);
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let threshold = self.threshold();
+        let Some(count) = &mut self.count else {
+            return;
+        };
+        count.steps = count.steps.saturating_add(ctx.loop_iterations());
+        if count.steps >= threshold && count.threshold_call.is_none() {
+            count.threshold_call = Some(call.span);
+        }
+    }
+}
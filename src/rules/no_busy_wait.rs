@@ -0,0 +1,198 @@
+use oxc_ast::ast::{AssignmentExpression, AssignmentTarget, CallExpression, Expression, Statement};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{FunctionScope, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_BUSY_WAIT: RuleMeta = RuleMeta {
+    id: "no-busy-wait",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Polling loops in a workflow must sleep or wait for an event",
+    explanation: "A `while` loop that checks on something until it is ready, such as \
+`while (!(await isDone()))` or a status re-read with `step.do` on every iteration, without \
+`step.sleep`, `step.sleepUntil` or `step.waitForEvent` between checks, runs back to back: it \
+burns through the instance's CPU time and step limit, and hammers whatever it polls. Sleep \
+between iterations, or have the other side send an event and wait for it with \
+`step.waitForEvent`. Loops that work through a list, like `while (ids.length)`, aren't polling.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+/// Step methods that pause the workflow
+const WAIT_METHODS: &[&str] = &["sleep", "sleepUntil", "waitForEvent"];
+
+/// A `while` or `do...while` loop in a workflow body
+#[derive(Debug)]
+struct PollingLoop {
+    span: Span,
+    /// The loop's keyword, `while` or `do`
+    keyword: Span,
+    /// Variables the loop's condition reads
+    condition: Vec<String>,
+    /// Whether the condition is `true`, so only a `break` ends the loop
+    forever: bool,
+    /// Whether the condition checks on something awaited, directly or through a variable
+    /// the loop assigns an awaited value to
+    polls: bool,
+    /// The first step call in the loop that doesn't wait, such as `step.do`
+    step: Option<String>,
+    waits: bool,
+}
+
+impl PollingLoop {
+    /// What the loop checks on each iteration, if it is polling
+    fn checks(&self) -> Option<String> {
+        if self.waits {
+            return None;
+        }
+        match &self.step {
+            Some(step) if self.polls || self.forever => Some(format!("calls `{}`", step)),
+            _ if self.polls => Some("checks an awaited value".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `expr` awaits something, as in `!(await this.env.KV.get('done'))`
+fn awaits(expr: &Expression) -> bool {
+    match expr.get_inner_expression() {
+        Expression::AwaitExpression(_) => true,
+        Expression::UnaryExpression(unary) => awaits(&unary.argument),
+        Expression::BinaryExpression(binary) => awaits(&binary.left) || awaits(&binary.right),
+        Expression::LogicalExpression(logical) => awaits(&logical.left) || awaits(&logical.right),
+        Expression::StaticMemberExpression(member) => awaits(&member.object),
+        Expression::CallExpression(call) => match call.callee.get_inner_expression() {
+            Expression::StaticMemberExpression(member) => awaits(&member.object),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The variables `expr` reads, as `status` in `status !== 'done'`
+fn read_variables(expr: &Expression, names: &mut Vec<String>) {
+    match expr.get_inner_expression() {
+        Expression::Identifier(id) => names.push(id.name.to_string()),
+        Expression::UnaryExpression(unary) => read_variables(&unary.argument, names),
+        Expression::BinaryExpression(binary) => {
+            read_variables(&binary.left, names);
+            read_variables(&binary.right, names);
+        }
+        Expression::LogicalExpression(logical) => {
+            read_variables(&logical.left, names);
+            read_variables(&logical.right, names);
+        }
+        Expression::StaticMemberExpression(member) => read_variables(&member.object, names),
+        _ => {}
+    }
+}
+
+/// Reports `while` loops in workflows that check on something awaited, or make step calls
+/// until they `break`, without ever sleeping or waiting
+#[derive(Debug, Default)]
+pub struct NoBusyWait {
+    loops: Vec<PollingLoop>,
+    /// Bodies of the functions entered
+    bodies: Vec<Option<Span>>,
+}
+
+impl Rule for NoBusyWait {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_BUSY_WAIT
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, scope: FunctionScope) {
+        self.bodies.push(scope.body);
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        // Loops in the function being left have been walked in full
+        let body = self.bodies.pop().flatten();
+        let (done, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.loops)
+            .into_iter()
+            .partition(|polling| {
+                body.is_none_or(|body| {
+                    body.start <= polling.span.start && polling.span.end <= body.end
+                })
+            });
+        self.loops = pending;
+        for polling in done {
+            let Some(checks) = polling.checks() else {
+                continue;
+            };
+            let keyword =
+                &ctx.source()[polling.keyword.start as usize..polling.keyword.end as usize];
+            let message = format!(
+                "This `{}` loop {} on every iteration without `step.sleep`, `step.sleepUntil` or `step.waitForEvent`, so it polls as fast as it can; sleep between iterations or wait for an event",
+                keyword, checks
+            );
+            let diagnostic = ctx.diagnostic(polling.keyword, &message, &NO_BUSY_WAIT);
+            ctx.report(diagnostic);
+        }
+    }
+
+    fn check_loop(&mut self, ctx: &mut RuleContext, loop_stmt: &Statement) {
+        if !ctx.in_workflow() || ctx.in_step_callback() {
+            return;
+        }
+        let (keyword, test) = match loop_stmt {
+            Statement::WhileStatement(while_stmt) => ("while", &while_stmt.test),
+            Statement::DoWhileStatement(do_while) => ("do", &do_while.test),
+            _ => return,
+        };
+        let mut condition = Vec::new();
+        read_variables(test, &mut condition);
+        let span = loop_stmt.span();
+        self.loops.push(PollingLoop {
+            span,
+            keyword: Span::new(span.start, span.start + keyword.len() as u32),
+            condition,
+            forever: matches!(test.get_inner_expression(), Expression::BooleanLiteral(b) if b.value),
+            polls: awaits(test),
+            step: None,
+            waits: false,
+        });
+    }
+
+    fn check_assignment(&mut self, _ctx: &mut RuleContext, assignment: &AssignmentExpression) {
+        // `status = await step.do(...)` re-reads what the condition checks
+        let AssignmentTarget::AssignmentTargetIdentifier(id) = &assignment.left else {
+            return;
+        };
+        if !awaits(&assignment.right) {
+            return;
+        }
+        for polling in &mut self.loops {
+            if polling.span.start <= assignment.span.start
+                && assignment.span.end <= polling.span.end
+                && polling
+                    .condition
+                    .iter()
+                    .any(|name| name == id.name.as_str())
+            {
+                polling.polls = true;
+            }
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        _ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let waits = WAIT_METHODS.contains(&call.method.as_str());
+        for polling in &mut self.loops {
+            if call.span.start < polling.span.start || polling.span.end < call.span.end {
+                continue;
+            }
+            if waits {
+                polling.waits = true;
+            } else if polling.step.is_none() {
+                polling.step = Some(format!("{}.{}", call.receiver, call.method));
+            }
+        }
+    }
+}
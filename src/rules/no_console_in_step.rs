@@ -0,0 +1,46 @@
+use oxc_ast::ast::CallExpression;
+use oxc_span::GetSpan;
+
+use crate::linter::Severity;
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta};
+
+pub const NO_CONSOLE_IN_STEP: RuleMeta = RuleMeta {
+    id: "no-console-in-step",
+    default_severity: Severity::Warning,
+    enabled_by_default: false,
+    fixable: false,
+    description: "Step callbacks must not log with `console`",
+    explanation: "For teams that route workflow logging through a structured logger binding, \
+this rule flags `console.*` calls inside step callbacks. Console output from a step is \
+repeated on every retry and isn't tied to the step's name or attempt, which makes it hard to \
+follow in Workers Logs. The rule is off by default; turn it on in `[rules]`.",
+    docs_url: "https://developers.cloudflare.com/workers/observability/logs/workers-logs/",
+};
+
+/// Reports `console.*` calls inside step callbacks
+#[derive(Debug, Default)]
+pub struct NoConsoleInStep;
+
+impl Rule for NoConsoleInStep {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_CONSOLE_IN_STEP
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        if !ctx.in_step_callback() {
+            return;
+        }
+        let Some(path) = callee_path(&call.callee) else {
+            return;
+        };
+        if !path.starts_with("console.") {
+            return;
+        }
+        let message = format!(
+            "`{}` is called inside a step callback; log through the project's structured logger instead",
+            path
+        );
+        let diagnostic = ctx.diagnostic(call.span(), &message, &NO_CONSOLE_IN_STEP);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,76 @@
+use oxc_ast::ast::{BindingPattern, Expression, VariableDeclarator};
+use oxc_span::GetSpan;
+
+use crate::linter::Severity;
+use crate::rules::{Rule, RuleContext, RuleMeta};
+
+pub const NO_DESTRUCTURED_STEP_METHODS: RuleMeta = RuleMeta {
+    id: "no-destructured-step-methods",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step methods must be called on the step object",
+    explanation: "`const { do: doStep } = step` or `const sleep = step.sleep` takes the method \
+off the step object, so calling it no longer passes the step as `this` and can fail when the \
+step runs. cashmere also only recognises step calls made on the step object, so none of its \
+other rules check calls like `doStep(...)`: an unawaited one goes unreported. Call the method \
+on the step, as in `step.do(...)`, or pass the step object around instead.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// The methods of `WorkflowStep`
+const STEP_METHODS: &[&str] = &["do", "sleep", "sleepUntil", "waitForEvent"];
+
+/// Reports step methods taken off a step object by destructuring or assignment
+#[derive(Debug, Default)]
+pub struct NoDestructuredStepMethods;
+
+impl Rule for NoDestructuredStepMethods {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_DESTRUCTURED_STEP_METHODS
+    }
+
+    fn check_variable(&mut self, ctx: &mut RuleContext, declarator: &VariableDeclarator) {
+        let Some(init) = &declarator.init else {
+            return;
+        };
+        match (&declarator.id, init.get_inner_expression()) {
+            (BindingPattern::ObjectPattern(pattern), Expression::Identifier(object))
+                if ctx.is_step_object(&object.name) =>
+            {
+                for property in &pattern.properties {
+                    let Some(method) = property.key.static_name() else {
+                        continue;
+                    };
+                    if property.computed || !STEP_METHODS.contains(&method.as_ref()) {
+                        continue;
+                    }
+                    let message = format!(
+                        "`{}` is destructured from `{}`, so calls to it lose the step as `this` and aren't checked as step calls; call `{}.{}(...)` instead",
+                        method, object.name, object.name, method
+                    );
+                    let diagnostic =
+                        ctx.diagnostic(property.span, &message, &NO_DESTRUCTURED_STEP_METHODS);
+                    ctx.report(diagnostic);
+                }
+            }
+            (BindingPattern::BindingIdentifier(_), Expression::StaticMemberExpression(member)) => {
+                let Expression::Identifier(object) = &member.object else {
+                    return;
+                };
+                let method = member.property.name.as_str();
+                if !ctx.is_step_object(&object.name) || !STEP_METHODS.contains(&method) {
+                    return;
+                }
+                let message = format!(
+                    "`{}.{}` is taken off the step object, so calls to it lose the step as `this` and aren't checked as step calls; call `{}.{}(...)` instead",
+                    object.name, method, object.name, method
+                );
+                let diagnostic =
+                    ctx.diagnostic(member.span(), &message, &NO_DESTRUCTURED_STEP_METHODS);
+                ctx.report(diagnostic);
+            }
+            _ => {}
+        }
+    }
+}
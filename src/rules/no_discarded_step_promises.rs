@@ -0,0 +1,95 @@
+use oxc_ast::ast::{Argument, CallExpression, Expression};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_DISCARDED_STEP_PROMISES: RuleMeta = RuleMeta {
+    id: "no-discarded-step-promises",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step calls in `forEach` callbacks and discarded `map` results must be aggregated",
+    explanation: "`items.forEach(async (item) => await step.do(...))` throws away the promise \
+each callback returns, so `run` moves on, and may finish, before the steps are recorded, and \
+their errors are swallowed. The same happens to the array of step promises from an \
+`items.map(...)` whose result is never used. Collect the promises and await them together: \
+`await Promise.all(items.map((item) => step.do(...)))`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// A callback whose returned promises are thrown away
+#[derive(Debug)]
+struct DiscardedCallback {
+    span: Span,
+    /// `forEach` or `map`
+    method: String,
+    /// Source of the array being iterated, such as `items`
+    array: String,
+}
+
+/// Reports step calls in `forEach` callbacks and in `map` callbacks whose result is discarded
+#[derive(Debug, Default)]
+pub struct NoDiscardedStepPromises {
+    callbacks: Vec<DiscardedCallback>,
+}
+
+impl Rule for NoDiscardedStepPromises {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_DISCARDED_STEP_PROMISES
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            return;
+        };
+        let method = member.property.name.as_str();
+        let discarded = match method {
+            "forEach" => true,
+            "map" | "flatMap" => ctx.is_statement_call(call.span),
+            _ => false,
+        };
+        if !discarded {
+            return;
+        }
+        let span = match call.arguments.first() {
+            Some(Argument::ArrowFunctionExpression(arrow)) => arrow.span,
+            Some(Argument::FunctionExpression(func)) => func.span,
+            _ => return,
+        };
+        let array = member.object.span();
+        self.callbacks.push(DiscardedCallback {
+            span,
+            method: method.to_string(),
+            array: ctx.source()[array.start as usize..array.end as usize].to_string(),
+        });
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let Some(callback) = self.callbacks.iter().rev().find(|callback| {
+            callback.span.start <= call.span.start && call.span.end <= callback.span.end
+        }) else {
+            return;
+        };
+        let problem = if callback.method == "forEach" {
+            "`forEach` discards the promises its callback returns".to_string()
+        } else {
+            format!(
+                "the array of promises from `{}` is discarded",
+                callback.method
+            )
+        };
+        let message = format!(
+            "`{}.{}` is called in a `{}` callback, but {}; use `await Promise.all({}.map(...))` instead",
+            call.receiver, call.method, callback.method, problem, callback.array
+        );
+        let diagnostic = ctx.diagnostic(call.span, &message, &NO_DISCARDED_STEP_PROMISES);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,136 @@
+use oxc_ast::ast::{Argument, BinaryOperator, CallExpression, Expression};
+use oxc_span::{GetSpan, Span};
+
+use crate::config::DynamicStepNameOptions;
+use crate::linter::{Severity, StepCall};
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_DYNAMIC_STEP_NAME: RuleMeta = RuleMeta {
+    id: "no-dynamic-step-name",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step names must not be built from values that change between replays",
+    explanation: "A step's name is the key its result is cached under. When a name is built \
+from something that differs on each replay, such as `Date.now()` or a value fetched outside a \
+step, the replayed step gets a new name, misses its cached result and runs again. Loop \
+variables and values from the workflow's `event` are stable; other values can be allowed \
+under `[rule_options.no-dynamic-step-name]`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Values a step name may always be built from, besides loop variables
+const ALWAYS_ALLOWED: &[&str] = &["event"];
+
+/// Reports step names built from values other than loop variables, `event` and the
+/// configured allowed values
+#[derive(Debug, Default)]
+pub struct NoDynamicStepName {
+    allow: Vec<String>,
+}
+
+impl NoDynamicStepName {
+    pub fn new(options: DynamicStepNameOptions) -> Self {
+        Self {
+            allow: options.allow,
+        }
+    }
+
+    fn is_allowed(&self, path: &str) -> bool {
+        ALWAYS_ALLOWED
+            .iter()
+            .copied()
+            .chain(self.allow.iter().map(String::as_str))
+            .any(|allowed| {
+                path == allowed
+                    || path
+                        .strip_prefix(allowed)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+    }
+
+    /// Collect the parts of a step name that may change between replays
+    fn check_name(&self, ctx: &RuleContext, name: &Expression, unstable: &mut Vec<Span>) {
+        match name {
+            Expression::TemplateLiteral(template) => {
+                for expr in &template.expressions {
+                    self.check_value(ctx, expr, unstable);
+                }
+            }
+            Expression::BinaryExpression(bin) if bin.operator == BinaryOperator::Addition => {
+                self.check_value(ctx, &bin.left, unstable);
+                self.check_value(ctx, &bin.right, unstable);
+            }
+            Expression::ParenthesizedExpression(paren) => {
+                self.check_name(ctx, &paren.expression, unstable)
+            }
+            Expression::CallExpression(_) | Expression::NewExpression(_) => {
+                unstable.push(name.span())
+            }
+            // A plain name such as `step.do(name, ...)` is usually a constant or a
+            // parameter of a helper, which this rule can't see through
+            _ => {}
+        }
+    }
+
+    /// Collect the parts of a value interpolated into a step name that may change
+    fn check_value(&self, ctx: &RuleContext, value: &Expression, unstable: &mut Vec<Span>) {
+        match value {
+            Expression::StringLiteral(_)
+            | Expression::NumericLiteral(_)
+            | Expression::BooleanLiteral(_) => {}
+            Expression::TemplateLiteral(_) => self.check_name(ctx, value, unstable),
+            Expression::ParenthesizedExpression(paren) => {
+                self.check_value(ctx, &paren.expression, unstable)
+            }
+            Expression::BinaryExpression(bin) => {
+                self.check_value(ctx, &bin.left, unstable);
+                self.check_value(ctx, &bin.right, unstable);
+            }
+            Expression::ConditionalExpression(cond) => {
+                self.check_value(ctx, &cond.test, unstable);
+                self.check_value(ctx, &cond.consequent, unstable);
+                self.check_value(ctx, &cond.alternate, unstable);
+            }
+            Expression::Identifier(_) | Expression::StaticMemberExpression(_) => {
+                let stable = callee_path(value).is_some_and(|path| {
+                    let root = path.split('.').next().unwrap_or_default();
+                    ctx.is_loop_variable(root) || self.is_allowed(&path)
+                });
+                if !stable {
+                    unstable.push(value.span());
+                }
+            }
+            _ => unstable.push(value.span()),
+        }
+    }
+}
+
+impl Rule for NoDynamicStepName {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_DYNAMIC_STEP_NAME
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        _call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let Some(name) = expr.arguments.first().and_then(Argument::as_expression) else {
+            return;
+        };
+        let mut unstable = Vec::new();
+        self.check_name(ctx, name, &mut unstable);
+        for part in unstable {
+            let text = &ctx.source()[part.start as usize..part.end as usize];
+            let message = format!(
+                "Step name uses `{}`, which can change between replays; build step names from loop variables and `event` only",
+                text
+            );
+            let diagnostic = ctx.diagnostic(part, &message, &NO_DYNAMIC_STEP_NAME);
+            ctx.report(diagnostic);
+        }
+    }
+}
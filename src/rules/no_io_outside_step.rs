@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use oxc_ast::ast::{BindingPattern, CallExpression, VariableDeclarator};
+use oxc_span::GetSpan;
+
+use crate::linter::Severity;
+use crate::rules::{callee_path, FunctionScope, Rule, RuleContext, RuleMeta};
+
+pub const NO_IO_OUTSIDE_STEP: RuleMeta = RuleMeta {
+    id: "no-io-outside-step",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Network calls in a workflow must be made inside a step callback",
+    explanation: "Only the results of steps are checkpointed. A `fetch` or a call on a binding \
+such as `this.env.API.fetch(...)` made directly in `run` runs again every time the workflow \
+is replayed after a step, repeating its side effects and possibly getting a different answer \
+each time. Wrap it in `step.do` so it runs once and its result is stored.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Methods of the bindings on `env` that go over the network: KV namespaces, R2 buckets,
+/// D1 databases, queues, services, Workflows, Workers AI and Vectorize indexes
+const BINDING_METHODS: &[&str] = &[
+    "get",
+    "getWithMetadata",
+    "put",
+    "list",
+    "delete",
+    "head",
+    "createMultipartUpload",
+    "prepare",
+    "batch",
+    "exec",
+    "send",
+    "sendBatch",
+    "fetch",
+    "connect",
+    "create",
+    "createBatch",
+    "run",
+    "query",
+    "insert",
+    "upsert",
+];
+
+/// Reports network calls in a workflow body that aren't inside a step callback
+#[derive(Debug, Default)]
+pub struct NoIoOutsideStep {
+    /// Variables holding `env` or one of its bindings, with the path they hold, such as
+    /// `db` for `this.env.DB` in `const { DB: db } = this.env`, for each function the
+    /// linter is inside, outermost first
+    aliases: Vec<HashMap<String, String>>,
+}
+
+impl NoIoOutsideStep {
+    /// `path` with a leading alias replaced by what it holds
+    fn resolve(&self, path: &str) -> String {
+        let (head, rest) = path.split_once('.').unwrap_or((path, ""));
+        match self.aliases.iter().rev().find_map(|scope| scope.get(head)) {
+            Some(origin) if rest.is_empty() => origin.clone(),
+            Some(origin) => format!("{}.{}", origin, rest),
+            None => path.to_string(),
+        }
+    }
+
+    fn alias(&mut self, name: &str, path: String) {
+        if self.aliases.is_empty() {
+            self.aliases.push(HashMap::new());
+        }
+        if let Some(scope) = self.aliases.last_mut() {
+            scope.insert(name.to_string(), path);
+        }
+    }
+}
+
+/// Whether `path` is `env` itself rather than something on it
+fn is_env(path: &str) -> bool {
+    path == "env" || path == "this.env"
+}
+
+/// The name of the binding `path` refers to, such as `DB` for `this.env.DB`
+fn binding(path: &str) -> Option<&str> {
+    let name = path
+        .strip_prefix("this.env.")
+        .or_else(|| path.strip_prefix("env."))?;
+    (!name.contains('.')).then_some(name)
+}
+
+/// Whether a call to `callee` goes over the network: `fetch`, or a method such as `get`
+/// or `prepare` called on a binding on `env`. Other calls on `env` values, like
+/// `this.env.LOG_LEVEL.toLowerCase()` on a variable, stay local.
+fn is_io_call(callee: &str) -> bool {
+    if callee == "fetch" || callee.ends_with(".fetch") {
+        return true;
+    }
+    callee.rsplit_once('.').is_some_and(|(object, method)| {
+        binding(object).is_some() && BINDING_METHODS.contains(&method)
+    })
+}
+
+impl Rule for NoIoOutsideStep {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_IO_OUTSIDE_STEP
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {
+        self.aliases.push(HashMap::new());
+    }
+
+    fn leave_function(&mut self, _ctx: &mut RuleContext) {
+        self.aliases.pop();
+    }
+
+    fn check_variable(&mut self, _ctx: &mut RuleContext, declarator: &VariableDeclarator) {
+        let Some(init) = &declarator.init else {
+            return;
+        };
+        let Some(path) = callee_path(init).map(|path| self.resolve(&path)) else {
+            return;
+        };
+        if !is_env(&path) && binding(&path).is_none() {
+            return;
+        }
+        match &declarator.id {
+            BindingPattern::BindingIdentifier(id) => self.alias(&id.name, path),
+            // `const { KV, DB: db } = this.env`
+            BindingPattern::ObjectPattern(object) if is_env(&path) => {
+                for property in &object.properties {
+                    let (Some(key), BindingPattern::BindingIdentifier(id)) =
+                        (property.key.static_name(), &property.value)
+                    else {
+                        continue;
+                    };
+                    self.alias(&id.name, format!("{}.{}", path, key));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        if !ctx.in_workflow() || ctx.in_step_callback() {
+            return;
+        }
+        let Some(callee) = callee_path(&call.callee) else {
+            return;
+        };
+        let resolved = self.resolve(&callee);
+        if !is_io_call(&resolved) {
+            return;
+        }
+        let message = if resolved == callee {
+            format!(
+                "`{}` runs again on every replay outside a step; move it into a `step.do` callback",
+                callee
+            )
+        } else {
+            format!(
+                "`{}` calls `{}`, which runs again on every replay outside a step; move it into a `step.do` callback",
+                callee, resolved
+            )
+        };
+        let diagnostic = ctx.diagnostic(call.span(), &message, &NO_IO_OUTSIDE_STEP);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,112 @@
+use oxc_ast::ast::{Argument, CallExpression, Expression};
+use oxc_span::GetSpan;
+
+use super::serializable_step_result::Returns;
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_LARGE_STEP_RESULT: RuleMeta = RuleMeta {
+    id: "no-large-step-result",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "`step.do` callbacks shouldn't return large payloads",
+    explanation: "The value a `step.do` callback returns is stored as the step's result, and a \
+step's result is limited to 1 MiB. Whole response bodies, like `await res.arrayBuffer()`, and \
+large literals can go over that limit and fail the step, or at best fill the instance's state \
+with data the workflow only passes along. Store large payloads in R2 or KV from inside the step \
+and return their key instead.",
+    docs_url: "https://developers.cloudflare.com/workflows/reference/limits/",
+};
+
+/// Source length above which a returned object or array literal is reported
+const LARGE_LITERAL_BYTES: u32 = 64 * 1024;
+
+/// Methods that read a whole response or request body as binary
+const BODY_METHODS: &[&str] = &["arrayBuffer", "blob", "bytes"];
+
+/// Reports `step.do` callbacks that return whole bodies or large literals
+#[derive(Debug, Default)]
+pub struct NoLargeStepResult;
+
+/// What makes `value` too large to return from a step, if it is. Variables declared in
+/// the callback are followed once, so `const buf = await res.arrayBuffer(); return buf;`
+/// is reported at the `return`.
+fn large_value(
+    ctx: &RuleContext,
+    value: &Expression,
+    returns: &Returns,
+    follow: bool,
+) -> Option<String> {
+    match value.get_inner_expression() {
+        Expression::AwaitExpression(await_expr) => {
+            large_value(ctx, &await_expr.argument, returns, follow)
+        }
+        Expression::CallExpression(call) if call.arguments.is_empty() => {
+            let Expression::StaticMemberExpression(member) = &call.callee else {
+                return None;
+            };
+            let method = member.property.name.as_str();
+            BODY_METHODS.contains(&method).then(|| {
+                let span = member.object.span();
+                let object = &ctx.source()[span.start as usize..span.end as usize];
+                format!("the whole body from `{}.{}()`", object, method)
+            })
+        }
+        expr @ (Expression::ObjectExpression(_) | Expression::ArrayExpression(_)) => {
+            let size = expr.span().size();
+            (size > LARGE_LITERAL_BYTES).then(|| {
+                let kind = if matches!(expr, Expression::ArrayExpression(_)) {
+                    "an array"
+                } else {
+                    "an object"
+                };
+                format!("{} literal of {} KiB", kind, size / 1024)
+            })
+        }
+        Expression::Identifier(id) if follow => {
+            let init = returns.variable(&id.name)?;
+            large_value(ctx, init, returns, false)
+        }
+        _ => None,
+    }
+}
+
+impl Rule for NoLargeStepResult {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_LARGE_STEP_RESULT
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "do" || expr.arguments.len() < 2 {
+            return;
+        }
+        let Some(returns) = expr
+            .arguments
+            .last()
+            .and_then(Argument::as_expression)
+            .and_then(Returns::of_callback)
+        else {
+            return;
+        };
+        let large: Vec<_> = returns
+            .values
+            .iter()
+            .filter_map(|value| Some((value.span(), large_value(ctx, value, &returns, true)?)))
+            .collect();
+        for (part, what) in large {
+            let message = format!(
+                "`{}.{}` callback returns {}, but step results are limited to 1 MiB; store large payloads in R2 and return their key",
+                call.receiver, call.method, what
+            );
+            let diagnostic = ctx.diagnostic(part, &message, &NO_LARGE_STEP_RESULT);
+            ctx.report(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use oxc_ast::ast::{Argument, CallExpression, Class, Expression, ImportDeclaration};
+use oxc_span::Span;
+
+use crate::linter::Severity;
+use crate::rules::{FunctionScope, Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const NO_NODE_BUILTINS: RuleMeta = RuleMeta {
+    id: "no-node-builtins",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflow modules must not import Node.js modules that Workers doesn't provide",
+    explanation: "Workers runs on its own runtime rather than Node.js. With the \
+`nodejs_compat` flag it provides a subset of Node's built-in modules, such as `node:buffer`, \
+`node:crypto` and `node:events`, but modules like `fs`, `child_process` and `net` have no \
+working implementation, so importing them fails when the Worker is deployed or when the code \
+runs. Use Workers APIs instead: R2 or KV for files, `fetch` for network requests and \
+`cloudflare:sockets` for TCP.",
+    docs_url: "https://developers.cloudflare.com/workers/runtime-apis/nodejs/",
+};
+
+/// Node.js modules Workers provides with the `nodejs_compat` flag
+const WORKERS_NODE_MODULES: &[&str] = &[
+    "assert",
+    "assert/strict",
+    "async_hooks",
+    "buffer",
+    "crypto",
+    "diagnostics_channel",
+    "dns",
+    "dns/promises",
+    "events",
+    "http",
+    "https",
+    "path",
+    "path/posix",
+    "path/win32",
+    "process",
+    "querystring",
+    "stream",
+    "stream/consumers",
+    "stream/promises",
+    "stream/web",
+    "string_decoder",
+    "timers",
+    "timers/promises",
+    "tls",
+    "url",
+    "util",
+    "util/types",
+    "zlib",
+];
+
+/// Node.js built-in modules Workers doesn't provide, reported without a `node:` prefix too
+const UNSUPPORTED_NODE_MODULES: &[&str] = &[
+    "child_process",
+    "cluster",
+    "dgram",
+    "fs",
+    "fs/promises",
+    "http2",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "perf_hooks",
+    "readline",
+    "repl",
+    "trace_events",
+    "tty",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+];
+
+/// Reports imports of Node.js modules Workers doesn't provide, in modules that define a
+/// `WorkflowEntrypoint` class
+#[derive(Debug, Default)]
+pub struct NoNodeBuiltins {
+    /// Function scopes entered, counting the top level of the file
+    depth: usize,
+    /// Whether the file defines a workflow class
+    has_workflow: bool,
+    /// Unsupported module specifiers and the spans of their string literals
+    imports: Vec<(String, Span)>,
+}
+
+/// Whether importing `specifier` fails on Workers
+fn is_unsupported(specifier: &str) -> bool {
+    match specifier.strip_prefix("node:") {
+        Some(module) => !WORKERS_NODE_MODULES.contains(&module),
+        None => UNSUPPORTED_NODE_MODULES.contains(&specifier),
+    }
+}
+
+impl Rule for NoNodeBuiltins {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_NODE_BUILTINS
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {
+        self.depth += 1;
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        self.depth -= 1;
+        if self.depth > 0 || !self.has_workflow {
+            return;
+        }
+        for (specifier, span) in self.imports.drain(..) {
+            let message = format!(
+                "`{}` isn't available in Workers, even with the `nodejs_compat` flag; use a Workers API instead",
+                specifier
+            );
+            let diagnostic = ctx.diagnostic(span, &message, &NO_NODE_BUILTINS);
+            ctx.report(diagnostic);
+        }
+    }
+
+    fn check_class(&mut self, _ctx: &mut RuleContext, class: &Class) {
+        self.has_workflow |= extends_workflow_entrypoint(class);
+    }
+
+    fn check_import(&mut self, _ctx: &mut RuleContext, import: &ImportDeclaration) {
+        if is_unsupported(&import.source.value) {
+            self.imports
+                .push((import.source.value.to_string(), import.source.span));
+        }
+    }
+
+    fn check_call(&mut self, _ctx: &mut RuleContext, call: &CallExpression) {
+        // `require("fs")`
+        if !matches!(&call.callee, Expression::Identifier(id) if id.name == "require") {
+            return;
+        }
+        if let Some(Argument::StringLiteral(specifier)) = call.arguments.first() {
+            if is_unsupported(&specifier.value) {
+                self.imports
+                    .push((specifier.value.to_string(), specifier.span));
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use oxc_ast::ast::{CallExpression, NewExpression};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::Severity;
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta};
+
+pub const NO_NONDETERMINISM: RuleMeta = RuleMeta {
+    id: "no-nondeterminism",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Clock and random values in a workflow must come from a step",
+    explanation: "Workflows replays `run` from the top after every step. `Date.now()`, \
+`new Date()`, `Math.random()` and `crypto.randomUUID()` return something different on each \
+replay, so step names, branches or payloads built from them change between runs. Read them \
+inside a `step.do` callback, whose result is stored and returned unchanged on replay.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Calls whose result changes from one replay to the next
+const NONDETERMINISTIC_CALLS: &[&str] = &["Date.now", "Math.random", "crypto.randomUUID"];
+
+/// Reports clock and random values read in a workflow body outside a step callback
+#[derive(Debug, Default)]
+pub struct NoNondeterminism;
+
+fn report(ctx: &mut RuleContext, span: Span, expression: &str) {
+    if !ctx.in_workflow() || ctx.in_step_callback() {
+        return;
+    }
+    let message = format!(
+        "`{}` changes on every replay outside a step; read it inside a `step.do` callback",
+        expression
+    );
+    let diagnostic = ctx.diagnostic(span, &message, &NO_NONDETERMINISM);
+    ctx.report(diagnostic);
+}
+
+impl Rule for NoNondeterminism {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_NONDETERMINISM
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Some(callee) = callee_path(&call.callee) else {
+            return;
+        };
+        if NONDETERMINISTIC_CALLS.contains(&callee.as_str()) {
+            report(ctx, call.span(), &format!("{}()", callee));
+        }
+    }
+
+    fn check_new(&mut self, ctx: &mut RuleContext, new_expr: &NewExpression) {
+        // `new Date(timestamp)` is fine; only the current time differs between replays
+        if new_expr.arguments.is_empty() && callee_path(&new_expr.callee).as_deref() == Some("Date")
+        {
+            report(ctx, new_expr.span(), "new Date()");
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use oxc_ast::ast::{
+    AssignmentExpression, Expression, IdentifierReference, SimpleAssignmentTarget, UpdateExpression,
+};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::Severity;
+use crate::rules::{Rule, RuleContext, RuleMeta};
+
+pub const NO_OUTER_MUTATION_IN_STEP: RuleMeta = RuleMeta {
+    id: "no-outer-mutation-in-step",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step callbacks must not assign to variables declared outside them",
+    explanation: "When a workflow replays, completed steps return their stored result without \
+running their callback again. Anything a callback writes to a variable declared outside it, \
+like `total += x`, is only set on the run that executed the step and is missing on every \
+replay. Return the value from the step and assign the result instead: \
+`total = await step.do(\"sum\", async () => total + x)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports assignments in step callbacks to variables declared outside the callback
+#[derive(Debug, Default)]
+pub struct NoOuterMutationInStep;
+
+/// The variable a write to `target` changes: the variable itself, or the object at the
+/// root of a property write like `totals.count = 1`
+fn written_variable<'b>(target: &'b SimpleAssignmentTarget) -> Option<&'b IdentifierReference<'b>> {
+    if let SimpleAssignmentTarget::AssignmentTargetIdentifier(id) = target {
+        return Some(id);
+    }
+    let mut object = target.as_member_expression()?.object();
+    loop {
+        match object {
+            Expression::Identifier(id) => return Some(id),
+            Expression::StaticMemberExpression(member) => object = &member.object,
+            Expression::ComputedMemberExpression(member) => object = &member.object,
+            _ => return None,
+        }
+    }
+}
+
+fn check_write(ctx: &mut RuleContext, target: &SimpleAssignmentTarget, span: Span) {
+    if !ctx.in_step_callback() {
+        return;
+    }
+    let Some(variable) = written_variable(target) else {
+        return;
+    };
+    if ctx.is_step_callback_local(&variable.name) {
+        return;
+    }
+    let message = format!(
+        "`{}` is declared outside this step callback, so the write is lost when the workflow replays; return the value from the step instead",
+        variable.name
+    );
+    let diagnostic = ctx.diagnostic(span, &message, &NO_OUTER_MUTATION_IN_STEP);
+    ctx.report(diagnostic);
+}
+
+impl Rule for NoOuterMutationInStep {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_OUTER_MUTATION_IN_STEP
+    }
+
+    fn check_assignment(&mut self, ctx: &mut RuleContext, assignment: &AssignmentExpression) {
+        if let Some(target) = assignment.left.as_simple_assignment_target() {
+            check_write(ctx, target, target.span());
+        }
+    }
+
+    fn check_update(&mut self, ctx: &mut RuleContext, update: &UpdateExpression) {
+        check_write(ctx, &update.argument, update.argument.span());
+    }
+}
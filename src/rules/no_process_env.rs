@@ -0,0 +1,65 @@
+use oxc_ast::ast::{Class, Expression, StaticMemberExpression};
+use oxc_span::Span;
+
+use crate::linter::Severity;
+use crate::rules::{FunctionScope, Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const NO_PROCESS_ENV: RuleMeta = RuleMeta {
+    id: "no-process-env",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflow modules must read bindings from `this.env`, not `process.env`",
+    explanation: "Workers don't have Node's environment: vars, secrets and bindings from the \
+Wrangler config are passed to the workflow class and read as `this.env.NAME`. Without the \
+`nodejs_compat` flag `process` doesn't exist at all, and even with it `process.env` is only \
+filled in when the Worker opts in, so `process.env.API_KEY` is usually `undefined` when the \
+workflow runs.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Reports `process.env` reads in modules that define a `WorkflowEntrypoint` class
+#[derive(Debug, Default)]
+pub struct NoProcessEnv {
+    /// Function scopes entered, counting the top level of the file
+    depth: usize,
+    /// Whether the file defines a workflow class
+    has_workflow: bool,
+    /// Spans of the `process.env` expressions seen, reported once the file has been walked
+    reads: Vec<Span>,
+}
+
+impl Rule for NoProcessEnv {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_PROCESS_ENV
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {
+        self.depth += 1;
+    }
+
+    fn leave_function(&mut self, ctx: &mut RuleContext) {
+        self.depth -= 1;
+        if self.depth > 0 || !self.has_workflow {
+            return;
+        }
+        for span in self.reads.drain(..) {
+            let message = "`process.env` isn't how Workers get their configuration; read vars, secrets and bindings from `this.env` in the workflow class";
+            let diagnostic = ctx.diagnostic(span, message, &NO_PROCESS_ENV);
+            ctx.report(diagnostic);
+        }
+    }
+
+    fn check_class(&mut self, _ctx: &mut RuleContext, class: &Class) {
+        self.has_workflow |= extends_workflow_entrypoint(class);
+    }
+
+    fn check_member(&mut self, _ctx: &mut RuleContext, member: &StaticMemberExpression) {
+        if member.property.name == "env"
+            && matches!(&member.object, Expression::Identifier(id) if id.name == "process")
+        {
+            self.reads.push(member.span);
+        }
+    }
+}
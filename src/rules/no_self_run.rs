@@ -0,0 +1,72 @@
+use oxc_ast::ast::{CallExpression, Class};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::Severity;
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const NO_SELF_RUN: RuleMeta = RuleMeta {
+    id: "no-self-run",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflows must not call their own `run` method",
+    explanation: "`run` is the entrypoint Workflows calls for an instance. Calling it again with \
+`this.run(...)` or `MyWorkflow.prototype.run(...)` bypasses the engine: the nested call \
+repeats every step with the same names in the same instance, so it gets the cached results of \
+the first pass or steps out of order on replay. Put shared logic in a separate method, or \
+start a new instance with `this.env.MY_WORKFLOW.create(...)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports calls to `run` on a workflow class from within that class
+#[derive(Debug, Default)]
+pub struct NoSelfRun {
+    /// `WorkflowEntrypoint` classes seen so far, with their names
+    classes: Vec<(Span, Option<String>)>,
+}
+
+impl Rule for NoSelfRun {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_SELF_RUN
+    }
+
+    fn check_class(&mut self, _ctx: &mut RuleContext, class: &Class) {
+        if extends_workflow_entrypoint(class) {
+            self.classes
+                .push((class.span, class.id.as_ref().map(|id| id.name.to_string())));
+        }
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Some(path) = callee_path(&call.callee) else {
+            return;
+        };
+        // `run.call(this, ...)` and `run.apply(this, ...)` invoke it too
+        let target = path
+            .strip_suffix(".call")
+            .or_else(|| path.strip_suffix(".apply"))
+            .unwrap_or(&path);
+        let Some((_, name)) = self
+            .classes
+            .iter()
+            .rev()
+            .find(|(span, _)| span.start <= call.span.start && call.span.end <= span.end)
+        else {
+            return;
+        };
+        let is_self_run = target == "this.run"
+            || name
+                .as_ref()
+                .is_some_and(|name| target == format!("{}.prototype.run", name));
+        if !is_self_run {
+            return;
+        }
+        let message = format!(
+            "`{}(...)` calls the workflow's entrypoint again, bypassing the engine and repeating its steps; move the shared logic into another method",
+            path
+        );
+        let diagnostic = ctx.diagnostic(call.span(), &message, &NO_SELF_RUN);
+        ctx.report(diagnostic);
+    }
+}
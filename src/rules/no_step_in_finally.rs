@@ -0,0 +1,59 @@
+use oxc_ast::ast::{CallExpression, TryStatement};
+use oxc_span::Span;
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_STEP_IN_FINALLY: RuleMeta = RuleMeta {
+    id: "no-step-in-finally",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step calls must not be made in `finally` blocks",
+    explanation: "A `finally` block runs whether the `try` block finished or threw, including \
+while a failed step's error is on its way out of `run`. A step called there is recorded on \
+some runs and not others, depending on where the error struck, which makes the instance's step \
+history hard to follow and to replay. Run cleanup steps in normal control flow, for example in \
+the `catch` block before rethrowing and again after the `try`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports step calls in `finally` blocks
+#[derive(Debug, Default)]
+pub struct NoStepInFinally {
+    finalizers: Vec<Span>,
+}
+
+impl Rule for NoStepInFinally {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_STEP_IN_FINALLY
+    }
+
+    fn check_try(&mut self, _ctx: &mut RuleContext, try_stmt: &TryStatement) {
+        if let Some(finalizer) = &try_stmt.finalizer {
+            self.finalizers.push(finalizer.span);
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let in_finally = self
+            .finalizers
+            .iter()
+            .any(|finalizer| finalizer.start <= call.span.start && call.span.end <= finalizer.end);
+        if !in_finally {
+            return;
+        }
+        let message = format!(
+            "`{}.{}` is called in a `finally` block, which also runs while an error is propagating, so whether the step is recorded depends on where the error struck; move it into normal control flow",
+            call.receiver, call.method
+        );
+        let diagnostic = ctx.diagnostic(call.span, &message, &NO_STEP_IN_FINALLY);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,63 @@
+use oxc_ast::ast::{CallExpression, Expression, NewExpression};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_STEP_IN_PROMISE_EXECUTOR: RuleMeta = RuleMeta {
+    id: "no-step-in-promise-executor",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step calls must not be made inside `new Promise` executors",
+    explanation: "Wrapping a step in `new Promise((resolve) => { step.do(...) })` hides the \
+step's own promise: it is neither awaited nor returned, so rejections are lost and the workflow \
+can't tell when the step finished, which breaks checkpointing it deterministically on replay. \
+Step calls already return promises, so await them directly: \
+`const result = await step.do(...)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports step calls made inside the executor passed to `new Promise`
+#[derive(Debug, Default)]
+pub struct NoStepInPromiseExecutor {
+    /// Executors passed to `new Promise`
+    executors: Vec<Span>,
+}
+
+impl Rule for NoStepInPromiseExecutor {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_STEP_IN_PROMISE_EXECUTOR
+    }
+
+    fn check_new(&mut self, _ctx: &mut RuleContext, new_expr: &NewExpression) {
+        if !matches!(&new_expr.callee, Expression::Identifier(id) if id.name == "Promise") {
+            return;
+        }
+        if let Some(executor) = new_expr.arguments.first() {
+            self.executors.push(executor.span());
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let in_executor = self
+            .executors
+            .iter()
+            .any(|executor| executor.start <= call.span.start && call.span.end <= executor.end);
+        if !in_executor {
+            return;
+        }
+        let message = format!(
+            "`{}.{}` is called inside a `new Promise` executor, which hides the step promise from the workflow; await the step directly instead of wrapping it",
+            call.receiver, call.method
+        );
+        let diagnostic = ctx.diagnostic(call.span, &message, &NO_STEP_IN_PROMISE_EXECUTOR);
+        ctx.report(diagnostic);
+    }
+}
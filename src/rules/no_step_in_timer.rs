@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use oxc_ast::ast::{Argument, CallExpression, Expression};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_STEP_IN_TIMER: RuleMeta = RuleMeta {
+    id: "no-step-in-timer",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step calls and step promises must not be used in timer callbacks",
+    explanation: "A workflow instance can hibernate between steps, and timers scheduled with \
+`setTimeout`, `setInterval` or `queueMicrotask` don't survive that: the callback may never \
+run, or run detached from the workflow, leaving the step promise dangling. Call steps directly \
+in the workflow body, and use `step.sleep` or `step.sleepUntil` to wait.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Functions that schedule a callback outside the workflow's control flow
+const TIMERS: &[&str] = &["setTimeout", "setInterval", "queueMicrotask"];
+
+/// Reports step calls made in, and step promises used by, timer callbacks
+#[derive(Debug, Default)]
+pub struct NoStepInTimer {
+    /// Arguments passed to timers, with the timer's name
+    timer_arguments: Vec<(Span, String)>,
+    /// Variables holding step promises
+    step_promises: HashSet<String>,
+}
+
+impl NoStepInTimer {
+    /// The timer whose arguments contain `span`, if any
+    fn timer_at(&self, span: Span) -> Option<&str> {
+        self.timer_arguments
+            .iter()
+            .find(|(argument, _)| argument.start <= span.start && span.end <= argument.end)
+            .map(|(_, timer)| timer.as_str())
+    }
+
+    fn report_promise(&self, ctx: &mut RuleContext, name: &str, span: Span, timer: &str) {
+        let message = format!(
+            "Step promise `{}` is used in a `{}` callback; timers don't survive workflow hibernation, so the promise is left dangling",
+            name, timer
+        );
+        let diagnostic = ctx.diagnostic(span, &message, &NO_STEP_IN_TIMER);
+        ctx.report(diagnostic);
+    }
+}
+
+impl Rule for NoStepInTimer {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_STEP_IN_TIMER
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let path = callee_path(&call.callee);
+        let timer = path
+            .as_deref()
+            .map(|path| path.strip_prefix("globalThis.").unwrap_or(path))
+            .filter(|name| TIMERS.contains(name));
+        if let Some(timer) = timer {
+            for argument in &call.arguments {
+                // `setTimeout(resolve, 1000, promise)` hands the promise to the timer too
+                if let Argument::Identifier(id) = argument {
+                    if self.step_promises.contains(id.name.as_str()) {
+                        self.report_promise(ctx, &id.name, id.span, timer);
+                    }
+                }
+                self.timer_arguments
+                    .push((argument.span(), timer.to_string()));
+            }
+            return;
+        }
+
+        // Calls on a step promise inside a timer callback, such as `p.then(...)`
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            return;
+        };
+        let Expression::Identifier(id) = &member.object else {
+            return;
+        };
+        if !self.step_promises.contains(id.name.as_str()) {
+            return;
+        }
+        if let Some(timer) = self.timer_at(call.span()).map(str::to_string) {
+            self.report_promise(ctx, &id.name, id.span, &timer);
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        if let StepUsage::Assigned {
+            name: Some(name), ..
+        } = usage
+        {
+            self.step_promises.insert(name.clone());
+        }
+        let Some(timer) = self.timer_at(call.span) else {
+            return;
+        };
+        let message = format!(
+            "`{}.{}` is called in a `{}` callback; timers don't survive workflow hibernation, so the step promise is left dangling. Call the step in the workflow body and use `step.sleep` to wait",
+            call.receiver, call.method, timer
+        );
+        let diagnostic = ctx.diagnostic(call.span, &message, &NO_STEP_IN_TIMER);
+        ctx.report(diagnostic);
+    }
+}
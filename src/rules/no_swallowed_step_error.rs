@@ -0,0 +1,106 @@
+use oxc_ast::ast::{CallExpression, CatchClause, Expression, Statement, TryStatement};
+use oxc_span::Span;
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_SWALLOWED_STEP_ERROR: RuleMeta = RuleMeta {
+    id: "no-swallowed-step-error",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Errors from awaited steps must not be caught and silently dropped",
+    explanation: "Once a step has used up its retries, `step.do` rejects with the last error. A \
+`catch` that is empty or only logs turns that permanent failure into a normal return: the \
+workflow carries on as if the step succeeded, usually with `undefined` where its result should \
+be, and the instance never shows as errored. Rethrow the error, or return or record a result \
+that says the step failed.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+/// Methods that only log, as in `console.error(e)` or `logger.warn(e)`
+const LOG_METHODS: &[&str] = &["log", "info", "warn", "error", "debug", "trace"];
+
+/// A `try` block with a `catch` clause
+#[derive(Debug)]
+struct CatchingTry {
+    block: Span,
+    /// Span of the `catch` keyword
+    catch: Span,
+    /// Whether the `catch` is empty or only logs
+    swallows: bool,
+    reported: bool,
+}
+
+/// Reports `catch` clauses that are empty or only log, around awaited step calls
+#[derive(Debug, Default)]
+pub struct NoSwallowedStepError {
+    tries: Vec<CatchingTry>,
+}
+
+/// Whether `statement` only logs, like `console.error(e);`
+fn is_log(statement: &Statement) -> bool {
+    let Statement::ExpressionStatement(expr_stmt) = statement else {
+        return false;
+    };
+    let Expression::CallExpression(call) = &expr_stmt.expression else {
+        return false;
+    };
+    callee_path(&call.callee).is_some_and(|path| {
+        let method = path.rsplit('.').next().unwrap_or(&path);
+        path.starts_with("console.") || LOG_METHODS.contains(&method)
+    })
+}
+
+fn swallows(handler: &CatchClause) -> bool {
+    handler.body.body.iter().all(is_log)
+}
+
+impl Rule for NoSwallowedStepError {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_SWALLOWED_STEP_ERROR
+    }
+
+    fn check_try(&mut self, _ctx: &mut RuleContext, try_stmt: &TryStatement) {
+        let Some(handler) = &try_stmt.handler else {
+            return;
+        };
+        self.tries.push(CatchingTry {
+            block: try_stmt.block.span,
+            catch: Span::new(
+                handler.span.start,
+                handler.span.start + "catch".len() as u32,
+            ),
+            swallows: swallows(handler),
+            reported: false,
+        });
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        if *usage != StepUsage::Awaited {
+            return;
+        }
+        // The innermost `try` is the one that catches the step's error
+        let Some(catching) = self.tries.iter_mut().rev().find(|catching| {
+            catching.block.start <= call.span.start && call.span.end <= catching.block.end
+        }) else {
+            return;
+        };
+        if !catching.swallows || catching.reported {
+            return;
+        }
+        catching.reported = true;
+        let message = format!(
+            "This `catch` swallows the error from `{}.{}`, so a step that failed for good looks like a success to the workflow; rethrow the error or return a result that records the failure",
+            call.receiver, call.method
+        );
+        let diagnostic = ctx.diagnostic(catching.catch, &message, &NO_SWALLOWED_STEP_ERROR);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,134 @@
+use oxc_ast::ast::{Argument, CallExpression, Expression, Statement, UnaryOperator};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const NO_UNUSED_STEP_RESULT: RuleMeta = RuleMeta {
+    id: "no-unused-step-result",
+    default_severity: Severity::Warning,
+    enabled_by_default: false,
+    fixable: false,
+    description: "Results returned from `step.do` callbacks should be used",
+    explanation: "Whatever a `step.do` callback returns is serialized and stored as the step's \
+result, counting towards the instance's state size, so that replays can skip the step. When the \
+workflow awaits the step and throws the result away, that storage buys nothing: either use the \
+result, or stop returning it from the callback.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/#step",
+};
+
+/// Reports `await step.do(...)` statements whose callback returns a value that is discarded
+#[derive(Debug, Default)]
+pub struct NoUnusedStepResult;
+
+/// Whether `value` is `undefined` or `void ...`, which return nothing worth storing
+fn is_undefined(value: &Expression) -> bool {
+    match value.get_inner_expression() {
+        Expression::Identifier(id) => id.name == "undefined",
+        Expression::UnaryExpression(unary) => unary.operator == UnaryOperator::Void,
+        _ => false,
+    }
+}
+
+/// Whether any of `statements` returns a value, not counting nested functions
+fn returns_value(statements: &[Statement]) -> bool {
+    statements.iter().any(statement_returns_value)
+}
+
+fn statement_returns_value(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::ReturnStatement(ret) => ret
+            .argument
+            .as_ref()
+            .is_some_and(|value| !is_undefined(value)),
+        Statement::BlockStatement(block) => returns_value(&block.body),
+        Statement::IfStatement(if_stmt) => {
+            statement_returns_value(&if_stmt.consequent)
+                || if_stmt
+                    .alternate
+                    .as_ref()
+                    .is_some_and(statement_returns_value)
+        }
+        Statement::ForStatement(for_stmt) => statement_returns_value(&for_stmt.body),
+        Statement::ForInStatement(for_in) => statement_returns_value(&for_in.body),
+        Statement::ForOfStatement(for_of) => statement_returns_value(&for_of.body),
+        Statement::WhileStatement(while_stmt) => statement_returns_value(&while_stmt.body),
+        Statement::DoWhileStatement(do_while) => statement_returns_value(&do_while.body),
+        Statement::LabeledStatement(labeled) => statement_returns_value(&labeled.body),
+        Statement::SwitchStatement(switch) => switch
+            .cases
+            .iter()
+            .any(|case| returns_value(&case.consequent)),
+        Statement::TryStatement(try_stmt) => {
+            returns_value(&try_stmt.block.body)
+                || try_stmt
+                    .handler
+                    .as_ref()
+                    .is_some_and(|handler| returns_value(&handler.body.body))
+                || try_stmt
+                    .finalizer
+                    .as_ref()
+                    .is_some_and(|finalizer| returns_value(&finalizer.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `callback` returns a value. Expression-bodied arrows only count when they
+/// return an object or array literal: `async () => notify(user)` is usually run for its
+/// effect, not its result.
+fn callback_returns_value(callback: &Expression) -> bool {
+    match callback {
+        Expression::ArrowFunctionExpression(arrow) if arrow.expression => {
+            arrow.get_expression().is_some_and(|body| {
+                matches!(
+                    body.get_inner_expression(),
+                    Expression::ObjectExpression(_) | Expression::ArrayExpression(_)
+                )
+            })
+        }
+        Expression::ArrowFunctionExpression(arrow) => returns_value(&arrow.body.statements),
+        Expression::FunctionExpression(func) => func
+            .body
+            .as_ref()
+            .is_some_and(|body| returns_value(&body.statements)),
+        _ => false,
+    }
+}
+
+impl Rule for NoUnusedStepResult {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_UNUSED_STEP_RESULT
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "do"
+            || expr.arguments.len() < 2
+            || !ctx.is_awaited_statement_call(call.span)
+        {
+            return;
+        }
+        let Some(callback) = expr.arguments.last().and_then(Argument::as_expression) else {
+            return;
+        };
+        if !callback_returns_value(callback) {
+            return;
+        }
+        let name = call
+            .name
+            .as_deref()
+            .map(|name| format!("('{}')", name))
+            .unwrap_or_default();
+        let message = format!(
+            "The result of `{}.{}{}` is discarded, but its callback returns a value that is stored as the step's result; use the result or stop returning it from the callback",
+            call.receiver, call.method, name
+        );
+        let diagnostic = ctx.diagnostic(call.span, &message, &NO_UNUSED_STEP_RESULT);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,120 @@
+use oxc_ast::ast::{CallExpression, Statement, TryStatement};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const PREFER_RETRIES_CONFIG: RuleMeta = RuleMeta {
+    id: "prefer-retries-config",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Use the `retries` config of `step.do` instead of a retry loop",
+    explanation: "`step.do` already retries a failing callback, with a configurable limit, delay \
+and backoff, and Workflows records each attempt so that replays pick up where they left off. \
+A loop that catches the step's error and tries again adds a new step, with a new name or a \
+reused one, for every attempt, and multiplies the step's own retries. Set \
+`step.do(name, { retries: { limit, delay, backoff } }, callback)` instead.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+/// A `try` block in a loop that looks like a manual retry
+#[derive(Debug)]
+struct RetryTry {
+    block: Span,
+    /// The loop's keyword, such as `for` or `while`
+    keyword: Span,
+    reported: bool,
+}
+
+/// Reports `for`, `while` and `do...while` loops that retry an awaited `step.do` by hand
+#[derive(Debug, Default)]
+pub struct PreferRetriesConfig {
+    tries: Vec<RetryTry>,
+}
+
+/// Whether `try_stmt` looks like one attempt of a retry: it leaves the loop once its block
+/// succeeds, or goes round again when its `catch` doesn't rethrow
+fn is_retry_attempt(try_stmt: &TryStatement) -> bool {
+    let Some(handler) = &try_stmt.handler else {
+        return false;
+    };
+    let catch = &handler.body.body;
+    if catch
+        .iter()
+        .any(|s| matches!(s, Statement::ThrowStatement(_)))
+    {
+        return false;
+    }
+    let leaves_on_success = try_stmt.block.body.iter().any(|s| {
+        matches!(
+            s,
+            Statement::BreakStatement(_) | Statement::ReturnStatement(_)
+        )
+    });
+    let continues_on_error = catch
+        .iter()
+        .any(|s| matches!(s, Statement::ContinueStatement(_)));
+    leaves_on_success || continues_on_error
+}
+
+impl Rule for PreferRetriesConfig {
+    fn meta(&self) -> &'static RuleMeta {
+        &PREFER_RETRIES_CONFIG
+    }
+
+    fn check_loop(&mut self, _ctx: &mut RuleContext, loop_stmt: &Statement) {
+        // `for...of` and `for...in` go through items rather than attempts
+        let (body, keyword) = match loop_stmt {
+            Statement::ForStatement(for_stmt) => (&for_stmt.body, "for"),
+            Statement::WhileStatement(while_stmt) => (&while_stmt.body, "while"),
+            Statement::DoWhileStatement(do_while) => (&do_while.body, "do"),
+            _ => return,
+        };
+        let statements = match body {
+            Statement::BlockStatement(block) => block.body.as_slice(),
+            body => std::slice::from_ref(body),
+        };
+        let start = loop_stmt.span().start;
+        for statement in statements {
+            if let Statement::TryStatement(try_stmt) = statement {
+                if is_retry_attempt(try_stmt) {
+                    self.tries.push(RetryTry {
+                        block: try_stmt.block.span,
+                        keyword: Span::new(start, start + keyword.len() as u32),
+                        reported: false,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        if call.method != "do" || *usage != StepUsage::Awaited {
+            return;
+        }
+        let Some(retry) =
+            self.tries.iter_mut().rev().find(|retry| {
+                retry.block.start <= call.span.start && call.span.end <= retry.block.end
+            })
+        else {
+            return;
+        };
+        if retry.reported {
+            return;
+        }
+        retry.reported = true;
+        let message = format!(
+            "This loop retries `{}.do` by catching its error and trying again; set `retries` in the step's config instead, like `{}.do(name, {{ retries: {{ limit: 5, delay: \"10 seconds\", backoff: \"exponential\" }} }}, callback)`",
+            call.receiver, call.receiver
+        );
+        let diagnostic = ctx.diagnostic(retry.keyword, &message, &PREFER_RETRIES_CONFIG);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use oxc_ast::ast::{CallExpression, Expression};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const PREFER_STEP_AWAIT: RuleMeta = RuleMeta {
+    id: "prefer-step-await",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Use `await` instead of `.then()`/`.catch()` on step promises",
+    explanation: "A workflow's `run` method is replayed from the top, with completed steps \
+returning their stored results. Handlers chained with `.then()`, `.catch()` or `.finally()` \
+run whenever the promise settles, which makes it hard to see what runs on a replay and which \
+errors reach the step's retries. `const result = await step.do(...)` inside `try`/`catch` \
+keeps the order of steps and their error handling in plain sight.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports `.then()`, `.catch()` and `.finally()` called on step promises
+#[derive(Debug, Default)]
+pub struct PreferStepAwait {
+    /// Calls that `.then()` and friends were chained on, with the chained method's
+    /// name and span, reported once the call turns out to be a step call
+    chained: Vec<(Span, String, Span)>,
+    /// Variables holding step promises
+    step_promises: HashSet<String>,
+}
+
+fn report(ctx: &mut RuleContext, method: &str, span: Span) {
+    let message = format!(
+        "Use `await` with try/catch instead of `.{}()` on a step promise; chained handlers make control flow and errors across replays harder to follow",
+        method
+    );
+    let diagnostic = ctx.diagnostic(span, &message, &PREFER_STEP_AWAIT);
+    ctx.report(diagnostic);
+}
+
+impl Rule for PreferStepAwait {
+    fn meta(&self) -> &'static RuleMeta {
+        &PREFER_STEP_AWAIT
+    }
+
+    fn check_call(&mut self, ctx: &mut RuleContext, call: &CallExpression) {
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            return;
+        };
+        let method = member.property.name.as_str();
+        if !matches!(method, "then" | "catch" | "finally") {
+            return;
+        }
+        match &member.object {
+            // The step call itself is checked next, so remember it until then
+            Expression::CallExpression(inner) => {
+                self.chained
+                    .push((inner.span(), method.to_string(), member.property.span));
+            }
+            Expression::Identifier(id) if self.step_promises.contains(id.name.as_str()) => {
+                report(ctx, method, member.property.span);
+            }
+            _ => {}
+        }
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        usage: &StepUsage,
+    ) {
+        if let StepUsage::Assigned {
+            name: Some(name), ..
+        } = usage
+        {
+            self.step_promises.insert(name.clone());
+        }
+        if let Some(index) = self
+            .chained
+            .iter()
+            .position(|(span, _, _)| *span == call.span)
+        {
+            let (_, method, span) = self.chained.remove(index);
+            report(ctx, &method, span);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use oxc_ast::ast::{
+    Argument, BindingPattern, CallExpression, Expression, FunctionBody, ObjectPropertyKind,
+    Statement,
+};
+use oxc_span::{GetSpan, Span};
+
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const SERIALIZABLE_STEP_RESULT: RuleMeta = RuleMeta {
+    id: "serializable-step-result",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "`step.do` callbacks must return serializable values",
+    explanation: "The value a `step.do` callback returns is serialized and stored as the step's \
+result, so that replays can skip the step. Functions, Symbols, Maps, Sets, instances of your own \
+classes and the step object don't survive serialization: they are lost or come back as plain \
+empty objects when the workflow replays. Return plain objects, arrays, strings, numbers and \
+booleans instead.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Built-in classes whose instances don't survive serialization
+const UNSERIALIZABLE_CLASSES: &[&str] = &["Map", "Set", "WeakMap", "WeakSet", "WeakRef"];
+
+/// Reports values returned from `step.do` callbacks that can't be serialized
+#[derive(Debug, Default)]
+pub struct SerializableStepResult;
+
+/// The returned values of a step callback, and the variables it declares with
+/// their initial values
+pub(super) struct Returns<'b, 'a> {
+    pub(super) values: Vec<&'b Expression<'a>>,
+    variables: Vec<(&'b str, &'b Expression<'a>)>,
+}
+
+impl<'b, 'a> Returns<'b, 'a> {
+    /// The returned values and variables of `callback`, if it is a function
+    pub(super) fn of_callback(callback: &'b Expression<'a>) -> Option<Self> {
+        let body: &FunctionBody = match callback {
+            Expression::ArrowFunctionExpression(arrow) => &arrow.body,
+            Expression::FunctionExpression(func) => func.body.as_ref()?,
+            _ => return None,
+        };
+        let mut returns = Returns {
+            values: Vec::new(),
+            variables: Vec::new(),
+        };
+        match callback {
+            Expression::ArrowFunctionExpression(arrow) if arrow.expression => {
+                returns.values.extend(arrow.get_expression());
+            }
+            _ => returns.collect(&body.statements),
+        }
+        Some(returns)
+    }
+
+    /// Collect the `return` statements of `statements`, not counting nested functions
+    fn collect(&mut self, statements: &'b [Statement<'a>]) {
+        for stmt in statements {
+            self.collect_statement(stmt);
+        }
+    }
+
+    fn collect_statement(&mut self, stmt: &'b Statement<'a>) {
+        match stmt {
+            Statement::ReturnStatement(ret) => self.values.extend(&ret.argument),
+            Statement::VariableDeclaration(decl) => {
+                for declarator in &decl.declarations {
+                    if let (BindingPattern::BindingIdentifier(id), Some(init)) =
+                        (&declarator.id, &declarator.init)
+                    {
+                        self.variables.push((id.name.as_str(), init));
+                    }
+                }
+            }
+            Statement::BlockStatement(block) => self.collect(&block.body),
+            Statement::IfStatement(if_stmt) => {
+                self.collect_statement(&if_stmt.consequent);
+                if let Some(alternate) = &if_stmt.alternate {
+                    self.collect_statement(alternate);
+                }
+            }
+            Statement::ForStatement(for_stmt) => self.collect_statement(&for_stmt.body),
+            Statement::ForInStatement(for_in) => self.collect_statement(&for_in.body),
+            Statement::ForOfStatement(for_of) => self.collect_statement(&for_of.body),
+            Statement::WhileStatement(while_stmt) => self.collect_statement(&while_stmt.body),
+            Statement::DoWhileStatement(do_while) => self.collect_statement(&do_while.body),
+            Statement::LabeledStatement(labeled) => self.collect_statement(&labeled.body),
+            Statement::SwitchStatement(switch) => {
+                for case in &switch.cases {
+                    self.collect(&case.consequent);
+                }
+            }
+            Statement::TryStatement(try_stmt) => {
+                self.collect(&try_stmt.block.body);
+                if let Some(handler) = &try_stmt.handler {
+                    self.collect(&handler.body.body);
+                }
+                if let Some(finalizer) = &try_stmt.finalizer {
+                    self.collect(&finalizer.body);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The initial value of the callback's variable `name`, if it declares one
+    pub(super) fn variable(&self, name: &str) -> Option<&'b Expression<'a>> {
+        self.variables
+            .iter()
+            .rev()
+            .find(|(variable, _)| *variable == name)
+            .map(|(_, init)| *init)
+    }
+}
+
+/// Collect the parts of a returned value that can't be serialized, with a description
+/// of each. `receiver` is the name of the step object.
+fn check_value(
+    ctx: &RuleContext,
+    value: &Expression,
+    returns: &Returns,
+    receiver: &str,
+    depth: usize,
+    unserializable: &mut Vec<(Span, String)>,
+) {
+    let what = match value {
+        Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_) => {
+            "a function".to_string()
+        }
+        Expression::ClassExpression(_) => "a class".to_string(),
+        Expression::CallExpression(call) if matches!(&call.callee, Expression::Identifier(id) if id.name == "Symbol") => {
+            "a Symbol".to_string()
+        }
+        Expression::StaticMemberExpression(member) if matches!(&member.object, Expression::Identifier(id) if id.name == "Symbol") => {
+            "a Symbol".to_string()
+        }
+        Expression::NewExpression(new_expr) => {
+            let Expression::Identifier(class) = &new_expr.callee else {
+                return;
+            };
+            if UNSERIALIZABLE_CLASSES.contains(&class.name.as_str()) {
+                format!("a `{}`", class.name)
+            } else if ctx.is_local_class(&class.name) {
+                format!("an instance of class `{}`", class.name)
+            } else {
+                return;
+            }
+        }
+        Expression::Identifier(id) if id.name == receiver => {
+            format!("the step object `{}`", receiver)
+        }
+        // Follow variables declared in the callback, once, so `const m = new Map(); return m;`
+        // is reported at the `return`
+        Expression::Identifier(id) if depth == 0 => {
+            if let Some(init) = returns.variable(&id.name) {
+                let mut found = Vec::new();
+                check_value(ctx, init, returns, receiver, depth + 1, &mut found);
+                if let Some((_, what)) = found.into_iter().next() {
+                    unserializable.push((value.span(), what));
+                }
+            }
+            return;
+        }
+        Expression::ParenthesizedExpression(paren) => {
+            return check_value(
+                ctx,
+                &paren.expression,
+                returns,
+                receiver,
+                depth,
+                unserializable,
+            )
+        }
+        Expression::ConditionalExpression(cond) => {
+            check_value(
+                ctx,
+                &cond.consequent,
+                returns,
+                receiver,
+                depth,
+                unserializable,
+            );
+            check_value(
+                ctx,
+                &cond.alternate,
+                returns,
+                receiver,
+                depth,
+                unserializable,
+            );
+            return;
+        }
+        Expression::AwaitExpression(await_expr) => {
+            return check_value(
+                ctx,
+                &await_expr.argument,
+                returns,
+                receiver,
+                depth,
+                unserializable,
+            )
+        }
+        Expression::ObjectExpression(object) => {
+            for property in &object.properties {
+                match property {
+                    ObjectPropertyKind::ObjectProperty(property) => check_value(
+                        ctx,
+                        &property.value,
+                        returns,
+                        receiver,
+                        depth,
+                        unserializable,
+                    ),
+                    ObjectPropertyKind::SpreadProperty(_) => {}
+                }
+            }
+            return;
+        }
+        Expression::ArrayExpression(array) => {
+            for element in &array.elements {
+                if let Some(element) = element.as_expression() {
+                    check_value(ctx, element, returns, receiver, depth, unserializable);
+                }
+            }
+            return;
+        }
+        _ => return,
+    };
+    unserializable.push((value.span(), what));
+}
+
+/// The parts of the values `callback` returns that can't be serialized
+fn check_callback(ctx: &RuleContext, callback: &Expression, receiver: &str) -> Vec<(Span, String)> {
+    let Some(returns) = Returns::of_callback(callback) else {
+        return Vec::new();
+    };
+    let mut unserializable = Vec::new();
+    for value in &returns.values {
+        check_value(ctx, value, &returns, receiver, 0, &mut unserializable);
+    }
+    unserializable
+}
+
+impl Rule for SerializableStepResult {
+    fn meta(&self) -> &'static RuleMeta {
+        &SERIALIZABLE_STEP_RESULT
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "do" || expr.arguments.len() < 2 {
+            return;
+        }
+        let Some(callback) = expr.arguments.last().and_then(Argument::as_expression) else {
+            return;
+        };
+        let unserializable = check_callback(ctx, callback, &call.receiver);
+        for (part, what) in unserializable {
+            let message = format!(
+                "`{}.{}` callback returns {}, which can't be serialized as the step's result; return plain objects, arrays, strings, numbers or booleans",
+                call.receiver, call.method, what
+            );
+            let diagnostic = ctx.diagnostic(part, &message, &SERIALIZABLE_STEP_RESULT);
+            ctx.report(diagnostic);
+        }
+    }
+}
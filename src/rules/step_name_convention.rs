@@ -0,0 +1,105 @@
+use oxc_ast::ast::CallExpression;
+use regex::Regex;
+
+use crate::config::{StepNameConventionOptions, KEBAB_CASE_PATTERN};
+use crate::linter::{Severity, StepCall};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const STEP_NAME_CONVENTION: RuleMeta = RuleMeta {
+    id: "step-name-convention",
+    default_severity: Severity::Warning,
+    enabled_by_default: false,
+    fixable: false,
+    description: "Step names must follow the project's naming convention",
+    explanation: "Step names show up in the Workflows dashboard, in logs and in `wrangler \
+workflows instances describe`. Names that follow one convention are easier to read and to \
+search for. This rule checks literal step names against a regular expression, kebab-case like \
+`fetch-user` by default, which can be changed under `[rule_options.step-name-convention]`. \
+Renaming a step changes the key its result is cached under, so rename steps before deploying \
+or while no instances are running.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports literal step names that don't match the configured pattern
+#[derive(Debug)]
+pub struct StepNameConvention {
+    /// `None` if the configured pattern isn't a valid regular expression
+    pattern: Option<Regex>,
+    kebab_case: bool,
+}
+
+impl StepNameConvention {
+    pub fn new(options: StepNameConventionOptions) -> Self {
+        Self {
+            pattern: Regex::new(&options.pattern).ok(),
+            kebab_case: options.pattern == KEBAB_CASE_PATTERN,
+        }
+    }
+}
+
+impl Default for StepNameConvention {
+    fn default() -> Self {
+        Self::new(StepNameConventionOptions::default())
+    }
+}
+
+/// `name` in kebab-case, splitting words on spaces, punctuation and lowercase-to-uppercase changes
+fn to_kebab_case(name: &str) -> String {
+    let mut kebab = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            let new_word = match previous {
+                None => false,
+                Some(prev) if !prev.is_ascii_alphanumeric() => true,
+                Some(prev) => c.is_ascii_uppercase() && !prev.is_ascii_uppercase(),
+            };
+            if new_word && !kebab.is_empty() {
+                kebab.push('-');
+            }
+            kebab.push(c.to_ascii_lowercase());
+        }
+        previous = Some(c);
+    }
+    kebab
+}
+
+impl Rule for StepNameConvention {
+    fn meta(&self) -> &'static RuleMeta {
+        &STEP_NAME_CONVENTION
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let (Some(pattern), Some(name), Some(name_span)) =
+            (&self.pattern, &call.name, call.name_span)
+        else {
+            return;
+        };
+        if pattern.is_match(name) {
+            return;
+        }
+        let suggestion = to_kebab_case(name);
+        let message = if self.kebab_case && !suggestion.is_empty() {
+            format!(
+                "Step name '{}' isn't kebab-case; name it like '{}'",
+                name, suggestion
+            )
+        } else if self.kebab_case {
+            format!("Step name '{}' isn't kebab-case", name)
+        } else {
+            format!(
+                "Step name '{}' doesn't match the step naming pattern `{}`",
+                name,
+                pattern.as_str()
+            )
+        };
+        let diagnostic = ctx.diagnostic(name_span, &message, &STEP_NAME_CONVENTION);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,132 @@
+use oxc_ast::ast::{
+    BindingPattern, Class, ClassElement, Expression, FormalParameter, StaticMemberExpression,
+    TSType, TSTypeName,
+};
+use oxc_span::{SourceType, Span};
+
+use crate::linter::Severity;
+use crate::rules::{Rule, RuleContext, RuleMeta};
+use crate::steps::extends_workflow_entrypoint;
+
+pub const TYPED_EVENT_PAYLOAD: RuleMeta = RuleMeta {
+    id: "typed-event-payload",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "TypeScript workflows must declare the type of their event payload",
+    explanation: "Parameters of a class method don't take their types from the base class, so \
+the `event` of a `run` method without an annotation is `any`, and so is `WorkflowEvent<any>`. \
+Everything read from `event.payload` and passed to steps is then unchecked, and a renamed or \
+missing field only shows up when an instance fails. Annotate the parameter with the payload \
+type, as in `async run(event: WorkflowEvent<Params>, step: WorkflowStep)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/events-and-parameters/",
+};
+
+/// The `run` method of a workflow class whose event payload is untyped
+#[derive(Debug)]
+struct UntypedRun {
+    body: Span,
+    event: String,
+    /// Why the payload is untyped, completing "because ..."
+    reason: String,
+    reported: bool,
+}
+
+/// Reports the first `event.payload` access in each TypeScript workflow `run` method whose
+/// event payload type is missing or `any`
+#[derive(Debug, Default)]
+pub struct TypedEventPayload {
+    runs: Vec<UntypedRun>,
+}
+
+/// Why the payload of the event parameter `param` named `name` is untyped, if it is
+fn untyped_reason(param: &FormalParameter, name: &str) -> Option<String> {
+    let Some(annotation) = &param.type_annotation else {
+        return Some(format!("`{}` has no type annotation", name));
+    };
+    let mut ty = &annotation.type_annotation;
+    while let TSType::TSTypeReference(reference) = ty {
+        let TSTypeName::IdentifierReference(id) = &reference.type_name else {
+            return None;
+        };
+        let argument = reference
+            .type_arguments
+            .as_ref()
+            .and_then(|arguments| arguments.params.first());
+        match (id.name.as_str(), argument) {
+            // `Readonly<WorkflowEvent<T>>`, as in the base class
+            ("Readonly", Some(inner)) => ty = inner,
+            ("WorkflowEvent", None) => {
+                return Some("`WorkflowEvent` is missing its payload type".to_string())
+            }
+            ("WorkflowEvent", Some(TSType::TSAnyKeyword(_))) => {
+                return Some(format!("`{}` is a `WorkflowEvent<any>`", name))
+            }
+            _ => return None,
+        }
+    }
+    matches!(ty, TSType::TSAnyKeyword(_)).then(|| format!("`{}` is typed `any`", name))
+}
+
+impl Rule for TypedEventPayload {
+    fn meta(&self) -> &'static RuleMeta {
+        &TYPED_EVENT_PAYLOAD
+    }
+
+    fn check_class(&mut self, ctx: &mut RuleContext, class: &Class) {
+        let is_typescript = SourceType::from_path(ctx.file_path())
+            .is_ok_and(|source_type| source_type.is_typescript());
+        if !is_typescript || !extends_workflow_entrypoint(class) {
+            return;
+        }
+        for element in &class.body.body {
+            let ClassElement::MethodDefinition(method) = element else {
+                continue;
+            };
+            if method.key.static_name().as_deref() != Some("run") {
+                continue;
+            }
+            let (Some(param), Some(body)) = (method.value.params.items.first(), &method.value.body)
+            else {
+                continue;
+            };
+            let BindingPattern::BindingIdentifier(id) = &param.pattern else {
+                continue;
+            };
+            if let Some(reason) = untyped_reason(param, &id.name) {
+                self.runs.push(UntypedRun {
+                    body: body.span,
+                    event: id.name.to_string(),
+                    reason,
+                    reported: false,
+                });
+            }
+        }
+    }
+
+    fn check_member(&mut self, ctx: &mut RuleContext, member: &StaticMemberExpression) {
+        if member.property.name != "payload" {
+            return;
+        }
+        let Expression::Identifier(object) = &member.object else {
+            return;
+        };
+        let Some(run) = self.runs.iter_mut().find(|run| {
+            run.body.start <= member.span.start
+                && member.span.end <= run.body.end
+                && object.name == run.event
+        }) else {
+            return;
+        };
+        if run.reported {
+            return;
+        }
+        run.reported = true;
+        let message = format!(
+            "`{}.payload` is `any` because {}; declare the payload type, as in `{}: WorkflowEvent<Params>`",
+            run.event, run.reason, run.event
+        );
+        let diagnostic = ctx.diagnostic(member.span, &message, &TYPED_EVENT_PAYLOAD);
+        ctx.report(diagnostic);
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use oxc_ast::ast::CallExpression;
+use oxc_span::Span;
+
+use crate::linter::{RelatedLocation, Severity, StepCall};
+use crate::rules::{FunctionScope, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const UNIQUE_STEP_NAMES: RuleMeta = RuleMeta {
+    id: "unique-step-names",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Step names must be unique within a workflow",
+    explanation: "Workflows stores the result of each step under its name and looks it up by \
+name when `run` is replayed. Two steps with the same name share one cached result, so the \
+second one returns the first one's output instead of running, or the other way around after \
+the code changes.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/rules-of-workflows/",
+};
+
+/// Reports step calls reusing the literal name of an earlier step in the same
+/// top-level function or method, such as a workflow's `run`
+#[derive(Debug, Default)]
+pub struct UniqueStepNames {
+    /// Function scopes entered, counting the top level of the file
+    depth: usize,
+    /// Where each step name was first used in the current top-level function
+    names: HashMap<String, Span>,
+}
+
+impl Rule for UniqueStepNames {
+    fn meta(&self) -> &'static RuleMeta {
+        &UNIQUE_STEP_NAMES
+    }
+
+    fn enter_function(&mut self, _ctx: &mut RuleContext, _scope: FunctionScope) {
+        self.depth += 1;
+        if self.depth == 2 {
+            self.names.clear();
+        }
+    }
+
+    fn leave_function(&mut self, _ctx: &mut RuleContext) {
+        if self.depth == 2 {
+            self.names.clear();
+        }
+        self.depth -= 1;
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let (Some(name), Some(name_span)) = (&call.name, call.name_span) else {
+            return;
+        };
+        let Some(&first) = self.names.get(name) else {
+            self.names.insert(name.clone(), name_span);
+            return;
+        };
+        let message = format!(
+            "Step name '{}' is already used in this workflow; steps with the same name share a cached result on replay",
+            name
+        );
+        let diagnostic = ctx
+            .diagnostic(name_span, &message, &UNIQUE_STEP_NAMES)
+            .with_related(RelatedLocation::new(
+                ctx.source(),
+                first,
+                &format!("'{}' first used here", name),
+            ));
+        ctx.report(diagnostic);
+    }
+}
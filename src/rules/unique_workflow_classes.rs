@@ -0,0 +1,17 @@
+use crate::linter::Severity;
+use crate::rules::RuleMeta;
+
+/// Checked once per run across every linted file rather than file by file,
+/// so there is no per-file [`Rule`](crate::rules::Rule) for it
+pub const UNIQUE_WORKFLOW_CLASSES: RuleMeta = RuleMeta {
+    id: "unique-workflow-classes",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflow classes in different files must have different names",
+    explanation: "A `[[workflows]]` binding names its Workflow by `class_name` alone. When two \
+modules export a `WorkflowEntrypoint` class with the same name, the binding can't say which one \
+it means: whichever the bundle ends up exporting wins, or the build fails on the duplicate \
+export. Give each workflow class a name of its own.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/#bind-from-a-worker",
+};
@@ -0,0 +1,158 @@
+use oxc_ast::ast::{
+    Argument, CallExpression, Expression, ObjectExpression, ObjectPropertyKind, UnaryOperator,
+};
+use oxc_span::GetSpan;
+
+use crate::linter::{Fix, LintDiagnostic, Severity, StepCall, TextEdit};
+use crate::rules::valid_sleep_duration::{check_duration_at, edit_distance};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const VALID_RETRY_CONFIG: RuleMeta = RuleMeta {
+    id: "valid-retry-config",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "`step.do` retry and timeout config must have valid values",
+    explanation: "The config object of `step.do(name, config, callback)` controls how the step \
+is retried: `retries.limit` is a non-negative whole number of attempts, `retries.delay` and \
+`timeout` are durations such as `\"30 seconds\"` or a number of milliseconds, and \
+`retries.backoff` is `\"constant\"`, `\"linear\"` or `\"exponential\"`. Invalid values are \
+rejected when the step runs, so the workflow fails at that step instead of at deploy time.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+const BACKOFFS: &[&str] = &["constant", "linear", "exponential"];
+
+/// Reports invalid `retries` and `timeout` values in `step.do` config objects
+#[derive(Debug, Default)]
+pub struct ValidRetryConfig;
+
+/// The statically named properties of an object literal, with their values
+fn properties<'a, 'b>(
+    object: &'b ObjectExpression<'a>,
+) -> impl Iterator<Item = (String, &'b Expression<'a>)> {
+    object
+        .properties
+        .iter()
+        .filter_map(|property| match property {
+            ObjectPropertyKind::ObjectProperty(property) if !property.computed => {
+                Some((property.key.static_name()?.to_string(), &property.value))
+            }
+            _ => None,
+        })
+}
+
+fn check_limit(ctx: &RuleContext, value: &Expression, diagnostics: &mut Vec<LintDiagnostic>) {
+    let problem = match value {
+        Expression::NumericLiteral(number) if number.value.fract() != 0.0 => "a whole number",
+        Expression::UnaryExpression(unary)
+            if unary.operator == UnaryOperator::UnaryNegation
+                && matches!(unary.argument, Expression::NumericLiteral(_)) =>
+        {
+            "non-negative"
+        }
+        Expression::StringLiteral(_)
+        | Expression::TemplateLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_)
+        | Expression::ObjectExpression(_)
+        | Expression::ArrayExpression(_) => "a number",
+        _ => return,
+    };
+    let text = &ctx.source()[value.span().start as usize..value.span().end as usize];
+    let message = format!("Invalid retries.limit {}: must be {}", text, problem);
+    diagnostics.push(ctx.diagnostic(value.span(), &message, &VALID_RETRY_CONFIG));
+}
+
+fn check_backoff(ctx: &RuleContext, value: &Expression, diagnostics: &mut Vec<LintDiagnostic>) {
+    let Expression::StringLiteral(backoff) = value else {
+        return;
+    };
+    if BACKOFFS.contains(&backoff.value.as_str()) {
+        return;
+    }
+    let span = backoff.span;
+    let text = &ctx.source()[span.start as usize..span.end as usize];
+    let mut message = format!(
+        "Invalid retries.backoff {}: must be \"constant\", \"linear\" or \"exponential\"",
+        text
+    );
+    let lower = backoff.value.to_ascii_lowercase();
+    let suggestion = BACKOFFS
+        .iter()
+        .map(|&known| (edit_distance(&lower, known), known))
+        .filter(|&(distance, _)| distance <= 3)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known);
+    let Some(suggestion) = suggestion else {
+        diagnostics.push(ctx.diagnostic(span, &message, &VALID_RETRY_CONFIG));
+        return;
+    };
+    message.push_str(&format!("; did you mean \"{}\"?", suggestion));
+    diagnostics.push(
+        ctx.diagnostic(span, &message, &VALID_RETRY_CONFIG)
+            .with_fix(Fix {
+                title: format!("Change to \"{}\"", suggestion),
+                edits: vec![TextEdit {
+                    start: span.start + 1,
+                    end: span.end - 1,
+                    replacement: suggestion.to_string(),
+                }],
+            }),
+    );
+}
+
+fn check_config(ctx: &RuleContext, config: &ObjectExpression) -> Vec<LintDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for (key, value) in properties(config) {
+        match (key.as_str(), value) {
+            ("timeout", _) => diagnostics.extend(check_duration_at(
+                ctx,
+                value.span(),
+                "timeout",
+                &VALID_RETRY_CONFIG,
+            )),
+            ("retries", Expression::ObjectExpression(retries)) => {
+                for (key, value) in properties(retries) {
+                    match key.as_str() {
+                        "limit" => check_limit(ctx, value, &mut diagnostics),
+                        "delay" => diagnostics.extend(check_duration_at(
+                            ctx,
+                            value.span(),
+                            "retries.delay",
+                            &VALID_RETRY_CONFIG,
+                        )),
+                        "backoff" => check_backoff(ctx, value, &mut diagnostics),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+impl Rule for ValidRetryConfig {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_RETRY_CONFIG
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let [_, Argument::ObjectExpression(config), _] = expr.arguments.as_slice() else {
+            return;
+        };
+        if call.method != "do" {
+            return;
+        }
+        for diagnostic in check_config(ctx, config) {
+            ctx.report(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use oxc_ast::ast::{BindingPattern, Class, ClassElement, FormalParameter, MethodDefinitionKind};
+use oxc_span::GetSpan;
+
+use crate::linter::{Fix, Severity, TextEdit};
+use crate::rules::{Rule, RuleContext, RuleMeta};
+use crate::steps::{extends_workflow_entrypoint, is_workflow_step};
+
+pub const VALID_RUN_PARAMS: RuleMeta = RuleMeta {
+    id: "valid-run-params",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "The `run` method of a workflow must take `(event, step)` in that order",
+    explanation: "Workflows calls `run(event, step)` with the event first and the step object \
+second. A `run(step, event)` or `run(step)` signature names the event `step`, so every \
+`step.do(...)` inside it fails at runtime, and the linter, which takes the second parameter \
+to be the step, checks the wrong calls. Declare the event first: `run(event, step)`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Reports `run` methods of `WorkflowEntrypoint` classes whose step parameter comes first
+#[derive(Debug, Default)]
+pub struct ValidRunParams;
+
+/// Whether `param` looks like the step object: typed `WorkflowStep` or named `step`
+fn is_step_param(param: &FormalParameter) -> bool {
+    is_workflow_step(param)
+        || matches!(&param.pattern, BindingPattern::BindingIdentifier(id) if id.name == "step")
+}
+
+impl Rule for ValidRunParams {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_RUN_PARAMS
+    }
+
+    fn check_class(&mut self, ctx: &mut RuleContext, class: &Class) {
+        if !extends_workflow_entrypoint(class) {
+            return;
+        }
+        for element in &class.body.body {
+            let ClassElement::MethodDefinition(method) = element else {
+                continue;
+            };
+            if method.kind != MethodDefinitionKind::Method
+                || method.key.static_name().as_deref() != Some("run")
+            {
+                continue;
+            }
+            let params = &method.value.params;
+            let (first, second) = match params.items.as_slice() {
+                [first] if params.rest.is_none() => (first, None),
+                [first, second, ..] => (first, Some(second)),
+                _ => continue,
+            };
+            if !is_step_param(first) || second.is_some_and(is_step_param) {
+                continue;
+            }
+            let first_text = &ctx.source()[first.span.start as usize..first.span.end as usize];
+            let Some(second) = second else {
+                // Without an event parameter, the step parameter receives the event
+                let message = format!(
+                    "`run` is called with `(event, step)`, so its only parameter `{}` receives the event; declare the event first: `run(event, {})`",
+                    first_text, first_text
+                );
+                let diagnostic = ctx.diagnostic(first.span(), &message, &VALID_RUN_PARAMS);
+                ctx.report(diagnostic);
+                continue;
+            };
+            let second_text = &ctx.source()[second.span.start as usize..second.span.end as usize];
+            let message = format!(
+                "`run` is called with `(event, step)`, but `{}` comes before `{}` here, so they receive each other's values; swap the parameters",
+                first_text, second_text
+            );
+            let diagnostic = ctx
+                .diagnostic(first.span(), &message, &VALID_RUN_PARAMS)
+                .with_fix(Fix {
+                    title: format!("Swap `{}` and `{}`", first_text, second_text),
+                    edits: vec![
+                        TextEdit {
+                            start: first.span.start,
+                            end: first.span.end,
+                            replacement: second_text.to_string(),
+                        },
+                        TextEdit {
+                            start: second.span.start,
+                            end: second.span.end,
+                            replacement: first_text.to_string(),
+                        },
+                    ],
+                });
+            ctx.report(diagnostic);
+        }
+    }
+}
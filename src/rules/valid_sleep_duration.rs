@@ -0,0 +1,271 @@
+use oxc_ast::ast::CallExpression;
+use oxc_span::Span;
+
+use crate::linter::{Fix, LintDiagnostic, Severity, StepCall, TextEdit};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const VALID_SLEEP_DURATION: RuleMeta = RuleMeta {
+    id: "valid-sleep-duration",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "`step.sleep` durations must be in a form and range Workflows accepts",
+    explanation: "`step.sleep` takes a number of milliseconds or a string of a number, a space \
+and one of `second`, `minute`, `hour`, `day`, `week`, `month` or `year` (optionally plural), \
+such as `\"90 minutes\"`, of at most 365 days. Anything else, like `\"90 mins\"`, `\"1h\"` or \
+`\"2 years\"`, is rejected when the step runs, failing the workflow instance partway through.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+/// Units accepted in duration strings, in their singular form
+const UNITS: &[&str] = &["second", "minute", "hour", "day", "week", "month", "year"];
+
+/// Milliseconds in each unit; months count as 30 days and years as 365
+const UNIT_MILLIS: &[(&str, f64)] = &[
+    ("second", 1_000.0),
+    ("minute", 60_000.0),
+    ("hour", 3_600_000.0),
+    ("day", 86_400_000.0),
+    ("week", 604_800_000.0),
+    ("month", 2_592_000_000.0),
+    ("year", 31_536_000_000.0),
+];
+
+/// The longest duration Workflows accepts for a sleep or a wait, 365 days
+const MAX_DURATION_MILLIS: f64 = 31_536_000_000.0;
+
+/// Common abbreviations and the unit they stand for
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("s", "second"),
+    ("sec", "second"),
+    ("secs", "second"),
+    ("m", "minute"),
+    ("min", "minute"),
+    ("mins", "minute"),
+    ("h", "hour"),
+    ("hr", "hour"),
+    ("hrs", "hour"),
+    ("d", "day"),
+    ("w", "week"),
+    ("wk", "week"),
+    ("wks", "week"),
+    ("mo", "month"),
+    ("mos", "month"),
+    ("y", "year"),
+    ("yr", "year"),
+    ("yrs", "year"),
+];
+
+/// Reports `step.sleep` durations that Workflows won't accept, fixing them when the
+/// intended duration is clear
+#[derive(Debug, Default)]
+pub struct ValidSleepDuration;
+
+/// Why a duration string is invalid, and the valid form it most likely meant
+struct InvalidDuration {
+    problem: &'static str,
+    suggestion: Option<String>,
+}
+
+/// Whether `text` is a duration string, valid or close to it, like `"1 hour"` or `"2h"`
+pub(super) fn looks_like_duration(text: &str) -> bool {
+    check_duration(text).is_none_or(|invalid| invalid.suggestion.is_some())
+}
+
+/// Check a duration string, returning `None` if Workflows accepts it
+fn check_duration(duration: &str) -> Option<InvalidDuration> {
+    let trimmed = duration.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let unit = unit.trim_start();
+    let value = number.parse::<f64>().ok().filter(|value| value.is_finite());
+
+    if value.is_some_and(|value| value < 0.0) {
+        return Some(InvalidDuration {
+            problem: "durations can't be negative",
+            suggestion: None,
+        });
+    }
+    let singular = unit.strip_suffix('s').unwrap_or(unit);
+    let known_unit = UNITS.contains(&singular);
+    if value.is_some() && known_unit && duration == format!("{} {}", number, unit) {
+        return None;
+    }
+
+    let problem = if unit.is_empty() {
+        "a unit is missing"
+    } else if value.is_none() {
+        "a number is missing"
+    } else if !known_unit {
+        "the unit isn't one of second, minute, hour, day, week, month or year in lowercase"
+    } else {
+        "expected a number and a unit separated by a single space"
+    };
+    let suggestion = nearest_unit(&unit.to_ascii_lowercase()).map(|unit| {
+        let number = if value.is_some() { number } else { "1" };
+        let plural = if number == "1" { "" } else { "s" };
+        format!("{} {}{}", number, unit, plural)
+    });
+    Some(InvalidDuration {
+        problem,
+        suggestion,
+    })
+}
+
+/// The length of a duration Workflows accepts, like `"90 minutes"`, in milliseconds
+pub(crate) fn duration_millis(duration: &str) -> Option<f64> {
+    let (number, unit) = duration.split_once(' ')?;
+    let singular = unit.strip_suffix('s').unwrap_or(unit);
+    let &(_, millis) = UNIT_MILLIS.iter().find(|(known, _)| *known == singular)?;
+    Some(number.parse::<f64>().ok()? * millis)
+}
+
+/// The length in milliseconds of the number or string literal at `span`, if it is a
+/// duration Workflows accepts
+pub(super) fn literal_duration_millis(ctx: &RuleContext, span: Span) -> Option<f64> {
+    let text = ctx.source()[span.start as usize..span.end as usize].trim();
+    if let Ok(millis) = text.parse::<f64>() {
+        return Some(millis);
+    }
+    let quote = text
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '\'' | '"' | '`'))?;
+    let duration = text[1..].strip_suffix(quote)?;
+    if (quote == '`' && duration.contains("${")) || check_duration(duration).is_some() {
+        return None;
+    }
+    duration_millis(duration)
+}
+
+/// A diagnostic for the duration at `span` if it is valid but longer than Workflows
+/// allows, or shorter than `min`, a duration string like `"1 minute"`
+pub(super) fn check_duration_range_at(
+    ctx: &RuleContext,
+    span: Span,
+    what: &str,
+    min: Option<&str>,
+    rule: &RuleMeta,
+) -> Option<LintDiagnostic> {
+    let millis = literal_duration_millis(ctx, span)?;
+    let text = &ctx.source()[span.start as usize..span.end as usize];
+    let problem = if millis > MAX_DURATION_MILLIS {
+        "it is longer than the maximum of 365 days".to_string()
+    } else {
+        let min = min.filter(|min| duration_millis(min).is_some_and(|min| millis < min))?;
+        let mut problem = format!("it is shorter than the minimum of {}", min);
+        if text.trim().parse::<f64>().is_ok() {
+            problem.push_str("; numbers are milliseconds");
+        }
+        problem
+    };
+    let message = format!("Invalid {} {}: {}", what, text, problem);
+    Some(ctx.diagnostic(span, &message, rule))
+}
+
+/// The unit `unit` most likely means: itself, an abbreviation's unit, or a unit
+/// within two typos of it
+fn nearest_unit(unit: &str) -> Option<&'static str> {
+    let singular = unit.strip_suffix('s').unwrap_or(unit);
+    if let Some(&known) = UNITS.iter().find(|&&known| known == singular) {
+        return Some(known);
+    }
+    if let Some(&(_, known)) = ABBREVIATIONS.iter().find(|(abbr, _)| *abbr == unit) {
+        return Some(known);
+    }
+    UNITS
+        .iter()
+        .map(|&known| (edit_distance(singular, known), known))
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known)
+}
+
+/// Levenshtein distance between two ASCII-ish strings
+pub(super) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// A diagnostic for the duration at `span` if Workflows won't accept it, with a fix
+/// when the intended duration is clear. `what` names the duration in the message, and
+/// expressions other than number and string literals are left alone.
+pub(super) fn check_duration_at(
+    ctx: &RuleContext,
+    span: Span,
+    what: &str,
+    rule: &RuleMeta,
+) -> Option<LintDiagnostic> {
+    let text = &ctx.source()[span.start as usize..span.end as usize];
+    if let Ok(millis) = text.trim().parse::<f64>() {
+        let message = format!("Invalid {} {}: durations can't be negative", what, text);
+        return (millis < 0.0).then(|| ctx.diagnostic(span, &message, rule));
+    }
+
+    // Only string literals (and templates without interpolations) can be checked
+    let quote = text
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '\'' | '"' | '`'))?;
+    let duration = text[1..].strip_suffix(quote)?;
+    if quote == '`' && duration.contains("${") {
+        return None;
+    }
+    let invalid = check_duration(duration)?;
+
+    let mut message = format!("Invalid {} {}: {}", what, text, invalid.problem);
+    if let Some(suggestion) = &invalid.suggestion {
+        message.push_str(&format!("; did you mean {}{}{}?", quote, suggestion, quote));
+    }
+    let diagnostic = ctx.diagnostic(span, &message, rule);
+    Some(match invalid.suggestion {
+        Some(suggestion) => diagnostic.with_fix(Fix {
+            title: format!("Change to {}{}{}", quote, suggestion, quote),
+            edits: vec![TextEdit {
+                start: span.start + 1,
+                end: span.end - 1,
+                replacement: suggestion,
+            }],
+        }),
+        None => diagnostic,
+    })
+}
+
+impl Rule for ValidSleepDuration {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_SLEEP_DURATION
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "sleep" {
+            return;
+        }
+        let Some(&span) = call.argument_spans.get(1) else {
+            return;
+        };
+        let diagnostic = check_duration_at(ctx, span, "sleep duration", &VALID_SLEEP_DURATION)
+            .or_else(|| {
+                check_duration_range_at(ctx, span, "sleep duration", None, &VALID_SLEEP_DURATION)
+            });
+        if let Some(diagnostic) = diagnostic {
+            ctx.report(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use oxc_ast::ast::CallExpression;
+
+use crate::linter::{Fix, Severity, StepCall, TextEdit};
+use crate::rules::valid_sleep_duration::looks_like_duration;
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const VALID_SLEEP_UNTIL: RuleMeta = RuleMeta {
+    id: "valid-sleep-until",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "`step.sleepUntil` must be given a Date, a timestamp or a date string",
+    explanation: "`step.sleepUntil` waits until a point in time: a `Date`, a timestamp in \
+milliseconds or a date string such as `\"2030-01-01T09:00:00Z\"`. A duration like `\"1 hour\"` \
+belongs to `step.sleep`; passed to `sleepUntil` it isn't a valid date, so the step fails \
+instead of sleeping.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Reports `step.sleepUntil` calls given a literal that isn't a point in time
+#[derive(Debug, Default)]
+pub struct ValidSleepUntil;
+
+/// `field` as a number, if it is exactly `digits` ASCII digits
+fn digits(field: &str, digits: usize) -> Option<u32> {
+    if field.len() != digits || !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Whether `date` is `YYYY`, `YYYY-MM` or `YYYY-MM-DD` naming a day that exists
+fn is_iso_day(date: &str) -> bool {
+    let mut parts = date.split('-');
+    let Some(year) = parts.next().and_then(|year| digits(year, 4)) else {
+        return false;
+    };
+    let Some(month) = parts.next() else {
+        return true;
+    };
+    let Some(month @ 1..=12) = digits(month, 2) else {
+        return false;
+    };
+    let Some(day) = parts.next() else {
+        return true;
+    };
+    digits(day, 2).is_some_and(|day| (1..=days_in_month(year, month)).contains(&day))
+        && parts.next().is_none()
+}
+
+/// Whether `clock` is `HH:MM`, `HH:MM:SS` or `HH:MM:SS.sss` naming a time that exists
+fn is_iso_clock(clock: &str) -> bool {
+    let mut fields = clock.split(':');
+    let hours_ok = fields
+        .next()
+        .and_then(|hours| digits(hours, 2))
+        .is_some_and(|hours| hours <= 23);
+    let minutes_ok = fields
+        .next()
+        .and_then(|minutes| digits(minutes, 2))
+        .is_some_and(|minutes| minutes <= 59);
+    let seconds_ok = fields.next().is_none_or(|seconds| {
+        let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, "0"));
+        digits(seconds, 2).is_some_and(|seconds| seconds <= 59)
+            && !fraction.is_empty()
+            && fraction.bytes().all(|b| b.is_ascii_digit())
+    });
+    hours_ok && minutes_ok && seconds_ok && fields.next().is_none()
+}
+
+/// Whether `text` is an ISO 8601 date that `Date.parse` accepts, such as `2030-01-01`
+/// or `2030-01-01T09:00:00.000Z`. Months, days, hours, minutes and seconds are range
+/// checked, since `Date.parse('2030-02-30')` is `NaN`.
+fn is_iso_date(text: &str) -> bool {
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    is_iso_day(date)
+        && time.is_none_or(|time| match time.strip_suffix('Z') {
+            Some(clock) => is_iso_clock(clock),
+            None => match time.rfind(['+', '-']) {
+                Some(offset) => is_iso_clock(&time[..offset]) && is_iso_clock(&time[offset + 1..]),
+                None => is_iso_clock(time),
+            },
+        })
+}
+
+/// Whether `Date.parse` would most likely understand `text`: an ISO date, or a
+/// date written out with a month name and a four-digit year
+fn is_date_string(text: &str) -> bool {
+    let text = text.trim();
+    if is_iso_date(text) {
+        return true;
+    }
+    let lower = text.to_ascii_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let has_month = words
+        .iter()
+        .any(|word| MONTHS.iter().any(|month| word.starts_with(month)));
+    let has_year = words
+        .iter()
+        .any(|word| word.len() == 4 && word.bytes().all(|b| b.is_ascii_digit()));
+    has_month && has_year
+}
+
+impl Rule for ValidSleepUntil {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_SLEEP_UNTIL
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "sleepUntil" {
+            return;
+        }
+        let Some(&span) = call.argument_spans.get(1) else {
+            return;
+        };
+        let text = ctx.source()[span.start as usize..span.end as usize].trim();
+        if matches!(text, "true" | "false" | "null" | "undefined")
+            || text.starts_with('{')
+            || text.starts_with('[')
+        {
+            let message = format!(
+                "`step.sleepUntil` needs a Date, a timestamp or a date string, not {}",
+                text
+            );
+            let diagnostic = ctx.diagnostic(span, &message, &VALID_SLEEP_UNTIL);
+            ctx.report(diagnostic);
+            return;
+        }
+
+        // Other expressions, such as `new Date(...)` or a variable, can't be checked
+        let Some(quote) = text
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '\'' | '"' | '`'))
+        else {
+            return;
+        };
+        let Some(value) = text[1..].strip_suffix(quote) else {
+            return;
+        };
+        if (quote == '`' && value.contains("${")) || is_date_string(value) {
+            return;
+        }
+
+        if looks_like_duration(value) {
+            let message = format!(
+                "{} is a duration, not a point in time; use `step.sleep` to sleep for a duration",
+                text
+            );
+            let diagnostic = ctx
+                .diagnostic(span, &message, &VALID_SLEEP_UNTIL)
+                .with_fix(Fix {
+                    title: "Use `step.sleep`".to_string(),
+                    edits: vec![TextEdit {
+                        start: call.method_span.start,
+                        end: call.method_span.end,
+                        replacement: "sleep".to_string(),
+                    }],
+                });
+            ctx.report(diagnostic);
+        } else {
+            let message = format!(
+                "{} isn't a date string; pass a Date, a timestamp or an ISO 8601 date such as \"2030-01-01T09:00:00Z\"",
+                text
+            );
+            let diagnostic = ctx.diagnostic(span, &message, &VALID_SLEEP_UNTIL);
+            ctx.report(diagnostic);
+        }
+    }
+}
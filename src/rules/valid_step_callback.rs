@@ -0,0 +1,96 @@
+use oxc_ast::ast::{CallExpression, Expression};
+use oxc_span::GetSpan;
+
+use crate::linter::{Fix, LintDiagnostic, Severity, StepCall, TextEdit};
+use crate::rules::{callee_path, Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const VALID_STEP_CALLBACK: RuleMeta = RuleMeta {
+    id: "valid-step-callback",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "The last argument of `step.do` must be a function",
+    explanation: "`step.do(name, callback)` runs `callback` as the step, so that its result is \
+stored and it is retried on failure. Passing `helper()` or `fetch(url)` instead calls it \
+straight away, every time the workflow replays and outside the step's retries, and hands \
+`step.do` a promise rather than a function. Wrap the work in a function: \
+`step.do(name, async () => helper())`.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/",
+};
+
+/// Reports `step.do` calls whose callback is a call, an `await`, a `new` expression or a literal
+#[derive(Debug, Default)]
+pub struct ValidStepCallback;
+
+/// What `callback` is, if it clearly isn't a function, and whether wrapping it in an
+/// arrow function keeps the intended work
+fn not_a_function(callback: &Expression) -> Option<(&'static str, bool)> {
+    match callback {
+        // `helper.bind(this)` returns a function
+        Expression::CallExpression(call)
+            if callee_path(&call.callee).is_some_and(|path| path.ends_with(".bind")) =>
+        {
+            None
+        }
+        Expression::CallExpression(_) => Some(("the result of a call", true)),
+        Expression::AwaitExpression(_) => Some(("an awaited value", true)),
+        Expression::NewExpression(_) => Some(("a new object", false)),
+        Expression::StringLiteral(_)
+        | Expression::TemplateLiteral(_)
+        | Expression::NumericLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_)
+        | Expression::ObjectExpression(_)
+        | Expression::ArrayExpression(_) => Some(("a literal", false)),
+        _ => None,
+    }
+}
+
+fn check_callback(ctx: &RuleContext, callback: &Expression) -> Option<LintDiagnostic> {
+    let (what, fixable) = not_a_function(callback)?;
+    let span = callback.span();
+    let text = &ctx.source()[span.start as usize..span.end as usize];
+    let message = format!(
+        "The `step.do` callback is {}, so `{}` runs outside the step when the workflow runs or replays; pass a function like `async () => {}`",
+        what, text, text
+    );
+    let diagnostic = ctx.diagnostic(span, &message, &VALID_STEP_CALLBACK);
+    if !fixable {
+        return Some(diagnostic);
+    }
+    Some(diagnostic.with_fix(Fix {
+        title: "Wrap in `async () => ...`".to_string(),
+        edits: vec![TextEdit {
+            start: span.start,
+            end: span.start,
+            replacement: "async () => ".to_string(),
+        }],
+    }))
+}
+
+impl Rule for ValidStepCallback {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_STEP_CALLBACK
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        let [_, .., callback] = expr.arguments.as_slice() else {
+            return;
+        };
+        if call.method != "do" || expr.arguments.len() > 3 {
+            return;
+        }
+        if let Some(diagnostic) = callback
+            .as_expression()
+            .and_then(|callback| check_callback(ctx, callback))
+        {
+            ctx.report(diagnostic);
+        }
+    }
+}
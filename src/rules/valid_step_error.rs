@@ -0,0 +1,84 @@
+use oxc_ast::ast::{Expression, ThrowStatement};
+use oxc_span::GetSpan;
+
+use crate::linter::{Fix, Severity, TextEdit};
+use crate::rules::{Rule, RuleContext, RuleMeta};
+
+pub const VALID_STEP_ERROR: RuleMeta = RuleMeta {
+    id: "valid-step-error",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "Errors thrown from workflows must be `Error`s, and `NonRetryableError` must come from `cloudflare:workflows`",
+    explanation: "Workflows stops retrying a step when it throws the `NonRetryableError` class \
+exported by `cloudflare:workflows`. A class of the same name declared locally or imported from \
+elsewhere is an ordinary error, so the step is retried anyway. Step callbacks should also throw \
+`Error`s rather than strings: Workflows records failed attempts by their error name and \
+message, which a thrown string doesn't have.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/sleeping-and-retrying/",
+};
+
+const WORKFLOWS_MODULE: &str = "cloudflare:workflows";
+
+/// Reports `NonRetryableError`s that don't come from `cloudflare:workflows` and strings
+/// thrown from step callbacks
+#[derive(Debug, Default)]
+pub struct ValidStepError;
+
+impl Rule for ValidStepError {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_STEP_ERROR
+    }
+
+    fn check_throw(&mut self, ctx: &mut RuleContext, throw: &ThrowStatement) {
+        match &throw.argument {
+            Expression::NewExpression(new_expr) => {
+                let Expression::Identifier(class) = &new_expr.callee else {
+                    return;
+                };
+                if class.name != "NonRetryableError" {
+                    return;
+                }
+                let origin = match ctx.import_of(&class.name) {
+                    Some((WORKFLOWS_MODULE, "NonRetryableError")) => return,
+                    Some((module, _)) => format!("is imported from `{}`", module),
+                    None if ctx.is_local_class(&class.name) => {
+                        "is a class declared in this file".to_string()
+                    }
+                    None => "isn't imported".to_string(),
+                };
+                let message = format!(
+                    "`NonRetryableError` {}, not from `{}`, so Workflows retries the step anyway; use `import {{ NonRetryableError }} from \"{}\"`",
+                    origin, WORKFLOWS_MODULE, WORKFLOWS_MODULE
+                );
+                let diagnostic = ctx.diagnostic(class.span, &message, &VALID_STEP_ERROR);
+                ctx.report(diagnostic);
+            }
+            Expression::StringLiteral(_) | Expression::TemplateLiteral(_)
+                if ctx.in_step_callback() =>
+            {
+                let span = throw.argument.span();
+                let message = "Throw an `Error` instead of a string from a step callback; Workflows records failed attempts by their error name and message, which a string doesn't have";
+                let diagnostic = ctx
+                    .diagnostic(span, message, &VALID_STEP_ERROR)
+                    .with_fix(Fix {
+                        title: "Wrap in `new Error(...)`".to_string(),
+                        edits: vec![
+                            TextEdit {
+                                start: span.start,
+                                end: span.start,
+                                replacement: "new Error(".to_string(),
+                            },
+                            TextEdit {
+                                start: span.end,
+                                end: span.end,
+                                replacement: ")".to_string(),
+                            },
+                        ],
+                    });
+                ctx.report(diagnostic);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use oxc_ast::ast::{Argument, CallExpression, Expression, ObjectPropertyKind};
+use oxc_span::GetSpan;
+
+use crate::config::WaitForEventOptions;
+use crate::linter::{Fix, LintDiagnostic, Severity, StepCall, TextEdit};
+use crate::rules::valid_sleep_duration::{
+    check_duration_at, check_duration_range_at, edit_distance,
+};
+use crate::rules::{Rule, RuleContext, RuleMeta, StepUsage};
+
+pub const VALID_WAIT_FOR_EVENT: RuleMeta = RuleMeta {
+    id: "valid-wait-for-event",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: true,
+    description: "`step.waitForEvent` options must have a `type` and only known keys",
+    explanation: "`step.waitForEvent(name, { type, timeout })` waits for an event of the given \
+`type`, for at most `timeout` (24 hours by default, and no more than 365 days). Without a \
+`type` no event can match, and a misspelled key such as `timout` is ignored, so the step \
+silently waits for the default timeout instead. Timeouts shorter than a minimum, 1 second by \
+default and set under `[rule_options.valid-wait-for-event]`, are reported too: `timeout: 5` \
+waits 5 milliseconds, not 5 minutes.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/events-and-parameters/",
+};
+
+/// Keys `step.waitForEvent` options accept
+const OPTION_KEYS: &[&str] = &["type", "timeout"];
+
+/// Reports `step.waitForEvent` calls whose options are missing `type`, have an invalid
+/// or out-of-range `timeout` or contain unknown keys
+#[derive(Debug)]
+pub struct ValidWaitForEvent {
+    /// The shortest timeout allowed, as a duration string
+    min_timeout: String,
+}
+
+impl ValidWaitForEvent {
+    pub fn new(options: WaitForEventOptions) -> Self {
+        Self {
+            min_timeout: options.min_timeout,
+        }
+    }
+}
+
+impl Default for ValidWaitForEvent {
+    fn default() -> Self {
+        Self::new(WaitForEventOptions::default())
+    }
+}
+
+fn check_options(
+    ctx: &RuleContext,
+    call: &CallExpression,
+    min_timeout: &str,
+) -> Vec<LintDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let options = match call.arguments.get(1) {
+        Some(Argument::ObjectExpression(options)) => options,
+        // Options built elsewhere can't be checked
+        Some(_) => return diagnostics,
+        None => {
+            let message = "`step.waitForEvent` needs options with the event `type` to wait for";
+            diagnostics.push(ctx.diagnostic(call.span(), message, &VALID_WAIT_FOR_EVENT));
+            return diagnostics;
+        }
+    };
+
+    let mut has_type = false;
+    let mut has_spread = false;
+    for property in &options.properties {
+        let property = match property {
+            ObjectPropertyKind::ObjectProperty(property) => property,
+            ObjectPropertyKind::SpreadProperty(_) => {
+                has_spread = true;
+                continue;
+            }
+        };
+        if property.computed {
+            has_spread = true;
+            continue;
+        }
+        let Some(key) = property.key.static_name() else {
+            continue;
+        };
+        match key.as_ref() {
+            "type" => has_type = true,
+            "timeout" => {
+                if !matches!(property.value, Expression::ObjectExpression(_)) {
+                    let span = property.value.span();
+                    let what = "waitForEvent timeout";
+                    diagnostics.extend(
+                        check_duration_at(ctx, span, what, &VALID_WAIT_FOR_EVENT).or_else(|| {
+                            check_duration_range_at(
+                                ctx,
+                                span,
+                                what,
+                                Some(min_timeout),
+                                &VALID_WAIT_FOR_EVENT,
+                            )
+                        }),
+                    );
+                }
+            }
+            unknown => {
+                let suggestion = OPTION_KEYS
+                    .iter()
+                    .find(|known| edit_distance(unknown, known) <= 2);
+                let key_span = property.key.span();
+                let mut message = format!(
+                    "Unknown `step.waitForEvent` option `{}` is ignored",
+                    unknown
+                );
+                let diagnostic = match suggestion {
+                    Some(known) => {
+                        message.push_str(&format!("; did you mean `{}`?", known));
+                        ctx.diagnostic(key_span, &message, &VALID_WAIT_FOR_EVENT)
+                            .with_fix(Fix {
+                                title: format!("Change to `{}`", known),
+                                edits: vec![TextEdit {
+                                    start: key_span.start,
+                                    end: key_span.end,
+                                    replacement: known.to_string(),
+                                }],
+                            })
+                    }
+                    None => ctx.diagnostic(key_span, &message, &VALID_WAIT_FOR_EVENT),
+                };
+                // A misspelled `type` is reported once, as the typo
+                has_type |= suggestion == Some(&"type");
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+    if !has_type && !has_spread {
+        let message = "`step.waitForEvent` options need the event `type` to wait for";
+        diagnostics.push(ctx.diagnostic(options.span, message, &VALID_WAIT_FOR_EVENT));
+    }
+    diagnostics
+}
+
+impl Rule for ValidWaitForEvent {
+    fn meta(&self) -> &'static RuleMeta {
+        &VALID_WAIT_FOR_EVENT
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method != "waitForEvent" {
+            return;
+        }
+        for diagnostic in check_options(ctx, expr, &self.min_timeout) {
+            ctx.report(diagnostic);
+        }
+    }
+}
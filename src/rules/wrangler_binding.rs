@@ -0,0 +1,17 @@
+use crate::linter::Severity;
+use crate::rules::RuleMeta;
+
+/// Checked once per run against the wrangler config rather than file by file,
+/// so there is no per-file [`Rule`](crate::rules::Rule) for it
+pub const WRANGLER_BINDING: RuleMeta = RuleMeta {
+    id: "wrangler-binding",
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Workflow classes and `[[workflows]]` bindings in the wrangler config must match",
+    explanation: "Wrangler creates a Workflow for each `[[workflows]]` entry by looking up its \
+`class_name` among the classes exported by the Worker's `main` module. A binding whose class \
+doesn't exist fails the deploy, and an exported `WorkflowEntrypoint` class without a binding is \
+never run, which usually means the binding was forgotten or points at an old name.",
+    docs_url: "https://developers.cloudflare.com/workflows/build/workers-api/#bind-from-a-worker",
+};
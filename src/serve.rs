@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use cashmere::cache::hash_content;
+use cashmere::config::Config;
+use cashmere::lint_structured_with_config;
+use cashmere::rules::ALL_RULES;
+
+/// Requests with a larger body are rejected with 413
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const MAX_HEADERS: usize = 32;
+/// Lint results kept for repeated requests; the cache is cleared when it fills up
+const MAX_CACHED_RESULTS: usize = 1024;
+
+#[derive(Deserialize)]
+struct LintRequest {
+    source: String,
+    #[serde(default = "default_filename")]
+    filename: String,
+}
+
+fn default_filename() -> String {
+    "workflow.ts".to_string()
+}
+
+/// State shared by every connection: the config resolved at startup and recent results
+struct Server {
+    config: Config,
+    /// Serialized results keyed by a hash of the file name and source
+    results: DashMap<u64, Arc<String>>,
+}
+
+struct Response {
+    status: u16,
+    body: Arc<String>,
+}
+
+impl Response {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body: Arc::new(body.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, json!({ "error": message }))
+    }
+}
+
+/// Serve the HTTP API on `host:port` until the process is stopped
+pub async fn run_http_server(host: &str, port: u16, config: Config) -> std::io::Result<()> {
+    let listener = TcpListener::bind((host, port)).await?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    let server = Arc::new(Server {
+        config,
+        results: DashMap::new(),
+    });
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = Arc::clone(&server);
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, server).await;
+        });
+    }
+}
+
+/// Read one request from the connection, answer it and close the connection
+async fn handle_connection(mut stream: TcpStream, server: Arc<Server>) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let (method, path, body_start, content_length) = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut request = httparse::Request::new(&mut headers);
+        match request.parse(&buffer) {
+            Ok(httparse::Status::Complete(body_start)) => {
+                let content_length = request
+                    .headers
+                    .iter()
+                    .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|header| std::str::from_utf8(header.value).ok())
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                break (
+                    request.method.unwrap_or("").to_string(),
+                    request.path.unwrap_or("").to_string(),
+                    body_start,
+                    content_length,
+                );
+            }
+            Ok(httparse::Status::Partial) if buffer.len() < MAX_BODY_BYTES => {}
+            _ => {
+                return write_response(&mut stream, Response::error(400, "malformed request")).await
+            }
+        }
+    };
+
+    if content_length > MAX_BODY_BYTES {
+        return write_response(&mut stream, Response::error(413, "request body too large")).await;
+    }
+    while buffer.len() < body_start + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = buffer[body_start..body_start + content_length].to_vec();
+
+    let response = tokio::task::spawn_blocking(move || route(&server, &method, &path, &body))
+        .await
+        .unwrap_or_else(|_| Response::error(500, "internal error"));
+    write_response(&mut stream, response).await
+}
+
+fn route(server: &Server, method: &str, path: &str, body: &[u8]) -> Response {
+    // Ignore any query string
+    let path = path.split('?').next().unwrap_or(path);
+    match (method, path) {
+        ("POST", "/lint") => lint(server, body),
+        ("GET", "/rules") => rules(),
+        (_, "/lint" | "/rules") => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn lint(server: &Server, body: &[u8]) -> Response {
+    let request: LintRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return Response::error(400, &format!("invalid request body: {}", err)),
+    };
+    let key = hash_content(&format!("{}\0{}", request.filename, request.source));
+    if let Some(cached) = server.results.get(&key) {
+        return Response {
+            status: 200,
+            body: Arc::clone(&cached),
+        };
+    }
+
+    let result = lint_structured_with_config(&request.source, &request.filename, &server.config);
+    let body = Arc::new(serde_json::to_string(&result).unwrap_or_default());
+    if server.results.len() >= MAX_CACHED_RESULTS {
+        server.results.clear();
+    }
+    server.results.insert(key, Arc::clone(&body));
+    Response { status: 200, body }
+}
+
+fn rules() -> Response {
+    let rules: Vec<serde_json::Value> = ALL_RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "default_severity": rule.default_severity.as_str(),
+                "fixable": rule.fixable,
+                "description": rule.description,
+                "explanation": rule.explanation,
+                "docs_url": rule.docs_url,
+            })
+        })
+        .collect();
+    Response::json(200, json!(rules))
+}
+
+async fn write_response(stream: &mut TcpStream, response: Response) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::linter::{LintDiagnostic, LintTimings};
+use crate::rules::ALL_RULES;
+
+/// Counters and timings collected during a run for `--stats`.
+/// Parse and lint times are summed across worker threads.
+#[derive(Debug, Default)]
+pub struct Stats {
+    files_walked: usize,
+    walk_time: Duration,
+    files_parsed: AtomicUsize,
+    parse_nanos: AtomicU64,
+    lint_nanos: AtomicU64,
+}
+
+impl Stats {
+    pub fn record_walk(&mut self, files_walked: usize, walk_time: Duration) {
+        self.files_walked = files_walked;
+        self.walk_time = walk_time;
+    }
+
+    /// Record a single parse and lint pass; safe to call from worker threads
+    pub fn record_lint(&self, timings: LintTimings) {
+        self.files_parsed.fetch_add(1, Ordering::Relaxed);
+        self.parse_nanos
+            .fetch_add(timings.parse.as_nanos() as u64, Ordering::Relaxed);
+        self.lint_nanos
+            .fetch_add(timings.lint.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Print the collected stats along with the number of reported diagnostics per rule
+    pub fn print(&self, diagnostics: &[LintDiagnostic], total_time: Duration) {
+        let parse_time = Duration::from_nanos(self.parse_nanos.load(Ordering::Relaxed));
+        let lint_time = Duration::from_nanos(self.lint_nanos.load(Ordering::Relaxed));
+
+        println!();
+        println!("Stats:");
+        println!(
+            "  files walked   {} ({:.2?})",
+            self.files_walked, self.walk_time
+        );
+        println!(
+            "  files parsed   {}",
+            self.files_parsed.load(Ordering::Relaxed)
+        );
+        println!("  parse time     {:.2?}", parse_time);
+        println!("  lint time      {:.2?}", lint_time);
+        println!("  total time     {:.2?}", total_time);
+
+        let id_width = ALL_RULES
+            .iter()
+            .map(|rule| rule.id.len())
+            .max()
+            .unwrap_or(0);
+        println!("  diagnostics per rule:");
+        for rule in ALL_RULES {
+            let count = diagnostics.iter().filter(|d| d.rule == rule.id).count();
+            println!("    {:<id_width$}  {}", rule.id, count);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_parser::{Parser as OxcParser, ParserReturn};
+use oxc_span::{SourceType, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::linter::{offset_to_line_col, Linter, StepCall};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkflowKind {
+    /// A class extending `WorkflowEntrypoint`
+    Class,
+    /// A function taking a `WorkflowStep` parameter
+    Function,
+}
+
+/// A workflow class or function and the step calls inside it
+#[derive(Debug, Clone)]
+pub struct Workflow {
+    pub name: String,
+    pub kind: WorkflowKind,
+    pub span: Span,
+    pub steps: Vec<StepCall>,
+    /// Names of the parameters holding the `WorkflowStep`, e.g. the `step` in `run(event, step)`
+    pub step_params: Vec<String>,
+}
+
+impl Workflow {
+    /// Summary such as "3 steps, 1 sleep, 0 waits"
+    pub fn inventory(&self) -> String {
+        let count = |methods: &[&str]| {
+            self.steps
+                .iter()
+                .filter(|step| methods.contains(&step.method.as_str()))
+                .count()
+        };
+        format!(
+            "{}, {}, {}",
+            plural(count(&["do"]), "step"),
+            plural(count(&["sleep", "sleepUntil"]), "sleep"),
+            plural(count(&["waitForEvent"]), "wait")
+        )
+    }
+}
+
+/// A class extending `WorkflowEntrypoint` that its module exports by name, with the
+/// position of the class name, so that checks across files don't parse the module again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedWorkflowClass {
+    /// The name the class is exported as
+    pub name: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl ExportedWorkflowClass {
+    fn new(source: &str, name: &str, span: Span) -> Self {
+        let (line, column) = offset_to_line_col(source, span.start as usize);
+        let (end_line, end_column) = offset_to_line_col(source, span.end as usize);
+        Self {
+            name: name.to_string(),
+            line,
+            column,
+            end_line,
+            end_column,
+            start: span.start,
+            end: span.end,
+        }
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Find the workflows declared at the top level of a file: classes extending
+/// `WorkflowEntrypoint` and functions taking a `WorkflowStep` parameter
+pub fn find_workflows(source: &str, file_path: &str) -> Vec<Workflow> {
+    let source_type = SourceType::from_path(file_path).unwrap_or_default();
+    let allocator = Allocator::default();
+    let ParserReturn { program, .. } = OxcParser::new(&allocator, source, source_type).parse();
+    workflows_in_program(&program, source, file_path)
+}
+
+/// Like [`find_workflows`], for a program that has already been parsed
+pub(crate) fn workflows_in_program(
+    program: &Program,
+    source: &str,
+    file_path: &str,
+) -> Vec<Workflow> {
+    let mut workflows = Vec::new();
+    for stmt in &program.body {
+        collect_workflows(stmt, &mut workflows);
+    }
+    if workflows.is_empty() {
+        return workflows;
+    }
+
+    // Reuse the linter's step detection and assign each call to its workflow
+    let mut linter = Linter::new(source, file_path);
+    linter.lint_program(program);
+    for step in linter.into_step_calls() {
+        if let Some(workflow) = workflows.iter_mut().find(|workflow| {
+            workflow.span.start <= step.span.start && step.span.end <= workflow.span.end
+        }) {
+            workflow.steps.push(step);
+        }
+    }
+    workflows
+}
+
+fn collect_workflows(stmt: &Statement, workflows: &mut Vec<Workflow>) {
+    match stmt {
+        Statement::ClassDeclaration(class) => check_class(class, workflows),
+        Statement::FunctionDeclaration(func) => check_function(func, workflows),
+        Statement::VariableDeclaration(decl) => check_variables(decl, workflows),
+        Statement::ExportNamedDeclaration(export) => match &export.declaration {
+            Some(Declaration::ClassDeclaration(class)) => check_class(class, workflows),
+            Some(Declaration::FunctionDeclaration(func)) => check_function(func, workflows),
+            Some(Declaration::VariableDeclaration(decl)) => check_variables(decl, workflows),
+            _ => {}
+        },
+        Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+            ExportDefaultDeclarationKind::ClassDeclaration(class) => check_class(class, workflows),
+            ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
+                check_function(func, workflows)
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Whether `class` extends `WorkflowEntrypoint`, directly or through a namespace
+pub(crate) fn extends_workflow_entrypoint(class: &Class) -> bool {
+    match &class.super_class {
+        Some(Expression::Identifier(id)) => id.name == "WorkflowEntrypoint",
+        Some(Expression::StaticMemberExpression(member)) => {
+            member.property.name == "WorkflowEntrypoint"
+        }
+        _ => false,
+    }
+}
+
+/// The named exports of a module, and the ones that are `WorkflowEntrypoint` classes
+pub(crate) fn module_exports(
+    program: &Program,
+    source: &str,
+) -> (Vec<String>, Vec<ExportedWorkflowClass>) {
+    let mut classes = Vec::new();
+    let mut exported = Vec::new();
+    // `export { Local as Exported }`, resolved once every local class is known
+    let mut specifiers = Vec::new();
+    let mut local_classes = Vec::new();
+    for stmt in &program.body {
+        match stmt {
+            Statement::ClassDeclaration(class) if extends_workflow_entrypoint(class) => {
+                local_classes.extend(class.id.as_ref());
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                if let Some(Declaration::ClassDeclaration(class)) = &export.declaration {
+                    if let Some(id) = &class.id {
+                        exported.push(id.name.to_string());
+                        if extends_workflow_entrypoint(class) {
+                            classes.push(ExportedWorkflowClass::new(source, &id.name, id.span));
+                        }
+                    }
+                }
+                for specifier in &export.specifiers {
+                    let name = specifier.exported.name().to_string();
+                    exported.push(name.clone());
+                    if export.source.is_none() {
+                        specifiers.push((specifier.local.name().to_string(), name));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for (local, name) in specifiers {
+        if let Some(id) = local_classes.iter().find(|id| id.name == local.as_str()) {
+            classes.push(ExportedWorkflowClass::new(source, &name, id.span));
+        }
+    }
+    (exported, classes)
+}
+
+fn check_class(class: &Class, workflows: &mut Vec<Workflow>) {
+    if extends_workflow_entrypoint(class) {
+        // The step is the second parameter of `run(event, step)`
+        let step_params = class
+            .body
+            .body
+            .iter()
+            .filter_map(|element| match element {
+                ClassElement::MethodDefinition(method)
+                    if method.key.static_name().as_deref() == Some("run") =>
+                {
+                    param_name(method.value.params.items.get(1)?)
+                }
+                _ => None,
+            })
+            .collect();
+        workflows.push(Workflow {
+            name: binding_name(class.id.as_ref()),
+            kind: WorkflowKind::Class,
+            span: class.span,
+            steps: Vec::new(),
+            step_params,
+        });
+    }
+}
+
+fn check_function(func: &Function, workflows: &mut Vec<Workflow>) {
+    if takes_workflow_step(&func.params) {
+        workflows.push(Workflow {
+            name: binding_name(func.id.as_ref()),
+            kind: WorkflowKind::Function,
+            span: func.span,
+            steps: Vec::new(),
+            step_params: workflow_step_params(&func.params),
+        });
+    }
+}
+
+fn check_variables(decl: &VariableDeclaration, workflows: &mut Vec<Workflow>) {
+    for declarator in &decl.declarations {
+        let params = match &declarator.init {
+            Some(Expression::ArrowFunctionExpression(arrow)) => &arrow.params,
+            Some(Expression::FunctionExpression(func)) => &func.params,
+            _ => continue,
+        };
+        if !takes_workflow_step(params) {
+            continue;
+        }
+        let name = match &declarator.id {
+            BindingPattern::BindingIdentifier(id) => id.name.to_string(),
+            _ => "default".to_string(),
+        };
+        workflows.push(Workflow {
+            name,
+            kind: WorkflowKind::Function,
+            span: declarator.span,
+            steps: Vec::new(),
+            step_params: workflow_step_params(params),
+        });
+    }
+}
+
+/// Whether any of `params` is annotated as a `WorkflowStep`
+pub(crate) fn takes_workflow_step(params: &FormalParameters) -> bool {
+    params.items.iter().any(is_workflow_step)
+}
+
+pub(crate) fn is_workflow_step(param: &FormalParameter) -> bool {
+    matches!(
+        param.type_annotation.as_deref().map(|annotation| &annotation.type_annotation),
+        Some(TSType::TSTypeReference(reference))
+            if matches!(&reference.type_name, TSTypeName::IdentifierReference(id) if id.name == "WorkflowStep")
+    )
+}
+
+/// The names of the parameters annotated as a `WorkflowStep`
+pub(crate) fn workflow_step_params(params: &FormalParameters) -> Vec<String> {
+    params
+        .items
+        .iter()
+        .filter(|param| is_workflow_step(param))
+        .filter_map(param_name)
+        .collect()
+}
+
+pub(crate) fn param_name(param: &FormalParameter) -> Option<String> {
+    match &param.pattern {
+        BindingPattern::BindingIdentifier(id) => Some(id.name.to_string()),
+        _ => None,
+    }
+}
+
+fn binding_name(id: Option<&BindingIdentifier>) -> String {
+    id.map(|id| id.name.to_string())
+        .unwrap_or_else(|| "default".to_string())
+}
@@ -0,0 +1,284 @@
+//! Declarative tests for rules, built-in or third-party.
+//!
+//! A [`RuleTester`] lints snippets with a chosen set of rules and checks them
+//! against the expected diagnostics and fixed output, either from inline cases
+//! or from a directory of fixture files:
+//!
+//! ```text
+//! fixtures/
+//!   valid/ok.ts              must produce no diagnostics
+//!   invalid/bad.ts           must produce the diagnostics in bad.ts.expected
+//!   invalid/bad.ts.expected  one `LINE:COLUMN MESSAGE` per line (MESSAGE optional)
+//!   invalid/bad.ts.fixed     the source after applying fixes; without it, fixes
+//!                            must leave the source unchanged
+//! ```
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::fix::apply_fixes;
+use crate::is_js_or_ts_file;
+use crate::linter::{lint_source_timed, LintDiagnostic};
+use crate::rules::{Rule, RuleRegistry};
+
+/// A diagnostic an invalid case is expected to report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedDiagnostic {
+    pub line: usize,
+    pub column: usize,
+    /// The exact message, or `None` to accept any message
+    pub message: Option<String>,
+}
+
+impl ExpectedDiagnostic {
+    pub fn at(line: usize, column: usize) -> Self {
+        Self {
+            line,
+            column,
+            message: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Parse a `LINE:COLUMN MESSAGE` line from an `.expected` fixture
+    fn parse(line: &str) -> Option<Self> {
+        let (position, message) = match line.split_once(' ') {
+            Some((position, message)) => (position, Some(message.trim())),
+            None => (line, None),
+        };
+        let (line, column) = position.split_once(':')?;
+        let expected = Self::at(line.parse().ok()?, column.parse().ok()?);
+        Some(match message.filter(|message| !message.is_empty()) {
+            Some(message) => expected.with_message(message),
+            None => expected,
+        })
+    }
+
+    fn matches(&self, diagnostic: &LintDiagnostic) -> bool {
+        self.line == diagnostic.line
+            && self.column == diagnostic.column
+            && self
+                .message
+                .as_ref()
+                .is_none_or(|message| *message == diagnostic.message)
+    }
+}
+
+/// A snippet that must be reported
+#[derive(Debug, Clone)]
+pub struct InvalidCase<'a> {
+    pub code: &'a str,
+    pub errors: Vec<ExpectedDiagnostic>,
+    /// The source after applying every fix, or `None` if fixes must not change it
+    pub output: Option<&'a str>,
+}
+
+/// Lints snippets with a set of rules and checks the results
+pub struct RuleTester {
+    rules: RuleRegistry,
+    filename: String,
+    step_methods: Vec<String>,
+}
+
+impl RuleTester {
+    /// Test the rules in `rules`
+    pub fn new(rules: RuleRegistry) -> Self {
+        Self {
+            rules,
+            filename: "workflow.ts".to_string(),
+            step_methods: Vec::new(),
+        }
+    }
+
+    /// Test a single rule on its own
+    pub fn for_rule<R: Rule + Default + 'static>() -> Self {
+        let mut rules = RuleRegistry::empty();
+        rules.register::<R>();
+        Self::new(rules)
+    }
+
+    /// File name that inline snippets are linted as, which picks the language (`workflow.ts` by default)
+    pub fn with_filename(mut self, filename: &str) -> Self {
+        self.filename = filename.to_string();
+        self
+    }
+
+    /// Extra methods treated like `step.do`, as with the `step_methods` setting
+    pub fn with_step_methods(mut self, step_methods: &[&str]) -> Self {
+        self.step_methods = step_methods.iter().map(|m| m.to_string()).collect();
+        self
+    }
+
+    /// Lint `code` with the tester's rules, sorted by position
+    pub fn lint(&self, code: &str) -> Vec<LintDiagnostic> {
+        self.lint_as(code, &self.filename)
+    }
+
+    fn lint_as(&self, code: &str, filename: &str) -> Vec<LintDiagnostic> {
+        let (mut diagnostics, _) =
+            lint_source_timed(code, filename, &self.step_methods, &self.rules);
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        diagnostics
+    }
+
+    /// Panic unless `code` produces no diagnostics
+    pub fn assert_valid(&self, code: &str) {
+        self.run(&[code], &[]);
+    }
+
+    /// Panic unless `code` produces exactly `errors` and fixes to `output`
+    pub fn assert_invalid(&self, code: &str, errors: &[ExpectedDiagnostic], output: Option<&str>) {
+        self.run(
+            &[],
+            &[InvalidCase {
+                code,
+                errors: errors.to_vec(),
+                output,
+            }],
+        );
+    }
+
+    /// Check every case, panicking with a report of all failures
+    pub fn run(&self, valid: &[&str], invalid: &[InvalidCase]) {
+        let mut failures = String::new();
+        for code in valid {
+            self.check_valid(&mut failures, "valid case", code, &self.filename);
+        }
+        for case in invalid {
+            self.check_invalid(&mut failures, "invalid case", case, &self.filename);
+        }
+        if !failures.is_empty() {
+            panic!("rule tests failed:\n{}", failures);
+        }
+    }
+
+    /// Check the fixtures in `dir/valid` and `dir/invalid`, linting each file under its own name
+    pub fn run_fixtures(&self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+        let mut failures = String::new();
+        let mut checked = 0;
+        for (kind, is_valid) in [("valid", true), ("invalid", false)] {
+            for path in fixture_files(&dir.join(kind)) {
+                checked += 1;
+                let label = path.display().to_string();
+                let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let Ok(code) = fs::read_to_string(&path) else {
+                    let _ = writeln!(failures, "{}: could not be read", label);
+                    continue;
+                };
+                if is_valid {
+                    self.check_valid(&mut failures, &label, &code, filename);
+                    continue;
+                }
+
+                let expected_path = path.with_file_name(format!("{}.expected", filename));
+                let Ok(expected) = fs::read_to_string(&expected_path) else {
+                    let _ = writeln!(failures, "{}: missing {}", label, expected_path.display());
+                    continue;
+                };
+                let mut errors = Vec::new();
+                for line in expected.lines().filter(|line| !line.trim().is_empty()) {
+                    match ExpectedDiagnostic::parse(line.trim()) {
+                        Some(error) => errors.push(error),
+                        None => {
+                            let _ = writeln!(
+                                failures,
+                                "{}: can't parse `{}`, expected `LINE:COLUMN MESSAGE`",
+                                expected_path.display(),
+                                line
+                            );
+                        }
+                    }
+                }
+                let output =
+                    fs::read_to_string(path.with_file_name(format!("{}.fixed", filename))).ok();
+                let case = InvalidCase {
+                    code: &code,
+                    errors,
+                    output: output.as_deref(),
+                };
+                self.check_invalid(&mut failures, &label, &case, filename);
+            }
+        }
+        if checked == 0 {
+            failures.push_str("no fixtures found\n");
+        }
+        if !failures.is_empty() {
+            panic!("rule fixtures in {} failed:\n{}", dir.display(), failures);
+        }
+    }
+
+    fn check_valid(&self, failures: &mut String, label: &str, code: &str, filename: &str) {
+        let diagnostics = self.lint_as(code, filename);
+        if !diagnostics.is_empty() {
+            let _ = writeln!(failures, "{}: expected no diagnostics, got:", label);
+            write_diagnostics(failures, &diagnostics);
+        }
+    }
+
+    fn check_invalid(
+        &self,
+        failures: &mut String,
+        label: &str,
+        case: &InvalidCase,
+        filename: &str,
+    ) {
+        let diagnostics = self.lint_as(case.code, filename);
+        let mut expected = case.errors.clone();
+        expected.sort_by_key(|e| (e.line, e.column));
+        let matched = expected.len() == diagnostics.len()
+            && expected.iter().zip(&diagnostics).all(|(e, d)| e.matches(d));
+        if !matched {
+            let _ = writeln!(failures, "{}: expected diagnostics:", label);
+            for error in &expected {
+                let _ = writeln!(
+                    failures,
+                    "    {}:{} {}",
+                    error.line,
+                    error.column,
+                    error.message.as_deref().unwrap_or("(any message)")
+                );
+            }
+            let _ = writeln!(failures, "  got:");
+            write_diagnostics(failures, &diagnostics);
+        }
+
+        let (fixed, _) = apply_fixes(case.code, &diagnostics);
+        let expected_output = case.output.unwrap_or(case.code);
+        if fixed != expected_output {
+            let _ = writeln!(
+                failures,
+                "{}: expected fixed output:\n{}\n  got:\n{}",
+                label, expected_output, fixed
+            );
+        }
+    }
+}
+
+fn write_diagnostics(out: &mut String, diagnostics: &[LintDiagnostic]) {
+    for diagnostic in diagnostics {
+        let _ = writeln!(
+            out,
+            "    {}:{} {} [{}]",
+            diagnostic.line, diagnostic.column, diagnostic.message, diagnostic.rule
+        );
+    }
+}
+
+/// JS/TS files directly inside `dir`, sorted by name
+fn fixture_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_js_or_ts_file(path))
+        .collect();
+    files.sort();
+    files
+}
@@ -0,0 +1,135 @@
+//! Hooks into the linter's traversal for custom workflow analyses, such as
+//! metrics or code generation, that need the same step detection as the rules.
+//!
+//! A [`Visitor`] is called as [`visit_source`] walks a file, in source order,
+//! with a [`VisitContext`] holding the parsed program and its workflows.
+
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    AssignmentExpression, CallExpression, Class, ImportDeclaration, NewExpression, Program,
+    Statement, StaticMemberExpression, ThrowStatement, TryStatement, UpdateExpression,
+    VariableDeclarator,
+};
+use oxc_parser::{Parser as OxcParser, ParserReturn};
+use oxc_span::{SourceType, Span};
+
+use crate::linter::{parse_errors, Linter, ParseError, StepCall};
+use crate::rules::{FunctionScope, StepUsage};
+use crate::steps::{workflows_in_program, Workflow};
+
+/// The file being visited
+pub struct VisitContext<'a> {
+    source: &'a str,
+    file_path: &'a str,
+    program: &'a Program<'a>,
+    workflows: Vec<Workflow>,
+}
+
+impl<'a> VisitContext<'a> {
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn file_path(&self) -> &'a str {
+        self.file_path
+    }
+
+    /// The whole parsed program, for analyses that need more than the hooks provide
+    pub fn program(&self) -> &'a Program<'a> {
+        self.program
+    }
+
+    /// The workflows declared at the top level of the file, with their step calls
+    pub fn workflows(&self) -> &[Workflow] {
+        &self.workflows
+    }
+
+    /// The workflow containing `span`, if any
+    pub fn workflow_at(&self, span: Span) -> Option<&Workflow> {
+        self.workflows
+            .iter()
+            .find(|workflow| workflow.span.start <= span.start && span.end <= workflow.span.end)
+    }
+
+    /// Whether `name` is a parameter holding a `WorkflowStep` in one of the file's workflows
+    pub fn is_step_param(&self, name: &str) -> bool {
+        self.workflows
+            .iter()
+            .any(|workflow| workflow.step_params.iter().any(|param| param == name))
+    }
+}
+
+/// Callbacks for the nodes the linter walks; every hook does nothing by default
+pub trait Visitor {
+    /// A function body (or the top level of the file) is entered
+    fn enter_function(&mut self, _ctx: &VisitContext, _scope: FunctionScope) {}
+
+    /// The innermost function body entered is left
+    fn leave_function(&mut self, _ctx: &VisitContext) {}
+
+    /// A call on a step object, such as `step.do(...)`
+    fn visit_step_call(&mut self, _ctx: &VisitContext, _call: &StepCall, _usage: &StepUsage) {}
+
+    /// Variables awaited by `await x` or `await Promise.all([x, y])`
+    fn visit_awaited_vars(&mut self, _ctx: &VisitContext, _names: &[&str]) {}
+
+    /// Any call expression, including step calls
+    fn visit_call(&mut self, _ctx: &VisitContext, _call: &CallExpression) {}
+
+    /// A `new` expression, such as `new Date()`
+    fn visit_new(&mut self, _ctx: &VisitContext, _new_expr: &NewExpression) {}
+
+    /// A `throw` statement
+    fn visit_throw(&mut self, _ctx: &VisitContext, _throw: &ThrowStatement) {}
+
+    /// A `try` statement, before its blocks are walked
+    fn visit_try(&mut self, _ctx: &VisitContext, _try_stmt: &TryStatement) {}
+
+    /// A `for`, `for...in`, `for...of`, `while` or `do...while` loop, before its body is walked
+    fn visit_loop(&mut self, _ctx: &VisitContext, _loop_stmt: &Statement) {}
+
+    /// An assignment, such as `total += x`
+    fn visit_assignment(&mut self, _ctx: &VisitContext, _assignment: &AssignmentExpression) {}
+
+    /// An increment or decrement, such as `count++`
+    fn visit_update(&mut self, _ctx: &VisitContext, _update: &UpdateExpression) {}
+
+    /// A variable declarator, such as the `x = 1` in `const x = 1`
+    fn visit_variable(&mut self, _ctx: &VisitContext, _declarator: &VariableDeclarator) {}
+
+    /// A class declaration or expression, before its members are walked
+    fn visit_class(&mut self, _ctx: &VisitContext, _class: &Class) {}
+
+    /// A static member access, such as `process.env`, before its object is walked
+    fn visit_member(&mut self, _ctx: &VisitContext, _member: &StaticMemberExpression) {}
+
+    /// An `import` declaration, such as `import fs from "node:fs"`
+    fn visit_import(&mut self, _ctx: &VisitContext, _import: &ImportDeclaration) {}
+}
+
+/// Parse `source` and walk it with `visitor`, treating `step_methods` as step methods
+/// in addition to the built-in ones. Returns the errors the parser recovered from.
+pub fn visit_source(
+    source: &str,
+    file_path: &str,
+    step_methods: &[String],
+    visitor: &mut dyn Visitor,
+) -> Vec<ParseError> {
+    let source_type = SourceType::from_path(file_path).unwrap_or_default();
+    let allocator = Allocator::default();
+    let ParserReturn {
+        program, errors, ..
+    } = OxcParser::new(&allocator, source, source_type).parse();
+
+    let ctx = VisitContext {
+        source,
+        file_path,
+        program: &program,
+        workflows: workflows_in_program(&program, source, file_path),
+    };
+    let mut linter = Linter::new(source, file_path)
+        .with_step_methods(step_methods)
+        .with_visitor(visitor, &ctx);
+    linter.lint_program(&program);
+    parse_errors(source, &errors)
+}
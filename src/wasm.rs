@@ -0,0 +1,63 @@
+//! Exports of [`lint_source_structured`] for `wasm32-unknown-unknown` builds, so a
+//! browser playground can lint code without a server.
+//!
+//! JavaScript hosts using wasm-bindgen's generated glue call [`lint_source`]
+//! (`lintSource`), which returns the [`LintResult`] JSON as a string. Hosts loading
+//! the bare module use the plain C ABI instead: they allocate buffers with [`cashmere_alloc`], writes the UTF-8 source and
+//! file name into them and calls [`cashmere_lint`]. The result is a buffer holding a
+//! little-endian `u32` length followed by that many bytes of [`LintResult`] JSON,
+//! which the host releases with [`cashmere_free`] once it has read it.
+
+use std::{ptr, slice};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::linter::{lint_source_structured, LintResult};
+
+/// Lint `source` as if it were the file `file_path`, returning [`LintResult`] JSON
+#[wasm_bindgen(js_name = lintSource)]
+pub fn lint_source(source: &str, file_path: &str) -> String {
+    let result: LintResult = lint_source_structured(source, file_path, &[]);
+    serde_json::to_string(&result).unwrap_or_default()
+}
+
+/// Allocate `len` bytes for the host to write into
+#[no_mangle]
+pub extern "C" fn cashmere_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// Release a buffer from [`cashmere_alloc`] or [`cashmere_lint`]
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from [`cashmere_alloc`], or from [`cashmere_lint`] with
+/// `len` being the length prefix plus four, and the buffer must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn cashmere_free(ptr: *mut u8, len: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+}
+
+/// Lint `source` as if it were the file `file_path`, returning a length-prefixed JSON buffer
+///
+/// # Safety
+///
+/// Both pointer and length pairs must describe initialized buffers, such as ones from
+/// [`cashmere_alloc`] that the host has filled in.
+#[no_mangle]
+pub unsafe extern "C" fn cashmere_lint(
+    source_ptr: *const u8,
+    source_len: usize,
+    file_path_ptr: *const u8,
+    file_path_len: usize,
+) -> *mut u8 {
+    let source = String::from_utf8_lossy(slice::from_raw_parts(source_ptr, source_len));
+    let file_path = String::from_utf8_lossy(slice::from_raw_parts(file_path_ptr, file_path_len));
+    let result: LintResult = lint_source_structured(&source, &file_path, &[]);
+    let json = serde_json::to_vec(&result).unwrap_or_default();
+
+    let mut buffer = Vec::with_capacity(json.len() + 4);
+    buffer.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&json);
+    Box::into_raw(buffer.into_boxed_slice()) as *mut u8
+}
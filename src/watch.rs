@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{Event, RecursiveMode, Watcher};
+use rayon::prelude::*;
+
+use cashmere::config::Config;
+use cashmere::linter::LintDiagnostic;
+use cashmere::report::{print_text_report, Format};
+use cashmere::stats::Stats;
+use cashmere::{collect_files, is_js_or_ts_file, lint_file, should_skip_dir};
+
+/// How long to wait for more filesystem events before re-linting.
+/// Editors frequently emit several events for a single save.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Lint `root`, then keep watching it and re-lint only the files that change
+pub fn run_watch(root: &Path, config: &Config, format: Format) {
+    let watch_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    let mut results: BTreeMap<PathBuf, Vec<LintDiagnostic>> = collect_files(root, config)
+        .into_par_iter()
+        .filter_map(|path| {
+            let diagnostics = lint_file(&path, config, &Stats::default())?;
+            Some((path, diagnostics))
+        })
+        .collect();
+    print_results(&results, format);
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            eprintln!("Failed to start file watcher: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let mode = if watch_root.is_dir() {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    if let Err(err) = watcher.watch(&watch_root, mode) {
+        eprintln!("Failed to watch {}: {}", root.display(), err);
+        std::process::exit(2);
+    }
+
+    println!("Watching for changes...");
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        collect_changed_paths(event, &mut changed);
+        // Coalesce bursts of events into a single re-lint
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_changed_paths(event, &mut changed);
+        }
+
+        let mut relinted = 0;
+        for changed_path in changed {
+            let Some(path) = display_path(root, &watch_root, &changed_path) else {
+                continue;
+            };
+            if !is_js_or_ts_file(&path) || config.is_ignored(&path) {
+                continue;
+            }
+
+            match lint_file(&path, config, &Stats::default()) {
+                Some(diagnostics) => {
+                    results.insert(path, diagnostics);
+                }
+                // The file was removed or is no longer readable
+                None => {
+                    results.remove(&path);
+                }
+            }
+            relinted += 1;
+        }
+
+        if relinted > 0 {
+            println!();
+            println!("--- Re-linted {} changed file(s) ---", relinted);
+            print_results(&results, format);
+            println!("Watching for changes...");
+        }
+    }
+}
+
+fn collect_changed_paths(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if !event.kind.is_access() => changed.extend(event.paths),
+        Ok(_) => {}
+        Err(err) => eprintln!("Watch error: {}", err),
+    }
+}
+
+/// Map an absolute path reported by the watcher back onto the path the user passed,
+/// skipping anything inside an ignored directory
+fn display_path(root: &Path, watch_root: &Path, changed: &Path) -> Option<PathBuf> {
+    if changed == watch_root {
+        return Some(root.to_path_buf());
+    }
+
+    let relative = changed.strip_prefix(watch_root).ok()?;
+    let in_skipped_dir = relative
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .any(|component| should_skip_dir(component.as_os_str().to_str().unwrap_or("")));
+    if in_skipped_dir {
+        return None;
+    }
+
+    Some(root.join(relative))
+}
+
+fn print_results(results: &BTreeMap<PathBuf, Vec<LintDiagnostic>>, format: Format) {
+    let diagnostics: Vec<LintDiagnostic> = results.values().flatten().cloned().collect();
+    print_text_report(format, &diagnostics, results.len());
+}
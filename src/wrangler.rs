@@ -0,0 +1,434 @@
+//! Reading the wrangler configuration of a Workers project, to find its entry
+//! module and Workflow bindings, and the modules reachable from that entry, and
+//! checking the Workflow classes those modules export.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use oxc_allocator::Allocator;
+use oxc_parser::{Parser as OxcParser, ParserReturn};
+use oxc_span::{SourceType, Span};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::is_js_or_ts_file;
+use crate::linter::LintDiagnostic;
+use crate::rules::{UNIQUE_WORKFLOW_CLASSES, WRANGLER_BINDING};
+use crate::steps::{self, ExportedWorkflowClass};
+
+/// Wrangler config file names, in the order wrangler prefers them
+pub const WRANGLER_CONFIG_FILES: &[&str] = &["wrangler.json", "wrangler.jsonc", "wrangler.toml"];
+
+/// Extensions tried, in order, for an import without one
+const MODULE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// A `[[workflows]]` binding
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WorkflowBinding {
+    pub name: String,
+    pub binding: String,
+    pub class_name: String,
+    /// The Worker that defines the class, if it isn't this one
+    #[serde(default)]
+    pub script_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WranglerFile {
+    #[serde(default)]
+    main: Option<String>,
+    #[serde(default)]
+    workflows: Vec<WorkflowBinding>,
+}
+
+/// The parts of a wrangler config that cashmere uses
+#[derive(Debug, Clone)]
+pub struct Wrangler {
+    /// The config file this was loaded from
+    pub path: PathBuf,
+    /// The Worker's entry module, resolved against the config file
+    pub main: Option<PathBuf>,
+    pub workflows: Vec<WorkflowBinding>,
+}
+
+impl Wrangler {
+    /// Find the nearest wrangler config in `start` or any of its ancestors
+    pub fn discover(start: &Path) -> Result<Option<Self>, String> {
+        let start = std::path::absolute(start).unwrap_or_else(|_| start.to_path_buf());
+        let dir = if start.is_file() {
+            start.parent().unwrap_or(&start)
+        } else {
+            &start
+        };
+        for ancestor in dir.ancestors() {
+            for name in WRANGLER_CONFIG_FILES {
+                let candidate = ancestor.join(name);
+                if candidate.is_file() {
+                    return Self::load(&candidate).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load a `wrangler.toml`, `wrangler.json` or `wrangler.jsonc` file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let invalid =
+            |err: String| format!("Invalid wrangler config in {}: {}", path.display(), err);
+        let file: WranglerFile = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&contents).map_err(|err| invalid(err.to_string()))?
+        } else {
+            serde_json::from_str(&strip_jsonc(&contents)).map_err(|err| invalid(err.to_string()))?
+        };
+
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(Self {
+            main: file.main.map(|main| dir.join(main)),
+            workflows: file.workflows,
+            path,
+        })
+    }
+}
+
+/// Remove comments and trailing commas from JSONC, leaving strings untouched
+fn strip_jsonc(source: &str) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            (']' | '}', _) => {
+                // Drop a trailing comma before the closing bracket
+                let trimmed = output.trim_end().len();
+                if output[..trimmed].ends_with(',') {
+                    output.truncate(trimmed - 1);
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// The JS/TS modules reachable from `entries` through relative static and dynamic
+/// imports and re-exports, including the entries themselves, sorted by path
+pub fn reachable_modules(entries: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut queue: VecDeque<PathBuf> = entries
+        .iter()
+        .filter(|entry| entry.is_file())
+        .map(|entry| std::path::absolute(entry).unwrap_or_else(|_| entry.clone()))
+        .collect();
+    while let Some(module) = queue.pop_front() {
+        if !seen.insert(module.clone()) {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(&module) else {
+            continue;
+        };
+        let dir = module.parent().unwrap_or(Path::new(""));
+        for specifier in imported_specifiers(&source, &module) {
+            if let Some(resolved) = resolve_relative(dir, &specifier) {
+                if !seen.contains(&resolved) {
+                    queue.push_back(resolved);
+                }
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+/// The specifiers a module imports at runtime, leaving out `import type`
+fn imported_specifiers(source: &str, path: &Path) -> Vec<String> {
+    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let allocator = Allocator::default();
+    let ParserReturn { module_record, .. } =
+        OxcParser::new(&allocator, source, source_type).parse();
+
+    let mut specifiers: Vec<String> = module_record
+        .requested_modules
+        .iter()
+        .filter(|(_, requests)| requests.iter().any(|request| !request.is_type))
+        .map(|(specifier, _)| specifier.to_string())
+        .collect();
+    // Only dynamic imports of a plain string can be followed
+    specifiers.extend(module_record.dynamic_imports.iter().filter_map(|import| {
+        let request =
+            source.get(import.module_request.start as usize..import.module_request.end as usize)?;
+        let quote = request
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '\'' | '"' | '`'))?;
+        let inner = request.strip_prefix(quote)?.strip_suffix(quote)?;
+        (!inner.contains("${")).then(|| inner.to_string())
+    }));
+    specifiers
+}
+
+/// Resolve a relative import the way bundlers do: as written, with a JS/TS extension
+/// added, with a TypeScript extension in place of a `.js` one, or as a directory index
+fn resolve_relative(dir: &Path, specifier: &str) -> Option<PathBuf> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+    let base = dir.join(specifier);
+    let mut candidates = vec![base.clone()];
+    for ext in MODULE_EXTENSIONS {
+        let mut with_ext = base.clone().into_os_string();
+        with_ext.push(format!(".{}", ext));
+        candidates.push(with_ext.into());
+    }
+    if let Some(stem) = specifier.strip_suffix(".js") {
+        candidates.push(dir.join(format!("{}.ts", stem)));
+        candidates.push(dir.join(format!("{}.tsx", stem)));
+    }
+    candidates.extend(
+        MODULE_EXTENSIONS
+            .iter()
+            .map(|ext| base.join(format!("index.{}", ext))),
+    );
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.is_file() && is_js_or_ts_file(candidate))
+        .map(|candidate| normalize(&candidate))
+}
+
+/// Remove `.` and `..` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Collect the files reachable from the `main` entry of the nearest wrangler config,
+/// limited to `root` and leaving out anything the config ignores
+pub fn collect_entrypoint_files(root: &Path, config: &Config) -> Result<Vec<PathBuf>, String> {
+    let wrangler = Wrangler::discover(root)?.ok_or_else(|| {
+        format!(
+            "No wrangler.json, wrangler.jsonc or wrangler.toml found in {} or its parents",
+            root.display()
+        )
+    })?;
+    let main = wrangler
+        .main
+        .ok_or_else(|| format!("{} has no `main` entry", wrangler.path.display()))?;
+    if !main.is_file() {
+        return Err(format!(
+            "The `main` entry {} in {} doesn't exist",
+            main.display(),
+            wrangler.path.display()
+        ));
+    }
+
+    let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    Ok(reachable_modules(&[main])
+        .into_iter()
+        .filter(|path| path.starts_with(&root) && !config.is_ignored(path))
+        .collect())
+}
+
+/// A class extending `WorkflowEntrypoint` that its module exports by name
+struct ModuleClass {
+    file: PathBuf,
+    /// The name the class is exported as
+    name: String,
+    /// Span of the class name in its module
+    span: Span,
+}
+
+/// The named exports of a module, and the ones that are `WorkflowEntrypoint` classes
+fn module_exports(source: &str, path: &Path) -> (Vec<String>, Vec<ModuleClass>) {
+    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let allocator = Allocator::default();
+    let ParserReturn { program, .. } = OxcParser::new(&allocator, source, source_type).parse();
+    let (exported, classes) = steps::module_exports(&program, source);
+    let classes = classes
+        .into_iter()
+        .map(|class| ModuleClass {
+            file: path.to_path_buf(),
+            name: class.name,
+            span: Span::new(class.start, class.end),
+        })
+        .collect();
+    (exported, classes)
+}
+
+/// Cross-check the `[[workflows]]` bindings of `wrangler` against the classes exported
+/// by the modules reachable from its `main` entry, for the `wrangler-binding` rule.
+/// Bindings to a class in another Worker (`script_name`) are left alone.
+pub fn check_bindings(wrangler: &Wrangler) -> Vec<LintDiagnostic> {
+    let Some(main) = wrangler.main.as_ref().filter(|main| main.is_file()) else {
+        return Vec::new();
+    };
+    let mut exported = BTreeSet::new();
+    let mut classes = Vec::new();
+    let mut sources = Vec::new();
+    for module in reachable_modules(std::slice::from_ref(main)) {
+        let Ok(source) = fs::read_to_string(&module) else {
+            continue;
+        };
+        let (names, module_classes) = module_exports(&source, &module);
+        exported.extend(names);
+        classes.extend(module_classes);
+        sources.push((module, source));
+    }
+
+    let local_bindings: Vec<&WorkflowBinding> = wrangler
+        .workflows
+        .iter()
+        .filter(|binding| binding.script_name.is_none())
+        .collect();
+    let config_name = wrangler
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut diagnostics = Vec::new();
+    for class in &classes {
+        if local_bindings
+            .iter()
+            .any(|binding| binding.class_name == class.name)
+        {
+            continue;
+        }
+        let Some((_, source)) = sources.iter().find(|(module, _)| *module == class.file) else {
+            continue;
+        };
+        let message = format!(
+            "Workflow class `{}` has no `[[workflows]]` binding in {}",
+            class.name, config_name
+        );
+        diagnostics.push(LintDiagnostic::new(
+            class.file.to_str().unwrap_or(""),
+            source,
+            class.span,
+            &message,
+            &WRANGLER_BINDING,
+        ));
+    }
+
+    let config_text = fs::read_to_string(&wrangler.path).unwrap_or_default();
+    let config_path = wrangler.path.to_str().unwrap_or("");
+    for binding in local_bindings {
+        if exported.contains(&binding.class_name) {
+            continue;
+        }
+        let message = format!(
+            "Binding `{}` uses class_name `{}`, but the Worker exports no such class",
+            binding.binding, binding.class_name
+        );
+        let span = class_name_span(&config_text, &binding.class_name).unwrap_or_default();
+        diagnostics.push(LintDiagnostic::new(
+            config_path,
+            &config_text,
+            span,
+            &message,
+            &WRANGLER_BINDING,
+        ));
+    }
+    diagnostics
+}
+
+/// Report `WorkflowEntrypoint` classes exported under the same name by more than one of
+/// `files`, given with the classes each exports, for the `unique-workflow-classes` rule.
+/// Each copy is reported, pointing at the first other file that exports the name.
+pub fn check_duplicate_classes(files: &[(&Path, &[ExportedWorkflowClass])]) -> Vec<LintDiagnostic> {
+    let mut by_name: HashMap<&str, Vec<(&Path, &ExportedWorkflowClass)>> = HashMap::new();
+    for (file, classes) in files {
+        for class in *classes {
+            by_name.entry(&class.name).or_default().push((file, class));
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for (file, classes) in files {
+        for class in *classes {
+            let Some((other_file, other)) = by_name[class.name.as_str()]
+                .iter()
+                .find(|(other_file, _)| other_file != file)
+            else {
+                continue;
+            };
+            let message = format!(
+                "Workflow class `{}` is also exported by {}:{}, so a `[[workflows]]` binding with class_name `{}` is ambiguous; give each class its own name",
+                class.name,
+                other_file.display(),
+                other.line,
+                class.name
+            );
+            diagnostics.push(LintDiagnostic {
+                file: file.to_str().unwrap_or("").to_string(),
+                line: class.line,
+                column: class.column,
+                end_line: class.end_line,
+                end_column: class.end_column,
+                start: class.start,
+                end: class.end,
+                message,
+                rule: UNIQUE_WORKFLOW_CLASSES.id.to_string(),
+                severity: UNIQUE_WORKFLOW_CLASSES.default_severity,
+                fix: None,
+                related: Vec::new(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Span of the quoted value in the first `class_name = "name"` (TOML) or
+/// `"class_name": "name"` (JSON) entry of a wrangler config
+fn class_name_span(text: &str, name: &str) -> Option<Span> {
+    text.match_indices("class_name").find_map(|(index, key)| {
+        let rest = &text[index + key.len()..];
+        let value = rest
+            .trim_start_matches(['"', '\''])
+            .trim_start()
+            .strip_prefix(['=', ':'])?
+            .trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let inner = value[1..].strip_prefix(name)?;
+        if !inner.starts_with(quote) {
+            return None;
+        }
+        let start = text.len() - value.len();
+        Some(Span::new(start as u32, (start + name.len() + 2) as u32))
+    })
+}
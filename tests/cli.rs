@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use std::io::Write;
 use tempfile::NamedTempFile;
 
@@ -34,7 +34,7 @@ export class MyWorkflow {
     let temp_path = temp_file.path().to_str().unwrap();
 
     // Run the linter
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -94,7 +94,7 @@ export class MyWorkflow {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -133,7 +133,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -175,7 +175,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -213,7 +213,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -250,7 +250,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -287,7 +287,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -321,7 +321,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -363,7 +363,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -400,7 +400,7 @@ async function workflow(step: WorkflowStep) {
     temp_file.write_all(typescript_code.as_bytes()).unwrap();
     let temp_path = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("cashmere").unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
     let output = cmd.arg(temp_path).output().unwrap();
     let stdout = String::from_utf8_lossy(&output.stdout);
 
@@ -421,3 +421,3137 @@ async function workflow(step: WorkflowStep) {
     println!("=== Actual Output ===");
     println!("{}", stdout);
 }
+
+#[test]
+fn test_watch_mode_relints_changed_files() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("workflow.ts");
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    await step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("cashmere"))
+        .arg("--watch")
+        .arg(dir.path())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let wait_for = |needle: &str| -> String {
+        let mut seen = String::new();
+        while let Ok(line) = rx.recv_timeout(Duration::from_secs(10)) {
+            seen.push_str(&line);
+            seen.push('\n');
+            if line.contains(needle) {
+                return seen;
+            }
+        }
+        panic!(
+            "Timed out waiting for {:?}\nOutput so far:\n{}",
+            needle, seen
+        );
+    };
+
+    let initial = wait_for("Watching for changes");
+    assert!(
+        initial.contains("No issues found (1 files checked)"),
+        "Expected a clean initial run\nActual output:\n{}",
+        initial
+    );
+
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let relinted = wait_for("Found 1 issue(s)");
+    assert!(
+        relinted.contains("Re-linted 1 changed file(s)"),
+        "Expected only the changed file to be re-linted\nActual output:\n{}",
+        relinted
+    );
+    assert!(
+        relinted.contains(":2:5 - `step.sleep` must be awaited."),
+        "Expected the new violation to be reported\nActual output:\n{}",
+        relinted
+    );
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_fix_inserts_missing_await() {
+    let typescript_code = r#"
+export class MyWorkflow {
+    async run(event, step: WorkflowStep) {
+        step.do('send-email', async () => {
+            return { sent: true };
+        });
+
+        const p = step.sleep('pause', '1 second');
+    }
+}
+"#;
+
+    let expected_code = r#"
+export class MyWorkflow {
+    async run(event, step: WorkflowStep) {
+        await step.do('send-email', async () => {
+            return { sent: true };
+        });
+
+        const p = step.sleep('pause', '1 second');
+    }
+}
+"#;
+
+    let mut temp_file = NamedTempFile::with_suffix(".ts").unwrap();
+    temp_file.write_all(typescript_code.as_bytes()).unwrap();
+    let temp_path = temp_file.path().to_str().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--fix").arg(temp_path).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let fixed = std::fs::read_to_string(temp_path).unwrap();
+    assert_eq!(fixed, expected_code);
+
+    // The assigned-but-never-awaited promise can't be fixed automatically
+    assert!(
+        stdout.contains("Fixed 1 issue(s) in 1 file(s)"),
+        "Expected one fix to be applied\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("`step.sleep` must be awaited.") && stdout.contains("Found 1 issue(s)"),
+        "Expected the unfixable issue to still be reported\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_fix_skips_non_async_functions() {
+    let typescript_code = r#"
+function workflow(step: WorkflowStep) {
+    step.do('send-email', async () => {
+        return { sent: true };
+    });
+}
+"#;
+
+    let mut temp_file = NamedTempFile::with_suffix(".ts").unwrap();
+    temp_file.write_all(typescript_code.as_bytes()).unwrap();
+    let temp_path = temp_file.path().to_str().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--fix").arg(temp_path).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Inserting `await` in a non-async function would be a syntax error
+    let fixed = std::fs::read_to_string(temp_path).unwrap();
+    assert_eq!(fixed, typescript_code);
+    assert!(
+        stdout.contains("Found 1 issue(s)"),
+        "Expected the issue to be reported but not fixed\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_fix_dry_run_prints_diff_without_writing() {
+    let typescript_code = r#"
+async function workflow(step: WorkflowStep) {
+    step.sleep('pause', '30 seconds');
+}
+"#;
+
+    let mut temp_file = NamedTempFile::with_suffix(".ts").unwrap();
+    temp_file.write_all(typescript_code.as_bytes()).unwrap();
+    let temp_path = temp_file.path().to_str().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--fix-dry-run").arg(temp_path).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains(&format!("--- {}", temp_path))
+            && stdout.contains(&format!("+++ {}", temp_path)),
+        "Expected a unified diff header for the file\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("-    step.sleep('pause', '30 seconds');")
+            && stdout.contains("+    await step.sleep('pause', '30 seconds');"),
+        "Expected the diff to insert `await`\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("1 fix(es) available in 1 file(s)"),
+        "Expected a fix summary\nActual output:\n{}",
+        stdout
+    );
+
+    // The file on disk is untouched and the issue still fails the run
+    let contents = std::fs::read_to_string(temp_path).unwrap();
+    assert_eq!(contents, typescript_code);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_init_scaffolds_config_for_wrangler_project() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("wrangler.toml"), "name = \"my-worker\"\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("init").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "init failed:\n{}", stdout);
+    assert!(
+        stdout.contains("Detected a wrangler project"),
+        "Expected wrangler to be detected\nActual output:\n{}",
+        stdout
+    );
+
+    let config = std::fs::read_to_string(dir.path().join("cashmere.toml")).unwrap();
+    assert!(config.contains("await-step = \"error\""));
+    assert!(config.contains("\".wrangler/**\""));
+
+    // Running init again must not clobber the existing config
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("init").arg(dir.path()).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+}
+
+#[test]
+fn test_config_file_ignores_paths_and_sets_rule_levels() {
+    let dir = tempfile::tempdir().unwrap();
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::create_dir_all(dir.path().join("generated")).unwrap();
+    std::fs::write(dir.path().join("src/workflow.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("generated/workflow.ts"), unawaited).unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "ignore = [\"generated/**\"]\n\n[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("warning: `step.sleep` must be awaited."),
+        "Expected the diagnostic to be downgraded to a warning\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Found 1 warning(s) in 1 file(s) checked"),
+        "Expected the generated directory to be ignored\nActual output:\n{}",
+        stdout
+    );
+    // Warnings alone don't fail the run
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_rules_command_lists_rule_metadata() {
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("rules").output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    let await_step = stdout
+        .lines()
+        .find(|line| line.starts_with("await-step"))
+        .unwrap_or_else(|| {
+            panic!(
+                "Expected await-step to be listed\nActual output:\n{}",
+                stdout
+            )
+        });
+    let columns: Vec<&str> = await_step.split_whitespace().collect();
+    assert_eq!(&columns[..3], &["await-step", "error", "yes"]);
+}
+
+#[test]
+fn test_unknown_rule_in_config_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nawait-steps = \"off\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Unknown rule \"await-steps\""),
+        "Expected an unknown rule error\nActual stderr:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_cache_reuses_diagnostics_for_unchanged_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let run = || {
+        let mut cmd = cargo_bin_cmd!("cashmere");
+        let output = cmd
+            .current_dir(dir.path())
+            .arg("--cache")
+            .arg("workflow.ts")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let first = run();
+    assert!(first.contains("`step.sleep` must be awaited."));
+    let cache_path = dir.path().join(".cashmere-cache");
+    let cache = std::fs::read_to_string(&cache_path).unwrap();
+    assert!(cache.contains("workflow.ts"));
+
+    // Tamper with the cached message to prove the unchanged file isn't re-linted
+    std::fs::write(
+        &cache_path,
+        cache.replace("must be awaited.", "came from the cache."),
+    )
+    .unwrap();
+    let second = run();
+    assert!(
+        second.contains("`step.sleep` came from the cache."),
+        "Expected cached diagnostics to be reused\nActual output:\n{}",
+        second
+    );
+
+    // Changing the config invalidates the whole cache
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+    let third = run();
+    assert!(
+        third.contains("warning: `step.sleep` must be awaited."),
+        "Expected the cache to be invalidated by the config change\nActual output:\n{}",
+        third
+    );
+}
+
+#[test]
+fn test_threads_option_and_env_var() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.ts", "b.ts", "c.ts"] {
+        std::fs::write(
+            dir.path().join(name),
+            "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+        )
+        .unwrap();
+    }
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--threads")
+        .arg("1")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let single_threaded = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(
+        single_threaded.contains("Found 3 issue(s) in 3 file(s) checked"),
+        "Expected all files to be linted\nActual output:\n{}",
+        single_threaded
+    );
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .env("CASHMERE_THREADS", "4")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let multi_threaded = String::from_utf8_lossy(&output.stdout).to_string();
+    // Output order doesn't depend on the number of threads
+    assert_eq!(single_threaded, multi_threaded);
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .env("CASHMERE_THREADS", "many")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--threads"));
+}
+
+#[test]
+fn test_max_warnings_threshold() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.ts", "b.ts"] {
+        std::fs::write(
+            dir.path().join(name),
+            "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+        )
+        .unwrap();
+    }
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--max-warnings")
+        .arg("2")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Expected 2 warnings to be within the threshold\nActual output:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--max-warnings")
+        .arg("1")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(
+        stdout.contains("Too many warnings (2, maximum allowed is 1)"),
+        "Expected the warning threshold to be reported\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_baseline_suppresses_existing_issues() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("workflow.ts");
+    let baseline_path = dir.path().join("baseline.json");
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--baseline-generate")
+        .arg(&baseline_path)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Wrote 1 issue(s) to baseline"),
+        "Expected the baseline to be written\nActual output:\n{}",
+        stdout
+    );
+
+    // Shift the existing issue down a line and introduce a new one
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    const x = 1;\n    step.sleep('pause', '1 second');\n    step.do('new-task', async () => x);\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--baseline")
+        .arg(&baseline_path)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("1 pre-existing issue(s) suppressed by baseline"),
+        "Expected the moved issue to still match the baseline\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(":4:5 - `step.do` must be awaited.") && stdout.contains("Found 1 issue(s)"),
+        "Expected only the new issue to be reported\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_changed_and_since_lint_only_changed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(dir.path())
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+
+    git(&["init", "-q"]);
+    std::fs::write(dir.path().join("committed.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("modified.ts"), "export {};\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+    git(&["tag", "v1"]);
+
+    std::fs::write(dir.path().join("modified.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("untracked.ts"), unawaited).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--changed").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Found 2 issue(s) in 2 file(s) checked")
+            && stdout.contains("modified.ts:2:5")
+            && stdout.contains("untracked.ts:2:5")
+            && !stdout.contains("committed.ts"),
+        "Expected only the changed files to be linted\nActual output:\n{}",
+        stdout
+    );
+
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--changed").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No issues found (0 files checked)"),
+        "Expected nothing to lint with a clean working tree\nActual output:\n{}",
+        stdout
+    );
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--since")
+        .arg("v1")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Found 2 issue(s) in 2 file(s) checked")
+            && !stdout.contains("committed.ts"),
+        "Expected files changed since v1 to be linted\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_stdin_uses_stdin_filename() {
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--stdin")
+        .arg("--stdin-filename")
+        .arg("src/workflow.ts")
+        .write_stdin("async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("src/workflow.ts:2:5 - `step.sleep` must be awaited."),
+        "Expected the diagnostic to be reported against the stdin filename\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_hook_install_blocks_commits_with_unawaited_steps() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .current_dir(dir.path())
+            .env("CASHMERE", assert_cmd::cargo::cargo_bin!("cashmere"))
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    assert!(git(&["init", "-q"]).status.success());
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .current_dir(dir.path())
+        .arg("hook")
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "hook install failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(dir.path().join(".git/hooks/pre-commit").is_file());
+
+    let file_path = dir.path().join("workflow.ts");
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    assert!(git(&["add", "workflow.ts"]).status.success());
+    let commit = git(&["commit", "-q", "-m", "unawaited"]);
+    // git forwards hook output to stderr
+    let hook_output = String::from_utf8_lossy(&commit.stderr);
+    assert!(
+        !commit.status.success(),
+        "Expected the hook to reject the commit\n{}",
+        hook_output
+    );
+    assert!(
+        hook_output.contains("workflow.ts:2:5 - `step.sleep` must be awaited."),
+        "Expected the hook to report the staged issue\n{}",
+        hook_output
+    );
+
+    std::fs::write(
+        &file_path,
+        "async function workflow(step: WorkflowStep) {\n    await step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    assert!(git(&["add", "workflow.ts"]).status.success());
+    let commit = git(&["commit", "-q", "-m", "awaited"]);
+    assert!(
+        commit.status.success(),
+        "Expected the hook to allow the commit\n{}",
+        String::from_utf8_lossy(&commit.stderr)
+    );
+}
+
+#[test]
+fn test_completions_include_rule_names() {
+    for shell in ["bash", "zsh", "fish"] {
+        let mut cmd = cargo_bin_cmd!("cashmere");
+        let output = cmd.arg("completions").arg(shell).output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "completions {} failed", shell);
+        assert!(
+            stdout.contains("deny") && stdout.contains("await-step"),
+            "Expected {} completions to offer rule names for --deny\nActual output:\n{}",
+            shell,
+            stdout
+        );
+    }
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("completions").arg("powershell").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--deny"));
+}
+
+#[test]
+fn test_deny_overrides_config_level() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--deny")
+        .arg("await-step")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Found 1 issue(s) in 1 file(s) checked"),
+        "Expected --deny to report the rule as an error\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--deny")
+        .arg("no-such-rule")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_print_config_shows_resolved_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("packages/app")).unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "ignore = [\"**/generated/**\"]\n\n[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("print-config")
+        .arg(dir.path().join("packages/app"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains(&format!(
+            "# Config file: {}",
+            dir.path().join("cashmere.toml").display()
+        )),
+        "Expected the config file in a parent directory to be found\nActual output:\n{}",
+        stdout
+    );
+    assert!(stdout.contains("\"**/generated/**\","));
+    assert!(stdout.contains("await-step = \"warn\"  # cashmere.toml"));
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .env("CASHMERE_THREADS", "3")
+        .arg("print-config")
+        .arg("--deny")
+        .arg("await-step")
+        .arg(dir.path().join("packages/app"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("await-step = \"error\"  # command line")
+            && stdout.contains("# Threads: 3"),
+        "Expected command-line and environment overrides to be shown\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_exit_zero_and_fail_on_policies() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    // Report-only mode: issues are printed but the run succeeds
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--exit-zero").arg(dir.path()).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found 1 issue(s)"));
+    assert!(output.status.success());
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    assert!(output.status.success(), "Warnings don't fail by default");
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--fail-on")
+        .arg("warning")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_quiet_prints_only_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--quiet").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout, "✗ Found 1 issue(s) in 1 file(s) checked\n");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_stats_reports_counts_and_timings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("clean.js"), "const x = 1;\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--stats")
+        .arg("--quiet")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Stats:"), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("  files walked   2 ("),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("  files parsed   2\n"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("  parse time     "), "stdout: {}", stdout);
+    assert!(stdout.contains("  lint time      "), "stdout: {}", stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.split_whitespace().eq(["await-step", "1"])),
+        "stdout: {}",
+        stdout
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_ndjson_format_prints_one_diagnostic_per_line() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n    step.do('save', async () => {});\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "ndjson"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2, "stdout: {}", stdout);
+    assert!(lines.iter().all(|d| d["rule"] == "await-step"));
+    assert_eq!(lines[0]["line"], 2);
+    assert_eq!(lines[1]["line"], 3);
+    // `step.sleep('pause', '1 second')` spans columns 5 to 36 of line 2
+    assert_eq!(lines[0]["end_line"], 2);
+    assert_eq!(lines[0]["end_column"], 36);
+    assert_eq!(lines[0]["start"], 50);
+    assert_eq!(lines[0]["end"], 81);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_ndjson_format_rejects_text_only_options() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "ndjson", "--quiet"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--format ndjson cannot be used with --quiet"));
+}
+
+#[test]
+fn test_markdown_format_groups_by_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let workflow = dir.path().join("workflow.ts");
+    std::fs::write(
+        &workflow,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("clean.ts"), "const x = 1;\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "markdown"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let expected = format!(
+        "## cashmere report
+
+✗ Found 1 issue(s) in 2 file(s) checked
+
+| Rule | Errors | Warnings |
+| --- | ---: | ---: |
+| `await-step` | 1 | 0 |
+
+### `{}`
+
+- **error** `await-step` at line 2, column 5: `step.sleep` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.
+",
+        workflow.display()
+    );
+    assert_eq!(stdout, expected);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_html_format_writes_standalone_report() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    let report_path = dir.path().join("report.html");
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "html", "--output"])
+        .arg(&report_path)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("✓ Wrote html report to"),
+        "stdout: {}",
+        stdout
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let report = std::fs::read_to_string(&report_path).unwrap();
+    assert!(report.starts_with("<!DOCTYPE html>"));
+    assert!(report.contains("✗ Found 1 issue(s) in 1 file(s) checked"));
+    assert!(report.contains("<input type=\"checkbox\" data-rule=\"await-step\" checked>"));
+    assert!(report.contains("<details class=\"file\" open>"));
+    // The snippet highlights the offending line
+    assert!(report.contains(
+        "<span class=\"line hit\"><span class=\"ln\">2</span>    step.sleep(<span class=\"str\">'pause'</span>"
+    ));
+}
+
+#[test]
+fn test_output_requires_report_format() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--output", "report.txt"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--output requires"));
+}
+
+#[test]
+fn test_tap_format_has_one_test_point_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let workflow = dir.path().join("workflow.ts");
+    std::fs::write(
+        &workflow,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "tap"])
+        .arg(&workflow)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let expected = format!(
+        "TAP version 13
+1..1
+not ok 1 - {}
+  ---
+  diagnostics:
+    - message: \"`step.sleep` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.\"
+      rule: await-step
+      severity: error
+      line: 2
+      column: 5
+      endLine: 2
+      endColumn: 36
+  ...
+",
+        workflow.display()
+    );
+    assert_eq!(stdout, expected);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_stylish_format_groups_by_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let workflow = dir.path().join("workflow.ts");
+    std::fs::write(
+        &workflow,
+        "async function workflow(step: WorkflowStep) {\n    step.do('save', async () => {});\n\n\n\n\n\n\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "stylish"])
+        .arg(&workflow)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let expected = format!(
+        "{}
+  2:5  error    `step.do` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.     await-step
+  9:5  error    `step.sleep` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.  await-step
+
+Rule totals:
+  await-step  2
+
+✗ Found 2 issue(s) in 1 file(s) checked
+",
+        workflow.display()
+    );
+    assert_eq!(stdout, expected);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_columns_count_characters_not_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let workflow = dir.path().join("workflow.ts");
+    std::fs::write(
+        &workflow,
+        "async function workflow(step: WorkflowStep) {\n    const é = '→'; step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .args(["--format", "ndjson"])
+        .arg(&workflow)
+        .output()
+        .unwrap();
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim()).unwrap();
+
+    assert_eq!(diagnostic["column"], 20);
+    assert_eq!(diagnostic["end_column"], 51);
+}
+
+/// A running `cashmere --lsp` process that exchanges framed JSON-RPC messages
+struct LspSession {
+    child: std::process::Child,
+    messages: std::sync::mpsc::Receiver<serde_json::Value>,
+    /// The capabilities the server advertised in its `initialize` response
+    server_capabilities: serde_json::Value,
+}
+
+impl LspSession {
+    fn start(capabilities: serde_json::Value) -> Self {
+        Self::start_in("file:///project", capabilities)
+    }
+
+    fn start_in(root_uri: &str, capabilities: serde_json::Value) -> Self {
+        Self::start_with(serde_json::json!({ "rootUri": root_uri, "capabilities": capabilities }))
+    }
+
+    fn start_with(initialize_params: serde_json::Value) -> Self {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_cashmere"))
+            .arg("--lsp")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+        let (tx, messages) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            use std::io::{BufRead, Read};
+            loop {
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    if stdout.read_line(&mut header).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let header = header.trim();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some(length) = header.strip_prefix("Content-Length: ") {
+                        content_length = length.parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                stdout.read_exact(&mut body).unwrap();
+                if tx.send(serde_json::from_slice(&body).unwrap()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut session = Self {
+            child,
+            messages,
+            server_capabilities: serde_json::Value::Null,
+        };
+        session.server_capabilities =
+            session.request(1, "initialize", initialize_params)["result"]["capabilities"].clone();
+        session.notify("initialized", serde_json::json!({}));
+        session
+    }
+
+    fn send(&mut self, message: serde_json::Value) {
+        let body = message.to_string();
+        let stdin = self.child.stdin.as_mut().unwrap();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: serde_json::Value) {
+        self.send(serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Send a request and wait for its response
+    fn request(&mut self, id: u64, method: &str, params: serde_json::Value) -> serde_json::Value {
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }));
+        self.wait_for(|message| message["id"] == id)
+    }
+
+    /// Wait for the first message from the server matching `predicate`
+    fn wait_for(&mut self, predicate: impl Fn(&serde_json::Value) -> bool) -> serde_json::Value {
+        loop {
+            let message = self
+                .messages
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .expect("timed out waiting for the LSP server");
+            if predicate(&message) {
+                return message;
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        self.notify(
+            "textDocument/didOpen",
+            serde_json::json!({
+                "textDocument": { "uri": uri, "languageId": "typescript", "version": 1, "text": text }
+            }),
+        );
+    }
+}
+
+impl Drop for LspSession {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_lsp_code_action_inserts_await() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let diagnostic = published["params"]["diagnostics"][0].clone();
+    assert_eq!(
+        diagnostic["range"],
+        serde_json::json!({
+            "start": { "line": 1, "character": 4 },
+            "end": { "line": 1, "character": 35 }
+        })
+    );
+
+    let response = lsp.request(
+        2,
+        "textDocument/codeAction",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "range": diagnostic["range"],
+            "context": { "diagnostics": [diagnostic] }
+        }),
+    );
+    let action = &response["result"][0];
+    assert_eq!(action["title"], "Add await");
+    assert_eq!(action["kind"], "quickfix");
+    assert_eq!(
+        action["edit"]["changes"][uri],
+        serde_json::json!([{
+            "range": {
+                "start": { "line": 1, "character": 4 },
+                "end": { "line": 1, "character": 4 }
+            },
+            "newText": "await "
+        }])
+    );
+}
+
+#[test]
+fn test_lsp_code_action_titles_describe_the_fix() {
+    let uri = "file:///project/workflow.js";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        "export class MyWorkflow extends WorkflowEntrypoint {\n    async run(event, step) {\n        step.do('charge', async () => {});\n        await step.sleep('pause', '90 mins');\n    }\n}\n",
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let diagnostics = published["params"]["diagnostics"]
+        .as_array()
+        .unwrap()
+        .clone();
+    let codes: Vec<_> = diagnostics.iter().map(|d| d["code"].clone()).collect();
+    assert_eq!(
+        codes,
+        vec![
+            serde_json::json!("await-step"),
+            serde_json::json!("valid-sleep-duration")
+        ]
+    );
+
+    let mut titles = Vec::new();
+    for (id, diagnostic) in diagnostics.iter().enumerate() {
+        let response = lsp.request(
+            id as u64 + 2,
+            "textDocument/codeAction",
+            serde_json::json!({
+                "textDocument": { "uri": uri },
+                "range": diagnostic["range"],
+                "context": { "diagnostics": [diagnostic] }
+            }),
+        );
+        titles.push(response["result"][0]["title"].clone());
+    }
+    assert_eq!(
+        titles,
+        vec![
+            serde_json::json!("Add await"),
+            serde_json::json!("Change to '90 minutes'")
+        ]
+    );
+}
+
+#[test]
+fn test_lsp_pull_diagnostics_with_result_ids() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({
+        "textDocument": { "diagnostic": { "dynamicRegistration": false } }
+    }));
+    lsp.open(
+        uri,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    );
+
+    // The server may still be processing didOpen, so retry until the document is known
+    let mut id = 2;
+    let report = loop {
+        let response = lsp.request(
+            id,
+            "textDocument/diagnostic",
+            serde_json::json!({ "textDocument": { "uri": uri } }),
+        );
+        id += 1;
+        if response["result"]["resultId"].is_string() {
+            break response["result"].clone();
+        }
+        assert!(id < 50, "document was never opened");
+    };
+    assert_eq!(report["kind"], "full");
+    assert_eq!(report["items"].as_array().unwrap().len(), 1);
+    assert_eq!(report["items"][0]["code"], "await-step");
+
+    let response = lsp.request(
+        id,
+        "textDocument/diagnostic",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "previousResultId": report["resultId"]
+        }),
+    );
+    assert_eq!(
+        response["result"],
+        serde_json::json!({ "kind": "unchanged", "resultId": report["resultId"] })
+    );
+}
+
+#[test]
+fn test_lsp_pull_diagnostics_right_after_change() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({
+        "textDocument": { "diagnostic": { "dynamicRegistration": false } }
+    }));
+    lsp.open(
+        uri,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    );
+    let mut id = 2;
+    let report = loop {
+        let response = lsp.request(
+            id,
+            "textDocument/diagnostic",
+            serde_json::json!({ "textDocument": { "uri": uri } }),
+        );
+        id += 1;
+        if response["result"]["resultId"].is_string() {
+            break response["result"].clone();
+        }
+        assert!(id < 50, "document was never opened");
+    };
+    assert_eq!(report["items"].as_array().unwrap().len(), 1);
+
+    // Pull before the debounced lint of the edit has run
+    lsp.notify(
+        "textDocument/didChange",
+        serde_json::json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{
+                "text": "async function workflow(step: WorkflowStep) {\n    await step.sleep('pause', '1 second');\n}\n"
+            }]
+        }),
+    );
+    let response = lsp.request(
+        id,
+        "textDocument/diagnostic",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "previousResultId": report["resultId"]
+        }),
+    );
+    let fixed = response["result"].clone();
+    assert_eq!(fixed["kind"], "full");
+    assert_ne!(fixed["resultId"], report["resultId"]);
+    assert_eq!(fixed["items"], serde_json::json!([]));
+
+    // Once the debounced lint has run, the report is still the same
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let response = lsp.request(
+        id + 1,
+        "textDocument/diagnostic",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "previousResultId": fixed["resultId"]
+        }),
+    );
+    assert_eq!(
+        response["result"],
+        serde_json::json!({ "kind": "unchanged", "resultId": fixed["resultId"] })
+    );
+}
+
+#[test]
+fn test_lsp_hover_explains_rule() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    assert_eq!(
+        published["params"]["diagnostics"][0]["codeDescription"]["href"],
+        "https://developers.cloudflare.com/workflows/build/rules-of-workflows/"
+    );
+
+    let hover = |lsp: &mut LspSession, id, line, character| {
+        lsp.request(
+            id,
+            "textDocument/hover",
+            serde_json::json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        )["result"]
+            .clone()
+    };
+
+    let result = hover(&mut lsp, 2, 1, 10);
+    let contents = result["contents"]["value"].as_str().unwrap();
+    assert_eq!(result["contents"]["kind"], "markdown");
+    assert!(contents.contains("`await-step`"), "hover: {}", contents);
+    assert!(contents.contains("replays"), "hover: {}", contents);
+    assert!(contents.contains("https://developers.cloudflare.com/workflows/"));
+
+    // Nothing to explain outside the flagged call
+    assert!(hover(&mut lsp, 3, 0, 2).is_null());
+}
+
+#[test]
+fn test_lsp_code_lens_shows_step_inventory() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        r#"import { WorkflowEntrypoint } from 'cloudflare:workers';
+
+export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        const user = await step.do('fetch user', async () => ({}));
+        await step.sleep('cool down', '1 minute');
+        await step.do('send email', async () => {});
+        await step.waitForEvent('approval', { type: 'approved' });
+    }
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    let response = lsp.request(
+        2,
+        "textDocument/codeLens",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    let lens = &response["result"][0];
+    assert_eq!(
+        lens["range"]["start"],
+        serde_json::json!({ "line": 2, "character": 7 })
+    );
+    assert_eq!(lens["command"]["title"], "2 steps, 1 sleep, 1 wait");
+    assert_eq!(lens["command"]["command"], "cashmere.listSteps");
+
+    // The message is shown before the command responds, so don't wait for the response
+    lsp.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "cashmere.listSteps",
+            "arguments": lens["command"]["arguments"]
+        }
+    }));
+    let shown = lsp.wait_for(|message| message["method"] == "window/showMessage");
+    assert_eq!(
+        shown["params"]["message"],
+        "MyWorkflow: do \"fetch user\" (line 5), sleep \"cool down\" (line 6), do \"send email\" (line 7), waitForEvent \"approval\" (line 8)"
+    );
+}
+
+#[test]
+fn test_lsp_document_symbols_list_named_steps() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('fetch user', async () => ({}));
+        await step.sleep('cool down', '1 minute');
+    }
+}
+
+export async function helper(step: WorkflowStep) {
+    await step.do('nested', async () => {});
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    let response = lsp.request(
+        2,
+        "textDocument/documentSymbol",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    let symbols = response["result"].as_array().unwrap();
+    assert_eq!(symbols.len(), 2);
+
+    assert_eq!(symbols[0]["name"], "MyWorkflow");
+    assert_eq!(symbols[0]["kind"], 5); // Class
+    let steps: Vec<(&str, &str)> = symbols[0]["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| {
+            (
+                step["name"].as_str().unwrap(),
+                step["detail"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        [("fetch user", "step.do"), ("cool down", "step.sleep")]
+    );
+    assert_eq!(
+        symbols[0]["children"][0]["range"]["start"],
+        serde_json::json!({ "line": 2, "character": 14 })
+    );
+
+    assert_eq!(symbols[1]["name"], "helper");
+    assert_eq!(symbols[1]["kind"], 12); // Function
+    assert_eq!(symbols[1]["children"][0]["name"], "nested");
+}
+
+#[test]
+fn test_lsp_lints_workspace_in_background() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("generated")).unwrap();
+    std::fs::write(root.join("src/workflow.ts"), unawaited).unwrap();
+    std::fs::write(root.join("generated/workflow.ts"), unawaited).unwrap();
+    std::fs::write(
+        root.join("cashmere.toml"),
+        "ignore = [\"generated/**\"]\n\n[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+    let root_uri = format!("file://{}", root.display());
+
+    let mut lsp = LspSession::start_in(&root_uri, serde_json::json!({}));
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    assert_eq!(
+        published["params"]["uri"],
+        format!("{}/src/workflow.ts", root_uri)
+    );
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2); // Warning, from cashmere.toml
+}
+
+#[test]
+fn test_lsp_workspace_scan_is_pulled_by_pull_clients() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    std::fs::write(
+        root.join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    let root_uri = format!("file://{}", root.display());
+
+    let mut lsp = LspSession::start_in(
+        &root_uri,
+        serde_json::json!({
+            "textDocument": { "diagnostic": { "dynamicRegistration": false } },
+            "workspace": { "diagnostics": { "refreshSupport": true } }
+        }),
+    );
+    assert_eq!(
+        lsp.server_capabilities["diagnosticProvider"]["workspaceDiagnostics"],
+        true
+    );
+
+    // The scan asks the client to pull instead of publishing a second copy
+    let message = lsp.wait_for(|message| {
+        message["method"] == "textDocument/publishDiagnostics"
+            || message["method"] == "workspace/diagnostic/refresh"
+    });
+    assert_eq!(message["method"], "workspace/diagnostic/refresh");
+    lsp.send(serde_json::json!({ "jsonrpc": "2.0", "id": message["id"], "result": null }));
+
+    let response = lsp.request(
+        100,
+        "workspace/diagnostic",
+        serde_json::json!({ "previousResultIds": [] }),
+    );
+    let items = response["result"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["uri"], format!("{}/workflow.ts", root_uri));
+    assert_eq!(items[0]["kind"], "full");
+    assert_eq!(items[0]["items"][0]["code"], "await-step");
+
+    let response = lsp.request(
+        101,
+        "workspace/diagnostic",
+        serde_json::json!({
+            "previousResultIds": [{ "uri": items[0]["uri"], "value": items[0]["resultId"] }]
+        }),
+    );
+    assert_eq!(response["result"]["items"][0]["kind"], "unchanged");
+}
+
+#[test]
+fn test_lsp_reports_closed_files_from_disk_to_pull_clients() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    std::fs::write(root.join("workflow.ts"), unawaited).unwrap();
+    let root_uri = format!("file://{}", root.display());
+    let uri = format!("{}/workflow.ts", root_uri);
+
+    let mut lsp = LspSession::start_in(
+        &root_uri,
+        serde_json::json!({
+            "textDocument": { "diagnostic": { "dynamicRegistration": false } },
+            "workspace": { "diagnostics": { "refreshSupport": true } }
+        }),
+    );
+    let refresh = lsp.wait_for(|message| message["method"] == "workspace/diagnostic/refresh");
+    lsp.send(serde_json::json!({ "jsonrpc": "2.0", "id": refresh["id"], "result": null }));
+
+    // While open, the file is reported from its own contents instead
+    lsp.open(&uri, "async function workflow(step: WorkflowStep) {}\n");
+    let response = lsp.request(
+        100,
+        "workspace/diagnostic",
+        serde_json::json!({ "previousResultIds": [] }),
+    );
+    assert_eq!(response["result"]["items"].as_array().unwrap().len(), 0);
+
+    lsp.notify(
+        "textDocument/didClose",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    let refresh = lsp.wait_for(|message| message["method"] == "workspace/diagnostic/refresh");
+    lsp.send(serde_json::json!({ "jsonrpc": "2.0", "id": refresh["id"], "result": null }));
+    let response = lsp.request(
+        101,
+        "workspace/diagnostic",
+        serde_json::json!({ "previousResultIds": [] }),
+    );
+    let items = response["result"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["uri"], uri.as_str());
+    assert_eq!(items[0]["items"][0]["code"], "await-step");
+}
+
+#[test]
+fn test_lsp_merges_editor_settings_with_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    std::fs::write(
+        root.join("cashmere.toml"),
+        "step_methods = [\"retryDo\"]\n\n[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+    let uri = format!("file://{}/workflow.ts", root.display());
+
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        &uri,
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n    step.retryDo('charge', async () => {});\n    step.doOnce('notify', async () => {});\n}\n",
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|d| d["severity"] == 2));
+
+    lsp.notify(
+        "workspace/didChangeConfiguration",
+        serde_json::json!({
+            "settings": {
+                "cashmere": {
+                    "rules": { "await-step": "error" },
+                    "stepMethods": ["doOnce"]
+                }
+            }
+        }),
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics.iter().all(|d| d["severity"] == 1));
+    assert_eq!(diagnostics[2]["range"]["start"]["line"], 3);
+}
+
+#[test]
+fn test_lsp_related_information_for_unawaited_variable() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        "async function workflow(step: WorkflowStep) {\n    const pending = step.do('charge', async () => {});\n    step.sleep('pause', '1 second');\n}\n",
+    );
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+
+    let assigned = diagnostics
+        .iter()
+        .find(|d| d["range"]["start"]["line"] == 1)
+        .unwrap();
+    let related = assigned["relatedInformation"].as_array().unwrap();
+    assert_eq!(related.len(), 2);
+    assert_eq!(related[0]["location"]["uri"], uri);
+    assert_eq!(
+        related[0]["location"]["range"],
+        serde_json::json!({ "start": { "line": 1, "character": 10 }, "end": { "line": 1, "character": 17 } })
+    );
+    assert!(related[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("`pending`"));
+    assert_eq!(
+        related[1]["location"]["range"],
+        serde_json::json!({ "start": { "line": 3, "character": 0 }, "end": { "line": 3, "character": 1 } })
+    );
+    assert!(related[1]["message"]
+        .as_str()
+        .unwrap()
+        .contains("goes out of scope"));
+
+    // A bare call has no variable to explain
+    let bare = diagnostics
+        .iter()
+        .find(|d| d["range"]["start"]["line"] == 2)
+        .unwrap();
+    assert!(bare.get("relatedInformation").is_none());
+}
+
+#[test]
+fn test_lsp_negotiates_position_encoding() {
+    let uri = "file:///project/workflow.ts";
+    let text = "async function workflow(step: WorkflowStep) {\n    const label = \"🚀\"; step.sleep('pause', '1 second');\n}\n";
+    let diagnostic_range = |capabilities| {
+        let mut lsp = LspSession::start(capabilities);
+        lsp.open(uri, text);
+        let published =
+            lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+        (
+            lsp.server_capabilities["positionEncoding"].clone(),
+            published["params"]["diagnostics"][0]["range"].clone(),
+        )
+    };
+
+    // Clients that don't offer encodings get UTF-16, where the emoji is two code units
+    let (encoding, range) = diagnostic_range(serde_json::json!({}));
+    assert_eq!(encoding, "utf-16");
+    assert_eq!(range["start"]["character"], 24);
+    assert_eq!(range["end"]["character"], 55);
+
+    let (encoding, range) = diagnostic_range(
+        serde_json::json!({ "general": { "positionEncodings": ["utf-8", "utf-16"] } }),
+    );
+    assert_eq!(encoding, "utf-8");
+    assert_eq!(range["start"]["character"], 26);
+
+    let (encoding, range) =
+        diagnostic_range(serde_json::json!({ "general": { "positionEncodings": ["utf-32"] } }));
+    assert_eq!(encoding, "utf-32");
+    assert_eq!(range["start"]["character"], 23);
+}
+
+#[test]
+fn test_lsp_debounces_rapid_changes() {
+    let uri = "file:///project/workflow.ts";
+    let unawaited = |count: usize| {
+        format!(
+            "async function workflow(step: WorkflowStep) {{\n{}}}\n",
+            (1..=count)
+                .map(|i| format!("    step.sleep('pause-{}', '1 second');\n", i))
+                .collect::<String>()
+        )
+    };
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(uri, &unawaited(1));
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    for version in 2..=4 {
+        lsp.notify(
+            "textDocument/didChange",
+            serde_json::json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": [{ "text": unawaited(version) }]
+            }),
+        );
+    }
+
+    // Only the last edit is linted
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    assert_eq!(
+        published["params"]["diagnostics"].as_array().unwrap().len(),
+        4
+    );
+    assert!(lsp
+        .messages
+        .recv_timeout(std::time::Duration::from_millis(400))
+        .is_err());
+}
+
+#[test]
+fn test_lsp_answers_requests_while_edits_are_debounced() {
+    let uri = "file:///project/workflow.ts";
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(uri, unawaited);
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    lsp.notify(
+        "workspace/didChangeConfiguration",
+        serde_json::json!({ "settings": { "cashmere": { "debounceMs": 2000 } } }),
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    // More edits than the server handles messages at once
+    for version in 2..=8 {
+        lsp.notify(
+            "textDocument/didChange",
+            serde_json::json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": [{ "text": unawaited }]
+            }),
+        );
+    }
+    lsp.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/hover",
+        "params": {
+            "textDocument": { "uri": uri },
+            "position": { "line": 1, "character": 10 }
+        },
+    }));
+
+    // The hover is answered before the debounced lint publishes anything
+    let started = std::time::Instant::now();
+    let response = lsp
+        .messages
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(response["id"], 2);
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    let published = lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    assert_eq!(
+        published["params"]["diagnostics"].as_array().unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn test_lsp_relints_when_config_file_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/workflow.ts"), unawaited).unwrap();
+    let root_uri = format!("file://{}", root.display());
+    let saved_uri = format!("{}/src/workflow.ts", root_uri);
+    let open_uri = format!("{}/src/open.ts", root_uri);
+
+    let mut lsp = LspSession::start_in(
+        &root_uri,
+        serde_json::json!({ "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } } }),
+    );
+    let registration = lsp.wait_for(|message| message["method"] == "client/registerCapability");
+    let registration = &registration["params"]["registrations"][0];
+    assert_eq!(registration["method"], "workspace/didChangeWatchedFiles");
+    assert_eq!(
+        registration["registerOptions"]["watchers"][0]["globPattern"],
+        "**/cashmere.toml"
+    );
+
+    lsp.open(&open_uri, unawaited);
+    let published = lsp.wait_for(|message| message["params"]["uri"] == open_uri.as_str());
+    assert_eq!(published["params"]["diagnostics"][0]["severity"], 1);
+
+    std::fs::write(
+        root.join("cashmere.toml"),
+        "[rules]\nawait-step = \"warn\"\n",
+    )
+    .unwrap();
+    lsp.notify(
+        "workspace/didChangeWatchedFiles",
+        serde_json::json!({
+            "changes": [{ "uri": format!("{}/cashmere.toml", root_uri), "type": 1 }]
+        }),
+    );
+
+    // Both the open document and the rest of the workspace pick up the new rule level
+    let published = lsp.wait_for(|message| message["params"]["uri"] == open_uri.as_str());
+    assert_eq!(published["params"]["diagnostics"][0]["severity"], 2);
+    lsp.wait_for(|message| {
+        message["params"]["uri"] == saved_uri.as_str()
+            && message["params"]["diagnostics"][0]["severity"] == 2
+    });
+}
+
+#[test]
+fn test_lsp_resolves_config_per_workspace_folder() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let unawaited =
+        "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n";
+    for package in ["a", "b", "c"] {
+        std::fs::create_dir_all(root.join("packages").join(package)).unwrap();
+        std::fs::write(
+            root.join("packages").join(package).join("workflow.ts"),
+            unawaited,
+        )
+        .unwrap();
+    }
+    for package in ["a", "c"] {
+        std::fs::write(
+            root.join("packages").join(package).join("cashmere.toml"),
+            "[rules]\nawait-step = \"warn\"\n",
+        )
+        .unwrap();
+    }
+    let root_uri = format!("file://{}", root.display());
+    let folder =
+        |path: &str| serde_json::json!({ "uri": format!("{}{}", root_uri, path), "name": path });
+    let file_uri = |package: &str| format!("{}/packages/{}/workflow.ts", root_uri, package);
+    let severity =
+        |message: &serde_json::Value| message["params"]["diagnostics"][0]["severity"].clone();
+
+    let mut lsp = LspSession::start_with(serde_json::json!({
+        "capabilities": {},
+        "workspaceFolders": [folder(""), folder("/packages/a")]
+    }));
+    assert_eq!(
+        lsp.server_capabilities["workspace"]["workspaceFolders"]["changeNotifications"],
+        true
+    );
+
+    // packages/a is its own folder, so it uses its own config rather than the outer folder's
+    let published = lsp.wait_for(|message| message["params"]["uri"] == file_uri("b").as_str());
+    assert_eq!(severity(&published), 1);
+    let published = lsp.wait_for(|message| message["params"]["uri"] == file_uri("a").as_str());
+    assert_eq!(severity(&published), 2);
+
+    lsp.notify(
+        "workspace/didChangeWorkspaceFolders",
+        serde_json::json!({
+            "event": { "added": [folder("/packages/c")], "removed": [folder("/packages/a")] }
+        }),
+    );
+    let (mut a_relinted, mut c_linted) = (false, false);
+    while !(a_relinted && c_linted) {
+        let published =
+            lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+        let uri = published["params"]["uri"].as_str().unwrap();
+        if uri == file_uri("a") && severity(&published) == 1 {
+            a_relinted = true;
+        }
+        if uri == file_uri("c") && severity(&published) == 2 {
+            c_linted = true;
+        }
+    }
+}
+
+#[test]
+fn test_lsp_semantic_tokens_mark_workflow_steps() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    assert_eq!(
+        lsp.server_capabilities["semanticTokensProvider"]["legend"]["tokenTypes"],
+        serde_json::json!(["workflowStep"])
+    );
+    lsp.open(
+        uri,
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('fetch', async () => {});
+        await step.sleep('pause', '1 second');
+    }
+}
+
+async function helper(step) {
+    await step.do('not a workflow', async () => {});
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    let response = lsp.request(
+        2,
+        "textDocument/semanticTokens/full",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    // `do` on line 2 and `sleep` on line 3; the call in `helper` isn't in a workflow
+    assert_eq!(
+        response["result"]["data"],
+        serde_json::json!([2, 19, 2, 0, 0, 1, 19, 5, 0, 0])
+    );
+}
+
+#[test]
+fn test_lsp_renames_step_names_within_workflow() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('send-email', async () => {});
+        await step.sleep('pause', '1 second');
+        await step.do('send-email', async () => {});
+    }
+}
+
+export class OtherWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('send-email', async () => {});
+    }
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let position = |line, character| serde_json::json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+
+    let prepared = lsp.request(2, "textDocument/prepareRename", position(2, 25));
+    assert_eq!(
+        prepared["result"],
+        serde_json::json!({
+            "range": { "start": { "line": 2, "character": 23 }, "end": { "line": 2, "character": 33 } },
+            "placeholder": "send-email"
+        })
+    );
+    // Only step name literals can be renamed
+    let prepared = lsp.request(3, "textDocument/prepareRename", position(2, 10));
+    assert!(prepared["result"].is_null());
+
+    let mut params = position(4, 30);
+    params["newName"] = serde_json::json!("send-welcome-email");
+    lsp.send(serde_json::json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/rename", "params": params }));
+    // The warning and the response can arrive in either order
+    let (mut warning, mut renamed) = (None, None);
+    while warning.is_none() || renamed.is_none() {
+        let message =
+            lsp.wait_for(|message| message["method"] == "window/showMessage" || message["id"] == 4);
+        if message["id"] == 4 {
+            renamed = Some(message);
+        } else {
+            warning = Some(message);
+        }
+    }
+    assert!(warning.unwrap()["params"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("replay identity"));
+    let renamed = renamed.unwrap();
+    let edits = renamed["result"]["changes"][uri].as_array().unwrap();
+    let lines: Vec<_> = edits
+        .iter()
+        .map(|edit| edit["range"]["start"]["line"].clone())
+        .collect();
+    // The step with the same name in the other workflow is left alone
+    assert_eq!(lines, [2, 4]);
+    assert!(edits
+        .iter()
+        .all(|edit| edit["newText"] == "send-welcome-email"));
+}
+
+#[test]
+fn test_lsp_completes_step_methods_and_durations() {
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    let mut complete = |id, name: &str, text: &str, line, character| {
+        let uri = format!("file:///project/{}.ts", name);
+        lsp.open(&uri, text);
+        lsp.request(
+            id,
+            "textDocument/completion",
+            serde_json::json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        )["result"]
+            .clone()
+    };
+    let labels = |result: &serde_json::Value| -> Vec<String> {
+        result
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let methods = complete(
+        2,
+        "methods",
+        "export class MyWorkflow extends WorkflowEntrypoint {\n    async run(event, step) {\n        await step.\n    }\n}\n",
+        2,
+        19,
+    );
+    assert_eq!(
+        labels(&methods),
+        ["do", "sleep", "sleepUntil", "waitForEvent"]
+    );
+
+    // Only the workflow's step parameter gets step methods
+    let lookalike = complete(
+        3,
+        "lookalike",
+        "export class MyWorkflow extends WorkflowEntrypoint {\n    async run(event, step) {\n        await otherStep.\n    }\n}\n",
+        2,
+        24,
+    );
+    assert!(lookalike.is_null());
+
+    let durations = complete(
+        4,
+        "durations",
+        "export class MyWorkflow extends WorkflowEntrypoint {\n    async run(event, step) {\n        await step.sleep('pause', '1 mi');\n    }\n}\n",
+        2,
+        39,
+    );
+    assert!(labels(&durations).contains(&"1 minute".to_string()));
+    assert_eq!(
+        durations[0]["textEdit"]["range"],
+        serde_json::json!({ "start": { "line": 2, "character": 35 }, "end": { "line": 2, "character": 39 } })
+    );
+}
+
+#[test]
+fn test_lsp_inlay_hints_show_default_retry_config() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('plain', async () => {});
+        await step.do('partial', { retries: { limit: 3 }, timeout: '1 minute' }, async () => {});
+        await step.do('configured', config, async () => {});
+        await step.sleep('pause', '1 second');
+    }
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+
+    let response = lsp.request(
+        2,
+        "textDocument/inlayHint",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 8, "character": 0 } }
+        }),
+    );
+    let hints = response["result"].as_array().unwrap();
+    assert_eq!(hints.len(), 2);
+    assert_eq!(
+        hints[0]["position"],
+        serde_json::json!({ "line": 2, "character": 29 })
+    );
+    assert_eq!(
+        hints[0]["label"],
+        "retries: 5, delay: 10 seconds, backoff: exponential, timeout: 10 minutes"
+    );
+    // Only the settings the config object leaves out
+    assert_eq!(hints[1]["position"]["line"], 3);
+    assert_eq!(hints[1]["label"], "delay: 10 seconds, backoff: exponential");
+}
+
+#[test]
+fn test_lsp_exports_workflow_graph() {
+    let uri = "file:///project/workflow.ts";
+    let mut lsp = LspSession::start(serde_json::json!({}));
+    lsp.open(
+        uri,
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        await step.do('fetch "user"', async () => ({}));
+        await step.sleep('cool down', '1 minute');
+        await step.waitForEvent('approval', { type: 'approved' });
+    }
+}
+"#,
+    );
+    lsp.wait_for(|message| message["method"] == "textDocument/publishDiagnostics");
+    let export = |lsp: &mut LspSession, id, format| {
+        lsp.request(
+            id,
+            "workspace/executeCommand",
+            serde_json::json!({
+                "command": "cashmere.exportGraph",
+                "arguments": [{ "uri": uri, "format": format }]
+            }),
+        )
+    };
+
+    assert_eq!(
+        export(&mut lsp, 2, "mermaid")["result"],
+        r#"flowchart TD
+    subgraph w0["MyWorkflow"]
+        w0s0["do: fetch #quot;user#quot;"]
+        w0s1(["sleep: cool down"])
+        w0s2{{"waitForEvent: approval"}}
+        w0s0 --> w0s1
+        w0s1 --> w0s2
+    end
+"#
+    );
+    assert_eq!(
+        export(&mut lsp, 3, "dot")["result"],
+        r#"digraph workflows {
+    subgraph cluster_0 {
+        label="MyWorkflow";
+        w0s0 [label="do: fetch \"user\"", shape=box];
+        w0s1 [label="sleep: cool down", shape=ellipse];
+        w0s2 [label="waitForEvent: approval", shape=hexagon];
+        w0s0 -> w0s1;
+        w0s1 -> w0s2;
+    }
+}
+"#
+    );
+    assert!(export(&mut lsp, 4, "svg")["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("Unknown graph format"));
+}
+
+#[test]
+fn test_lsp_reports_and_cancels_workspace_scan_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    for i in 0..20 {
+        std::fs::write(
+            root.join(format!("workflow{}.ts", i)),
+            "async function workflow(step: WorkflowStep) {\n    step.sleep('pause', '1 second');\n}\n",
+        )
+        .unwrap();
+    }
+    let root_uri = format!("file://{}", root.display());
+    let scan = |cancel: bool| {
+        let mut lsp = LspSession::start_in(
+            &root_uri,
+            serde_json::json!({ "window": { "workDoneProgress": true } }),
+        );
+        let create = lsp.wait_for(|message| message["method"] == "window/workDoneProgress/create");
+        let token = create["params"]["token"].clone();
+        if cancel {
+            lsp.notify(
+                "window/workDoneProgress/cancel",
+                serde_json::json!({ "token": token }),
+            );
+        }
+        lsp.send(serde_json::json!({ "jsonrpc": "2.0", "id": create["id"], "result": null }));
+
+        let begin = lsp.wait_for(|message| message["method"] == "$/progress");
+        assert_eq!(begin["params"]["token"], token);
+        assert_eq!(begin["params"]["value"]["kind"], "begin");
+        assert_eq!(begin["params"]["value"]["cancellable"], true);
+        let end = lsp.wait_for(|message| {
+            message["method"] == "$/progress" && message["params"]["value"]["kind"] == "end"
+        });
+        end["params"]["value"]["message"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(scan(false), "Linted 20 files");
+    assert!(scan(true).starts_with("Cancelled after linting"));
+}
+
+#[test]
+fn test_mcp_server_lints_text_and_explains_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("workflow.ts");
+    std::fs::write(
+        &file,
+        "async function workflow(step: WorkflowStep) {\n    await step.sleep('pause', '1 second');\n}\n",
+    )
+    .unwrap();
+    let requests = [
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05", "capabilities": {}}}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "lint_text", "arguments": {"source": "async function workflow(step: WorkflowStep) {\n    step.do('a', async () => 1);\n}\n"}}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "lint_file", "arguments": {"path": file.to_str().unwrap()}}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {"name": "explain_rule", "arguments": {"rule": "await-step"}}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 6, "method": "tools/call", "params": {"name": "lint_file", "arguments": {}}}),
+    ];
+    let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("mcp").write_stdin(input).output().unwrap();
+    assert!(output.status.success());
+    let responses: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    // The notification gets no response
+    assert_eq!(responses.len(), 6);
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "cashmere");
+
+    let tools: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, vec!["lint_file", "lint_text", "explain_rule"]);
+
+    let linted = &responses[2]["result"]["structuredContent"];
+    assert_eq!(linted["diagnostics"][0]["rule"], "await-step");
+    assert_eq!(linted["diagnostics"][0]["line"], 2);
+
+    let clean = &responses[3]["result"];
+    assert_eq!(
+        clean["structuredContent"]["diagnostics"],
+        serde_json::json!([])
+    );
+    assert!(clean["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("No problems found"));
+
+    let explanation = responses[4]["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap();
+    assert!(explanation.starts_with("await-step (error, fixable)"));
+    assert!(explanation.contains("replays the `run` method"));
+
+    assert_eq!(responses[5]["error"]["code"], -32602);
+}
+
+/// Send a raw HTTP request and return the status code and JSON body of the response
+fn http_request(addr: &str, request: &str) -> (u16, serde_json::Value) {
+    use std::io::Read;
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn test_serve_lints_posted_source_and_lists_rules() {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_cashmere"))
+        .args(["serve", "--port", "0"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = std::io::BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    std::io::BufRead::read_line(&mut stderr, &mut line).unwrap();
+    let addr = line
+        .trim()
+        .strip_prefix("Listening on http://")
+        .unwrap()
+        .to_string();
+
+    let body = serde_json::json!({
+        "source": "async function workflow(step: WorkflowStep) {\n    step.do('a', async () => 1);\n}\n",
+        "filename": "src/workflow.ts",
+    })
+    .to_string();
+    let post = format!(
+        "POST /lint HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    for _ in 0..2 {
+        // The second request is answered from the result cache
+        let (status, result) = http_request(&addr, &post);
+        assert_eq!(status, 200);
+        assert_eq!(result["file"], "src/workflow.ts");
+        assert_eq!(result["diagnostics"][0]["rule"], "await-step");
+        assert_eq!(result["diagnostics"][0]["line"], 2);
+    }
+
+    let (status, rules) = http_request(&addr, "GET /rules HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert_eq!(status, 200);
+    assert_eq!(rules[0]["id"], "await-step");
+    assert_eq!(rules[0]["fixable"], true);
+
+    let (status, error) = http_request(
+        &addr,
+        "POST /lint HTTP/1.1\r\nHost: localhost\r\nContent-Length: 3\r\n\r\n{x}",
+    );
+    assert_eq!(status, 400);
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid request body"));
+
+    let (status, _) = http_request(&addr, "GET /lint HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert_eq!(status, 405);
+    let (status, _) = http_request(&addr, "GET /nope HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert_eq!(status, 404);
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[test]
+fn test_eslint_json_format_matches_eslint_schema() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "async function workflow(step: WorkflowStep) {\n    const café = 1;\n    step.do('a', async () => café);\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("clean.ts"), "export const x = 1;\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg(dir.path())
+        .args(["--format", "eslint-json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let clean = results
+        .iter()
+        .find(|r| r["filePath"].as_str().unwrap().ends_with("clean.ts"))
+        .unwrap();
+    assert_eq!(clean["messages"], serde_json::json!([]));
+    assert_eq!(clean["errorCount"], 0);
+
+    let workflow = results
+        .iter()
+        .find(|r| r["filePath"].as_str().unwrap().ends_with("workflow.ts"))
+        .unwrap();
+    assert!(std::path::Path::new(workflow["filePath"].as_str().unwrap()).is_absolute());
+    assert_eq!(workflow["errorCount"], 1);
+    assert_eq!(workflow["warningCount"], 0);
+    assert_eq!(workflow["fixableErrorCount"], 1);
+    let message = &workflow["messages"][0];
+    assert_eq!(message["ruleId"], "await-step");
+    assert_eq!(message["severity"], 2);
+    assert_eq!(message["line"], 3);
+    assert_eq!(message["column"], 5);
+    // The range counts UTF-16 code units, so `é` counts once
+    let offset = "async function workflow(step: WorkflowStep) {\n    const café = 1;\n    "
+        .encode_utf16()
+        .count();
+    assert_eq!(message["fix"]["range"], serde_json::json!([offset, offset]));
+    assert_eq!(message["fix"]["text"], "await ");
+}
+
+#[test]
+fn test_banned_calls_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        r#"
+[[banned_calls]]
+call = "console.log"
+message = "Log with the workflow logger, console.log runs again on every retry"
+severity = "warn"
+within = "step"
+
+[[banned_calls]]
+call = "legacyHttpClient.get"
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        r#"export class MyWorkflow extends WorkflowEntrypoint {
+    async run(event, step) {
+        console.log('starting');
+        await step.do('fetch', async () => {
+            console.log('fetching');
+            return legacyHttpClient.get('/users');
+        });
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(":5:13 - warning: Log with the workflow logger, console.log runs again on every retry [banned-call]"),
+        "Expected console.log inside the step to be reported as a warning\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(":6:20 - `legacyHttpClient.get` must not be called [banned-call]"),
+        "Expected the banned client call to be reported\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        !stdout.contains(":3:9"),
+        "Expected console.log outside steps to be allowed\nActual output:\n{}",
+        stdout
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    // The rule level overrides every ban's severity
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .arg("--rule")
+        .arg("await-step")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("banned-call"),
+        "Expected --rule to turn banned calls off\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_invalid_banned_call_is_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[[banned_calls]]\ncall = \"console.log()\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("workflow.ts"), "console.log('hi');\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Invalid banned call \"console.log()\""),
+        "Expected an invalid banned call error\nActual output:\n{}",
+        stderr
+    );
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn test_plugin_rules_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("plugins")).unwrap();
+    std::fs::copy(
+        "tests/fixtures/plugins/flag-first-statement.wat",
+        dir.path().join("plugins/flag-first-statement.wat"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "plugins = [\"plugins/flag-first-statement.wat\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "import { legacy } from 'legacy';\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .contains(":1:1 - warning: Statement flagged by the org plugin [org/first-statement]"),
+        "Expected the plugin's diagnostic\nActual output:\n{}",
+        stdout
+    );
+    assert!(output.status.success());
+
+    // Plugin rules take levels like built-in rules
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "plugins = [\"plugins/flag-first-statement.wat\"]\n\n[rules]\n\"org/first-statement\" = \"error\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(":1:1 - Statement flagged by the org plugin [org/first-statement]"),
+        "Expected the plugin's diagnostic as an error\nActual output:\n{}",
+        stdout
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_missing_plugin_is_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "plugins = [\"plugins/missing.wasm\"]\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("workflow.ts"), "export {};\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    let expected = if cfg!(feature = "plugins") {
+        "Failed to load plugin"
+    } else {
+        "built without the `plugins` feature"
+    };
+    assert!(
+        stderr.contains(expected),
+        "Expected a plugin error\nActual output:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_github_summary_format_annotates_and_writes_job_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.ts"),
+        "async function run(step: WorkflowStep) {\n    step.sleep('a', '1 second');\n    step.sleep('b', '1 second');\n}\n",
+    )
+    .unwrap();
+    let summary_path = dir.path().join("summary.md");
+    std::fs::write(&summary_path, "Earlier step output\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd
+        .current_dir(dir.path())
+        .env("GITHUB_STEP_SUMMARY", &summary_path)
+        .arg("--format")
+        .arg("github-summary")
+        .arg("--max-annotations")
+        .arg("1")
+        .arg(".")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("::group::a.ts (2 issue(s))\n::error file=a.ts,line=2,col=5,endLine=2,endColumn=32,title=cashmere (await-step)::`step.sleep` must be awaited."),
+        "Expected a grouped annotation with a relative path\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("a.ts:3:5 - error: `step.sleep` must be awaited."),
+        "Expected the issue over the budget to be logged without an annotation\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("::notice title=cashmere::1 more issue(s) were not annotated (limit 1)"),
+        "Expected a notice about the annotation budget\nActual output:\n{}",
+        stdout
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let summary = std::fs::read_to_string(&summary_path).unwrap();
+    assert!(
+        summary.starts_with("Earlier step output\n## cashmere report"),
+        "Expected the job summary to be appended\nActual summary:\n{}",
+        summary
+    );
+    assert!(summary.contains("| `await-step` | 2 | 0 |"));
+}
+
+#[test]
+fn test_entrypoints_lints_modules_reachable_from_wrangler_main() {
+    let dir = tempfile::tempdir().unwrap();
+    let unawaited =
+        "export async function run(step: WorkflowStep) {\n    step.sleep('nap', '1 second');\n}\n";
+    std::fs::create_dir_all(dir.path().join("src/workflows/billing")).unwrap();
+    std::fs::write(
+        dir.path().join("wrangler.toml"),
+        "name = \"app\"\nmain = \"src/index.ts\"\n\n[[workflows]]\nname = \"billing\"\nbinding = \"BILLING\"\nclass_name = \"Billing\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("src/index.ts"),
+        "import type { Env } from './env';\nexport { Billing } from './workflows/billing';\nconst lazy = () => import('./lazy.js');\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("src/env.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("src/workflows/billing/index.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("src/lazy.ts"), unawaited).unwrap();
+    std::fs::write(dir.path().join("src/unused.ts"), unawaited).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--entrypoints").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Found 2 issue(s) in 3 file(s) checked"),
+        "Expected the entry, the re-exported workflow and the dynamic import to be linted\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("billing/index.ts:2:5"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("lazy.ts:2:5"), "stdout: {}", stdout);
+    assert!(
+        !stdout.contains("unused.ts") && !stdout.contains("env.ts"),
+        "Expected unreachable and type-only imports to be skipped\nActual output:\n{}",
+        stdout
+    );
+
+    // wrangler.jsonc takes precedence and may have comments and trailing commas
+    std::fs::write(
+        dir.path().join("wrangler.jsonc"),
+        "{\n  // The unused module is the entry here\n  \"main\": \"src/unused.ts\", /* no bindings */\n  \"workflows\": [],\n}\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--entrypoints").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("unused.ts:2:5") && stdout.contains("in 1 file(s) checked"),
+        "Expected only the jsonc entry to be linted\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_entrypoints_without_wrangler_config_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("index.ts"), "export {};\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg("--entrypoints").arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("No wrangler.json, wrangler.jsonc or wrangler.toml found"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_wrangler_binding_checks_classes_against_bindings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(
+        dir.path().join("wrangler.toml"),
+        "name = \"app\"\nmain = \"src/index.ts\"\n\n[[workflows]]\nname = \"billing\"\nbinding = \"BILLING\"\nclass_name = \"Billing\"\n\n[[workflows]]\nname = \"refunds\"\nbinding = \"REFUNDS\"\nclass_name = \"Refunds\"\n\n[[workflows]]\nname = \"shared\"\nbinding = \"SHARED\"\nclass_name = \"Shared\"\nscript_name = \"other-worker\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("src/index.ts"),
+        "export { Billing } from './billing';\nclass Signup extends WorkflowEntrypoint {}\nexport { Signup };\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("src/billing.ts"),
+        "export class Billing extends WorkflowEntrypoint {}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("index.ts:2:7")
+            && stdout.contains(
+                "Workflow class `Signup` has no `[[workflows]]` binding in wrangler.toml"
+            ),
+        "Expected the unbound class to be reported\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("wrangler.toml:12:14")
+            && stdout.contains(
+                "Binding `REFUNDS` uses class_name `Refunds`, but the Worker exports no such class"
+            ),
+        "Expected the binding without a class to be reported\nActual output:\n{}",
+        stdout
+    );
+    assert!(
+        !stdout.contains("Billing") && !stdout.contains("Shared"),
+        "Expected bound classes and bindings to other Workers to pass\nActual output:\n{}",
+        stdout
+    );
+    assert!(!output.status.success());
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nwrangler-binding = \"off\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("wrangler-binding") && output.status.success(),
+        "Expected the rule to be turned off\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_rule_options_allow_step_name_values() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "export async function run(event, step: WorkflowStep) {\n    const batch = await step.do('batch', async () => ({ id: 1 }));\n    await step.do(`process-${batch.id}`, async () => 1);\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:3:30") && stdout.contains("[no-dynamic-step-name]"),
+        "Expected the interpolated value to be reported\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.no-dynamic-step-name]\nallow = [\"batch\"]\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("no-dynamic-step-name"),
+        "Expected `batch` to be allowed by the rule options\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.no-dynamic-step-name]\nallow = [\"batch[0]\"]\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Invalid allowed step name value \"batch[0]\""),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_rule_options_max_steps_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "export async function run(event, step: WorkflowStep) {\n    for (let i = 0; i < 20; i++) {\n        await step.do(`page-${i}`, async () => i);\n    }\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("max-steps"),
+        "Expected 20 steps to be under the default limit\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.max-steps]\nlimit = 10\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:3:15") && stdout.contains("more than the limit of 10 steps"),
+        "Expected the configured limit to be used\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.max-steps]\nlimit = 0\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Invalid max-steps limit 0"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_step_name_convention_is_opt_in() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "export async function run(event, step: WorkflowStep) {\n    await step.do(\"Fetch User\", async () => 1);\n    await step.do(\"load_orders\", async () => 2);\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("step-name-convention"),
+        "Expected the rule to be off by default\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nstep-name-convention = \"warn\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:2:19") && stdout.contains("workflow.ts:3:19"),
+        "Expected both names to be reported as not kebab-case\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rules]\nstep-name-convention = \"warn\"\n\n[rule_options.step-name-convention]\npattern = \"^[a-z]+(_[a-z]+)*$\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:2:19") && !stdout.contains("workflow.ts:3:19"),
+        "Expected the configured pattern to be used\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.step-name-convention]\npattern = \"^[a-z\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Invalid step-name-convention pattern"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_rule_options_wait_for_event_min_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "export async function run(event, step: WorkflowStep) {\n    await step.waitForEvent(\"approval\", { type: \"approved\", timeout: \"30 seconds\" });\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No issues found"),
+        "Expected 30 seconds to be over the default minimum\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.valid-wait-for-event]\nmin_timeout = \"1 minute\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:2:70")
+            && stdout.contains("shorter than the minimum of 1 minute"),
+        "Expected the configured minimum to be used\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.valid-wait-for-event]\nmin_timeout = \"1 min\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Invalid valid-wait-for-event min_timeout \"1 min\""),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_rule_options_await_step_escapes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("workflow.ts"),
+        "export async function run(event, step: WorkflowStep) {\n    const p = step.do(\"charge\", async () => charge());\n    await track(p);\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("workflow.ts:2:15") && stdout.contains("`p` is passed to `track`"),
+        "Expected the escaped promise to be reported by default\nActual output:\n{}",
+        stdout
+    );
+
+    std::fs::write(
+        dir.path().join("cashmere.toml"),
+        "[rule_options.await-step]\nescapes = \"handled\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No issues found"),
+        "Expected escapes = \"handled\" to count the promise as awaited\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_unique_workflow_classes_across_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("billing")).unwrap();
+    std::fs::create_dir_all(dir.path().join("legacy")).unwrap();
+    std::fs::write(
+        dir.path().join("billing/workflow.ts"),
+        "export class Billing extends WorkflowEntrypoint {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("legacy/workflow.ts"),
+        "// Kept until the migration is done\nclass Billing extends WorkflowEntrypoint {}\nexport { Billing };\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("legacy/index.ts"),
+        "export { Billing } from './workflow';\nexport class Refunds extends WorkflowEntrypoint {}\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("billing/workflow.ts:1:14")
+            && stdout.contains("legacy/workflow.ts:2:7")
+            && stdout.contains("Workflow class `Billing` is also exported by")
+            && stdout.contains("Found 2 issue(s)"),
+        "Expected both copies of the class to be reported, and not the re-export\nActual output:\n{}",
+        stdout
+    );
+
+    let mut cmd = cargo_bin_cmd!("cashmere");
+    let output = cmd.arg(dir.path().join("billing")).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No issues found"),
+        "Expected files outside the linted path to be left out\nActual output:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_unique_workflow_classes_from_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["billing.ts", "legacy.ts"] {
+        std::fs::write(
+            dir.path().join(name),
+            "export class Billing extends WorkflowEntrypoint {}\n",
+        )
+        .unwrap();
+    }
+
+    let run = || {
+        let mut cmd = cargo_bin_cmd!("cashmere");
+        let output = cmd
+            .current_dir(dir.path())
+            .arg("--cache")
+            .arg(".")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let first = run();
+    assert!(
+        first.contains("Found 2 issue(s)"),
+        "Actual output:\n{}",
+        first
+    );
+    let cache = std::fs::read_to_string(dir.path().join(".cashmere-cache")).unwrap();
+    assert!(cache.contains("Billing"));
+
+    // Both files are unchanged, so their classes come from the cache
+    let second = run();
+    assert!(
+        second.contains("billing.ts:1:14")
+            && second.contains("legacy.ts:1:14")
+            && second.contains("Found 2 issue(s)"),
+        "Expected cached workflow classes to be checked\nActual output:\n{}",
+        second
+    );
+}
@@ -0,0 +1,7 @@
+export async function notify(step: WorkflowStep) {
+  const s = step;
+  let current;
+  current = s;
+  s.do('send email', async () => true);
+  current.sleep('cool down', '1 minute');
+}
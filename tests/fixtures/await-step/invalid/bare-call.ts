@@ -0,0 +1,3 @@
+export async function notify(step: WorkflowStep) {
+  step.do('send email', async () => true);
+}
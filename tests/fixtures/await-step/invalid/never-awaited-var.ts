@@ -0,0 +1,3 @@
+export async function later(step: WorkflowStep) {
+  const pending = step.sleep('wait', '1 hour');
+}
@@ -0,0 +1,7 @@
+export async function sendAll(step: WorkflowStep, users: string[]) {
+  const pending = [];
+  for (const user of users) {
+    pending.push(step.do(`send ${user}`, async () => send(user)));
+  }
+  console.log(`queued ${pending.length}`);
+}
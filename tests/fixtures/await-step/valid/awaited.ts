@@ -0,0 +1,6 @@
+export class Checkout extends WorkflowEntrypoint {
+  async run(event, step) {
+    await step.do('charge', async () => ({ ok: true }));
+    await step.sleep('cool down', '1 minute');
+  }
+}
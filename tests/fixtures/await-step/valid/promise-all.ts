@@ -0,0 +1,5 @@
+export async function fanOut(step: WorkflowStep) {
+  const a = step.do('a', async () => 1);
+  const b = step.do('b', async () => 2);
+  await Promise.all([a, b]);
+}
@@ -0,0 +1,23 @@
+export async function sendAll(step: WorkflowStep, users: string[]) {
+  const pending = [];
+  for (const user of users) {
+    pending.push(step.do(`send ${user}`, async () => send(user)));
+  }
+  await Promise.all(pending);
+
+  const byRegion = {
+    eu: step.do('sync eu', async () => sync('eu')),
+    us: step.do('sync us', async () => sync('us')),
+  };
+  await Promise.allSettled(Object.values(byRegion));
+
+  const retries = [step.do('retry a', async () => retry('a'))];
+  retries[1] = step.do('retry b', async () => retry('b'));
+  await Promise.all([...retries]);
+
+  const byStage = { fetch: step.do('fetch', async () => load()) };
+  const loaded = await byStage.fetch;
+  const parsed = [];
+  parsed[0] = step.do('parse', async () => parse(loaded));
+  await Promise.race([parsed[0], timeout()]);
+}
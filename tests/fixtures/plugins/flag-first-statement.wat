@@ -0,0 +1,22 @@
+;; A minimal cashmere plugin for tests. `cashmere_check` ignores its input and
+;; always reports the first six bytes of the file.
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 4096))
+  (data (i32.const 16) "{\"api_version\":1,\"rules\":[{\"id\":\"org/first-statement\",\"description\":\"Reports the first statement of every file\",\"default_severity\":\"warning\",\"docs_url\":\"https://example.com/org/first-statement\"}]}")
+  (data (i32.const 1024) "[{\"start\":0,\"end\":6,\"message\":\"Statement flagged by the org plugin\"}]")
+
+  (func (export "cashmere_alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $len)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow (i32.add (i32.shr_u (local.get $len) (i32.const 16)) (i32.const 1))))))
+    (local.get $ptr))
+
+  (func (export "cashmere_rules") (result i64)
+    (i64.const 68719476932))
+
+  (func (export "cashmere_check") (param $ptr i32) (param $len i32) (result i64)
+    (i64.const 4398046511173)))
@@ -0,0 +1,302 @@
+use cashmere::linter::{lint_source_timed, Severity, StepCall};
+use cashmere::rules::{
+    FunctionScope, Rule, RuleContext, RuleMeta, RuleRegistry, StepUsage, AWAIT_STEP,
+};
+use cashmere::stats::Stats;
+use cashmere::visit::{visit_source, VisitContext, Visitor};
+use cashmere::{collect_files, lint_file, lint_source, lint_source_structured, Config, LintResult};
+use oxc_ast::ast::CallExpression;
+
+const UNAWAITED: &str = r#"
+export class MyWorkflow {
+    async run(event, step) {
+        step.do('send-email', async () => {
+            return { sent: true };
+        });
+    }
+}
+"#;
+
+#[test]
+fn test_library_lints_source() {
+    let diagnostics = lint_source(UNAWAITED, "workflow.ts", &[]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "await-step");
+    assert_eq!(diagnostics[0].line, 4);
+}
+
+#[test]
+fn test_library_structured_results_round_trip() {
+    let result = lint_source_structured(UNAWAITED, "workflow.ts", &[]);
+    assert_eq!(result.file, "workflow.ts");
+    assert!(result.parse_errors.is_empty());
+    assert_eq!(result.diagnostics.len(), 1);
+    let fix = result.diagnostics[0].fix.as_ref().unwrap();
+    assert_eq!(fix.edits[0].replacement, "await ");
+
+    let json = serde_json::to_string(&result).unwrap();
+    assert!(!json.contains("parse_errors"));
+    let parsed: LintResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, result);
+}
+
+#[test]
+fn test_library_reports_parse_errors() {
+    let result = lint_source_structured("const x = ;\n", "broken.ts", &[]);
+    assert_eq!(result.parse_errors.len(), 1);
+    assert_eq!(result.parse_errors[0].line, 1);
+    assert_eq!(result.parse_errors[0].column, 11);
+}
+
+#[test]
+fn test_library_lints_project_with_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("cashmere.toml"), "ignore = [\"skip/**\"]\n").unwrap();
+    std::fs::create_dir_all(dir.path().join("skip")).unwrap();
+    std::fs::create_dir_all(dir.path().join("node_modules")).unwrap();
+    std::fs::write(dir.path().join("workflow.ts"), UNAWAITED).unwrap();
+    std::fs::write(dir.path().join("skip/workflow.ts"), UNAWAITED).unwrap();
+    std::fs::write(dir.path().join("node_modules/dep.js"), UNAWAITED).unwrap();
+
+    let config = Config::discover(dir.path()).unwrap();
+    let files = collect_files(dir.path(), &config);
+    assert_eq!(files, vec![dir.path().join("workflow.ts")]);
+
+    let diagnostics = lint_file(&files[0], &config, &Stats::default()).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+}
+
+const NO_SLEEP: RuleMeta = RuleMeta {
+    id: "no-sleep",
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    fixable: false,
+    description: "Disallow step.sleep",
+    explanation: "",
+    docs_url: "https://example.com/no-sleep",
+};
+
+#[derive(Default)]
+struct NoSleep;
+
+impl Rule for NoSleep {
+    fn meta(&self) -> &'static RuleMeta {
+        &NO_SLEEP
+    }
+
+    fn check_step_call(
+        &mut self,
+        ctx: &mut RuleContext,
+        call: &StepCall,
+        _expr: &CallExpression,
+        _usage: &StepUsage,
+    ) {
+        if call.method == "sleep" {
+            let diagnostic = ctx.diagnostic(call.span, "no sleeping", &NO_SLEEP);
+            ctx.report(diagnostic);
+        }
+    }
+}
+
+#[test]
+fn test_library_runs_registered_rules() {
+    let source = r#"
+export class MyWorkflow {
+    async run(event, step) {
+        await step.sleep('nap', '1 minute');
+        step.do('unawaited', async () => {});
+    }
+}
+"#;
+    let mut registry = RuleRegistry::builtin();
+    registry.register::<NoSleep>();
+    let mut rules: Vec<&str> = registry.metas().map(|meta| meta.id).collect();
+    rules.sort();
+    assert_eq!(
+        rules,
+        vec![
+            "async-run",
+            "await-step",
+            "await-workflow-api",
+            "banned-call",
+            "conditional-await",
+            "export-workflow-class",
+            "max-steps",
+            "no-busy-wait",
+            "no-console-in-step",
+            "no-destructured-step-methods",
+            "no-discarded-step-promises",
+            "no-dynamic-step-name",
+            "no-io-outside-step",
+            "no-large-step-result",
+            "no-node-builtins",
+            "no-nondeterminism",
+            "no-outer-mutation-in-step",
+            "no-process-env",
+            "no-self-run",
+            "no-sleep",
+            "no-step-in-finally",
+            "no-step-in-promise-executor",
+            "no-step-in-timer",
+            "no-swallowed-step-error",
+            "no-unused-step-result",
+            "prefer-retries-config",
+            "prefer-step-await",
+            "serializable-step-result",
+            "step-name-convention",
+            "typed-event-payload",
+            "unique-step-names",
+            "valid-retry-config",
+            "valid-run-params",
+            "valid-sleep-duration",
+            "valid-sleep-until",
+            "valid-step-callback",
+            "valid-step-error",
+            "valid-wait-for-event"
+        ]
+    );
+
+    let (diagnostics, _) = lint_source_timed(source, "workflow.ts", &[], &registry);
+    let mut found: Vec<&str> = diagnostics.iter().map(|d| d.rule.as_str()).collect();
+    found.sort();
+    assert_eq!(found, vec!["await-step", "no-sleep"]);
+
+    // Rules left out of the registry aren't run
+    registry.retain(|meta| meta.id != AWAIT_STEP.id);
+    let (diagnostics, _) = lint_source_timed(source, "workflow.ts", &[], &registry);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "no-sleep");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+}
+
+#[test]
+fn test_library_diagnostics_are_in_source_order() {
+    // `await-step` reports when it leaves the function, after `no-sleep` has reported
+    let source = r#"
+export class MyWorkflow {
+    async run(event, step) {
+        step.do('unawaited', async () => {});
+        await step.sleep('nap', '1 minute');
+    }
+}
+"#;
+    let mut registry = RuleRegistry::builtin();
+    registry.register::<NoSleep>();
+    let (diagnostics, _) = lint_source_timed(source, "workflow.ts", &[], &registry);
+    let positions: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.line, d.rule.as_str()))
+        .collect();
+    assert_eq!(positions, vec![(4, "await-step"), (5, "no-sleep")]);
+}
+
+/// Counts step calls per workflow and the deepest function nesting they appear at
+#[derive(Default)]
+struct StepMetrics {
+    depth: usize,
+    max_step_depth: usize,
+    steps: Vec<(String, String, bool)>,
+    calls: usize,
+}
+
+impl Visitor for StepMetrics {
+    fn enter_function(&mut self, _ctx: &VisitContext, _scope: FunctionScope) {
+        self.depth += 1;
+    }
+
+    fn leave_function(&mut self, _ctx: &VisitContext) {
+        self.depth -= 1;
+    }
+
+    fn visit_step_call(&mut self, ctx: &VisitContext, call: &StepCall, usage: &StepUsage) {
+        let workflow = ctx.workflow_at(call.span).map(|w| w.name.clone());
+        self.steps.push((
+            workflow.unwrap_or_default(),
+            call.name.clone().unwrap_or_default(),
+            *usage == StepUsage::Awaited && ctx.is_step_param(&call.receiver),
+        ));
+        self.max_step_depth = self.max_step_depth.max(self.depth);
+    }
+
+    fn visit_call(&mut self, _ctx: &VisitContext, _call: &oxc_ast::ast::CallExpression) {
+        self.calls += 1;
+    }
+}
+
+#[test]
+fn test_library_visitor_sees_step_calls() {
+    let source = r#"
+import { WorkflowEntrypoint, WorkflowStep } from "cloudflare:workers";
+
+export class Billing extends WorkflowEntrypoint {
+    async run(event, step: WorkflowStep) {
+        await step.do('charge', async () => fetch('/charge'));
+        step.sleep('wait', '1 day');
+    }
+}
+"#;
+    let mut metrics = StepMetrics::default();
+    let parse_errors = visit_source(source, "workflow.ts", &[], &mut metrics);
+    assert!(parse_errors.is_empty());
+    assert_eq!(metrics.depth, 0);
+    assert_eq!(metrics.max_step_depth, 2);
+    assert_eq!(metrics.calls, 3);
+    assert_eq!(
+        metrics.steps,
+        vec![
+            ("Billing".to_string(), "charge".to_string(), true),
+            ("Billing".to_string(), "wait".to_string(), false),
+        ]
+    );
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_abi_returns_length_prefixed_json() {
+    use cashmere::wasm::{cashmere_alloc, cashmere_free, cashmere_lint};
+
+    let write = |bytes: &[u8]| {
+        let ptr = cashmere_alloc(bytes.len());
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+        ptr
+    };
+    let source = write(UNAWAITED.as_bytes());
+    let file_path = write(b"workflow.ts");
+
+    unsafe {
+        let result = cashmere_lint(source, UNAWAITED.len(), file_path, "workflow.ts".len());
+        let len = u32::from_le_bytes(*(result as *const [u8; 4])) as usize;
+        let json = std::slice::from_raw_parts(result.add(4), len);
+        let parsed: LintResult = serde_json::from_slice(json).unwrap();
+        assert_eq!(parsed.file, "workflow.ts");
+        assert_eq!(parsed.diagnostics.len(), 1);
+
+        cashmere_free(result, len + 4);
+        cashmere_free(source, UNAWAITED.len());
+        cashmere_free(file_path, "workflow.ts".len());
+    }
+}
+
+#[cfg(feature = "capi")]
+#[test]
+fn test_capi_returns_json_string() {
+    use cashmere::capi::{cashmere_free_string, cashmere_lint_utf8};
+    use std::ffi::CStr;
+
+    unsafe {
+        let result = cashmere_lint_utf8(
+            UNAWAITED.as_ptr().cast(),
+            UNAWAITED.len(),
+            c"workflow.ts".as_ptr(),
+        );
+        assert!(!result.is_null());
+        let parsed: LintResult =
+            serde_json::from_str(CStr::from_ptr(result).to_str().unwrap()).unwrap();
+        assert_eq!(parsed.diagnostics.len(), 1);
+        cashmere_free_string(result);
+
+        let invalid = [0xff, 0xfe];
+        let result = cashmere_lint_utf8(invalid.as_ptr().cast(), 2, c"workflow.ts".as_ptr());
+        assert!(result.is_null());
+    }
+}
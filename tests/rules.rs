@@ -0,0 +1,1183 @@
+use cashmere::config::{
+    AwaitStepOptions, EscapePolicy, MaxStepsOptions, StepNameConventionOptions, WaitForEventOptions,
+};
+use cashmere::rules::RuleRegistry;
+use cashmere::rules::{
+    AsyncRun, AwaitStep, AwaitWorkflowApi, ConditionalAwait, ExportWorkflowClass, MaxSteps,
+    NoBusyWait, NoConsoleInStep, NoDestructuredStepMethods, NoDiscardedStepPromises,
+    NoDynamicStepName, NoIoOutsideStep, NoLargeStepResult, NoNodeBuiltins, NoNondeterminism,
+    NoOuterMutationInStep, NoProcessEnv, NoSelfRun, NoStepInFinally, NoStepInPromiseExecutor,
+    NoStepInTimer, NoSwallowedStepError, NoUnusedStepResult, PreferRetriesConfig, PreferStepAwait,
+    SerializableStepResult, StepNameConvention, TypedEventPayload, UniqueStepNames,
+    ValidRetryConfig, ValidRunParams, ValidSleepDuration, ValidSleepUntil, ValidStepCallback,
+    ValidStepError, ValidWaitForEvent, AWAIT_STEP, MAX_STEPS, STEP_NAME_CONVENTION,
+    VALID_WAIT_FOR_EVENT,
+};
+use cashmere::testing::{ExpectedDiagnostic, InvalidCase, RuleTester};
+
+#[test]
+fn test_await_step_fixtures() {
+    RuleTester::for_rule::<AwaitStep>().run_fixtures(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/await-step"
+    ));
+}
+
+#[test]
+fn test_await_step_cases() {
+    RuleTester::for_rule::<AwaitStep>().run(
+        &[
+            "async function run(step) { await step.do('a', async () => 1); }",
+            "async function run(step) { return await Promise.race([step.sleep('a', '1 second')]); }",
+            "async function run(step, ids) { await Promise.all(ids.map((id) => step.do(`a-${id}`, async () => id))); }",
+            "async function run(step, ids) { await Promise.allSettled(ids.map(function (id) { return step.do(`a-${id}`, async () => id); })); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(step) { step.do('a', async () => 1); }",
+                errors: vec![ExpectedDiagnostic::at(1, 28)],
+                output: Some("async function run(step) { await step.do('a', async () => 1); }"),
+            },
+            InvalidCase {
+                // Not fixable: `await` isn't allowed in a non-async function
+                code: "function run(step) { step.sleep('a', '1 second'); }",
+                errors: vec![ExpectedDiagnostic::at(1, 22)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_rule_tester_respects_step_methods() {
+    RuleTester::for_rule::<AwaitStep>()
+        .with_step_methods(&["doWithRetry"])
+        .assert_invalid(
+            "async function run(step) { step.doWithRetry('a'); }",
+            &[ExpectedDiagnostic::at(1, 28).with_message(
+                "`step.doWithRetry` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.",
+            )],
+            Some("async function run(step) { await step.doWithRetry('a'); }"),
+        );
+}
+
+#[test]
+#[should_panic(expected = "expected no diagnostics")]
+fn test_rule_tester_reports_failures() {
+    RuleTester::for_rule::<AwaitStep>().assert_valid("function run(step) { step.do('a'); }");
+}
+
+#[test]
+fn test_no_io_outside_step_cases() {
+    RuleTester::for_rule::<NoIoOutsideStep>().run(
+        &[
+            // Inside step callbacks, with or without a config object
+            "class W extends WorkflowEntrypoint { async run(event, step) { await step.do('a', async () => fetch('/a')); } }",
+            "class W extends WorkflowEntrypoint { async run(event, step) { await step.do('a', { retries: { limit: 1 } }, async () => { await this.env.KV.get('k'); }); } }",
+            "class W extends WorkflowEntrypoint { async run(event, step) { const p = step.do('a', async () => fetch('/a')); await p; } }",
+            // Step objects not named `step`
+            "class W extends WorkflowEntrypoint { async run(event, s) { await s.do('a', async () => fetch('/a')); } }",
+            "export async function run(event, wf: WorkflowStep) { await wf.do('a', async () => fetch('/a')); }",
+            // Variables on `env` aren't bindings
+            "class W extends WorkflowEntrypoint { async run(event, step) { const level = this.env.LOG_LEVEL.toLowerCase(); if (this.env.MODE.startsWith('dev')) {} } }",
+            "class W extends WorkflowEntrypoint { async run(event, step) { const { BASE_URL } = this.env; const base = this.env.BASE_URL; if (base.endsWith('/') || BASE_URL.includes('?')) {} } }",
+            // Aliases don't leak out of the function declaring them
+            "export default { async fetch(request, env) { const db = env.DB; return new Response('ok'); } };\nclass W extends WorkflowEntrypoint { async run(event, step) { const db = openCache(); await db.get('k'); } }",
+            // Outside a workflow
+            "async function handler(request) { return fetch(request); }",
+            "class W extends WorkflowEntrypoint { async fetch() { return fetch('/a'); } }",
+        ],
+        &[
+            InvalidCase {
+                code: "class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    const res = await fetch('/a');\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 23).with_message(
+                    "`fetch` runs again on every replay outside a step; move it into a `step.do` callback",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "export async function run(event, wf: WorkflowStep) {\n  const res = await fetch('/a');\n  await wf.do('a', async () => fetch('/b'));\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 21)],
+                output: None,
+            },
+            InvalidCase {
+                code: "export async function run(event, step: WorkflowStep) {\n  await this.env.API.fetch('/a');\n  await step.do('a', async () => env.KV.put('k', 'v'));\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 9)],
+                output: None,
+            },
+            InvalidCase {
+                code: "class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    const { DB: db, BUCKET } = this.env;\n    const kv = this.env.KV;\n    await db.prepare('SELECT 1').first();\n    await BUCKET.put('k', 'v');\n    await step.do('a', async () => kv.put('k', 'v'));\n    await kv.get('k');\n  }\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(5, 11).with_message(
+                        "`db.prepare` calls `this.env.DB.prepare`, which runs again on every replay outside a step; move it into a `step.do` callback",
+                    ),
+                    ExpectedDiagnostic::at(6, 11),
+                    ExpectedDiagnostic::at(8, 11),
+                ],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_no_nondeterminism_cases() {
+    RuleTester::for_rule::<NoNondeterminism>().run(
+        &[
+            "class W extends WorkflowEntrypoint { async run(event, step) { const id = await step.do('id', async () => crypto.randomUUID()); } }",
+            "class W extends WorkflowEntrypoint { async run(event, step) { const at = new Date(event.timestamp); } }",
+            // Step objects not named `step`
+            "export async function run(event, wf: WorkflowStep) { await wf.do('stamp', async () => ({ at: Date.now(), id: crypto.randomUUID() })); }",
+            "class W extends WorkflowEntrypoint { async run(event, s) { await s.do('stamp', async () => Date.now()); } }",
+            "function handler() { return Date.now(); }",
+        ],
+        &[InvalidCase {
+            code: "export async function run(event, step: WorkflowStep) {\n  const at = new Date();\n  await step.do(`report-${Date.now()}`, async () => Math.random());\n  if (Math.random() > 0.5) {}\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(2, 14).with_message(
+                    "`new Date()` changes on every replay outside a step; read it inside a `step.do` callback",
+                ),
+                ExpectedDiagnostic::at(3, 27),
+                ExpectedDiagnostic::at(4, 7),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_unique_step_names_cases() {
+    RuleTester::for_rule::<UniqueStepNames>().run(
+        &[
+            "class W extends WorkflowEntrypoint { async run(event, step) { await step.do('a', async () => 1); await step.sleep('b', '1 second'); } }",
+            // Each workflow has its own names
+            "async function first(step) { await step.do('a', async () => 1); }\nasync function second(step) { await step.do('a', async () => 1); }",
+        ],
+        &[InvalidCase {
+            code: "class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    await step.do('fetch', async () => 1);\n    await step.sleep('fetch', '1 second');\n  }\n}",
+            errors: vec![ExpectedDiagnostic::at(4, 22).with_message(
+                "Step name 'fetch' is already used in this workflow; steps with the same name share a cached result on replay",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_dynamic_step_name_cases() {
+    RuleTester::for_rule::<NoDynamicStepName>().run(
+        &[
+            "async function run(event, step) { for (let i = 0; i < 3; i++) { await step.do(`page-${i + 1}`, async () => i); } }",
+            "async function run(event, step) { for (const { id } of event.payload.items) { await step.do('item-' + id, async () => id); } }",
+            "async function run(event, step) { await Promise.all(event.payload.ids.map((id, index) => step.do(`fetch-${id}-${index}`, async () => id))); }",
+            "async function run(event, step) { await step.do(`user-${event.payload.userId}`, async () => 1); }",
+            "async function run(event, step) { await step.do(name, async () => 1); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  await step.do(`task-${Date.now()}`, async () => 1);\n  await step.do('user-' + user.id, async () => 1);\n  await step.sleep(crypto.randomUUID(), '1 second');\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(2, 25).with_message(
+                    "Step name uses `Date.now()`, which can change between replays; build step names from loop variables and `event` only",
+                ),
+                ExpectedDiagnostic::at(3, 27),
+                ExpectedDiagnostic::at(4, 20),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_valid_sleep_duration_cases() {
+    RuleTester::for_rule::<ValidSleepDuration>().run(
+        &[
+            "async function run(step) { await step.sleep('a', '90 minutes'); await step.sleep('b', '1 day'); }",
+            "async function run(step) { await step.sleep('a', 1500); await step.sleep('b', `${n} hours`); await step.sleep('c', delay); }",
+            "async function run(step) { await step.sleep('a', '1 year'); await step.sleep('b', '12 months'); await step.sleep('c', 31536000000); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(step) {\n  await step.sleep('a', '90 mins');\n  await step.sleep('b', \"2hours\");\n  await step.sleep('c', 'minute');\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(2, 25).with_message(
+                        "Invalid sleep duration '90 mins': the unit isn't one of second, minute, hour, day, week, month or year in lowercase; did you mean '90 minutes'?",
+                    ),
+                    ExpectedDiagnostic::at(3, 25).with_message(
+                        "Invalid sleep duration \"2hours\": expected a number and a unit separated by a single space; did you mean \"2 hours\"?",
+                    ),
+                    ExpectedDiagnostic::at(4, 25).with_message(
+                        "Invalid sleep duration 'minute': a number is missing; did you mean '1 minute'?",
+                    ),
+                ],
+                output: Some("async function run(step) {\n  await step.sleep('a', '90 minutes');\n  await step.sleep('b', \"2 hours\");\n  await step.sleep('c', '1 minute');\n}"),
+            },
+            InvalidCase {
+                code: "async function run(step) { await step.sleep('a', '-5 seconds'); await step.sleep('b', '3 fortnights'); }",
+                errors: vec![
+                    ExpectedDiagnostic::at(1, 50)
+                        .with_message("Invalid sleep duration '-5 seconds': durations can't be negative"),
+                    ExpectedDiagnostic::at(1, 87),
+                ],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(step) { await step.sleep('a', '2 years'); await step.sleep('b', 40000000000); }",
+                errors: vec![
+                    ExpectedDiagnostic::at(1, 50).with_message(
+                        "Invalid sleep duration '2 years': it is longer than the maximum of 365 days",
+                    ),
+                    ExpectedDiagnostic::at(1, 84),
+                ],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_valid_sleep_until_cases() {
+    RuleTester::for_rule::<ValidSleepUntil>().run(
+        &[
+            "async function run(event, step) { await step.sleepUntil('a', new Date('2030-01-01')); await step.sleepUntil('b', 1893456000000); }",
+            "async function run(event, step) { await step.sleepUntil('a', '2030-01-01T09:00:00.000Z'); await step.sleepUntil('b', '2030-01-01 09:00+02:00'); }",
+            "async function run(event, step) { await step.sleepUntil('a', 'Tue, 01 Jan 2030 09:00:00 GMT'); await step.sleepUntil('b', event.payload.at); }",
+            "async function run(step) { await step.sleepUntil('a', '2028-02-29T23:59:59.999-05:00'); await step.sleepUntil('b', '2030-12'); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(step) {\n  await step.sleepUntil('a', '1 hour');\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 30).with_message(
+                    "'1 hour' is a duration, not a point in time; use `step.sleep` to sleep for a duration",
+                )],
+                output: Some("async function run(step) {\n  await step.sleep('a', '1 hour');\n}"),
+            },
+            InvalidCase {
+                code: "async function run(step) { await step.sleepUntil('a', 'next tuesday'); await step.sleepUntil('b', null); }",
+                errors: vec![
+                    ExpectedDiagnostic::at(1, 55),
+                    ExpectedDiagnostic::at(1, 99).with_message(
+                        "`step.sleepUntil` needs a Date, a timestamp or a date string, not null",
+                    ),
+                ],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(step) {\n  await step.sleepUntil('a', '2024-13-01');\n  await step.sleepUntil('b', '2030-02-30');\n  await step.sleepUntil('c', '2030-01-01T25:99');\n  await step.sleepUntil('d', '2030-01-01T09:00:60Z');\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(2, 30).with_message(
+                        "'2024-13-01' isn't a date string; pass a Date, a timestamp or an ISO 8601 date such as \"2030-01-01T09:00:00Z\"",
+                    ),
+                    ExpectedDiagnostic::at(3, 30),
+                    ExpectedDiagnostic::at(4, 30),
+                    ExpectedDiagnostic::at(5, 30),
+                ],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_valid_wait_for_event_cases() {
+    RuleTester::for_rule::<ValidWaitForEvent>().run(
+        &[
+            "async function run(step) { await step.waitForEvent('approval', { type: 'approved', timeout: '1 hour' }); }",
+            "async function run(step) { await step.waitForEvent('approval', options); await step.waitForEvent('b', { ...defaults, timeout: 60000 }); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(step) {\n  await step.waitForEvent('approval', { type: 'approved', timout: '1 hour' });\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 59).with_message(
+                    "Unknown `step.waitForEvent` option `timout` is ignored; did you mean `timeout`?",
+                )],
+                output: Some("async function run(step) {\n  await step.waitForEvent('approval', { type: 'approved', timeout: '1 hour' });\n}"),
+            },
+            InvalidCase {
+                code: "async function run(step) {\n  await step.waitForEvent('a', { timeout: '2 hrs' });\n  await step.waitForEvent('b');\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(2, 32).with_message(
+                        "`step.waitForEvent` options need the event `type` to wait for",
+                    ),
+                    ExpectedDiagnostic::at(2, 43).with_message(
+                        "Invalid waitForEvent timeout '2 hrs': the unit isn't one of second, minute, hour, day, week, month or year in lowercase; did you mean '2 hours'?",
+                    ),
+                    ExpectedDiagnostic::at(3, 9),
+                ],
+                output: Some("async function run(step) {\n  await step.waitForEvent('a', { timeout: '2 hours' });\n  await step.waitForEvent('b');\n}"),
+            },
+            InvalidCase {
+                code: "async function run(step) {\n  await step.waitForEvent('a', { type: 'a', timeout: 5 });\n  await step.waitForEvent('b', { type: 'b', timeout: '2 years' });\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(2, 54).with_message(
+                        "Invalid waitForEvent timeout 5: it is shorter than the minimum of 1 second; numbers are milliseconds",
+                    ),
+                    ExpectedDiagnostic::at(3, 54).with_message(
+                        "Invalid waitForEvent timeout '2 years': it is longer than the maximum of 365 days",
+                    ),
+                ],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_valid_wait_for_event_configured_min_timeout() {
+    let mut rules = RuleRegistry::empty();
+    rules.register_with(&VALID_WAIT_FOR_EVENT, || {
+        Box::new(ValidWaitForEvent::new(WaitForEventOptions {
+            min_timeout: "5 minutes".to_string(),
+        }))
+    });
+    RuleTester::new(rules).run(
+        &["async function run(step) { await step.waitForEvent('a', { type: 'a', timeout: '1 hour' }); await step.waitForEvent('b', { type: 'b' }); }"],
+        &[InvalidCase {
+            code: "async function run(step) {\n  await step.waitForEvent('a', { type: 'a', timeout: '30 seconds' });\n}",
+            errors: vec![ExpectedDiagnostic::at(2, 54).with_message(
+                "Invalid waitForEvent timeout '30 seconds': it is shorter than the minimum of 5 minutes",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_valid_retry_config_cases() {
+    RuleTester::for_rule::<ValidRetryConfig>().run(
+        &[
+            "async function run(step) { await step.do('a', { retries: { limit: 5, delay: '10 seconds', backoff: 'exponential' }, timeout: '15 minutes' }, async () => 1); }",
+            "async function run(step) { await step.do('a', { retries: { limit: Infinity, delay: 1000 }, timeout: config.timeout }, async () => 1); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(step) {\n  await step.do('a', {\n    retries: { limit: -1, delay: '10 secs', backoff: 'exponentail' },\n    timeout: 'forever',\n  }, async () => 1);\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 23).with_message("Invalid retries.limit -1: must be non-negative"),
+                ExpectedDiagnostic::at(3, 34).with_message(
+                    "Invalid retries.delay '10 secs': the unit isn't one of second, minute, hour, day, week, month or year in lowercase; did you mean '10 seconds'?",
+                ),
+                ExpectedDiagnostic::at(3, 54).with_message(
+                    "Invalid retries.backoff 'exponentail': must be \"constant\", \"linear\" or \"exponential\"; did you mean \"exponential\"?",
+                ),
+                ExpectedDiagnostic::at(4, 14).with_message("Invalid timeout 'forever': a number is missing"),
+            ],
+            output: Some("async function run(step) {\n  await step.do('a', {\n    retries: { limit: -1, delay: '10 seconds', backoff: 'exponential' },\n    timeout: 'forever',\n  }, async () => 1);\n}"),
+        }],
+    );
+}
+
+#[test]
+fn test_serializable_step_result_cases() {
+    RuleTester::for_rule::<SerializableStepResult>().run(
+        &[
+            "async function run(step) { await step.do('a', async () => ({ id: 1, tags: ['x'], at: new Date().toISOString() })); }",
+            "async function run(step) { await step.do('a', async () => { const items = [1, 2].map((n) => n * 2); return items; }); }",
+            "async function run(step) { await step.do('a', async () => { const f = () => 1; return f(); }); }",
+            "async function run(step) { await step.do('a', async () => new Response('ok').text()); }",
+        ],
+        &[
+            InvalidCase {
+                code: "class Cart {}\nasync function run(step) {\n  await step.do('a', async () => new Map());\n  await step.do('b', async () => {\n    const cart = new Cart();\n    if (cart) return cart;\n    return { step, done: () => true, id: Symbol('id') };\n  });\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(3, 34).with_message(
+                        "`step.do` callback returns a `Map`, which can't be serialized as the step's result; return plain objects, arrays, strings, numbers or booleans",
+                    ),
+                    ExpectedDiagnostic::at(6, 22).with_message(
+                        "`step.do` callback returns an instance of class `Cart`, which can't be serialized as the step's result; return plain objects, arrays, strings, numbers or booleans",
+                    ),
+                    ExpectedDiagnostic::at(7, 14).with_message(
+                        "`step.do` callback returns the step object `step`, which can't be serialized as the step's result; return plain objects, arrays, strings, numbers or booleans",
+                    ),
+                    ExpectedDiagnostic::at(7, 26),
+                    ExpectedDiagnostic::at(7, 42),
+                ],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, workflowStep) {\n  await workflowStep.do('a', async () => new Set());\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 42).with_message(
+                    "`workflowStep.do` callback returns a `Set`, which can't be serialized as the step's result; return plain objects, arrays, strings, numbers or booleans",
+                )],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_no_outer_mutation_in_step_cases() {
+    RuleTester::for_rule::<NoOuterMutationInStep>().run(
+        &[
+            "async function run(event, step) { let total = 0; total = await step.do('sum', async () => { let sum = 0; for (const x of [1, 2]) { sum += x; } return total + sum; }); }",
+            "async function run(event, step) { await step.do('a', async (attempt) => { attempt = 1; const seen = {}; seen.a = true; function bump(n) { n++; return n; } try { } catch (err) { err = null; } }); }",
+            "async function run(event, step) { let count = 0; count++; }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  let total = 0;\n  const stats = { runs: 0 };\n  await step.do('sum', async () => {\n    total += 1;\n    stats.runs++;\n    const inner = () => { total = 0; };\n  });\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(5, 5).with_message(
+                    "`total` is declared outside this step callback, so the write is lost when the workflow replays; return the value from the step instead",
+                ),
+                ExpectedDiagnostic::at(6, 5),
+                ExpectedDiagnostic::at(7, 27),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_prefer_step_await_cases() {
+    RuleTester::for_rule::<PreferStepAwait>().run(
+        &[
+            "async function run(event, step) { try { const user = await step.do('load', async () => fetchUser()); } catch (err) { } }",
+            "async function run(event, step) { await step.do('load', async () => fetch(url).then((res) => res.json())); }",
+            "async function run(event, step) { const p = load(); p.then(done); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  await step.do('load', async () => 1).then((n) => n + 1).catch(log);\n  const p = step.sleep('pause', '1 hour');\n  p.finally(done);\n  await p;\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(2, 40).with_message(
+                    "Use `await` with try/catch instead of `.then()` on a step promise; chained handlers make control flow and errors across replays harder to follow",
+                ),
+                ExpectedDiagnostic::at(4, 5),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_step_in_timer_cases() {
+    RuleTester::for_rule::<NoStepInTimer>().run(
+        &[
+            "async function run(event, step) { await step.sleep('wait', '1 minute'); await step.do('a', async () => 1); }",
+            "async function run(event, step) { await step.do('poll', async () => { setTimeout(() => console.log('slow'), 1000); }); }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); setTimeout(() => console.log(other.then), 10); await p; }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  setTimeout(async () => {\n    await step.do('late', async () => 1);\n  }, 1000);\n  const p = step.do('a', async () => 1);\n  queueMicrotask(() => p.then(done));\n  globalThis.setInterval(resolve, 10, p);\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 11).with_message(
+                    "`step.do` is called in a `setTimeout` callback; timers don't survive workflow hibernation, so the step promise is left dangling. Call the step in the workflow body and use `step.sleep` to wait",
+                ),
+                ExpectedDiagnostic::at(6, 24).with_message(
+                    "Step promise `p` is used in a `queueMicrotask` callback; timers don't survive workflow hibernation, so the promise is left dangling",
+                ),
+                ExpectedDiagnostic::at(7, 39),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_valid_step_error_cases() {
+    RuleTester::for_rule::<ValidStepError>().run(
+        &[
+            "import { NonRetryableError } from 'cloudflare:workflows';\nasync function run(event, step) { await step.do('a', async () => { throw new NonRetryableError('bad input'); }); }",
+            "import { NonRetryableError as Fatal } from 'cloudflare:workflows';\nasync function run(event, step) { throw new Fatal('bad input'); }",
+            "async function run(event, step) { await step.do('a', async () => { throw new Error('failed'); }); }",
+            "function parse(text) { throw 'not a step callback'; }",
+        ],
+        &[
+            InvalidCase {
+                code: "class NonRetryableError extends Error {}\nasync function run(event, step) {\n  await step.do('a', async () => {\n    if (bad) throw new NonRetryableError('bad');\n    throw `failed ${id}`;\n  });\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(4, 24).with_message(
+                        "`NonRetryableError` is a class declared in this file, not from `cloudflare:workflows`, so Workflows retries the step anyway; use `import { NonRetryableError } from \"cloudflare:workflows\"`",
+                    ),
+                    ExpectedDiagnostic::at(5, 11).with_message(
+                        "Throw an `Error` instead of a string from a step callback; Workflows records failed attempts by their error name and message, which a string doesn't have",
+                    ),
+                ],
+                output: Some("class NonRetryableError extends Error {}\nasync function run(event, step) {\n  await step.do('a', async () => {\n    if (bad) throw new NonRetryableError('bad');\n    throw new Error(`failed ${id}`);\n  });\n}"),
+            },
+            InvalidCase {
+                code: "import { NonRetryableError } from './errors';\nasync function run(event, step) { throw new NonRetryableError('bad'); }",
+                errors: vec![ExpectedDiagnostic::at(2, 45).with_message(
+                    "`NonRetryableError` is imported from `./errors`, not from `cloudflare:workflows`, so Workflows retries the step anyway; use `import { NonRetryableError } from \"cloudflare:workflows\"`",
+                )],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_max_steps_cases() {
+    RuleTester::for_rule::<MaxSteps>().run(
+        &[
+            "class W extends WorkflowEntrypoint { async run(event, step) { for (let i = 0; i < 100; i++) { await step.do(`page-${i}`, async () => i); } }",
+            "class W extends WorkflowEntrypoint { async run(event, step) { for (const id of event.payload.ids) { await step.do(`item-${id}`, async () => id); } }",
+        ],
+        &[InvalidCase {
+            code: "class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    for (let i = 0; i < 2000; i++) {\n      await step.do(`page-${i}`, async () => i);\n    }\n  }\n}",
+            errors: vec![ExpectedDiagnostic::at(4, 13).with_message(
+                "Workflow makes about 2000 step calls, more than the limit of 1024 steps per instance; batch work into fewer steps",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_max_steps_configured_limit() {
+    let mut rules = RuleRegistry::empty();
+    rules.register_with(&MAX_STEPS, || {
+        Box::new(MaxSteps::new(MaxStepsOptions { limit: 10 }))
+    });
+    RuleTester::new(rules).run(
+        &["class W extends WorkflowEntrypoint { async run(event, step) { for (const id of [1, 2, 3]) { await step.do(`a-${id}`, async () => id); await step.do(`b-${id}`, async () => id); } }"],
+        &[InvalidCase {
+            code: "class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    await step.do('setup', async () => 1);\n    for (let i = 0; i < 4; i++) {\n      await step.do(`a-${i}`, async () => i);\n      await step.sleep(`wait-${i}`, '1 minute');\n    }\n  }\n}",
+            errors: vec![ExpectedDiagnostic::at(6, 13).with_message(
+                "Workflow makes about 9 step calls, close to the limit of 10 steps per instance",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_valid_step_callback_cases() {
+    RuleTester::for_rule::<ValidStepCallback>().run(
+        &[
+            "async function run(event, step) { await step.do('a', async () => helper()); }",
+            "async function run(event, step) { await step.do('a', { retries: { limit: 2 } }, fetchUser); }",
+            "async function run(event, step) { await step.do('a', this.load.bind(this)); }",
+            "async function run(event, step) { await cache.do('a', helper()); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('load', loadUser(event.payload.id));\n  await step.do('save', { timeout: '1 minute' }, await save());\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(2, 25).with_message(
+                        "The `step.do` callback is the result of a call, so `loadUser(event.payload.id)` runs outside the step when the workflow runs or replays; pass a function like `async () => loadUser(event.payload.id)`",
+                    ),
+                    ExpectedDiagnostic::at(3, 50),
+                ],
+                output: Some("async function run(event, step) {\n  await step.do('load', async () => loadUser(event.payload.id));\n  await step.do('save', { timeout: '1 minute' }, async () => await save());\n}"),
+            },
+            InvalidCase {
+                code: "async function run(event, step) { await step.do('a', new Promise((resolve) => resolve(1))); }",
+                errors: vec![ExpectedDiagnostic::at(1, 54)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_no_step_in_promise_executor_cases() {
+    RuleTester::for_rule::<NoStepInPromiseExecutor>().run(
+        &[
+            "async function run(event, step) { const user = await step.do('load', async () => new Promise((resolve) => setTimeout(resolve, 10))); }",
+            "async function run(event, step) { await new Promise((resolve) => resolve(1)); await step.do('a', async () => 1); }",
+            "async function run(event, step) { await new Promise(() => {}).then(() => step.do('a', async () => 1)); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  const result = await new Promise((resolve) => {\n    step.do('load', async () => 1).then(resolve);\n  });\n  await new Promise(async (resolve) => resolve(await step.sleep('wait', '1 minute')));\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 5).with_message(
+                    "`step.do` is called inside a `new Promise` executor, which hides the step promise from the workflow; await the step directly instead of wrapping it",
+                ),
+                ExpectedDiagnostic::at(5, 54),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_discarded_step_promises_cases() {
+    RuleTester::for_rule::<NoDiscardedStepPromises>().run(
+        &[
+            "async function run(event, step) { await Promise.all(event.ids.map((id) => step.do(`a-${id}`, async () => id))); }",
+            "async function run(event, step) { const pages = event.ids.map((id) => step.do(`a-${id}`, async () => id)); await Promise.all(pages); }",
+            "async function run(event, step) { for (const id of event.ids) { await step.do(`a-${id}`, async () => id); } }",
+            "async function run(event, step) { await step.do('a', async () => { event.ids.forEach((id) => log(id)); }); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  event.payload.ids.forEach(async (id) => {\n    await step.do(`a-${id}`, async () => id);\n  });\n  event.ids.map((id) => step.sleep(`b-${id}`, '1 minute'));\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 11).with_message(
+                    "`step.do` is called in a `forEach` callback, but `forEach` discards the promises its callback returns; use `await Promise.all(event.payload.ids.map(...))` instead",
+                ),
+                ExpectedDiagnostic::at(5, 25).with_message(
+                    "`step.sleep` is called in a `map` callback, but the array of promises from `map` is discarded; use `await Promise.all(event.ids.map(...))` instead",
+                ),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_await_workflow_api_cases() {
+    RuleTester::for_rule::<AwaitWorkflowApi>().run(
+        &[
+            "export default { async fetch(request, env) { const instance = await env.MY_WORKFLOW.create({ params: {} }); return Response.json(await instance.status()); } }",
+            "export default { async fetch(request, env) { const instance = await env.MY_WORKFLOW.get(id); await instance.terminate(); } }",
+            "export default { fetch(request, env) { return env.MY_WORKFLOW.create(); } }",
+            "async function handler(env, video) { video.pause(); env.KV.get('k'); }",
+        ],
+        &[
+            InvalidCase {
+                code: "export default {\n  async fetch(request, env, ctx) {\n    env.MY_WORKFLOW.create({ id });\n    const instance = await this.env.MY_WORKFLOW.get(id);\n    instance.pause();\n    (await env.MY_WORKFLOW.get(other)).sendEvent({ type: 'approved' });\n    const pending = env.MY_WORKFLOW.create();\n  },\n};",
+                errors: vec![
+                    ExpectedDiagnostic::at(3, 5).with_message(
+                        "`env.MY_WORKFLOW.create()` must be awaited; otherwise the Worker can finish before Workflows receives the call, and its errors are swallowed",
+                    ),
+                    ExpectedDiagnostic::at(5, 5),
+                    ExpectedDiagnostic::at(6, 5),
+                    ExpectedDiagnostic::at(7, 21).with_message(
+                        "`pending` is assigned the Promise returned by `env.MY_WORKFLOW.create()` rather than the workflow instance; add `await`",
+                    ),
+                ],
+                output: Some("export default {\n  async fetch(request, env, ctx) {\n    await env.MY_WORKFLOW.create({ id });\n    const instance = await this.env.MY_WORKFLOW.get(id);\n    await instance.pause();\n    await (await env.MY_WORKFLOW.get(other)).sendEvent({ type: 'approved' });\n    const pending = await env.MY_WORKFLOW.create();\n  },\n};"),
+            },
+            InvalidCase {
+                // Not fixable: `await` isn't allowed in a non-async function
+                code: "function schedule(env) { env.MY_WORKFLOW.createBatch([{ id: 'a' }]); }",
+                errors: vec![ExpectedDiagnostic::at(1, 26)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_async_run_cases() {
+    RuleTester::for_rule::<AsyncRun>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) { await step.do('a', async () => 1); } }",
+            "export class W extends WorkflowEntrypoint { run(event, step) { return this.process(event, step); } }",
+            "export class Runner { run(task) { task(); } }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) {} helper() { return 1; } }",
+        ],
+        &[
+            InvalidCase {
+                code: "export class OrderWorkflow extends WorkflowEntrypoint {\n  public run(event: WorkflowEvent<Params>, step: WorkflowStep) {\n    step.do('a', async () => 1);\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 10).with_message(
+                    "`run` of `OrderWorkflow` isn't `async`, so it can't await its steps and Workflows doesn't get a Promise to wait on; declare it `async run(...)`",
+                )],
+                output: Some("export class OrderWorkflow extends WorkflowEntrypoint {\n  public async run(event: WorkflowEvent<Params>, step: WorkflowStep) {\n    step.do('a', async () => 1);\n  }\n}"),
+            },
+            InvalidCase {
+                code: "export default class extends WorkflowEntrypoint { run(event, step) { return 1; } }",
+                errors: vec![ExpectedDiagnostic::at(1, 51)],
+                output: Some("export default class extends WorkflowEntrypoint { async run(event, step) { return 1; } }"),
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_valid_run_params_cases() {
+    RuleTester::for_rule::<ValidRunParams>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) {} }",
+            "export class W extends WorkflowEntrypoint { async run(event: WorkflowEvent<Params>, step: WorkflowStep) {} }",
+            "export class W extends WorkflowEntrypoint { async run() {} }",
+            "export class Job { async run(step, event) {} }",
+        ],
+        &[
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint {\n  async run(s: WorkflowStep, e: WorkflowEvent<Params>) {\n    await s.do('a', async () => e.payload);\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 13).with_message(
+                    "`run` is called with `(event, step)`, but `s: WorkflowStep` comes before `e: WorkflowEvent<Params>` here, so they receive each other's values; swap the parameters",
+                )],
+                output: Some("export class W extends WorkflowEntrypoint {\n  async run(e: WorkflowEvent<Params>, s: WorkflowStep) {\n    await s.do('a', async () => e.payload);\n  }\n}"),
+            },
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint { async run(step) { await step.do('a', async () => 1); } }",
+                errors: vec![ExpectedDiagnostic::at(1, 55).with_message(
+                    "`run` is called with `(event, step)`, so its only parameter `step` receives the event; declare the event first: `run(event, step)`",
+                )],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_export_workflow_class_cases() {
+    RuleTester::for_rule::<ExportWorkflowClass>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) {} }",
+            "export default class W extends WorkflowEntrypoint { async run(event, step) {} }",
+            "class W extends WorkflowEntrypoint { async run(event, step) {} }\nexport { W as OrderWorkflow };",
+            "class W extends WorkflowEntrypoint { async run(event, step) {} }\nexport default W;",
+            "class Helper { run() {} }",
+            "function make() { return class extends WorkflowEntrypoint { async run(event, step) {} }; }",
+        ],
+        &[InvalidCase {
+            code: "import { WorkflowEntrypoint } from 'cloudflare:workers';\nclass OrderWorkflow extends WorkflowEntrypoint {\n  async run(event, step) {}\n}\nexport { Other } from './other';",
+            errors: vec![ExpectedDiagnostic::at(2, 7).with_message(
+                "Workflow class `OrderWorkflow` isn't exported, so no `[[workflows]]` binding can use it and the workflow never runs; export it",
+            )],
+            output: Some("import { WorkflowEntrypoint } from 'cloudflare:workers';\nexport class OrderWorkflow extends WorkflowEntrypoint {\n  async run(event, step) {}\n}\nexport { Other } from './other';"),
+        }],
+    );
+}
+
+#[test]
+fn test_no_swallowed_step_error_cases() {
+    RuleTester::for_rule::<NoSwallowedStepError>().run(
+        &[
+            "async function run(event, step) { try { await step.do('a', async () => 1); } catch (e) { console.error(e); throw e; } }",
+            "async function run(event, step) { try { await step.do('a', async () => 1); } catch (e) { return { ok: false, error: String(e) }; } }",
+            "async function run(event, step) { try { await step.do('a', async () => 1); } finally { console.log('done'); } }",
+            "async function run(event, step) { try { await step.do('a', async () => { try { await fetch('/a'); } catch {} }); } catch (e) { throw e; } }",
+            "async function run(event, step) { try { parse(event.payload); } catch {} await step.do('a', async () => 1); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  try {\n    await step.do('a', async () => 1);\n    await step.do('b', async () => 2);\n  } catch (e) {\n    logger.error('step failed', e);\n  }\n  try { await step.sleep('wait', '1 hour'); } catch {}\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(5, 5).with_message(
+                    "This `catch` swallows the error from `step.do`, so a step that failed for good looks like a success to the workflow; rethrow the error or return a result that records the failure",
+                ),
+                ExpectedDiagnostic::at(8, 47),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_step_in_finally_cases() {
+    RuleTester::for_rule::<NoStepInFinally>().run(
+        &[
+            "async function run(event, step) { try { await step.do('a', async () => 1); } finally { console.log('done'); } }",
+            "async function run(event, step) { try { await step.do('a', async () => 1); } catch (e) { await step.do('cleanup', async () => 2); throw e; } await step.do('cleanup', async () => 2); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  try {\n    await step.do('charge', async () => 1);\n  } finally {\n    await step.do('release', async () => 2);\n  }\n}",
+            errors: vec![ExpectedDiagnostic::at(5, 11).with_message(
+                "`step.do` is called in a `finally` block, which also runs while an error is propagating, so whether the step is recorded depends on where the error struck; move it into normal control flow",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_prefer_retries_config_cases() {
+    RuleTester::for_rule::<PreferRetriesConfig>().run(
+        &[
+            "async function run(event, step) { for (const id of event.ids) { try { await step.do(`a-${id}`, async () => id); } catch (e) { console.error(e); } } }",
+            "async function run(event, step) { for (let i = 0; i < 3; i++) { try { await step.do(`a-${i}`, async () => i); } catch (e) { throw e; } } }",
+            "async function run(event, step) { await step.do('a', { retries: { limit: 5, delay: '10 seconds', backoff: 'exponential' } }, async () => 1); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  let result;\n  for (let attempt = 0; attempt < 3; attempt++) {\n    try {\n      result = await step.do(`charge-${attempt}`, async () => 1);\n      break;\n    } catch (e) {\n      await step.sleep(`backoff-${attempt}`, '1 minute');\n    }\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 3).with_message(
+                    "This loop retries `step.do` by catching its error and trying again; set `retries` in the step's config instead, like `step.do(name, { retries: { limit: 5, delay: \"10 seconds\", backoff: \"exponential\" } }, callback)`",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  do {\n    try {\n      return await step.do('sync', async () => 1);\n    } catch {\n      continue;\n    }\n  } while (true);\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 3)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_no_self_run_cases() {
+    RuleTester::for_rule::<NoSelfRun>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) { await this.process(event, step); } async process(event, step) {} }",
+            "export class Task { start() { return this.run(); } run() {} }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) { await this.env.OTHER.create({ params: event.payload }); } }",
+        ],
+        &[InvalidCase {
+            code: "export class OrderWorkflow extends WorkflowEntrypoint {\n  async run(event, step) {\n    if (event.payload.retry) return this.run(event, step);\n  }\n  async again(event, step) {\n    await OrderWorkflow.prototype.run.call(this, event, step);\n  }\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 37).with_message(
+                    "`this.run(...)` calls the workflow's entrypoint again, bypassing the engine and repeating its steps; move the shared logic into another method",
+                ),
+                ExpectedDiagnostic::at(6, 11),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_busy_wait_cases() {
+    RuleTester::for_rule::<NoBusyWait>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) { let ready = false; while (!ready) { ready = await step.do('check', async () => poll()); if (!ready) await step.sleep('wait', '1 minute'); } } }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) { let page = 0; while (page < 3) { page++; } } }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) { do { await step.waitForEvent('ping', { type: 'ping' }); } while (await step.do('more', async () => more())); } }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) { await step.do('drain', async () => { while (await queue.next()) {} }); } }",
+            // Working through a list isn't polling
+            "export class W extends WorkflowEntrypoint { async run(event, step) { const ids = event.payload.ids; while (ids.length) { const id = ids.shift(); await step.do(`process-${id}`, async () => handle(id)); } } }",
+            "export class W extends WorkflowEntrypoint { async run(event, step) { while (true) { const token = next(); if (!token) break; } } }",
+        ],
+        &[
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    let status = 'pending';\n    while (status !== 'done') {\n      status = await step.do('check status', async () => check());\n    }\n    do {\n      await step.do('poll', async () => poll());\n    } while (true);\n  }\n}",
+                errors: vec![
+                    ExpectedDiagnostic::at(4, 5).with_message(
+                        "This `while` loop calls `step.do` on every iteration without `step.sleep`, `step.sleepUntil` or `step.waitForEvent`, so it polls as fast as it can; sleep between iterations or wait for an event",
+                    ),
+                    ExpectedDiagnostic::at(7, 5),
+                ],
+                output: None,
+            },
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    while (!(await this.env.KV.get('done'))) {}\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 5).with_message(
+                    "This `while` loop checks an awaited value on every iteration without `step.sleep`, `step.sleepUntil` or `step.waitForEvent`, so it polls as fast as it can; sleep between iterations or wait for an event",
+                )],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_step_name_convention_cases() {
+    RuleTester::for_rule::<StepNameConvention>().run(
+        &[
+            "async function run(event, step) { await step.do('fetch-user', async () => 1); await step.sleep('wait-2', '1 minute'); }",
+            "async function run(event, step) { await step.do(`charge-${event.payload.id}`, async () => 1); await step.do(name, async () => 2); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  await step.do('Fetch user', async () => 1);\n  await step.waitForEvent('orderShipped', { type: 'shipped' });\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(2, 17)
+                    .with_message("Step name 'Fetch user' isn't kebab-case; name it like 'fetch-user'"),
+                ExpectedDiagnostic::at(3, 27).with_message(
+                    "Step name 'orderShipped' isn't kebab-case; name it like 'order-shipped'",
+                ),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_step_name_convention_configured_pattern() {
+    let mut rules = RuleRegistry::empty();
+    rules.register_with(&STEP_NAME_CONVENTION, || {
+        Box::new(StepNameConvention::new(StepNameConventionOptions {
+            pattern: "^[a-z]+(_[a-z]+)*$".to_string(),
+        }))
+    });
+    RuleTester::new(rules).run(
+        &["async function run(event, step) { await step.do('fetch_user', async () => 1); }"],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  await step.do('fetch-user', async () => 1);\n}",
+            errors: vec![ExpectedDiagnostic::at(2, 17).with_message(
+                "Step name 'fetch-user' doesn't match the step naming pattern `^[a-z]+(_[a-z]+)*$`",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_destructured_step_methods_cases() {
+    RuleTester::for_rule::<NoDestructuredStepMethods>().run(
+        &[
+            "async function run(event, step) { const { payload } = event; const s = step; await s.do('a', async () => payload); }",
+            "async function run(event, step) { const doStep = step.do.bind(step); const { do: task } = helpers; }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  const { do: doStep, sleep } = step;\n  const s = step;\n  const wait = s.waitForEvent;\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(2, 11).with_message(
+                    "`do` is destructured from `step`, so calls to it lose the step as `this` and aren't checked as step calls; call `step.do(...)` instead",
+                ),
+                ExpectedDiagnostic::at(2, 23),
+                ExpectedDiagnostic::at(4, 16).with_message(
+                    "`s.waitForEvent` is taken off the step object, so calls to it lose the step as `this` and aren't checked as step calls; call `s.waitForEvent(...)` instead",
+                ),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_console_in_step_cases() {
+    RuleTester::for_rule::<NoConsoleInStep>().run(
+        &[
+            "async function run(event, step) { console.log('starting'); await step.do('a', async () => { this.env.LOGGER.info('a'); }); }",
+            "async function run(event, step) { await step.do('a', async () => { logger.error('failed'); }); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  await step.do('charge', async () => {\n    console.log('charging');\n    try { await charge(); } catch (e) { console.error(e); throw e; }\n  });\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(3, 5).with_message(
+                    "`console.log` is called inside a step callback; log through the project's structured logger instead",
+                ),
+                ExpectedDiagnostic::at(4, 41),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_process_env_cases() {
+    RuleTester::for_rule::<NoProcessEnv>().run(
+        &[
+            "export class W extends WorkflowEntrypoint { async run(event, step) { await step.do('a', async () => fetch(this.env.API_URL)); } }",
+            "export default { fetch() { return new Response(process.env.GREETING); } }",
+        ],
+        &[InvalidCase {
+            code: "const { API_KEY } = process.env;\nexport class W extends WorkflowEntrypoint {\n  async run(event, step) {\n    await step.do('a', async () => fetch(process.env.API_URL, { headers: { key: API_KEY } }));\n  }\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(1, 21).with_message(
+                    "`process.env` isn't how Workers get their configuration; read vars, secrets and bindings from `this.env` in the workflow class",
+                ),
+                ExpectedDiagnostic::at(4, 42),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_no_node_builtins_cases() {
+    RuleTester::for_rule::<NoNodeBuiltins>().run(
+        &[
+            "import { Buffer } from 'node:buffer';\nimport { EventEmitter } from 'events';\nexport class W extends WorkflowEntrypoint { async run(event, step) {} }",
+            "import fs from 'fs';\nexport function readConfig() { return fs.readFileSync('config.json'); }",
+        ],
+        &[InvalidCase {
+            code: "import { readFile } from 'node:fs/promises';\nimport { exec } from 'child_process';\nconst net = require('net');\nexport class W extends WorkflowEntrypoint {\n  async run(event, step) {}\n}",
+            errors: vec![
+                ExpectedDiagnostic::at(1, 26).with_message(
+                    "`node:fs/promises` isn't available in Workers, even with the `nodejs_compat` flag; use a Workers API instead",
+                ),
+                ExpectedDiagnostic::at(2, 22),
+                ExpectedDiagnostic::at(3, 21),
+            ],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_typed_event_payload_cases() {
+    RuleTester::for_rule::<TypedEventPayload>().run(
+        &[
+            "export class W extends WorkflowEntrypoint<Env, Params> { async run(event: WorkflowEvent<Params>, step: WorkflowStep) { await step.do('a', async () => event.payload.id); } }",
+            "export class W extends WorkflowEntrypoint<Env> { async run(event: Readonly<WorkflowEvent<{ id: string }>>, step: WorkflowStep) { return event.payload.id; } }",
+            "export class W extends WorkflowEntrypoint { async run({ payload }, step: WorkflowStep) { return payload.id; } }",
+        ],
+        &[
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint<Env, Params> {\n  async run(event, step: WorkflowStep) {\n    const id = event.payload.id;\n    await step.do('a', async () => event.payload.name);\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 16).with_message(
+                    "`event.payload` is `any` because `event` has no type annotation; declare the payload type, as in `event: WorkflowEvent<Params>`",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "export class W extends WorkflowEntrypoint {\n  async run(e: WorkflowEvent<any>, step: WorkflowStep) {\n    return e.payload;\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 12).with_message(
+                    "`e.payload` is `any` because `e` is a `WorkflowEvent<any>`; declare the payload type, as in `e: WorkflowEvent<Params>`",
+                )],
+                output: None,
+            },
+        ],
+    );
+    RuleTester::for_rule::<TypedEventPayload>()
+        .with_filename("workflow.js")
+        .assert_valid("export class W extends WorkflowEntrypoint { async run(event, step) { return event.payload; } }");
+}
+
+#[test]
+fn test_no_unused_step_result_cases() {
+    RuleTester::for_rule::<NoUnusedStepResult>().run(
+        &[
+            "async function run(event, step) { const user = await step.do('load', async () => { return await loadUser(); }); }",
+            "async function run(event, step) { await step.do('notify', async () => { await notify(); }); }",
+            "async function run(event, step) { await step.do('notify', async () => { if (done) return; await notify(); return undefined; }); }",
+            "async function run(event, step) { await step.do('notify', async () => notify()); }",
+            "async function run(event, step) { await step.do('notify', async () => { const send = () => { return 1; }; await send(); }); }",
+            "async function run(event, step) { return await step.do('load', async () => ({ id: 1 })); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('charge', async () => {\n    const receipt = await charge();\n    return receipt;\n  });\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 9).with_message(
+                    "The result of `step.do('charge')` is discarded, but its callback returns a value that is stored as the step's result; use the result or stop returning it from the callback",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('load', async () => ({ id: 1 }));\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 9)],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('load', { retries: { limit: 2 } }, async function () {\n    try { return await load(); } catch (e) { return null; }\n  });\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 9)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_no_large_step_result_cases() {
+    let large_array = format!(
+        "async function run(event, step) {{\n  await step.do('seed', async () => {{\n    return [{}];\n  }});\n}}",
+        vec!["'row'"; 20000].join(", ")
+    );
+    RuleTester::for_rule::<NoLargeStepResult>().run(
+        &[
+            "async function run(event, step) { await step.do('save', async () => { const res = await fetch(url); await env.BUCKET.put('file', await res.arrayBuffer()); return 'file'; }); }",
+            "async function run(event, step) { await step.do('read', async () => (await fetch(url)).json()); }",
+            "async function run(event, step) { await step.do('ids', async () => [1, 2, 3]); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('download', async () => {\n    const res = await fetch(url);\n    return await res.arrayBuffer();\n  });\n}",
+                errors: vec![ExpectedDiagnostic::at(4, 12).with_message(
+                    "`step.do` callback returns the whole body from `res.arrayBuffer()`, but step results are limited to 1 MiB; store large payloads in R2 and return their key",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  await step.do('download', async () => {\n    const blob = await (await fetch(url)).blob();\n    return blob;\n  });\n}",
+                errors: vec![ExpectedDiagnostic::at(4, 12).with_message(
+                    "`step.do` callback returns the whole body from `(await fetch(url)).blob()`, but step results are limited to 1 MiB; store large payloads in R2 and return their key",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, s: WorkflowStep) {\n  await s.do('download', async () => (await fetch(url)).arrayBuffer());\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 38).with_message(
+                    "`s.do` callback returns the whole body from `(await fetch(url)).arrayBuffer()`, but step results are limited to 1 MiB; store large payloads in R2 and return their key",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: &large_array,
+                errors: vec![ExpectedDiagnostic::at(3, 12).with_message(
+                    "`step.do` callback returns an array literal of 136 KiB, but step results are limited to 1 MiB; store large payloads in R2 and return their key",
+                )],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_conditional_await_cases() {
+    RuleTester::for_rule::<ConditionalAwait>().run(
+        &[
+            "async function run(event, step) { const p = step.do('a', async () => 1); if (event.payload.fast) { log(); } await p; }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); if (event.payload.fast) { await p; } else { await p; } }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); switch (event.payload.kind) { case 'a': case 'b': await p; break; default: await Promise.all([p]); } }",
+            "async function run(event, step) { if (event.payload.fast) { const p = step.do('a', async () => 1); await p; } }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); event.payload.fast ? await p : await p; }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); do { await p; } while (false); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  const p = step.do('charge', async () => charge());\n  if (event.payload.notify) {\n    await p;\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 13).with_message(
+                    "`p` holds the `step.do` promise but is only awaited on some paths, so it dangles on the others; await it where every path reaches, or in every branch",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  const p = step.do('a', async () => 1);\n  switch (event.payload.kind) {\n    case 'a':\n      await p;\n      break;\n    case 'b':\n      break;\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 13)],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  const p = step.sleep('pause', '1 hour');\n  for (const item of event.payload.items) {\n    await p;\n  }\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 13)],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  const p = step.do('a', async () => 1);\n  event.payload.wait && (await p);\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 13)],
+                output: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_await_step_escaped_promises() {
+    RuleTester::for_rule::<AwaitStep>().run(
+        &[
+            "async function run(event, step) { const p = step.do('a', async () => 1); log(p); await p; }",
+            "async function run(event, step) { const p = step.do('a', async () => 1); await Promise.all([p]); }",
+        ],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  const p = step.do('a', async () => 1);\n  await this.handle(p);\n}",
+            errors: vec![ExpectedDiagnostic::at(2, 13).with_message(
+                "`step.do` must be awaited, but `p` is passed to `this.handle`, where cashmere can't see whether it is awaited. Await it here, or set `escapes = \"handled\"` under `[rule_options.await-step]` if your helpers await the promises they are given.",
+            )],
+            output: None,
+        }],
+    );
+
+    let mut rules = RuleRegistry::empty();
+    rules.register_with(&AWAIT_STEP, || {
+        Box::new(AwaitStep::new(AwaitStepOptions {
+            escapes: EscapePolicy::Handled,
+        }))
+    });
+    RuleTester::new(rules).run(
+        &["async function run(event, step) { const p = step.do('a', async () => 1); await handle(p); }"],
+        &[InvalidCase {
+            code: "async function run(event, step) {\n  const p = step.do('a', async () => 1);\n  log(p.status);\n}",
+            errors: vec![ExpectedDiagnostic::at(2, 13).with_message(
+                "`step.do` must be awaited. Not awaiting creates a dangling Promise that can cause race conditions and swallowed errors.",
+            )],
+            output: None,
+        }],
+    );
+}
+
+#[test]
+fn test_await_step_stored_promises() {
+    RuleTester::for_rule::<AwaitStep>().run(
+        &[
+            "async function run(event, step) { const pending = []; pending.push(step.do('a', async () => 1)); await Promise.all(pending); }",
+            "async function run(event, step) { const byId = {}; byId.a = step.do('a', async () => 1); await Promise.all(Object.values(byId)); }",
+            "async function run(event, step) { const all = [step.sleep('a', '1 hour')]; await Promise.race([...all, timeout()]); }",
+            "async function run(event, step) { const obj = { a: step.do('a', async () => 1) }; await obj.a; }",
+            "async function run(event, step) { const results = []; results[0] = step.do('a', async () => 1); await Promise.all([results[0]]); }",
+        ],
+        &[
+            InvalidCase {
+                code: "async function run(event, step) {\n  const byId = { a: step.do('a', async () => 1) };\n  return byId;\n}",
+                errors: vec![ExpectedDiagnostic::at(2, 21).with_message(
+                    "`step.do` must be awaited, but it is stored in `byId`, which is never awaited or passed to an awaited `Promise.all` and friends.",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  const pending = [];\n  pending.push(step.do('a', async () => 1));\n  Promise.all(pending);\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 16).with_message(
+                    "`step.do` must be awaited, but it is stored in `pending`, which is never awaited or passed to an awaited `Promise.all` and friends.",
+                )],
+                output: None,
+            },
+            InvalidCase {
+                code: "async function run(event, step) {\n  const pending = [];\n  pending.push(step.do('a', async () => 1));\n  await drain(pending);\n}",
+                errors: vec![ExpectedDiagnostic::at(3, 16).with_message(
+                    "`step.do` must be awaited, but `pending` is passed to `drain`, where cashmere can't see whether it is awaited. Await it here, or set `escapes = \"handled\"` under `[rule_options.await-step]` if your helpers await the promises they are given.",
+                )],
+                output: None,
+            },
+        ],
+    );
+}